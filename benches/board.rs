@@ -0,0 +1,72 @@
+//! measures the checks every move goes through: validating the candidate moves of a
+//! position, and making a move and querying the state of the board afterwards
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use tictactoe_extreme::{Board, Move};
+
+/// a board in the middle of a game, reached by always making the first allowed move
+fn midgame_board(move_count: usize) -> Board {
+    let mut board = Board::new();
+    for _ in 0..move_count {
+        let coordinates = board.get_allowed_moves()[0];
+        board.insert_move(coordinates, None).unwrap();
+    }
+    board
+}
+
+fn validate_moves(c: &mut Criterion) {
+    let board = midgame_board(20);
+    let moves: Vec<Move> = board
+        .get_allowed_moves()
+        .into_iter()
+        .map(|coordinates| Move::new(coordinates, board.get_next_player()))
+        .collect();
+    c.bench_function("validate allowed moves", |b| {
+        b.iter(|| {
+            for new_move in &moves {
+                black_box(&board).validate_move(*new_move).unwrap();
+            }
+        })
+    });
+}
+
+/// validating a move with the states of the sub boards cached from an earlier query of
+/// the board, and on a board that has to compute them first, as it does after every move
+fn validate_move_cache(c: &mut Criterion) {
+    let uncached = midgame_board(20);
+    let cached = uncached.clone();
+    cached.get_state();
+    let new_move = Move::new(uncached.get_allowed_moves()[0], uncached.get_next_player());
+    c.bench_function("validate move (cached)", |b| {
+        b.iter_batched(
+            || cached.clone(),
+            |board| board.validate_move(black_box(new_move)).unwrap(),
+            criterion::BatchSize::SmallInput,
+        )
+    });
+    c.bench_function("validate move (uncached)", |b| {
+        b.iter_batched(
+            || uncached.clone(),
+            |board| board.validate_move(black_box(new_move)).unwrap(),
+            criterion::BatchSize::SmallInput,
+        )
+    });
+}
+
+fn insert_move(c: &mut Criterion) {
+    let board = midgame_board(20);
+    let coordinates = board.get_allowed_moves()[0];
+    c.bench_function("insert move and get state", |b| {
+        b.iter_batched(
+            || board.clone(),
+            |mut board| {
+                board.insert_move(coordinates, None).unwrap();
+                black_box(board.get_state())
+            },
+            criterion::BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, validate_moves, validate_move_cache, insert_move);
+criterion_main!(benches);
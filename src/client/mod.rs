@@ -0,0 +1,265 @@
+use crate::{websocket::OutgoingMessage, ApiConfig, Board, GameData, Move};
+use futures_util::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde_json::{from_str, to_string};
+use std::fmt::Display;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientError {
+    /// the request could not be sent or its response could not be read
+    Http(String),
+    /// the websocket connection failed
+    WebSocket(String),
+    /// the server answered with an error, e.g. because the move was invalid
+    Server(String),
+}
+
+impl Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Http(message) => write!(f, "request failed: {}", message),
+            Self::WebSocket(message) => write!(f, "websocket connection failed: {}", message),
+            Self::Server(message) => write!(f, "the server returned an error: {}", message),
+        }
+    }
+}
+
+/// a client for the REST API and the websocket server.
+/// The addresses are given as `host:port`, like `Server::get_address` returns them.
+#[derive(Debug, Clone)]
+pub struct GameClient {
+    api_address: String,
+    websocket_address: String,
+    /// the path the REST endpoints are mounted under, see `ApiConfig::base_path`
+    base_path: String,
+    http: reqwest::Client,
+}
+
+impl GameClient {
+    pub fn new(api_address: &str, websocket_address: &str) -> Self {
+        Self {
+            api_address: api_address.to_string(),
+            websocket_address: websocket_address.to_string(),
+            base_path: ApiConfig::DEFAULT_BASE_PATH.to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// the client for a server whose endpoints are mounted under another path than
+    /// `/api/v1`, e.g. behind a reverse proxy
+    pub fn with_base_path(mut self, base_path: &str) -> Self {
+        self.base_path = base_path.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// creates a new game and returns its id
+    pub async fn create_game(&self) -> Result<Uuid, ClientError> {
+        let request = self.http.put(self.api_url("/games"));
+        Self::parse(Self::send(request).await?)
+    }
+
+    /// returns the data of the game
+    pub async fn get_game(&self, game_id: Uuid) -> Result<GameData, ClientError> {
+        let request = self.http.get(self.api_url(&format!("/games/{}", game_id)));
+        Self::parse(Self::send(request).await?)
+    }
+
+    /// makes a move in the game
+    pub async fn add_move(&self, game_id: Uuid, new_move: Move) -> Result<(), ClientError> {
+        let body = to_string(&new_move).map_err(|e| ClientError::Http(e.to_string()))?;
+        let request = self
+            .http
+            .post(self.api_url(&format!("/games/{}/moves", game_id)))
+            .header("content-type", "application/json")
+            .body(body);
+        match Self::parse::<String>(Self::send(request).await?)?.as_str() {
+            "ok" => Ok(()),
+            other => Err(ClientError::Server(other.to_string())),
+        }
+    }
+
+    /// subscribes to the game and returns the board after every update, starting with the
+    /// current board. The stream ends when the server closes the connection, which it does
+    /// once the game is over.
+    pub async fn subscribe(
+        &self,
+        game_id: Uuid,
+    ) -> Result<impl Stream<Item = Result<Board, ClientError>>, ClientError> {
+        let (ws_stream, _) = connect_async(format!("ws://{}/{}", self.websocket_address, game_id))
+            .await
+            .map_err(|e| ClientError::WebSocket(e.to_string()))?;
+
+        Ok(ws_stream.filter_map(|message| async move {
+            let text = match message {
+                Ok(Message::Text(text)) => text,
+                // pings and the close frame carry no updates
+                Ok(_) => return None,
+                Err(e) => return Some(Err(ClientError::WebSocket(e.to_string()))),
+            };
+            match from_str::<OutgoingMessage>(&text) {
+                Ok(OutgoingMessage::GameState { game_state, .. }) => Some(Ok(game_state)),
+                Ok(OutgoingMessage::Error { error_message }) => {
+                    Some(Err(ClientError::Server(format!("{:?}", error_message))))
+                }
+                Ok(_) => None,
+                Err(e) => Some(Err(ClientError::WebSocket(e.to_string()))),
+            }
+        }))
+    }
+
+    /// the url of an endpoint, given by its path below the base path
+    fn api_url(&self, path: &str) -> String {
+        format!("http://{}{}{}", self.api_address, self.base_path, path)
+    }
+
+    async fn send(request: reqwest::RequestBuilder) -> Result<String, ClientError> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ClientError::Http(e.to_string()))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| ClientError::Http(e.to_string()))?;
+        match status.is_success() {
+            true => Ok(body),
+            false => Err(ClientError::Server(body)),
+        }
+    }
+
+    /// the server answers errors with a 200 and the serialized error kind of its
+    /// data provider, so a body that isn't the expected type is an error
+    fn parse<R: DeserializeOwned>(body: String) -> Result<R, ClientError> {
+        from_str(&body).map_err(|_| ClientError::Server(body))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        rest_api::ApiServer, websocket::WebSocketServer, CacheProvider, GameState, Player, Server,
+    };
+    use serial_test::serial;
+    use std::time::Duration;
+    use tokio::{
+        spawn,
+        time::{sleep, timeout},
+    };
+
+    #[tokio::test]
+    #[serial]
+    async fn full_game() {
+        let data_provider = CacheProvider::default();
+        let mut api = ApiServer::new(
+            "127.0.0.1".to_string(),
+            rand::random::<u16>(),
+            data_provider.clone(),
+        );
+        let mut websocket = WebSocketServer::new(
+            "127.0.0.1".to_string(),
+            rand::random::<u16>(),
+            data_provider,
+        );
+        let client = GameClient::new(&api.get_address(), &websocket.get_address());
+        spawn(async move { api.start().await.unwrap() });
+        spawn(async move { websocket.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let game_id = client.create_game().await.unwrap();
+        let mut updates = Box::pin(client.subscribe(game_id).await.unwrap());
+        let board = timeout(Duration::from_secs(1), updates.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert!(board.moves.is_empty());
+
+        let moves = [
+            ((1, 1), Player::X),
+            ((4, 4), Player::O),
+            ((3, 4), Player::X),
+            ((1, 4), Player::O),
+            ((3, 5), Player::X),
+            ((1, 7), Player::O),
+            ((3, 3), Player::X),
+            ((0, 0), Player::O),
+            ((0, 2), Player::X),
+            ((0, 6), Player::O),
+            ((2, 0), Player::X),
+            ((8, 2), Player::O),
+            ((6, 8), Player::X),
+            ((2, 8), Player::O),
+            ((7, 7), Player::X),
+            ((2, 5), Player::O),
+            ((8, 6), Player::X),
+        ];
+        for (move_count, (coordinates, player)) in moves.into_iter().enumerate() {
+            client
+                .add_move(game_id, Move::new(coordinates, player))
+                .await
+                .unwrap();
+            let board = timeout(Duration::from_secs(1), updates.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            assert_eq!(board.moves.len(), move_count + 1);
+        }
+
+        let game_data = client.get_game(game_id).await.unwrap();
+        assert_eq!(game_data.moves.len(), moves.len());
+        assert_eq!(
+            Board::from(game_data).get_state(),
+            GameState::Won { winner: Player::X }
+        );
+        // the server closes the connection once the game is over
+        let update = timeout(Duration::from_secs(1), updates.next())
+            .await
+            .unwrap();
+        assert!(update.is_none());
+
+        // errors of the data provider are reported as server errors
+        assert!(matches!(
+            client
+                .add_move(Uuid::new_v4(), Move::new((0, 0), Player::X))
+                .await,
+            Err(ClientError::Server(_))
+        ));
+        assert!(matches!(
+            client.get_game(Uuid::new_v4()).await,
+            Err(ClientError::Server(_))
+        ));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn base_path() {
+        let data_provider = CacheProvider::default();
+        let mut api = ApiServer::new(
+            "127.0.0.1".to_string(),
+            rand::random::<u16>(),
+            data_provider.clone(),
+        );
+        api.base_path = "/ttt/api".to_string();
+        let client = GameClient::new(&api.get_address(), "127.0.0.1:0").with_base_path("/ttt/api/");
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let game_id = client.create_game().await.unwrap();
+        client
+            .add_move(game_id, Move::new((4, 4), Player::X))
+            .await
+            .unwrap();
+        assert_eq!(client.get_game(game_id).await.unwrap().moves.len(), 1);
+
+        // the default base path isn't served
+        assert!(GameClient::new(client.api_address.as_str(), "127.0.0.1:0")
+            .create_game()
+            .await
+            .is_err());
+    }
+}
@@ -1,6 +1,6 @@
 pub use std::{fmt::Debug, future::Future};
 
-use crate::DataProvider;
+use crate::{Config, DataProvider, ServerConfig};
 pub mod rest_api;
 pub mod r#static;
 pub mod websocket;
@@ -11,8 +11,8 @@ pub trait ServerArgs: Sized {
 
 pub trait Server<T: DataProvider + Default>: Sized {
     type ErrorKind: Debug;
-    const DEFAULT_PORT: u16 = 3000;
-    const DEFAULT_HOST: &'static str = "127.0.0.1";
+    const DEFAULT_PORT: u16 = ServerConfig::DEFAULT_PORT;
+    const DEFAULT_HOST: &'static str = ServerConfig::DEFAULT_HOST;
 
     fn new(host: String, port: u16, data_provider: T) -> Self;
 
@@ -24,6 +24,12 @@ pub trait Server<T: DataProvider + Default>: Sized {
 
     fn with_data_provider(data_provider: T) -> Self;
 
+    /// creates the server with its part of the configuration
+    fn from_config(config: &Config, data_provider: T) -> Self;
+
     // loads environment variables or uses default values if not set
-    fn from_env(data_provider: T) -> Self;
+    fn from_env(data_provider: T) -> Self {
+        let config = Config::from_env().unwrap_or_else(|e| panic!("Invalid configuration: {}", e));
+        Self::from_config(&config, data_provider)
+    }
 }
@@ -1,19 +1,78 @@
-use crate::{DataProvider, Server};
+use crate::{ApiConfig, Config, DataProvider, Server};
 use actix_web::{
-    web::{get, post, put, Data},
-    App, HttpServer,
+    dev::{Service, ServiceRequest},
+    error::{InternalError, JsonPayloadError},
+    http::header::CONTENT_LENGTH,
+    web::{delete, get, post, put, scope, Data, JsonConfig, PayloadConfig},
+    App, HttpResponse, HttpServer,
+};
+use futures_util::future::{ready, Either};
+use serde_json::json;
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
 };
-use std::sync::{Arc, Mutex};
 
+mod openapi;
 mod v1;
-use v1::{add_move, create_game, get_game, get_games};
+use v1::{
+    add_move, advance_tournament, clear_games, create_game, create_tournament, get_analysis,
+    get_featured_game, get_game, get_games, get_global_stats, get_info, get_last_move, get_moves,
+    get_openapi, get_playable, get_player_games, get_player_stats, get_render, get_replay_svg,
+    get_results, get_tournament, join_game, seed_games, stream_game_events, truncate_game,
+    AdminToken, ServerPorts, ServerRuntime,
+};
+#[cfg(feature = "png")]
+use v1::{get_png, PngCache};
 
 /*
 Endpoints:
 * GET  /api/v1/games                 -> DataProvider::get_games
+       ?since=<rfc3339>              -> DataProvider::list_games_since(since)
+* GET  /api/v1/games/featured        -> DataProvider::get_featured_game
+* GET  /api/v1/games/results?limit=<n>&offset=<n> -> DataProvider::list_results(limit, offset)
 * GET  /api/v1/games/{game_id}       -> DataProvider::get_game_data(game_id)
+       If-None-Match: <etag>         -> 304 if the ETag (GameData::version_hash) matches
 * PUT  /api/v1/games                 -> DataProvider::create_game(None)
-* POST /api/v1/games/{game_id}/moves -> DataProvider::add_move(game_id, body.move) // TODO: Add authentication
+       { "first_move": <move> }      -> DataProvider::create_game_with_move(None, body.first_move)
+       X-Player-Name: <name> or { "created_by": <name> } -> DataProvider::create_game_from(None, template)
+       { "settings": <settings> }    -> DataProvider::create_game_from(None, template), e.g. for a private game
+       ?id=<uuid> or { "id": <uuid> } -> the game is created with this id instead of None, 409 if it is taken
+* POST /api/v1/games/{game_id}/moves -> DataProvider::add_move(game_id, body.move), the player defaults to the next player // TODO: Add authentication
+       ?move_count=<n>               -> DataProvider::add_move_if_count(game_id, n, body.move)
+       ?coord=e5[&player=X]          -> the move is read from the query instead of the body
+* GET  /api/v1/games/{game_id}/moves -> the moves of DataProvider::get_game_data(game_id)
+       ?annotated=true               -> Board::annotated_moves() of DataProvider::get_board(game_id)
+* DELETE /api/v1/games/{game_id}/moves?after=<n> -> DataProvider::truncate_game(game_id, n), requires the bearer token of a registered player or the admin
+* GET  /api/v1/games/{game_id}/moves/last -> DataProvider::get_last_move(game_id)
+* GET  /api/v1/games/{game_id}/playable/{row}/{column} -> Board::preview of the next player's move, 404 if the game doesn't exist
+* GET  /api/v1/games/{game_id}/events/stream -> DataProvider::subscribe_to_game(game_id) as server-sent events
+* PUT  /api/v1/games/{game_id}/players -> DataProvider::register_player(game_id, token)
+* GET  /api/v1/games/{game_id}/analysis?depth=<n> -> Board::analyze(n) of DataProvider::get_board(game_id)
+* GET  /api/v1/games/{game_id}/render -> Board::render_snapshot() of DataProvider::get_board(game_id)
+* GET  /api/v1/games/{game_id}/replay.svg?frame_ms=<n> -> Board::to_replay_svg(n) of DataProvider::get_board(game_id)
+* GET  /api/v1/games/{game_id}/image.png -> Board::to_png() of DataProvider::get_board(game_id), cached until the game changes
+       only routed with the `png` feature
+* GET  /api/v1/me/games              -> DataProvider::get_player_games(token)
+* GET  /api/v1/stats                 -> DataProvider::get_global_stats
+* GET  /api/v1/players/{token}/stats -> DataProvider::get_player_stats(token)
+* POST /api/v1/tournaments           -> Tournament::new(body.participants), DataProvider::store_tournament
+* GET  /api/v1/tournaments/{tournament_id} -> DataProvider::get_tournament(tournament_id)
+* POST /api/v1/tournaments/{tournament_id}/advance -> Tournament::update of DataProvider::get_tournament(tournament_id)
+* POST /api/v1/admin/seed?count=<n>&moves=<m> -> n games with up to m random moves, DataProvider::create_game_from
+       only routed if ENABLE_SEED_ENDPOINT is set, it is meant for load tests
+* DELETE /api/v1/admin/games        -> DataProvider::clear_all, requires `Authorization: Bearer <ADMIN_TOKEN>`
+* GET  /api/v1/info                  -> the version, uptime and ports, DataProvider::kind and DataProvider::ping
+       together with DataProvider::storage_description, e.g. the redis storage mode
+* GET  /api/v1/openapi.json          -> OpenAPI 3 document describing these endpoints
+
+The endpoints are mounted under `API_BASE_PATH`, `/api/v1` by default.
+
+Bodies larger than `API_MAX_BODY_SIZE` bytes are answered with 413 Payload Too Large.
+
+The player token is sent as `Authorization: Bearer <token>`. The data of a private game, see
+`GameSettings::private`, may only be read with the token of one of its players, other clients
+get 401 Unauthorized.
 
 */
 
@@ -38,6 +97,41 @@ pub struct ApiServer<T: DataProvider> {
     pub port: u16,
     pub host: String,
     pub data_provider: T,
+    /// the largest request body that is accepted, in bytes
+    pub max_body_size: usize,
+    /// whether `POST /api/v1/admin/seed` is routed. It creates games in bulk and must
+    /// stay disabled in production.
+    pub enable_seed_endpoint: bool,
+    /// the token the admin endpoints require, they refuse every request without one
+    pub admin_token: Option<String>,
+    /// the path all endpoints are mounted under, `/api/v1` by default
+    pub base_path: String,
+    /// the configured ports of the other servers, reported by `/api/v1/info`
+    pub webserver_port: Option<u16>,
+    pub websocket_port: Option<u16>,
+}
+
+impl<T: DataProvider> ApiServer<T> {
+    pub const DEFAULT_MAX_BODY_SIZE: usize = ApiConfig::DEFAULT_MAX_BODY_SIZE;
+
+    /// the response to a body larger than `max_body_size`, with a JSON error like the
+    /// errors of the data providers
+    fn payload_too_large(max_body_size: usize) -> HttpResponse {
+        HttpResponse::PayloadTooLarge()
+            .json(json!({ "PayloadTooLarge": { "limit": max_body_size } }))
+    }
+
+    /// whether the request announces a body larger than `max_body_size`. Handlers with an
+    /// optional body don't see the errors of the extractors, so the announced length is
+    /// checked before the request is routed.
+    fn announces_large_body(request: &ServiceRequest, max_body_size: usize) -> bool {
+        request
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok())
+            .and_then(|length| length.parse::<usize>().ok())
+            .is_some_and(|length| length > max_body_size)
+    }
 }
 
 impl<T: DataProvider + Default + 'static> Server<T> for ApiServer<T> {
@@ -48,6 +142,12 @@ impl<T: DataProvider + Default + 'static> Server<T> for ApiServer<T> {
             port,
             host,
             data_provider,
+            max_body_size: Self::DEFAULT_MAX_BODY_SIZE,
+            enable_seed_endpoint: false,
+            admin_token: None,
+            base_path: ApiConfig::DEFAULT_BASE_PATH.to_string(),
+            webserver_port: None,
+            websocket_port: None,
         }
     }
 
@@ -56,48 +156,160 @@ impl<T: DataProvider + Default + 'static> Server<T> for ApiServer<T> {
             port: Self::DEFAULT_PORT,
             host: Self::DEFAULT_HOST.to_string(),
             data_provider: T::default(),
+            max_body_size: Self::DEFAULT_MAX_BODY_SIZE,
+            enable_seed_endpoint: false,
+            admin_token: None,
+            base_path: ApiConfig::DEFAULT_BASE_PATH.to_string(),
+            webserver_port: None,
+            websocket_port: None,
         }
     }
 
     fn get_address(&self) -> String {
         format!("{}:{}", self.host, self.port)
     }
-    fn from_env(data_provider: T) -> Self {
-        let port = std::env::var("API_PORT").unwrap_or_else(|_| Self::DEFAULT_PORT.to_string());
-        let host = std::env::var("API_HOST").unwrap_or_else(|_| Self::DEFAULT_HOST.to_string());
-        let port = port.parse::<u16>().unwrap_or(Self::DEFAULT_PORT);
-        Self::new(host, port, data_provider)
+    fn from_config(config: &Config, data_provider: T) -> Self {
+        Self {
+            port: config.api.server.port,
+            host: config.api.server.host.clone(),
+            data_provider,
+            max_body_size: config.api.max_body_size,
+            enable_seed_endpoint: config.api.enable_seed_endpoint,
+            admin_token: config.api.admin_token.clone(),
+            base_path: config.api.base_path.clone(),
+            webserver_port: Some(config.webserver.server.port),
+            websocket_port: Some(config.websocket.server.port),
+        }
     }
     fn with_data_provider(data_provider: T) -> Self {
         Self {
             port: Self::DEFAULT_PORT,
             host: Self::DEFAULT_HOST.to_string(),
             data_provider,
+            max_body_size: Self::DEFAULT_MAX_BODY_SIZE,
+            enable_seed_endpoint: false,
+            admin_token: None,
+            base_path: ApiConfig::DEFAULT_BASE_PATH.to_string(),
+            webserver_port: None,
+            websocket_port: None,
         }
     }
     async fn start(&mut self) -> Result<(), std::io::Error> {
         let api = Arc::new(Mutex::new(self.data_provider.clone()));
-        HttpServer::new(move || {
+        let max_body_size = self.max_body_size;
+        let enable_seed_endpoint = self.enable_seed_endpoint;
+        let base_path = self.base_path.clone();
+        let admin_token = Data::new(AdminToken(self.admin_token.clone()));
+        let runtime = Data::new(ServerRuntime {
+            ports: ServerPorts {
+                webserver: self.webserver_port,
+                api: self.port,
+                websocket: self.websocket_port,
+            },
+            started_at: Instant::now(),
+            base_path: self.base_path.clone(),
+        });
+        #[cfg(feature = "png")]
+        let png_cache = Data::new(PngCache::default());
+        let server = HttpServer::new(move || {
             let api = api.clone();
+            // bodies sent without a length are cut off by the extractors
+            let json_config =
+                JsonConfig::default()
+                    .limit(max_body_size)
+                    .error_handler(move |err, _request| match err {
+                        JsonPayloadError::Overflow { .. }
+                        | JsonPayloadError::OverflowKnownLength { .. } => {
+                            InternalError::from_response(
+                                err,
+                                Self::payload_too_large(max_body_size),
+                            )
+                            .into()
+                        }
+                        err => err.into(),
+                    });
+            // the endpoints are mounted under the configured base path
+            let mut routes = scope(&base_path)
+                .route("/games", get().to(get_games::<T>))
+                .route("/games/featured", get().to(get_featured_game::<T>))
+                .route("/games/results", get().to(get_results::<T>))
+                .route("/games/{game_id}", get().to(get_game::<T>))
+                .route("/games", put().to(create_game::<T>))
+                .route("/games/{game_id}/moves", post().to(add_move::<T>))
+                .route("/games/{game_id}/moves", get().to(get_moves::<T>))
+                .route("/games/{game_id}/moves", delete().to(truncate_game::<T>))
+                .route("/games/{game_id}/moves/last", get().to(get_last_move::<T>))
+                .route(
+                    "/games/{game_id}/playable/{row}/{column}",
+                    get().to(get_playable::<T>),
+                )
+                .route(
+                    "/games/{game_id}/events/stream",
+                    get().to(stream_game_events::<T>),
+                )
+                .route("/games/{game_id}/players", put().to(join_game::<T>))
+                .route("/games/{game_id}/analysis", get().to(get_analysis::<T>))
+                .route("/games/{game_id}/render", get().to(get_render::<T>))
+                .route("/games/{game_id}/replay.svg", get().to(get_replay_svg::<T>))
+                .route("/me/games", get().to(get_player_games::<T>))
+                .route("/stats", get().to(get_global_stats::<T>))
+                .route(
+                    "/players/{player_token}/stats",
+                    get().to(get_player_stats::<T>),
+                )
+                .route("/tournaments", post().to(create_tournament::<T>))
+                .route(
+                    "/tournaments/{tournament_id}",
+                    get().to(get_tournament::<T>),
+                )
+                .route(
+                    "/tournaments/{tournament_id}/advance",
+                    post().to(advance_tournament::<T>),
+                )
+                .route("/admin/games", delete().to(clear_games::<T>))
+                .route("/info", get().to(get_info::<T>))
+                .route("/openapi.json", get().to(get_openapi));
+            #[cfg(feature = "png")]
+            {
+                routes = routes
+                    .app_data(png_cache.clone())
+                    .route("/games/{game_id}/image.png", get().to(get_png::<T>));
+            }
+            if enable_seed_endpoint {
+                routes = routes.route("/admin/seed", post().to(seed_games::<T>));
+            }
             App::new()
                 .app_data(Data::new(api))
-                // .route("/api/v1/games", web::get().to(api.get_games))
-                .route("/api/v1/games", get().to(get_games::<T>))
-                .route("/api/v1/games/{game_id}", get().to(get_game::<T>))
-                .route("/api/v1/games", put().to(create_game::<T>))
-                .route("/api/v1/games/{game_id}/moves", post().to(add_move::<T>))
+                .app_data(runtime.clone())
+                .app_data(admin_token.clone())
+                .app_data(json_config)
+                .app_data(PayloadConfig::new(max_body_size))
+                .wrap_fn(move |request, service| {
+                    if Self::announces_large_body(&request, max_body_size) {
+                        let response =
+                            request.into_response(Self::payload_too_large(max_body_size));
+                        return Either::Left(ready(Ok(response)));
+                    }
+                    Either::Right(service.call(request))
+                })
+                .service(routes)
         })
-        .bind(self.get_address())
-        .unwrap()
-        .run()
-        .await
+        .bind(self.get_address())?
+        .run();
+        server.await
     }
 }
 
 #[cfg(test)]
 mod test {
+    use super::v1::{LastMove, ListedResult, Playability, ServerInfo};
     use super::*;
-    use crate::{CacheProvider, CacheProviderArgs, GameData, Move, Player};
+    use crate::{
+        Analysis, AnnotatedMove, BoardSize, CacheProvider, CacheProviderArgs, Field, GameData,
+        GameResult, GameSettings, GameState, GlobalStats, InvalidMove, Move, Player, PlayerStats,
+        RenderSnapshot, Tournament,
+    };
+    use chrono::Utc;
     use reqwest::{Client, StatusCode};
     use serial_test::serial;
     use std::time::Duration;
@@ -108,8 +320,14 @@ mod test {
         let random_port = rand::random::<u16>();
         ApiServer {
             port: random_port,
-            data_provider: existing_provider.unwrap_or(CacheProvider::default()),
+            data_provider: existing_provider.unwrap_or_default(),
             host: ApiServer::<CacheProvider>::DEFAULT_HOST.to_string(),
+            max_body_size: ApiServer::<CacheProvider>::DEFAULT_MAX_BODY_SIZE,
+            enable_seed_endpoint: false,
+            admin_token: None,
+            base_path: ApiConfig::DEFAULT_BASE_PATH.to_string(),
+            webserver_port: None,
+            websocket_port: None,
         }
     }
 
@@ -134,7 +352,7 @@ mod test {
         //     .is_test(true)
         //     .try_init()
         //     .expect("Failed to init logger");
-        let mut data_provider = CacheProvider::new(CacheProviderArgs {}).unwrap();
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
 
         let mut game_uuids = vec![
             Uuid::new_v4(),
@@ -172,6 +390,69 @@ mod test {
         assert_eq!(remote_uuids, game_uuids);
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn get_games_error() {
+        let data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let hash_map = data_provider.hash_map.clone();
+        // a panic while the games are locked poisons the lock
+        std::thread::spawn(move || {
+            let _games = hash_map.lock().unwrap();
+            panic!("poisoning the lock");
+        })
+        .join()
+        .unwrap_err();
+
+        let mut api = get_cache_api(Some(data_provider));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let response = Client::new()
+            .get(format!("http://{}/api/v1/games", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "\"LockError\"");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn get_games_since() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        data_provider.create_game(None).unwrap();
+        sleep(Duration::from_millis(10)).await;
+        let since = Utc::now();
+        sleep(Duration::from_millis(10)).await;
+        let new_game = data_provider.create_game(None).unwrap();
+
+        let mut api = get_cache_api(Some(data_provider));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let response = client
+            .get(format!("http://{}/api/v1/games", addr))
+            .query(&[("since", since.to_rfc3339())])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            serde_json::from_str::<Vec<Uuid>>(&response.text().await.unwrap()).unwrap(),
+            vec![new_game]
+        );
+
+        let response = client
+            .get(format!("http://{}/api/v1/games?since=yesterday", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
     #[tokio::test]
     #[serial]
     async fn get_game() {
@@ -180,7 +461,7 @@ mod test {
         //     .is_test(true)
         //     .try_init()
         //     .expect("Failed to init logger");
-        let mut data_provider = CacheProvider::new(CacheProviderArgs {}).unwrap();
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
 
         let game_uuid = Uuid::new_v4();
 
@@ -211,6 +492,79 @@ mod test {
         assert_eq!(remote_data, data);
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn get_game_etag() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let game_uuid = data_provider.create_game(None).unwrap();
+        data_provider
+            .add_move(game_uuid, Move::new((0, 0), Player::X))
+            .unwrap();
+
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let url = format!("http://{}/api/v1/games/{}", addr, game_uuid);
+        let client = Client::new();
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers().get("etag").unwrap().clone();
+        assert_eq!(
+            etag,
+            format!(
+                "\"{:016x}\"",
+                data_provider
+                    .get_game_data(game_uuid)
+                    .unwrap()
+                    .version_hash()
+            )
+            .as_str()
+        );
+
+        let response = client
+            .get(&url)
+            .header("if-none-match", etag.clone())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers().get("etag"), Some(&etag));
+        assert!(response.text().await.unwrap().is_empty());
+
+        // a new move changes the ETag
+        data_provider
+            .add_move(game_uuid, Move::new((0, 1), Player::O))
+            .unwrap();
+        let response = client
+            .get(&url)
+            .header("if-none-match", etag.clone())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_ne!(response.headers().get("etag"), Some(&etag));
+
+        // so does abandoning the game, although the moves stay the same
+        let etag = response.headers().get("etag").unwrap().clone();
+        assert!(data_provider.abandon_game(game_uuid).unwrap());
+        let response = client
+            .get(&url)
+            .header("if-none-match", etag.clone())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_ne!(response.headers().get("etag"), Some(&etag));
+        assert!(
+            serde_json::from_str::<GameData>(&response.text().await.unwrap())
+                .unwrap()
+                .result
+                .is_some_and(|result| result.abandoned)
+        );
+    }
+
     #[tokio::test]
     #[serial]
     async fn add_move() {
@@ -220,7 +574,7 @@ mod test {
         //     .try_init()
         //     .expect("Failed to init logger");
 
-        let mut data_provider = CacheProvider::new(CacheProviderArgs {}).unwrap();
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
 
         let game_uuid = Uuid::new_v4();
 
@@ -257,4 +611,1609 @@ mod test {
 
         assert_eq!(remote_data.moves, vec![new_move]);
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn add_move_out_of_bounds() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let game_uuid = data_provider.create_game(None).unwrap();
+
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let response = client
+            .post(format!("http://{}/api/v1/games/{}/moves", addr, game_uuid))
+            .body(serde_json::to_string(&Move::new((9, 0), Player::X)).unwrap())
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            serde_json::from_str::<InvalidMove>(&response.text().await.unwrap()).unwrap(),
+            InvalidMove::OutOfBounds
+        );
+
+        let response = client
+            .put(format!("http://{}/api/v1/games", addr))
+            .body(serde_json::json!({ "first_move": Move::new((4, 12), Player::X) }).to_string())
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(data_provider.get_games().unwrap(), vec![game_uuid]);
+        assert!(data_provider
+            .get_game_data(game_uuid)
+            .unwrap()
+            .moves
+            .is_empty());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn add_move_on_other_board_size() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let mut game_data = GameData::new();
+        game_data.settings = GameSettings {
+            board_size: BoardSize {
+                rows: 1,
+                columns: 4,
+            },
+            ..Default::default()
+        };
+        let game_uuid = data_provider.create_game_from(None, game_data).unwrap();
+
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let response = client
+            .post(format!("http://{}/api/v1/games/{}/moves", addr, game_uuid))
+            .body(serde_json::to_string(&Move::new((3, 0), Player::X)).unwrap())
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = client
+            .post(format!("http://{}/api/v1/games/{}/moves", addr, game_uuid))
+            .body(serde_json::to_string(&Move::new((2, 11), Player::X)).unwrap())
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.text().await.unwrap(), "\"ok\"");
+
+        let response = client
+            .get(format!("http://{}/api/v1/games/{}", addr, game_uuid))
+            .send()
+            .await
+            .unwrap();
+        let remote_data =
+            serde_json::from_str::<GameData>(&response.text().await.unwrap()).unwrap();
+        assert_eq!(remote_data.moves, vec![Move::new((2, 11), Player::X)]);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn add_move_to_ended_game() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let game_uuid = data_provider
+            .create_game_with_move(None, Move::new((4, 4), Player::X))
+            .unwrap();
+        data_provider.abandon_game(game_uuid).unwrap();
+
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        // it is nobody's turn, so neither player is told to wait for the other one
+        let client = Client::new();
+        for player in [Player::X, Player::O] {
+            let response = client
+                .post(format!(
+                    "http://{}/api/v1/games/{}/moves?coord=d4&player={}",
+                    addr, game_uuid, player
+                ))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap(),
+                json!({ "InvalidMove": InvalidMove::GameEnded })
+            );
+        }
+        assert_eq!(data_provider.move_count(game_uuid), Ok(1));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn add_move_without_player() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let game_uuid = data_provider.create_game(None).unwrap();
+
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        for coordinates in [(4, 4), (3, 4)] {
+            let response = client
+                .post(format!("http://{}/api/v1/games/{}/moves", addr, game_uuid))
+                .body(serde_json::json!({ "coordinates": coordinates }).to_string())
+                .header("Content-Type", "application/json")
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.text().await.unwrap(), "\"ok\"");
+        }
+
+        assert_eq!(
+            data_provider.get_game_data(game_uuid).unwrap().moves,
+            vec![Move::new((4, 4), Player::X), Move::new((3, 4), Player::O)]
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn add_move_with_explicit_player() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let game_uuid = data_provider.create_game(None).unwrap();
+
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let response = client
+            .post(format!("http://{}/api/v1/games/{}/moves", addr, game_uuid))
+            .body(serde_json::to_string(&Move::new((4, 4), Player::O)).unwrap())
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<InvalidMove>(&response.text().await.unwrap()).unwrap(),
+            InvalidMove::NotYourTurn
+        );
+        assert!(data_provider
+            .get_game_data(game_uuid)
+            .unwrap()
+            .moves
+            .is_empty());
+
+        let response = client
+            .post(format!("http://{}/api/v1/games/{}/moves", addr, game_uuid))
+            .body(serde_json::to_string(&Move::new((4, 4), Player::X)).unwrap())
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.text().await.unwrap(), "\"ok\"");
+        assert_eq!(
+            data_provider.get_game_data(game_uuid).unwrap().moves,
+            vec![Move::new((4, 4), Player::X)]
+        );
+
+        // moves breaking the rules are refused before they reach the data provider
+        for (coordinates, invalid_move) in [
+            ((4, 4), InvalidMove::FieldOccupied),
+            ((0, 0), InvalidMove::SubBoardNotActive),
+        ] {
+            let response = client
+                .post(format!("http://{}/api/v1/games/{}/moves", addr, game_uuid))
+                .body(serde_json::to_string(&Move::new(coordinates, Player::O)).unwrap())
+                .header("Content-Type", "application/json")
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap(),
+                json!({ "InvalidMove": invalid_move })
+            );
+        }
+        // unless the client is behind, which is a conflict
+        let response = client
+            .post(format!(
+                "http://{}/api/v1/games/{}/moves?move_count=0",
+                addr, game_uuid
+            ))
+            .body(serde_json::to_string(&Move::new((4, 4), Player::O)).unwrap())
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap(),
+            json!("Conflict")
+        );
+        assert_eq!(
+            data_provider.get_game_data(game_uuid).unwrap().moves,
+            vec![Move::new((4, 4), Player::X)]
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn create_game_with_first_move() {
+        let data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let opening = Move::new((4, 4), Player::X);
+        let response = client
+            .put(format!("http://{}/api/v1/games", addr))
+            .body(serde_json::json!({ "first_move": opening }).to_string())
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let game_id = serde_json::from_str::<Uuid>(&response.text().await.unwrap()).unwrap();
+        assert_eq!(
+            data_provider.get_game_data(game_id).unwrap().moves,
+            vec![opening]
+        );
+
+        let response = client
+            .put(format!("http://{}/api/v1/games", addr))
+            .body(serde_json::json!({ "first_move": Move::new((4, 4), Player::O) }).to_string())
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .unwrap();
+        assert!(serde_json::from_str::<Uuid>(&response.text().await.unwrap()).is_err());
+        assert_eq!(data_provider.get_games().unwrap(), vec![game_id]);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn create_game_with_id() {
+        let data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let game_id = Uuid::new_v4();
+        let response = client
+            .put(format!("http://{}/api/v1/games?id={}", addr, game_id))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            serde_json::from_str::<Uuid>(&response.text().await.unwrap()).unwrap(),
+            game_id
+        );
+
+        let other_game_id = Uuid::new_v4();
+        let opening = Move::new((4, 4), Player::X);
+        let response = client
+            .put(format!("http://{}/api/v1/games", addr))
+            .body(serde_json::json!({ "id": other_game_id, "first_move": opening }).to_string())
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Uuid>(&response.text().await.unwrap()).unwrap(),
+            other_game_id
+        );
+        assert_eq!(
+            data_provider.get_game_data(other_game_id).unwrap().moves,
+            vec![opening]
+        );
+
+        // a taken id doesn't replace the existing game
+        let response = client
+            .put(format!("http://{}/api/v1/games", addr))
+            .body(serde_json::json!({ "id": game_id, "first_move": opening }).to_string())
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert!(data_provider
+            .get_game_data(game_id)
+            .unwrap()
+            .moves
+            .is_empty());
+
+        for id in [Uuid::nil().to_string(), "not-a-uuid".to_string()] {
+            let response = client
+                .put(format!("http://{}/api/v1/games?id={}", addr, id))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+        assert_eq!(data_provider.get_games().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn create_game_created_by() {
+        let mut api = get_cache_api(None);
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let get_game = |game_id: Uuid| {
+            let client = client.clone();
+            let addr = addr.clone();
+            async move {
+                let response = client
+                    .get(format!("http://{}/api/v1/games/{}", addr, game_id))
+                    .send()
+                    .await
+                    .unwrap();
+                serde_json::from_str::<GameData>(&response.text().await.unwrap()).unwrap()
+            }
+        };
+
+        let response = client
+            .put(format!("http://{}/api/v1/games", addr))
+            .header("X-Player-Name", "alice")
+            .send()
+            .await
+            .unwrap();
+        let game_id = serde_json::from_str::<Uuid>(&response.text().await.unwrap()).unwrap();
+        assert_eq!(
+            get_game(game_id).await.created_by,
+            Some("alice".to_string())
+        );
+
+        let opening = Move::new((4, 4), Player::X);
+        let response = client
+            .put(format!("http://{}/api/v1/games", addr))
+            .body(serde_json::json!({ "first_move": opening, "created_by": "bob" }).to_string())
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .unwrap();
+        let game_id = serde_json::from_str::<Uuid>(&response.text().await.unwrap()).unwrap();
+        let game_data = get_game(game_id).await;
+        assert_eq!(game_data.created_by, Some("bob".to_string()));
+        assert_eq!(game_data.moves, vec![opening]);
+
+        // the header takes precedence over the body
+        let response = client
+            .put(format!("http://{}/api/v1/games", addr))
+            .body(serde_json::json!({ "created_by": "bob" }).to_string())
+            .header("Content-Type", "application/json")
+            .header("X-Player-Name", "alice")
+            .send()
+            .await
+            .unwrap();
+        let game_id = serde_json::from_str::<Uuid>(&response.text().await.unwrap()).unwrap();
+        assert_eq!(
+            get_game(game_id).await.created_by,
+            Some("alice".to_string())
+        );
+
+        let response = client
+            .put(format!("http://{}/api/v1/games", addr))
+            .send()
+            .await
+            .unwrap();
+        let game_id = serde_json::from_str::<Uuid>(&response.text().await.unwrap()).unwrap();
+        assert_eq!(get_game(game_id).await.created_by, None);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn private_game() {
+        let data_provider = CacheProvider::default();
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        // the opening move is checked against the board size of the settings
+        let response = client
+            .put(format!("http://{}/api/v1/games", addr))
+            .body(
+                serde_json::json!({
+                    "first_move": Move::new((4, 4), Player::X),
+                    "settings": { "private": true, "board_size": { "rows": 1, "columns": 1 } }
+                })
+                .to_string(),
+            )
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = client
+            .put(format!("http://{}/api/v1/games", addr))
+            .body(
+                serde_json::json!({
+                    "first_move": Move::new((4, 4), Player::X),
+                    "settings": { "private": true }
+                })
+                .to_string(),
+            )
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .unwrap();
+        let game_id = serde_json::from_str::<Uuid>(&response.text().await.unwrap()).unwrap();
+        assert!(
+            data_provider
+                .get_game_data(game_id)
+                .unwrap()
+                .settings
+                .private
+        );
+
+        let paths = [
+            "",
+            "/moves",
+            "/moves?annotated=true",
+            "/moves/last",
+            "/playable/3/3",
+            "/events/stream",
+            "/analysis?depth=1",
+            "/render",
+            "/replay.svg",
+            #[cfg(feature = "png")]
+            "/image.png",
+        ];
+        let player_token = Uuid::new_v4();
+        for path in paths {
+            let url = format!("http://{}/api/v1/games/{}{}", addr, game_id, path);
+            let response = client.get(&url).send().await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", path);
+            let response = client
+                .get(&url)
+                .bearer_auth(player_token)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", path);
+        }
+
+        let response = client
+            .put(format!("http://{}/api/v1/games/{}/players", addr, game_id))
+            .bearer_auth(player_token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        for path in paths {
+            let url = format!("http://{}/api/v1/games/{}{}", addr, game_id, path);
+            let response = client
+                .get(&url)
+                .bearer_auth(player_token)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", path);
+        }
+        let response = client
+            .get(format!("http://{}/api/v1/games/{}", addr, game_id))
+            .bearer_auth(player_token)
+            .send()
+            .await
+            .unwrap();
+        let game_data = serde_json::from_str::<GameData>(&response.text().await.unwrap()).unwrap();
+        assert_eq!(game_data.moves, vec![Move::new((4, 4), Player::X)]);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn add_move_with_coordinate() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let game_uuid = data_provider.create_game(None).unwrap();
+
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        for coord in ["e5", "e4"] {
+            let response = client
+                .post(format!(
+                    "http://{}/api/v1/games/{}/moves?coord={}",
+                    addr, game_uuid, coord
+                ))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.text().await.unwrap(), "\"ok\"");
+        }
+
+        let response = client
+            .post(format!(
+                "http://{}/api/v1/games/{}/moves?coord=5e",
+                addr, game_uuid
+            ))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            serde_json::from_str::<InvalidMove>(&response.text().await.unwrap()).unwrap(),
+            InvalidMove::OutOfBounds
+        );
+
+        assert_eq!(
+            data_provider.get_game_data(game_uuid).unwrap().moves,
+            vec![Move::new((4, 4), Player::X), Move::new((3, 4), Player::O)]
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn truncate_game() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let game_uuid = data_provider.create_game(None).unwrap();
+        let player_token = Uuid::new_v4();
+        let moves = [
+            Move::new((0, 0), Player::X),
+            Move::new((1, 1), Player::O),
+            Move::new((3, 3), Player::X),
+        ];
+        for new_move in moves {
+            data_provider.add_move(game_uuid, new_move).unwrap();
+        }
+
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let url = format!("http://{}/api/v1/games/{}/moves?after=1", addr, game_uuid);
+        // only registered players may take back moves
+        let response = client.delete(&url).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = client
+            .delete(&url)
+            .bearer_auth(player_token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        data_provider
+            .register_player(game_uuid, player_token)
+            .unwrap();
+        let response = client
+            .delete(&url)
+            .bearer_auth(player_token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            serde_json::from_str::<Vec<Move>>(&response.text().await.unwrap()).unwrap(),
+            moves[1..]
+        );
+        assert_eq!(
+            data_provider.get_game_data(game_uuid).unwrap().moves,
+            moves[..1]
+        );
+
+        let response = client
+            .delete(format!("http://{}/api/v1/games/{}/moves", addr, game_uuid))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // a game that has ended is final
+        data_provider.abandon_game(game_uuid).unwrap();
+        let response = client
+            .delete(format!(
+                "http://{}/api/v1/games/{}/moves?after=0",
+                addr, game_uuid
+            ))
+            .bearer_auth(player_token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap(),
+            json!({ "InvalidMove": InvalidMove::GameEnded })
+        );
+        assert_eq!(
+            data_provider.get_game_data(game_uuid).unwrap().moves,
+            moves[..1]
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn get_last_move() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let game_uuid = data_provider.create_game(None).unwrap();
+
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let get_last_move = || async {
+            let response = client
+                .get(format!(
+                    "http://{}/api/v1/games/{}/moves/last",
+                    addr, game_uuid
+                ))
+                .send()
+                .await
+                .unwrap();
+            serde_json::from_str::<Option<LastMove>>(&response.text().await.unwrap()).unwrap()
+        };
+        assert_eq!(get_last_move().await, None);
+
+        data_provider
+            .add_move(game_uuid, Move::new((0, 0), Player::X))
+            .unwrap();
+        data_provider
+            .add_move(game_uuid, Move::new((1, 1), Player::O))
+            .unwrap();
+        assert_eq!(
+            get_last_move().await,
+            Some(LastMove {
+                index: 1,
+                last_move: Move::new((1, 1), Player::O)
+            })
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn seed_games() {
+        let data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        let addr = api.get_address();
+        let mut disabled_api = get_cache_api(Some(data_provider.clone()));
+        let disabled_addr = disabled_api.get_address();
+        api.enable_seed_endpoint = true;
+        spawn(async move { api.start().await.unwrap() });
+        spawn(async move { disabled_api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let seed = |addr: &str, query: String| {
+            client
+                .post(format!("http://{}/api/v1/admin/seed?{}", addr, query))
+                .send()
+        };
+        let response = seed(&addr, "count=3&moves=10".to_string()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let game_ids: Vec<Uuid> = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+        assert_eq!(game_ids.len(), 3);
+        for game_id in game_ids {
+            // no game can end within 10 moves
+            let board = data_provider.get_board(game_id).unwrap();
+            assert_eq!(board.moves.len(), 10);
+            assert!(board.get_state().is_in_progress());
+        }
+
+        let too_many = format!("count={}", super::v1::MAX_SEED_COUNT + 1);
+        let response = seed(&addr, too_many).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // the endpoint doesn't exist unless it is enabled
+        let response = seed(&disabled_addr, "count=3".to_string()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(data_provider.get_games().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn clear_games() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        data_provider.create_game(None).unwrap();
+        data_provider.create_game(None).unwrap();
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        api.admin_token = Some("secret".to_string());
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let clear = |token: Option<&'static str>| {
+            let request = client.delete(format!("http://{}/api/v1/admin/games", addr));
+            match token {
+                Some(token) => request.header("Authorization", format!("Bearer {}", token)),
+                None => request,
+            }
+            .send()
+        };
+        for token in [None, Some("wrong")] {
+            let response = clear(token).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        assert_eq!(data_provider.get_games().unwrap().len(), 2);
+
+        let response = clear(Some("secret")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "2");
+        assert_eq!(data_provider.get_games().unwrap(), vec![]);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn get_info() {
+        let mut api = get_cache_api(None);
+        api.websocket_port = Some(4000);
+        let port = api.port;
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let response = Client::new()
+            .get(format!("http://{}/api/v1/info", addr))
+            .send()
+            .await
+            .unwrap();
+        let info: ServerInfo = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            info.ports,
+            ServerPorts {
+                webserver: None,
+                api: port,
+                websocket: Some(4000)
+            }
+        );
+        assert_eq!(info.base_path, "/api/v1");
+        assert_eq!(info.data_provider.kind, "cache");
+        assert_eq!(info.data_provider.storage, None);
+        assert!(info.data_provider.healthy);
+        assert_eq!(info.data_provider.error, None);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn base_path() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let game_uuid = data_provider.create_game(None).unwrap();
+
+        let mut api = get_cache_api(Some(data_provider));
+        api.base_path = "/ttt/api".to_string();
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let response = client
+            .get(format!("http://{}/ttt/api/games", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let games: Vec<Uuid> = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+        assert_eq!(games, vec![game_uuid]);
+
+        // the default base path isn't routed anymore
+        let response = client
+            .get(format!("http://{}/api/v1/games", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = client
+            .get(format!("http://{}/ttt/api/info", addr))
+            .send()
+            .await
+            .unwrap();
+        let info: ServerInfo = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+        assert_eq!(info.base_path, "/ttt/api");
+
+        let response = client
+            .get(format!("http://{}/ttt/api/openapi.json", addr))
+            .send()
+            .await
+            .unwrap();
+        let document: serde_json::Value =
+            serde_json::from_str(&response.text().await.unwrap()).unwrap();
+        assert!(document["paths"]["/ttt/api/games/{game_id}"].is_object());
+        assert!(document["paths"]["/api/v1/games/{game_id}"].is_null());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn get_player_games() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+
+        let own_game = data_provider.create_game(None).unwrap();
+        let other_game = data_provider.create_game(None).unwrap();
+
+        let own_token = Uuid::new_v4();
+        let other_token = Uuid::new_v4();
+
+        let mut api = get_cache_api(Some(data_provider));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        for (game_id, token) in [(own_game, own_token), (other_game, other_token)] {
+            let response = client
+                .put(format!("http://{}/api/v1/games/{}/players", addr, game_id))
+                .bearer_auth(token)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let response = client
+            .get(format!("http://{}/api/v1/me/games", addr))
+            .bearer_auth(own_token)
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let remote_uuids =
+            serde_json::from_str::<Vec<Uuid>>(&response.text().await.unwrap()).unwrap();
+
+        assert_eq!(remote_uuids, vec![own_game]);
+
+        let response = client
+            .get(format!("http://{}/api/v1/me/games", addr))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+    #[tokio::test]
+    #[serial]
+    async fn get_stats() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+        let x_token = Uuid::new_v4();
+        let o_token = Uuid::new_v4();
+        data_provider.register_player(game_id, x_token).unwrap();
+        data_provider.register_player(game_id, o_token).unwrap();
+        for (coordinates, player) in [
+            ((1, 1), Player::X),
+            ((4, 4), Player::O),
+            ((3, 4), Player::X),
+            ((1, 4), Player::O),
+            ((3, 5), Player::X),
+            ((1, 7), Player::O),
+            ((3, 3), Player::X),
+            ((0, 0), Player::O),
+            ((0, 2), Player::X),
+            ((0, 6), Player::O),
+            ((2, 0), Player::X),
+            ((8, 2), Player::O),
+            ((6, 8), Player::X),
+            ((2, 8), Player::O),
+            ((7, 7), Player::X),
+            ((2, 5), Player::O),
+            ((8, 6), Player::X),
+        ] {
+            data_provider
+                .add_move(game_id, Move::new(coordinates, player))
+                .unwrap();
+        }
+
+        let mut api = get_cache_api(Some(data_provider));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let response = client
+            .get(format!("http://{}/api/v1/stats", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            serde_json::from_str::<GlobalStats>(&response.text().await.unwrap()).unwrap(),
+            GlobalStats {
+                x_wins: 1,
+                o_wins: 0,
+                draws: 0
+            }
+        );
+
+        for (token, expected_stats) in [
+            (
+                x_token,
+                PlayerStats {
+                    wins: 1,
+                    ..Default::default()
+                },
+            ),
+            (
+                o_token,
+                PlayerStats {
+                    losses: 1,
+                    ..Default::default()
+                },
+            ),
+            (Uuid::new_v4(), PlayerStats::default()),
+        ] {
+            let response = client
+                .get(format!("http://{}/api/v1/players/{}/stats", addr, token))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(
+                serde_json::from_str::<PlayerStats>(&response.text().await.unwrap()).unwrap(),
+                expected_stats
+            );
+        }
+    }
+    #[tokio::test]
+    #[serial]
+    async fn stream_game_events() {
+        let mut data_provider = CacheProvider::default();
+        let game_uuid = data_provider.create_game(None).unwrap();
+
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let mut response = client
+            .get(format!(
+                "http://{}/api/v1/games/{}/events/stream",
+                addr, game_uuid
+            ))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()["content-type"].to_str().unwrap(),
+            "text/event-stream"
+        );
+
+        let moves = [Move::new((0, 0), Player::X), Move::new((0, 1), Player::O)];
+        let mut buffer = String::new();
+        // the first frame holds the state at subscription time, then one frame per move
+        for expected_move_count in 0..=moves.len() {
+            if expected_move_count > 0 {
+                data_provider
+                    .add_move(game_uuid, moves[expected_move_count - 1])
+                    .unwrap();
+            }
+            while !buffer.contains("\n\n") {
+                let chunk = tokio::time::timeout(Duration::from_secs(1), response.chunk())
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .unwrap();
+                buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+            }
+            let frame_end = buffer.find("\n\n").unwrap();
+            let frame: String = buffer.drain(..frame_end + 2).collect();
+            let game_data =
+                serde_json::from_str::<GameData>(frame.trim().strip_prefix("data: ").unwrap())
+                    .unwrap();
+            assert_eq!(game_data.moves, moves[..expected_move_count]);
+        }
+    }
+    #[tokio::test]
+    #[serial]
+    async fn get_featured_game() {
+        let mut data_provider = CacheProvider::default();
+
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let response = client
+            .get(format!("http://{}/api/v1/games/featured", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let moves = [
+            Move::new((0, 0), Player::X),
+            Move::new((0, 1), Player::O),
+            Move::new((0, 3), Player::X),
+        ];
+        let mut busiest_game = None;
+        for move_count in [1, 0, 3, 2] {
+            let game_id = data_provider.create_game(None).unwrap();
+            for new_move in &moves[..move_count] {
+                data_provider.add_move(game_id, *new_move).unwrap();
+            }
+            if move_count == 3 {
+                busiest_game = Some(game_id);
+            }
+        }
+        // neither is a game that has a result, however busy it is
+        let abandoned_game = data_provider
+            .create_game_from(None, {
+                let mut template = GameData::new();
+                template.moves = moves.to_vec();
+                template.moves.push(Move::new((1, 0), Player::O));
+                template
+            })
+            .unwrap();
+        data_provider.abandon_game(abandoned_game).unwrap();
+        // and games that can't be loaded are skipped
+        let mut broken_game = GameData::new();
+        broken_game.moves = vec![Move::new((0, 0), Player::X); 5];
+        data_provider
+            .hash_map
+            .lock()
+            .unwrap()
+            .insert(broken_game.game_id, broken_game);
+        // a private game is never featured, however busy it is
+        let mut template = GameData::new();
+        template.settings.private = true;
+        template.moves = moves.to_vec();
+        template.moves.push(Move::new((1, 0), Player::O));
+        data_provider.create_game_from(None, template).unwrap();
+
+        let response = client
+            .get(format!("http://{}/api/v1/games/featured", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            serde_json::from_str::<Uuid>(&response.text().await.unwrap()).unwrap(),
+            busiest_game.unwrap()
+        );
+    }
+    #[tokio::test]
+    #[serial]
+    async fn get_results() {
+        let mut data_provider = CacheProvider::default();
+        let finished_game = data_provider.create_game(None).unwrap();
+        for (coordinates, player) in [
+            ((1, 1), Player::X),
+            ((4, 4), Player::O),
+            ((3, 4), Player::X),
+            ((1, 4), Player::O),
+            ((3, 5), Player::X),
+            ((1, 7), Player::O),
+            ((3, 3), Player::X),
+            ((0, 0), Player::O),
+            ((0, 2), Player::X),
+            ((0, 6), Player::O),
+            ((2, 0), Player::X),
+            ((8, 2), Player::O),
+            ((6, 8), Player::X),
+            ((2, 8), Player::O),
+            ((7, 7), Player::X),
+            ((2, 5), Player::O),
+            ((8, 6), Player::X),
+        ] {
+            data_provider
+                .add_move(finished_game, Move::new(coordinates, player))
+                .unwrap();
+        }
+        sleep(Duration::from_millis(2)).await;
+        let running_game = data_provider.create_game(None).unwrap();
+
+        let mut api = get_cache_api(Some(data_provider));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let response = client
+            .get(format!("http://{}/api/v1/games/results", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            serde_json::from_str::<Vec<ListedResult>>(&response.text().await.unwrap()).unwrap(),
+            vec![
+                ListedResult {
+                    game_id: running_game,
+                    result: None
+                },
+                ListedResult {
+                    game_id: finished_game,
+                    result: Some(GameResult {
+                        winner: Some(Player::X),
+                        move_count: 17,
+                        abandoned: false,
+                    })
+                }
+            ]
+        );
+
+        let response = client
+            .get(format!(
+                "http://{}/api/v1/games/results?limit=1&offset=1",
+                addr
+            ))
+            .send()
+            .await
+            .unwrap();
+        let results =
+            serde_json::from_str::<Vec<ListedResult>>(&response.text().await.unwrap()).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].game_id, finished_game);
+
+        let response = client
+            .get(format!("http://{}/api/v1/games/results?limit=1000", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+    #[tokio::test]
+    #[serial]
+    async fn payload_too_large() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        api.max_body_size = 64;
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let padding = " ".repeat(64);
+        for url in [
+            format!("http://{}/api/v1/games/{}/moves", addr, game_id),
+            format!("http://{}/api/v1/tournaments", addr),
+        ] {
+            let response = client
+                .post(url)
+                .header("content-type", "application/json")
+                .body(format!(r#"{{"coordinates":[4,4]{}}}"#, padding))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap(),
+                serde_json::json!({ "PayloadTooLarge": { "limit": 64 } })
+            );
+        }
+        assert!(data_provider
+            .get_game_data(game_id)
+            .unwrap()
+            .moves
+            .is_empty());
+
+        // bodies within the limit are accepted
+        let response = client
+            .post(format!("http://{}/api/v1/games/{}/moves", addr, game_id))
+            .header("content-type", "application/json")
+            .body(r#"{"coordinates":[4,4]}"#)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            data_provider.get_game_data(game_id).unwrap().moves,
+            vec![Move::new((4, 4), Player::X)]
+        );
+    }
+    #[tokio::test]
+    #[serial]
+    async fn get_openapi() {
+        let mut api = get_cache_api(None);
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let response = client
+            .get(format!("http://{}/api/v1/openapi.json", addr))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let document =
+            serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+
+        assert!(document["openapi"].as_str().unwrap().starts_with('3'));
+        assert!(document["paths"]["/api/v1/games"].is_object());
+        for schema in ["GameData", "Move", "GameState", "Player", "Tournament"] {
+            assert!(
+                document["components"]["schemas"][schema].is_object(),
+                "missing schema {}",
+                schema
+            );
+        }
+    }
+    #[tokio::test]
+    #[serial]
+    async fn get_analysis() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let game_uuid = data_provider.create_game(None).unwrap();
+        data_provider
+            .add_move(game_uuid, Move::new((4, 4), Player::X))
+            .unwrap();
+
+        let mut api = get_cache_api(Some(data_provider));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let response = Client::new()
+            .get(format!(
+                "http://{}/api/v1/games/{}/analysis?depth=2",
+                addr, game_uuid
+            ))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let analysis = serde_json::from_str::<Analysis>(&response.text().await.unwrap()).unwrap();
+        assert_eq!(analysis.principal_variation.len(), 2);
+        // X played into the center, so O has to answer in the center sub board
+        let first_move = analysis.principal_variation[0];
+        assert_eq!(first_move.player, Player::O);
+        assert!((3..6).contains(&first_move.coordinates.0));
+        assert!((3..6).contains(&first_move.coordinates.1));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn get_render() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let game_uuid = data_provider.create_game(None).unwrap();
+        data_provider
+            .add_move(game_uuid, Move::new((4, 4), Player::X))
+            .unwrap();
+
+        let mut api = get_cache_api(Some(data_provider));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let response = Client::new()
+            .get(format!("http://{}/api/v1/games/{}/render", addr, game_uuid))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let snapshot =
+            serde_json::from_str::<RenderSnapshot>(&response.text().await.unwrap()).unwrap();
+        assert_eq!(snapshot.fields[4][4], Field::Occupied { player: Player::X });
+        assert_eq!(snapshot.forced_subboard, Some((1, 1)));
+        assert_eq!(snapshot.allowed_moves.len(), 8);
+        assert_eq!(snapshot.next_player, Player::O);
+        assert!(matches!(snapshot.state, GameState::InProgress { .. }));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn get_moves() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let game_uuid = data_provider.create_game(None).unwrap();
+        let moves = [Move::new((4, 4), Player::X), Move::new((3, 3), Player::O)];
+        for new_move in moves {
+            data_provider.add_move(game_uuid, new_move).unwrap();
+        }
+
+        let mut api = get_cache_api(Some(data_provider));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let response = client
+            .get(format!("http://{}/api/v1/games/{}/moves", addr, game_uuid))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            serde_json::from_str::<Vec<Move>>(&response.text().await.unwrap()).unwrap(),
+            moves
+        );
+
+        let response = client
+            .get(format!(
+                "http://{}/api/v1/games/{}/moves?annotated=true",
+                addr, game_uuid
+            ))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let annotated_moves =
+            serde_json::from_str::<Vec<AnnotatedMove>>(&response.text().await.unwrap()).unwrap();
+        assert_eq!(
+            annotated_moves,
+            vec![
+                AnnotatedMove {
+                    move_: moves[0],
+                    subboard: (1, 1),
+                    captured_subboard: None,
+                    sent_to: (1, 1),
+                },
+                AnnotatedMove {
+                    move_: moves[1],
+                    subboard: (1, 1),
+                    captured_subboard: None,
+                    sent_to: (0, 0),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn get_playable() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let game_uuid = data_provider.create_game(None).unwrap();
+        // O is sent to the sub board in the middle
+        data_provider
+            .add_move(game_uuid, Move::new((4, 4), Player::X))
+            .unwrap();
+
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let playable = |row: usize, column: usize| {
+            client
+                .get(format!(
+                    "http://{}/api/v1/games/{}/playable/{}/{}",
+                    addr, game_uuid, row, column
+                ))
+                .send()
+        };
+        let response = playable(3, 3).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            serde_json::from_str::<Playability>(&response.text().await.unwrap()).unwrap(),
+            Playability {
+                playable: true,
+                reason: None,
+                // O's move sends X to the top left sub board
+                forced_subboard: Some((0, 0)),
+                next_player: Some(Player::X),
+            }
+        );
+
+        let response = playable(4, 4).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            serde_json::from_str::<Playability>(&response.text().await.unwrap()).unwrap(),
+            Playability {
+                playable: false,
+                reason: Some(InvalidMove::FieldOccupied.to_string()),
+                forced_subboard: None,
+                next_player: None,
+            }
+        );
+
+        let response = playable(0, 0).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            serde_json::from_str::<Playability>(&response.text().await.unwrap()).unwrap(),
+            Playability {
+                playable: false,
+                reason: Some(InvalidMove::SubBoardNotActive.to_string()),
+                forced_subboard: None,
+                next_player: None,
+            }
+        );
+
+        // nothing was played
+        assert_eq!(
+            data_provider.get_game_data(game_uuid).unwrap().moves.len(),
+            1
+        );
+
+        let response = playable(9, 0).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = client
+            .get(format!(
+                "http://{}/api/v1/games/{}/playable/0/0",
+                addr,
+                Uuid::new_v4()
+            ))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn get_replay_svg() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let game_uuid = data_provider.create_game(None).unwrap();
+        for new_move in [
+            Move::new((4, 4), Player::X),
+            Move::new((3, 3), Player::O),
+            Move::new((0, 0), Player::X),
+        ] {
+            data_provider.add_move(game_uuid, new_move).unwrap();
+        }
+
+        let mut api = get_cache_api(Some(data_provider));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let response = Client::new()
+            .get(format!(
+                "http://{}/api/v1/games/{}/replay.svg?frame_ms=200",
+                addr, game_uuid
+            ))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "image/svg+xml"
+        );
+        let svg = response.text().await.unwrap();
+        // one animation step per move
+        assert_eq!(svg.matches("<set ").count(), 3);
+        assert!(svg.contains(r#"begin="600ms""#));
+
+        let response = Client::new()
+            .get(format!(
+                "http://{}/api/v1/games/{}/replay.svg?frame_ms=0",
+                addr, game_uuid
+            ))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[cfg(feature = "png")]
+    #[tokio::test]
+    #[serial]
+    async fn get_png() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let game_uuid = data_provider.create_game(None).unwrap();
+        data_provider
+            .add_move(game_uuid, Move::new((4, 4), Player::X))
+            .unwrap();
+
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let get_image = || async {
+            let response = Client::new()
+                .get(format!(
+                    "http://{}/api/v1/games/{}/image.png",
+                    addr, game_uuid
+                ))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers().get("content-type").unwrap(), "image/png");
+            response.bytes().await.unwrap()
+        };
+        let png = get_image().await;
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert_eq!(get_image().await, png);
+
+        // the cached image is replaced once the game changes
+        data_provider
+            .add_move(game_uuid, Move::new((3, 3), Player::O))
+            .unwrap();
+        let changed_png = get_image().await;
+        assert!(changed_png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert_ne!(changed_png, png);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn tournaments() {
+        let data_provider = CacheProvider::new(CacheProviderArgs::default()).unwrap();
+        let mut api = get_cache_api(Some(data_provider.clone()));
+        let addr = api.get_address();
+        spawn(async move { api.start().await.unwrap() });
+        sleep(Duration::from_millis(100)).await;
+
+        let client = Client::new();
+        let response = client
+            .post(format!("http://{}/api/v1/tournaments", addr))
+            .body(serde_json::json!({ "participants": ["a", "b"] }).to_string())
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let tournament =
+            serde_json::from_str::<Tournament>(&response.text().await.unwrap()).unwrap();
+        let game_id = tournament.rounds[0][0].game_id.unwrap();
+
+        data_provider
+            .hash_map
+            .lock()
+            .unwrap()
+            .get_mut(&game_id)
+            .unwrap()
+            .result = Some(GameResult {
+            winner: Some(Player::O),
+            move_count: 17,
+            abandoned: false,
+        });
+
+        // viewing the bracket doesn't advance it
+        let url = format!(
+            "http://{}/api/v1/tournaments/{}",
+            addr, tournament.tournament_id
+        );
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let viewed = serde_json::from_str::<Tournament>(&response.text().await.unwrap()).unwrap();
+        assert_eq!(viewed, tournament);
+
+        let response = client
+            .post(format!("{}/advance", url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let tournament =
+            serde_json::from_str::<Tournament>(&response.text().await.unwrap()).unwrap();
+        assert_eq!(tournament.winner(), Some("b"));
+        let response = client.get(&url).send().await.unwrap();
+        let viewed = serde_json::from_str::<Tournament>(&response.text().await.unwrap()).unwrap();
+        assert_eq!(viewed, tournament);
+
+        let response = client
+            .post(format!("http://{}/api/v1/tournaments", addr))
+            .body(serde_json::json!({ "participants": ["a"] }).to_string())
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+    #[tokio::test]
+    async fn start_on_used_port() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let mut api = ApiServer::new("127.0.0.1".to_string(), port, CacheProvider::default());
+        assert!(api.start().await.is_err());
+    }
 }
@@ -0,0 +1,589 @@
+use super::v1::{
+    LastMove, ListedResult, MoveRequest, Playability, ServerInfo, DEFAULT_FRAME_MS,
+    DEFAULT_RESULTS_LIMIT, MAX_FRAME_MS, MAX_RESULTS_LIMIT, MAX_SEED_COUNT,
+};
+use crate::{
+    Analysis, AnnotatedMove, ApiConfig, Board, BoardSize, GameData, GameState, GlobalStats, Move,
+    PlayerStats, RenderSnapshot, Tournament,
+};
+
+use schemars::gen::SchemaSettings;
+use serde_json::{json, Value};
+
+/// builds the OpenAPI 3 document describing the REST API mounted under `base_path`.
+/// The component schemas are derived from the serde types that are sent over the wire.
+pub fn get_openapi_document(base_path: &str) -> Value {
+    // every subschema is registered as a component together with its dependencies
+    let mut generator = SchemaSettings::openapi3().into_generator();
+    generator.subschema_for::<GameData>();
+    generator.subschema_for::<Move>();
+    generator.subschema_for::<AnnotatedMove>();
+    generator.subschema_for::<GameState>();
+    generator.subschema_for::<Tournament>();
+    generator.subschema_for::<Analysis>();
+    generator.subschema_for::<RenderSnapshot>();
+    generator.subschema_for::<MoveRequest>();
+    generator.subschema_for::<ListedResult>();
+    generator.subschema_for::<LastMove>();
+    generator.subschema_for::<Playability>();
+    generator.subschema_for::<GlobalStats>();
+    generator.subschema_for::<PlayerStats>();
+    generator.subschema_for::<ServerInfo>();
+    let schemas = generator.take_definitions();
+
+    let game_id = json!({
+        "name": "game_id",
+        "in": "path",
+        "required": true,
+        "schema": { "type": "string", "format": "uuid" }
+    });
+    let tournament_id = json!({
+        "name": "tournament_id",
+        "in": "path",
+        "required": true,
+        "schema": { "type": "string", "format": "uuid" }
+    });
+    let uuid = json!({ "type": "string", "format": "uuid" });
+    let uuids = json!({ "type": "array", "items": uuid });
+    let bearer = json!([{ "bearer": [] }]);
+    // a private game may only be read with the token of one of its players
+    let viewer = json!([{}, { "bearer": [] }]);
+    let private_game = json!({
+        "description": "the game is private and the token of none of its players was sent"
+    });
+    // the bounds of the largest board, the board of a game may be smaller
+    let max_field_size = BoardSize {
+        rows: BoardSize::MAX,
+        columns: BoardSize::MAX,
+    }
+    .field_size();
+
+    let document = json!({
+        "openapi": "3.0.0",
+        "info": {
+            "title": "Tictactoe Extreme",
+            "version": env!("CARGO_PKG_VERSION")
+        },
+        "paths": {
+            "/api/v1/games": {
+                "get": {
+                    "summary": "list the ids of all games",
+                    "parameters": [{
+                        "name": "since",
+                        "in": "query",
+                        "required": false,
+                        "description": "only list the games created after this time, oldest first",
+                        "schema": { "type": "string", "format": "date-time" }
+                    }],
+                    "responses": { "200": json_response("the game ids", &uuids) }
+                },
+                "put": {
+                    "summary": "create a new game, optionally together with X's opening move",
+                    "parameters": [
+                        {
+                            "name": "X-Player-Name",
+                            "in": "header",
+                            "required": false,
+                            "description": "the name of the player creating the game, takes precedence over created_by",
+                            "schema": { "type": "string" }
+                        },
+                        {
+                            "name": "id",
+                            "in": "query",
+                            "required": false,
+                            "description": "the id of the new game, takes precedence over the id in the body",
+                            "schema": { "type": "string", "format": "uuid" }
+                        }
+                    ],
+                    "requestBody": {
+                        "required": false,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "properties": {
+                                        "first_move": schema_ref("Move"),
+                                        "created_by": { "type": "string" },
+                                        "id": { "type": "string", "format": "uuid" },
+                                        "settings": schema_ref("GameSettings")
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": json_response("the id of the new game", &uuid),
+                        "400": { "description": "the id is not a valid game id or the first move is outside of the board" },
+                        "409": { "description": "a game with the id already exists" }
+                    }
+                }
+            },
+            "/api/v1/games/results": {
+                "get": {
+                    "summary": "list the newest games together with their results",
+                    "parameters": [
+                        {
+                            "name": "limit",
+                            "in": "query",
+                            "required": false,
+                            "description": "how many games are listed at most",
+                            "schema": {
+                                "type": "integer",
+                                "minimum": 0,
+                                "maximum": MAX_RESULTS_LIMIT,
+                                "default": DEFAULT_RESULTS_LIMIT
+                            }
+                        },
+                        {
+                            "name": "offset",
+                            "in": "query",
+                            "required": false,
+                            "description": "how many of the newest games are skipped",
+                            "schema": { "type": "integer", "minimum": 0, "default": 0 }
+                        }
+                    ],
+                    "responses": {
+                        "200": json_response(
+                            "the games, newest first",
+                            &json!({ "type": "array", "items": schema_ref("ListedResult") })
+                        ),
+                        "400": { "description": "`limit` is out of range" }
+                    }
+                }
+            },
+            "/api/v1/games/featured": {
+                "get": {
+                    "summary": "get the in-progress game with the most moves",
+                    "responses": {
+                        "200": json_response("the id of the featured game", &uuid),
+                        "404": { "description": "there is no game in progress" }
+                    }
+                }
+            },
+            "/api/v1/games/{game_id}": {
+                "get": {
+                    "summary": "get the data of a game",
+                    "security": viewer,
+                    "parameters": [
+                        game_id,
+                        {
+                            "name": "If-None-Match",
+                            "in": "header",
+                            "required": false,
+                            "description": "the ETag of the game data the client already has",
+                            "schema": { "type": "string" }
+                        }
+                    ],
+                    "responses": {
+                        "200": json_response("the game data", &schema_ref("GameData")),
+                        "304": { "description": "the game data has not changed" },
+                        "401": private_game
+                    }
+                }
+            },
+            "/api/v1/games/{game_id}/moves": {
+                "get": {
+                    "summary": "list the moves of a game in the order they were made",
+                    "security": viewer,
+                    "parameters": [
+                        game_id,
+                        {
+                            "name": "annotated",
+                            "in": "query",
+                            "required": false,
+                            "description": "annotate every move with the sub board it was made in, whether it captured it and the sub board it sent the next player to",
+                            "schema": { "type": "boolean", "default": false }
+                        }
+                    ],
+                    "responses": {
+                        "200": json_response(
+                            "the moves, or the annotated moves, or an error",
+                            &json!({
+                                "oneOf": [
+                                    { "type": "array", "items": schema_ref("Move") },
+                                    { "type": "array", "items": schema_ref("AnnotatedMove") }
+                                ]
+                            })
+                        ),
+                        "401": private_game
+                    }
+                },
+                "post": {
+                    "summary": "make a move",
+                    "parameters": [
+                        game_id,
+                        {
+                            "name": "move_count",
+                            "in": "query",
+                            "required": false,
+                            "description": "only make the move if the game has this many moves",
+                            "schema": { "type": "integer", "minimum": 0 }
+                        },
+                        {
+                            "name": "coord",
+                            "in": "query",
+                            "required": false,
+                            "description": "the field to play as a human readable coordinate like `e5`, replacing the body",
+                            "schema": { "type": "string", "example": "e5" }
+                        },
+                        {
+                            "name": "player",
+                            "in": "query",
+                            "required": false,
+                            "description": "the player making the move given by `coord`, defaults to the player whose turn it is",
+                            "schema": schema_ref("Player")
+                        }
+                    ],
+                    "requestBody": {
+                        "required": false,
+                        "content": { "application/json": { "schema": schema_ref("MoveRequest") } }
+                    },
+                    "responses": {
+                        "200": json_response("ok or an error", &json!({})),
+                        "400": { "description": "no move was given, or `\"out_of_bounds\"` if `coord` can't be read or the move is outside of the board" }
+                    }
+                },
+                "delete": {
+                    "summary": "take back every move after the first `after` moves of a game that hasn't ended",
+                    "security": [{ "bearer": [] }, { "admin": [] }],
+                    "parameters": [
+                        game_id,
+                        {
+                            "name": "after",
+                            "in": "query",
+                            "required": true,
+                            "description": "the number of moves to keep",
+                            "schema": { "type": "integer", "minimum": 0 }
+                        }
+                    ],
+                    "responses": {
+                        "200": json_response(
+                            "the removed moves or an error",
+                            &json!({ "type": "array", "items": schema_ref("Move") })
+                        ),
+                        "400": { "description": "`after` is missing" },
+                        "401": { "description": "neither the token of a player registered in the game nor the admin token was sent" }
+                    }
+                }
+            },
+            "/api/v1/games/{game_id}/moves/last": {
+                "get": {
+                    "summary": "get the last move of a game together with its index",
+                    "security": viewer,
+                    "parameters": [game_id],
+                    "responses": {
+                        "200": json_response(
+                            "the last move, null if no move was made yet",
+                            &json!({ "oneOf": [schema_ref("LastMove"), { "type": "null" }] })
+                        ),
+                        "401": private_game
+                    }
+                }
+            },
+            "/api/v1/games/{game_id}/playable/{row}/{column}": {
+                "get": {
+                    "summary": "check whether the next player may play a field and where it sends the opponent, without making the move",
+                    "security": viewer,
+                    "parameters": [
+                        game_id,
+                        {
+                            "name": "row",
+                            "in": "path",
+                            "required": true,
+                            "description": "the row of the field, up to the number of rows of fields of the board of the game",
+                            "schema": { "type": "integer", "minimum": 0, "maximum": max_field_size.0 - 1 }
+                        },
+                        {
+                            "name": "column",
+                            "in": "path",
+                            "required": true,
+                            "description": "the column of the field, up to the number of columns of fields of the board of the game",
+                            "schema": { "type": "integer", "minimum": 0, "maximum": max_field_size.1 - 1 }
+                        }
+                    ],
+                    "responses": {
+                        "200": json_response("whether the field is playable, or an error", &schema_ref("Playability")),
+                        "400": { "description": "the field is not on the board" },
+                        "401": private_game,
+                        "404": { "description": "the game does not exist" }
+                    }
+                }
+            },
+            "/api/v1/games/{game_id}/events/stream": {
+                "get": {
+                    "summary": "stream every update of a game as server-sent events",
+                    "security": viewer,
+                    "parameters": [game_id],
+                    "responses": {
+                        "200": {
+                            "description": "one `data:` frame holding the game data per update",
+                            "content": {
+                                "text/event-stream": { "schema": schema_ref("GameData") }
+                            }
+                        },
+                        "401": private_game,
+                        "404": { "description": "the game does not exist" }
+                    }
+                }
+            },
+            "/api/v1/games/{game_id}/players": {
+                "put": {
+                    "summary": "join a game with the player token",
+                    "parameters": [game_id],
+                    "security": bearer,
+                    "responses": {
+                        "200": json_response("ok or an error", &json!({})),
+                        "401": { "description": "no valid player token was sent" }
+                    }
+                }
+            },
+            "/api/v1/games/{game_id}/analysis": {
+                "get": {
+                    "summary": "search the best line of moves from the current position",
+                    "security": viewer,
+                    "parameters": [
+                        game_id,
+                        {
+                            "name": "depth",
+                            "in": "query",
+                            "required": false,
+                            "description": "how many moves deep to search",
+                            "schema": {
+                                "type": "integer",
+                                "minimum": 0,
+                                "maximum": Board::MAX_ANALYSIS_DEPTH
+                            }
+                        }
+                    ],
+                    "responses": {
+                        "200": json_response("the analysis or an error", &schema_ref("Analysis")),
+                        "401": private_game
+                    }
+                }
+            },
+            "/api/v1/games/{game_id}/render": {
+                "get": {
+                    "summary": "get the fields, sub board states and allowed moves needed to draw the board",
+                    "security": viewer,
+                    "parameters": [game_id],
+                    "responses": {
+                        "200": json_response("the render snapshot or an error", &schema_ref("RenderSnapshot")),
+                        "401": private_game
+                    }
+                }
+            },
+            "/api/v1/games/{game_id}/replay.svg": {
+                "get": {
+                    "summary": "download the game as an animated SVG image replaying its moves",
+                    "security": viewer,
+                    "parameters": [
+                        game_id,
+                        {
+                            "name": "frame_ms",
+                            "in": "query",
+                            "required": false,
+                            "description": "how long each move is shown, in milliseconds",
+                            "schema": {
+                                "type": "integer",
+                                "minimum": 1,
+                                "maximum": MAX_FRAME_MS,
+                                "default": DEFAULT_FRAME_MS
+                            }
+                        }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "the animated replay, or an error as JSON",
+                            "content": { "image/svg+xml": { "schema": { "type": "string" } } }
+                        },
+                        "400": { "description": "`frame_ms` is out of range" },
+                        "401": private_game
+                    }
+                }
+            },
+            "/api/v1/me/games": {
+                "get": {
+                    "summary": "list the games the player token has joined",
+                    "security": bearer,
+                    "responses": {
+                        "200": json_response("the game ids", &uuids),
+                        "401": { "description": "no valid player token was sent" }
+                    }
+                }
+            },
+            "/api/v1/stats": {
+                "get": {
+                    "summary": "count the wins of either player and the draws of all finished games",
+                    "responses": {
+                        "200": json_response("the tallies", &schema_ref("GlobalStats"))
+                    }
+                }
+            },
+            "/api/v1/players/{player_token}/stats": {
+                "get": {
+                    "summary": "count the wins, losses and draws of the games a player token played",
+                    "parameters": [{
+                        "name": "player_token",
+                        "in": "path",
+                        "required": true,
+                        "schema": { "type": "string", "format": "uuid" }
+                    }],
+                    "responses": {
+                        "200": json_response("the tallies", &schema_ref("PlayerStats"))
+                    }
+                }
+            },
+            "/api/v1/tournaments": {
+                "post": {
+                    "summary": "create a single-elimination tournament and schedule its first round",
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "required": ["participants"],
+                                    "properties": {
+                                        "participants": {
+                                            "type": "array",
+                                            "items": { "type": "string" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": json_response("the new tournament", &schema_ref("Tournament")),
+                        "400": { "description": "the participants are invalid" }
+                    }
+                }
+            },
+            "/api/v1/tournaments/{tournament_id}": {
+                "get": {
+                    "summary": "get the bracket of a tournament as it was last advanced",
+                    "parameters": [tournament_id],
+                    "responses": {
+                        "200": json_response("the tournament or an error", &schema_ref("Tournament"))
+                    }
+                }
+            },
+            "/api/v1/tournaments/{tournament_id}/advance": {
+                "post": {
+                    "summary": "advance the bracket of a tournament with the finished games and schedule the next games",
+                    "parameters": [tournament_id],
+                    "responses": {
+                        "200": json_response("the advanced tournament or an error", &schema_ref("Tournament"))
+                    }
+                }
+            },
+            "/api/v1/admin/seed": {
+                "post": {
+                    "summary": "create games with random legal moves for load tests, only available if ENABLE_SEED_ENDPOINT is set",
+                    "parameters": [
+                        {
+                            "name": "count",
+                            "in": "query",
+                            "required": true,
+                            "schema": { "type": "integer", "minimum": 0, "maximum": MAX_SEED_COUNT }
+                        },
+                        {
+                            "name": "moves",
+                            "in": "query",
+                            "required": false,
+                            "description": "the number of random moves of each game, fewer if the game ends before",
+                            "schema": { "type": "integer", "minimum": 0, "default": 0 }
+                        }
+                    ],
+                    "responses": {
+                        "200": json_response("the ids of the new games", &uuids),
+                        "400": { "description": "count is too large" },
+                        "404": { "description": "the endpoint is disabled" }
+                    }
+                }
+            },
+            "/api/v1/admin/games": {
+                "delete": {
+                    "summary": "remove every game with its players and subscriptions, statistics and tournaments are kept",
+                    "security": [{ "admin": [] }],
+                    "responses": {
+                        "200": json_response("the number of removed games or an error", &json!({ "type": "integer", "minimum": 0 })),
+                        "401": { "description": "the admin token wasn't sent or no admin token is configured" }
+                    }
+                }
+            },
+            "/api/v1/info": {
+                "get": {
+                    "summary": "get the version, uptime and ports of the servers and the health of their data provider",
+                    "responses": {
+                        "200": json_response("what is running", &schema_ref("ServerInfo"))
+                    }
+                }
+            },
+            "/api/v1/openapi.json": {
+                "get": {
+                    "summary": "this document",
+                    "responses": { "200": json_response("the OpenAPI document", &json!({})) }
+                }
+            }
+        },
+        "components": {
+            "schemas": schemas,
+            "securitySchemes": {
+                "bearer": { "type": "http", "scheme": "bearer" },
+                "admin": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "the token configured as ADMIN_TOKEN"
+                }
+            }
+        }
+    });
+    #[cfg(feature = "png")]
+    let document = {
+        let mut document = document;
+        document["paths"]["/api/v1/games/{game_id}/image.png"] = json!({
+            "get": {
+                "summary": "download the board of the game as a PNG image",
+                "security": viewer,
+                "parameters": [game_id],
+                "responses": {
+                    "200": {
+                        "description": "the image, or an error as JSON",
+                        "content": { "image/png": { "schema": { "type": "string", "format": "binary" } } }
+                    },
+                    "401": private_game
+                }
+            }
+        });
+        document
+    };
+    with_base_path(document, base_path)
+}
+
+/// moves the paths, which are written under the default base path, to `base_path`
+fn with_base_path(mut document: Value, base_path: &str) -> Value {
+    if let Some(paths) = document["paths"].as_object_mut() {
+        *paths = std::mem::take(paths)
+            .into_iter()
+            .map(|(path, item)| {
+                let relative_path = path
+                    .strip_prefix(ApiConfig::DEFAULT_BASE_PATH)
+                    .unwrap_or(&path);
+                (format!("{}{}", base_path, relative_path), item)
+            })
+            .collect();
+    }
+    document
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn json_response(description: &str, schema: &Value) -> Value {
+    json!({
+        "description": description,
+        "content": { "application/json": { "schema": schema } }
+    })
+}
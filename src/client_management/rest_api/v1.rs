@@ -1,21 +1,88 @@
-use crate::{DataProvider, Move};
+use super::openapi::get_openapi_document;
+use crate::{
+    parse_algebraic, Board, Coordinates, DataProvider, GameData, GameResult, GameSettings,
+    InvalidMove, Move, Player, Tournament,
+};
 
 use actix_web::{
-    web::{Data, Json, Path},
-    HttpRequest, Responder,
+    http::header::{AUTHORIZATION, ETAG, IF_NONE_MATCH},
+    web::{block, Bytes, Data, Json, Path, Query},
+    HttpRequest, HttpResponse,
 };
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use serde_json::to_string;
-use std::sync::{Arc, Mutex};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio_stream::wrappers::WatchStream;
 use uuid::Uuid;
 
+#[derive(Deserialize)]
+pub struct SinceSelector {
+    /// only list the games created after this time
+    since: Option<DateTime<Utc>>,
+}
+
 pub async fn get_games<T: DataProvider>(
     _request: HttpRequest,
+    query: Query<SinceSelector>,
+    games: Data<Arc<Mutex<T>>>,
+) -> HttpResponse {
+    let games = games.lock().unwrap();
+    let games = match query.since {
+        Some(since) => games.list_games_since(since),
+        None => games.get_games(),
+    };
+    match games {
+        Ok(games) => HttpResponse::Ok().body(to_string(&games).unwrap()),
+        Err(err) => HttpResponse::Ok().body(to_string(&err).unwrap()),
+    }
+}
+
+pub const DEFAULT_RESULTS_LIMIT: usize = 20;
+pub const MAX_RESULTS_LIMIT: usize = 100;
+
+#[derive(Deserialize)]
+pub struct ResultsSelector {
+    /// how many games are listed at most
+    limit: Option<usize>,
+    /// how many of the newest games are skipped
+    offset: Option<usize>,
+}
+
+/// a game listed in the archive together with its result
+#[derive(Serialize, Deserialize, JsonSchema, Debug, PartialEq, Eq)]
+pub struct ListedResult {
+    pub game_id: Uuid,
+    /// None while the game is in progress
+    pub result: Option<GameResult>,
+}
+
+/// lists the newest games with their results, a page at a time
+pub async fn get_results<T: DataProvider>(
+    query: Query<ResultsSelector>,
     games: Data<Arc<Mutex<T>>>,
-) -> impl Responder {
+) -> HttpResponse {
+    let limit = query.limit.unwrap_or(DEFAULT_RESULTS_LIMIT);
+    if limit > MAX_RESULTS_LIMIT {
+        return HttpResponse::BadRequest()
+            .body(format!("limit must be at most {}", MAX_RESULTS_LIMIT));
+    }
     let games = games.lock().unwrap();
-    let games = games.get_games().unwrap();
-    serde_json::to_string(&games).unwrap()
+    match games.list_results(limit, query.offset.unwrap_or_default()) {
+        Ok(results) => {
+            let results: Vec<ListedResult> = results
+                .into_iter()
+                .map(|(game_id, result)| ListedResult { game_id, result })
+                .collect();
+            HttpResponse::Ok().body(to_string(&results).unwrap())
+        }
+        Err(err) => HttpResponse::Ok().body(to_string(&err).unwrap()),
+    }
 }
 
 #[derive(Deserialize)]
@@ -23,39 +90,840 @@ pub struct GameSelector {
     game_id: Uuid,
 }
 
+/// returns the game data with its version hash as ETag. If the client already has
+/// that version (`If-None-Match`), only `304 Not Modified` is returned.
 pub async fn get_game<T: DataProvider>(
     path: Path<GameSelector>,
-    _request: HttpRequest,
+    request: HttpRequest,
     games: Data<Arc<Mutex<T>>>,
-) -> impl Responder {
+) -> HttpResponse {
     let games = games.lock().unwrap();
-    match games.get_game_data(path.game_id) {
-        Ok(game_data) => to_string(&game_data).unwrap(),
-        Err(err) => to_string(&err).unwrap(),
+    if let Err(response) = authorize_viewer(&*games, path.game_id, &request) {
+        return response;
     }
+    let game_data = match games.get_game_data(path.game_id) {
+        Ok(game_data) => game_data,
+        Err(err) => return HttpResponse::Ok().body(to_string(&err).unwrap()),
+    };
+    let etag = format!("\"{:016x}\"", game_data.version_hash());
+    let not_modified = request
+        .headers()
+        .get(IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+        });
+    if not_modified {
+        return HttpResponse::NotModified()
+            .insert_header((ETAG, etag))
+            .finish();
+    }
+    HttpResponse::Ok()
+        .insert_header((ETAG, etag))
+        .body(to_string(&game_data).unwrap())
+}
+
+#[derive(Deserialize)]
+pub struct CreateGameRequest {
+    /// X's opening move, made together with the creation of the game
+    first_move: Option<Move>,
+    /// the name of the player creating the game, unless it is sent as `X-Player-Name`
+    created_by: Option<String>,
+    /// the id of the new game, unless it is given as `?id=`
+    id: Option<Uuid>,
+    /// the settings the game is played with, e.g. to make it private or to play on
+    /// another board size
+    settings: Option<GameSettings>,
+}
+
+/// a client chosen id for a new game, e.g. for links generated before the game exists
+#[derive(Deserialize)]
+pub struct CreateGameSelector {
+    id: Option<Uuid>,
 }
 
+/// the header naming the player who creates a game
+pub const PLAYER_NAME_HEADER: &str = "X-Player-Name";
+
+/// creates a game with a server generated id, or with the id the client asked for.
+/// An id that is already taken is answered with 409 Conflict.
 pub async fn create_game<T: DataProvider>(
-    _request: HttpRequest,
+    request: HttpRequest,
+    query: Query<CreateGameSelector>,
     games: Data<Arc<Mutex<T>>>,
-) -> impl Responder {
+    body: Option<Json<CreateGameRequest>>,
+) -> HttpResponse {
     let mut games = games.lock().unwrap();
-    match games.create_game(None) {
-        Ok(game_id) => to_string(&game_id).unwrap(),
-        Err(err) => to_string(&err).unwrap(),
+    let (first_move, body_created_by, body_id, settings) = match body {
+        Some(body) => {
+            let body = body.into_inner();
+            (
+                body.first_move,
+                body.created_by,
+                body.id,
+                body.settings.unwrap_or_default(),
+            )
+        }
+        None => (None, None, None, GameSettings::default()),
+    };
+    // the opening move is checked here, a move outside of the board is a malformed request
+    if let Some(Err(invalid_move)) = first_move.map(|first_move| {
+        Move::try_new(
+            first_move.coordinates,
+            first_move.player,
+            settings.board_size.field_size(),
+        )
+    }) {
+        return HttpResponse::BadRequest().json(invalid_move);
+    }
+    let game_id = query.id.or(body_id);
+    if game_id.is_some_and(|game_id| game_id.is_nil()) {
+        return HttpResponse::BadRequest().body("the nil uuid can't be the id of a game");
+    }
+    if let Some(game_id) = game_id {
+        match games.game_exists(game_id) {
+            Ok(false) => {}
+            Ok(true) => return HttpResponse::Conflict().finish(),
+            Err(err) => return HttpResponse::Ok().body(to_string(&err).unwrap()),
+        }
+    }
+    let created_by = request
+        .headers()
+        .get(PLAYER_NAME_HEADER)
+        .and_then(|name| name.to_str().ok())
+        .map(str::to_string)
+        .or(body_created_by)
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
+    let result = if created_by.is_some() || settings != GameSettings::default() {
+        let mut template = GameData::new();
+        template.created_by = created_by;
+        template.settings = settings;
+        template.moves.extend(first_move);
+        games.create_game_from(game_id, template)
+    } else if let Some(first_move) = first_move {
+        games.create_game_with_move(game_id, first_move)
+    } else {
+        games.create_game(game_id)
+    };
+    match result {
+        Ok(game_id) => HttpResponse::Ok().body(to_string(&game_id).unwrap()),
+        Err(err) => HttpResponse::Ok().body(to_string(&err).unwrap()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct MoveCountSelector {
+    /// the number of moves the client expects the game to have before its move
+    move_count: Option<usize>,
+}
+
+/// a move given as a human readable coordinate like `?coord=e5`, which is easier
+/// to type by hand than a json body
+#[derive(Deserialize)]
+pub struct CoordinateSelector {
+    coord: Option<String>,
+    /// defaults to the player whose turn it is
+    player: Option<Player>,
+}
+
+/// the json body of a move. Unlike `Move` the player may be left out, the server
+/// knows whose turn it is.
+#[derive(Deserialize, JsonSchema)]
+pub struct MoveRequest {
+    pub coordinates: Coordinates,
+    /// defaults to the player whose turn it is
+    pub player: Option<Player>,
+}
+
+/// returns the player whose turn it is if none is given, and rejects a given player
+/// whose turn it isn't. In a game that has a result it is nobody's turn.
+fn resolve_player<T: DataProvider>(
+    games: &T,
+    game_id: Uuid,
+    player: Option<Player>,
+) -> Result<(Board, Player), HttpResponse> {
+    let game_data = match games.get_game_data(game_id) {
+        Ok(game_data) => game_data,
+        Err(err) => return Err(HttpResponse::Ok().body(to_string(&err).unwrap())),
+    };
+    let invalid_move_response = |invalid_move: InvalidMove| {
+        HttpResponse::Ok().body(to_string(&T::ErrorKind::from(invalid_move)).unwrap())
+    };
+    if game_data.result.is_some() {
+        return Err(invalid_move_response(InvalidMove::GameEnded));
+    }
+    let board = Board::try_from_game_data(game_data).map_err(invalid_move_response)?;
+    let next_player = board.expected_player_for_next_move();
+    match player {
+        Some(player) if player != next_player => {
+            Err(HttpResponse::Ok().body(to_string(&InvalidMove::NotYourTurn).unwrap()))
+        }
+        _ => Ok((board, next_player)),
     }
 }
 
 pub async fn add_move<T: DataProvider>(
     _request: HttpRequest,
     path: Path<GameSelector>,
+    query: Query<MoveCountSelector>,
+    coordinate: Query<CoordinateSelector>,
+    games: Data<Arc<Mutex<T>>>,
+    body: Option<Json<MoveRequest>>,
+) -> HttpResponse {
+    let mut games = games.lock().unwrap();
+    let (coordinates, player) = match (&coordinate.coord, body) {
+        // a coordinate that can't be read names no field of the board, it is refused
+        // like a move outside of the board
+        (Some(coord), _) => match parse_algebraic(coord) {
+            Ok(coordinates) => (coordinates, coordinate.player),
+            Err(_) => return HttpResponse::BadRequest().json(InvalidMove::OutOfBounds),
+        },
+        (None, Some(body)) => (body.coordinates, body.player),
+        (None, None) => {
+            return HttpResponse::BadRequest().body("expected a json move or a coord parameter")
+        }
+    };
+    let (board, player) = match resolve_player(&*games, path.game_id, player) {
+        Ok(resolved) => resolved,
+        Err(response) => return response,
+    };
+    let new_move = match Move::try_new(coordinates, player, board.get_field_size()) {
+        Ok(new_move) => new_move,
+        Err(invalid_move) => return HttpResponse::BadRequest().json(invalid_move),
+    };
+    // the move is checked against the rules of the game. If the game has changed since
+    // the move count the client expects, the data provider refuses it as a conflict.
+    if query
+        .move_count
+        .is_none_or(|expected_count| expected_count == board.moves.len())
+    {
+        if let Err(invalid_move) = board.validate_move(new_move) {
+            let err = T::ErrorKind::from(invalid_move);
+            return HttpResponse::Ok().body(to_string(&err).unwrap());
+        }
+    }
+    let result = match query.move_count {
+        Some(expected_count) => games.add_move_if_count(path.game_id, expected_count, new_move),
+        None => games.add_move(path.game_id, new_move),
+    };
+    match result {
+        Err(err) => HttpResponse::Ok().body(to_string(&err).unwrap()),
+        Ok(_) => HttpResponse::Ok().body(to_string(&"ok").unwrap()),
+    }
+}
+
+/// the token of `ADMIN_TOKEN`, which the admin endpoints require as
+/// `Authorization: Bearer <token>`
+pub struct AdminToken(pub Option<String>);
+
+impl AdminToken {
+    /// whether the request carries the admin token. If no token is configured, no
+    /// request does.
+    fn authorizes(&self, request: &HttpRequest) -> bool {
+        let Some(admin_token) = &self.0 else {
+            return false;
+        };
+        request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|header| header.to_str().ok())
+            .and_then(|header| header.strip_prefix("Bearer "))
+            .is_some_and(|token| token.trim() == admin_token)
+    }
+}
+
+/// removes every game and returns how many were removed, see `DataProvider::clear_all`
+pub async fn clear_games<T: DataProvider>(
+    request: HttpRequest,
+    admin_token: Data<AdminToken>,
+    games: Data<Arc<Mutex<T>>>,
+) -> HttpResponse {
+    if !admin_token.authorizes(&request) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    match games.lock().unwrap().clear_all() {
+        Ok(removed) => HttpResponse::Ok().json(removed),
+        Err(err) => HttpResponse::Ok().body(to_string(&err).unwrap()),
+    }
+}
+
+/// the most games a single seed request may create
+pub const MAX_SEED_COUNT: usize = 1000;
+
+#[derive(Deserialize)]
+pub struct SeedSelector {
+    count: usize,
+    /// the number of random moves of each game
+    #[serde(default)]
+    moves: usize,
+}
+
+/// creates games with random legal moves for load tests and returns their ids. A game
+/// that ends before it has `moves` moves is stored as it ended. Only routed if
+/// `ENABLE_SEED_ENDPOINT` is set.
+pub async fn seed_games<T: DataProvider>(
+    query: Query<SeedSelector>,
+    games: Data<Arc<Mutex<T>>>,
+) -> HttpResponse {
+    if query.count > MAX_SEED_COUNT {
+        return HttpResponse::BadRequest()
+            .body(format!("count must be at most {}", MAX_SEED_COUNT));
+    }
+    let mut games = games.lock().unwrap();
+    let mut rng = rand::thread_rng();
+    let mut game_ids = Vec::with_capacity(query.count);
+    for _ in 0..query.count {
+        let mut board = Board::new();
+        board.play_random_moves(query.moves, &mut rng);
+        match games.create_game_from(None, board.into()) {
+            Ok(game_id) => game_ids.push(game_id),
+            Err(err) => return HttpResponse::Ok().body(to_string(&err).unwrap()),
+        }
+    }
+    HttpResponse::Ok().json(game_ids)
+}
+
+/// the configured port of every server. Only the port of the api server is known if
+/// it wasn't created from the configuration.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerPorts {
+    pub webserver: Option<u16>,
+    pub api: u16,
+    pub websocket: Option<u16>,
+}
+
+/// the facts about the running api server that `get_info` reports
+pub struct ServerRuntime {
+    pub ports: ServerPorts,
+    pub started_at: Instant,
+    pub base_path: String,
+}
+
+/// the data provider the servers share
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+pub struct DataProviderInfo {
+    /// e.g. `cache` or `redis`
+    pub kind: String,
+    /// how the games are stored, e.g. `RedisJSON documents`, if the storage can store
+    /// them in more than one way and could be reached
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage: Option<String>,
+    /// whether the storage could be reached
+    pub healthy: bool,
+    /// why the storage couldn't be reached
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// what is running, see `GET /api/v1/info`
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+pub struct ServerInfo {
+    pub version: String,
+    /// the seconds since the api server was started
+    pub uptime_seconds: u64,
+    pub ports: ServerPorts,
+    /// the path the endpoints are mounted under, e.g. `/api/v1`
+    pub base_path: String,
+    /// the websocket and static servers use the same data provider, so its health is
+    /// theirs as well
+    pub data_provider: DataProviderInfo,
+}
+
+pub async fn get_info<T: DataProvider>(
+    games: Data<Arc<Mutex<T>>>,
+    runtime: Data<ServerRuntime>,
+) -> HttpResponse {
+    let games = games.lock().unwrap();
+    let ping = games.ping();
+    let info = ServerInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        uptime_seconds: runtime.started_at.elapsed().as_secs(),
+        ports: runtime.ports,
+        base_path: runtime.base_path.clone(),
+        data_provider: DataProviderInfo {
+            kind: games.kind().to_string(),
+            storage: games.storage_description(),
+            healthy: ping.is_ok(),
+            error: ping.err().map(|e| e.to_string()),
+        },
+    };
+    HttpResponse::Ok().json(info)
+}
+
+#[derive(Deserialize)]
+pub struct MovesSelector {
+    /// annotate every move with the sub boards it was made in and sent to
+    #[serde(default)]
+    annotated: bool,
+}
+
+/// returns the moves of the game in the order they were made, with `?annotated=true`
+/// as `AnnotatedMove`s
+pub async fn get_moves<T: DataProvider>(
+    request: HttpRequest,
+    path: Path<GameSelector>,
+    query: Query<MovesSelector>,
+    games: Data<Arc<Mutex<T>>>,
+) -> HttpResponse {
+    let games = games.lock().unwrap();
+    if let Err(response) = authorize_viewer(&*games, path.game_id, &request) {
+        return response;
+    }
+    let body = if query.annotated {
+        match games.get_board(path.game_id) {
+            Ok(board) => to_string(&board.annotated_moves()).unwrap(),
+            Err(err) => to_string(&err).unwrap(),
+        }
+    } else {
+        match games.get_game_data(path.game_id) {
+            Ok(game_data) => to_string(&game_data.moves).unwrap(),
+            Err(err) => to_string(&err).unwrap(),
+        }
+    };
+    HttpResponse::Ok().body(body)
+}
+
+#[derive(Deserialize)]
+pub struct CellSelector {
+    game_id: Uuid,
+    row: usize,
+    column: usize,
+}
+
+/// whether the next player may play a field, see `get_playable`
+#[derive(Serialize, Deserialize, JsonSchema, Debug, PartialEq, Eq)]
+pub struct Playability {
+    pub playable: bool,
+    /// why the field can't be played, if it can't
+    pub reason: Option<String>,
+    /// the sub board the opponent is sent to by playing the field, if the field can be
+    /// played and the opponent is sent to a single sub board
+    pub forced_subboard: Option<Coordinates>,
+    /// the player whose turn it is after playing the field, if it can be played
+    pub next_player: Option<Player>,
+}
+
+/// checks whether the next player may play the field right now, without making the move
+pub async fn get_playable<T: DataProvider>(
+    request: HttpRequest,
+    path: Path<CellSelector>,
     games: Data<Arc<Mutex<T>>>,
-    body: Json<Move>,
-) -> impl Responder {
+) -> HttpResponse {
     let mut games = games.lock().unwrap();
-    let new_move = body.into_inner();
-    match games.add_move(path.game_id, new_move) {
+    if !games.game_exists(path.game_id).unwrap_or(false) {
+        return HttpResponse::NotFound().finish();
+    }
+    if let Err(response) = authorize_viewer(&*games, path.game_id, &request) {
+        return response;
+    }
+    let board = match games.get_board(path.game_id) {
+        Ok(board) => board,
+        Err(err) => return HttpResponse::Ok().body(to_string(&err).unwrap()),
+    };
+    let coordinates = (path.row, path.column);
+    if !board.in_bounds(coordinates) {
+        return HttpResponse::BadRequest().json(InvalidMove::OutOfBounds);
+    }
+    let preview = board.preview(Move::new(coordinates, board.get_next_player()));
+    HttpResponse::Ok().json(match preview {
+        Ok(preview) => Playability {
+            playable: true,
+            reason: None,
+            forced_subboard: preview.forced_subboard(),
+            next_player: Some(preview.get_next_player()),
+        },
+        Err(reason) => Playability {
+            playable: false,
+            reason: Some(reason.to_string()),
+            forced_subboard: None,
+            next_player: None,
+        },
+    })
+}
+
+/// the last move of a game together with its index, see `DataProvider::get_last_move`
+#[derive(Serialize, Deserialize, JsonSchema, Debug, PartialEq, Eq)]
+pub struct LastMove {
+    /// the number of moves made before this one
+    pub index: usize,
+    #[serde(rename = "move")]
+    pub last_move: Move,
+}
+
+/// returns the last move of the game, or null if no move was made yet
+pub async fn get_last_move<T: DataProvider>(
+    request: HttpRequest,
+    path: Path<GameSelector>,
+    games: Data<Arc<Mutex<T>>>,
+) -> HttpResponse {
+    let games = games.lock().unwrap();
+    if let Err(response) = authorize_viewer(&*games, path.game_id, &request) {
+        return response;
+    }
+    let body = match games.get_last_move(path.game_id) {
+        Ok(last_move) => {
+            to_string(&last_move.map(|(index, last_move)| LastMove { index, last_move })).unwrap()
+        }
         Err(err) => to_string(&err).unwrap(),
-        Ok(_) => to_string(&"ok").unwrap(),
+    };
+    HttpResponse::Ok().body(body)
+}
+
+#[derive(Deserialize)]
+pub struct TruncateSelector {
+    /// the number of moves to keep
+    after: usize,
+}
+
+/// takes back every move after the first `after` moves and returns the removed moves.
+/// Only the players registered in the game and the admin may do so, and games that have
+/// ended can't be taken back.
+pub async fn truncate_game<T: DataProvider>(
+    request: HttpRequest,
+    path: Path<GameSelector>,
+    query: Query<TruncateSelector>,
+    admin_token: Data<AdminToken>,
+    games: Data<Arc<Mutex<T>>>,
+) -> HttpResponse {
+    let mut games = games.lock().unwrap();
+    let is_player = || {
+        get_player_token(&request).is_some_and(|player_token| {
+            games
+                .get_player_games(player_token)
+                .is_ok_and(|game_ids| game_ids.contains(&path.game_id))
+        })
+    };
+    if !admin_token.authorizes(&request) && !is_player() {
+        return HttpResponse::Unauthorized().finish();
+    }
+    match games.truncate_game(path.game_id, query.after) {
+        Ok(removed_moves) => HttpResponse::Ok().body(to_string(&removed_moves).unwrap()),
+        Err(err) => HttpResponse::Ok().body(to_string(&err).unwrap()),
+    }
+}
+
+/// reads the player token from the `Authorization: Bearer <token>` header
+fn get_player_token(request: &HttpRequest) -> Option<Uuid> {
+    request
+        .headers()
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .and_then(|token| Uuid::parse_str(token.trim()).ok())
+}
+
+/// answers with 401 Unauthorized unless the client may view the game, see
+/// `DataProvider::may_view`. Players of a private game send their token as
+/// `Authorization: Bearer <token>`.
+fn authorize_viewer<T: DataProvider>(
+    games: &T,
+    game_id: Uuid,
+    request: &HttpRequest,
+) -> Result<(), HttpResponse> {
+    match games.may_view(game_id, get_player_token(request)) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(HttpResponse::Unauthorized().finish()),
+        Err(err) => Err(HttpResponse::Ok().body(to_string(&err).unwrap())),
+    }
+}
+
+pub async fn join_game<T: DataProvider>(
+    request: HttpRequest,
+    path: Path<GameSelector>,
+    games: Data<Arc<Mutex<T>>>,
+) -> HttpResponse {
+    let Some(player_token) = get_player_token(&request) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let mut games = games.lock().unwrap();
+    match games.register_player(path.game_id, player_token) {
+        Err(err) => HttpResponse::Ok().body(to_string(&err).unwrap()),
+        Ok(_) => HttpResponse::Ok().body(to_string(&"ok").unwrap()),
+    }
+}
+
+pub async fn get_player_games<T: DataProvider>(
+    request: HttpRequest,
+    games: Data<Arc<Mutex<T>>>,
+) -> HttpResponse {
+    let Some(player_token) = get_player_token(&request) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let games = games.lock().unwrap();
+    match games.get_player_games(player_token) {
+        Ok(game_ids) => HttpResponse::Ok().body(to_string(&game_ids).unwrap()),
+        Err(err) => HttpResponse::Ok().body(to_string(&err).unwrap()),
+    }
+}
+
+pub async fn get_global_stats<T: DataProvider>(games: Data<Arc<Mutex<T>>>) -> HttpResponse {
+    let games = games.lock().unwrap();
+    match games.get_global_stats() {
+        Ok(stats) => HttpResponse::Ok().body(to_string(&stats).unwrap()),
+        Err(err) => HttpResponse::Ok().body(to_string(&err).unwrap()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PlayerSelector {
+    player_token: Uuid,
+}
+
+pub async fn get_player_stats<T: DataProvider>(
+    path: Path<PlayerSelector>,
+    games: Data<Arc<Mutex<T>>>,
+) -> HttpResponse {
+    let games = games.lock().unwrap();
+    match games.get_player_stats(path.player_token) {
+        Ok(stats) => HttpResponse::Ok().body(to_string(&stats).unwrap()),
+        Err(err) => HttpResponse::Ok().body(to_string(&err).unwrap()),
+    }
+}
+
+/// streams every update of a game as server-sent events.
+/// The subscription is dropped together with the stream once the client disconnects.
+pub async fn stream_game_events<T: DataProvider>(
+    request: HttpRequest,
+    path: Path<GameSelector>,
+    games: Data<Arc<Mutex<T>>>,
+) -> HttpResponse {
+    let rx = {
+        let mut games = games.lock().unwrap();
+        if let Err(response) = authorize_viewer(&*games, path.game_id, &request) {
+            return response;
+        }
+        match games.subscribe_to_game(path.game_id) {
+            Ok(rx) => rx,
+            Err(err) => return HttpResponse::NotFound().body(to_string(&err).unwrap()),
+        }
+    };
+    let events = WatchStream::new(rx).map(|game_data| {
+        Ok::<_, actix_web::Error>(Bytes::from(format!(
+            "data: {}\n\n",
+            to_string(&game_data).unwrap()
+        )))
+    });
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .streaming(events)
+}
+
+pub async fn get_featured_game<T: DataProvider>(games: Data<Arc<Mutex<T>>>) -> HttpResponse {
+    let games = games.lock().unwrap();
+    match games.get_featured_game() {
+        Ok(Some(game_id)) => HttpResponse::Ok().body(to_string(&game_id).unwrap()),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(err) => HttpResponse::Ok().body(to_string(&err).unwrap()),
+    }
+}
+
+pub async fn get_openapi(runtime: Data<ServerRuntime>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .body(get_openapi_document(&runtime.base_path).to_string())
+}
+
+#[derive(Deserialize)]
+pub struct TournamentRequest {
+    participants: Vec<String>,
+}
+
+pub async fn create_tournament<T: DataProvider>(
+    games: Data<Arc<Mutex<T>>>,
+    body: Json<TournamentRequest>,
+) -> HttpResponse {
+    let mut tournament = match Tournament::new(body.into_inner().participants) {
+        Ok(tournament) => tournament,
+        Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+    };
+    let mut games = games.lock().unwrap();
+    // schedules the games of the first round
+    let result = tournament
+        .update(&mut *games)
+        .and_then(|_| games.store_tournament(&tournament));
+    match result {
+        Ok(_) => HttpResponse::Ok().body(to_string(&tournament).unwrap()),
+        Err(err) => HttpResponse::Ok().body(to_string(&err).unwrap()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct TournamentSelector {
+    tournament_id: Uuid,
+}
+
+/// returns the bracket of a tournament as it was last advanced, see `advance_tournament`
+pub async fn get_tournament<T: DataProvider>(
+    path: Path<TournamentSelector>,
+    games: Data<Arc<Mutex<T>>>,
+) -> HttpResponse {
+    match games.lock().unwrap().get_tournament(path.tournament_id) {
+        Ok(tournament) => HttpResponse::Ok().body(to_string(&tournament).unwrap()),
+        Err(err) => HttpResponse::Ok().body(to_string(&err).unwrap()),
+    }
+}
+
+/// picks up the results of finished games and schedules the next games of the
+/// tournament, see `Tournament::update`, and returns the advanced bracket
+pub async fn advance_tournament<T: DataProvider>(
+    path: Path<TournamentSelector>,
+    games: Data<Arc<Mutex<T>>>,
+) -> HttpResponse {
+    let mut games = games.lock().unwrap();
+    let mut tournament = match games.get_tournament(path.tournament_id) {
+        Ok(tournament) => tournament,
+        Err(err) => return HttpResponse::Ok().body(to_string(&err).unwrap()),
+    };
+    let result = tournament.update(&mut *games).and_then(|changed| {
+        if changed {
+            games.store_tournament(&tournament)
+        } else {
+            Ok(())
+        }
+    });
+    match result {
+        Ok(_) => HttpResponse::Ok().body(to_string(&tournament).unwrap()),
+        Err(err) => HttpResponse::Ok().body(to_string(&err).unwrap()),
+    }
+}
+
+/// returns the board of the game if the client may view it, see `authorize_viewer`
+fn load_viewable_board<T: DataProvider>(
+    games: &Mutex<T>,
+    game_id: Uuid,
+    request: &HttpRequest,
+) -> Result<Board, HttpResponse> {
+    let games = games.lock().unwrap();
+    authorize_viewer(&*games, game_id, request)?;
+    games
+        .get_board(game_id)
+        .map_err(|err| HttpResponse::Ok().body(to_string(&err).unwrap()))
+}
+
+#[derive(Deserialize)]
+pub struct AnalysisSelector {
+    /// how many moves deep to search, capped at `Board::MAX_ANALYSIS_DEPTH`
+    depth: Option<usize>,
+}
+
+/// searches the best line of moves from the current position of the game
+pub async fn get_analysis<T: DataProvider>(
+    request: HttpRequest,
+    path: Path<GameSelector>,
+    query: Query<AnalysisSelector>,
+    games: Data<Arc<Mutex<T>>>,
+) -> HttpResponse {
+    let board = match load_viewable_board(&games, path.game_id, &request) {
+        Ok(board) => board,
+        Err(response) => return response,
+    };
+    let depth = query.depth.unwrap_or(Board::MAX_ANALYSIS_DEPTH);
+    // the search is cpu bound and must not block the workers handling other requests
+    match block(move || board.analyze(depth)).await {
+        Ok(analysis) => HttpResponse::Ok().body(to_string(&analysis).unwrap()),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// returns everything a client needs to draw the board of the game in one response
+pub async fn get_render<T: DataProvider>(
+    request: HttpRequest,
+    path: Path<GameSelector>,
+    games: Data<Arc<Mutex<T>>>,
+) -> HttpResponse {
+    match load_viewable_board(&games, path.game_id, &request) {
+        Ok(board) => HttpResponse::Ok().body(to_string(&board.render_snapshot()).unwrap()),
+        Err(response) => response,
+    }
+}
+
+pub const DEFAULT_FRAME_MS: u64 = 500;
+pub const MAX_FRAME_MS: u64 = 60_000;
+
+#[derive(Deserialize)]
+pub struct ReplaySelector {
+    /// how long each move is shown before the next one is made, in milliseconds
+    frame_ms: Option<u64>,
+}
+
+/// renders the game as an animated SVG image that replays its moves
+pub async fn get_replay_svg<T: DataProvider>(
+    request: HttpRequest,
+    path: Path<GameSelector>,
+    query: Query<ReplaySelector>,
+    games: Data<Arc<Mutex<T>>>,
+) -> HttpResponse {
+    let frame_ms = query.frame_ms.unwrap_or(DEFAULT_FRAME_MS);
+    if frame_ms == 0 || frame_ms > MAX_FRAME_MS {
+        return HttpResponse::BadRequest()
+            .body(format!("frame_ms must be between 1 and {}", MAX_FRAME_MS));
     }
+    let board = match load_viewable_board(&games, path.game_id, &request) {
+        Ok(board) => board,
+        Err(response) => return response,
+    };
+    HttpResponse::Ok()
+        .content_type("image/svg+xml")
+        .body(board.to_replay_svg(Duration::from_millis(frame_ms)))
+}
+
+/// the last PNG image rendered of every game, together with the version hash of the
+/// game data it shows. An image is rendered again once the game data has changed.
+#[cfg(feature = "png")]
+#[derive(Default)]
+pub struct PngCache(Mutex<std::collections::HashMap<Uuid, (u64, Bytes)>>);
+
+/// renders the board of the game as a PNG image, e.g. for chat integrations that can't
+/// show SVG images
+#[cfg(feature = "png")]
+pub async fn get_png<T: DataProvider>(
+    request: HttpRequest,
+    path: Path<GameSelector>,
+    games: Data<Arc<Mutex<T>>>,
+    cache: Data<PngCache>,
+) -> HttpResponse {
+    let game_data = {
+        let games = games.lock().unwrap();
+        if let Err(response) = authorize_viewer(&*games, path.game_id, &request) {
+            return response;
+        }
+        match games.get_game_data(path.game_id) {
+            Ok(game_data) => game_data,
+            Err(err) => return HttpResponse::Ok().body(to_string(&err).unwrap()),
+        }
+    };
+    let version_hash = game_data.version_hash();
+    let cached = cache
+        .0
+        .lock()
+        .unwrap()
+        .get(&path.game_id)
+        .filter(|(hash, _)| *hash == version_hash)
+        .map(|(_, png)| png.clone());
+    let png = match cached {
+        Some(png) => png,
+        None => {
+            let board = match Board::try_from_game_data(game_data) {
+                Ok(board) => board,
+                Err(err) => return HttpResponse::Ok().body(to_string(&err).unwrap()),
+            };
+            // rasterizing is cpu bound and must not block the workers handling other requests
+            let png = match block(move || board.to_png()).await {
+                Ok(png) => Bytes::from(png),
+                Err(_) => return HttpResponse::InternalServerError().finish(),
+            };
+            cache
+                .0
+                .lock()
+                .unwrap()
+                .insert(path.game_id, (version_hash, png.clone()));
+            png
+        }
+    };
+    HttpResponse::Ok().content_type("image/png").body(png)
 }
@@ -1,21 +1,65 @@
-use crate::{DataProvider, Server};
-use actix_files::Files;
-use actix_web::{App, HttpServer};
+use crate::{Config, DataProvider, Server, WebServerConfig};
+use actix_files::{Files, NamedFile};
+use actix_web::{
+    dev::{fn_service, Service, ServiceRequest, ServiceResponse},
+    http::{
+        header::{HeaderValue, CACHE_CONTROL, CONTENT_TYPE},
+        StatusCode,
+    },
+    App, HttpServer,
+};
 use log::debug;
-use std::marker::PhantomData;
+use std::{marker::PhantomData, time::Duration};
 
 pub struct StaticServer<T> {
     pub port: u16,
     pub host: String,
+    /// how long browsers may cache the assets without asking again
+    pub cache_max_age: Duration,
     phantom: PhantomData<T>,
 }
 
+/// the directory the files are served from
+const ROOT: &str = "./static";
+const INDEX_FILE: &str = "index.html";
+
+/// the `Cache-Control` header of a response. Assets are cached for `max_age`, while
+/// html pages are revalidated on every request, so a new deployment is picked up
+/// right away. The revalidation is cheap thanks to the ETag and Last-Modified headers.
+fn cache_control(response: &ServiceResponse, max_age: Duration) -> Option<HeaderValue> {
+    let status = response.status();
+    if !status.is_success() && status != StatusCode::NOT_MODIFIED {
+        return None;
+    }
+    let is_html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("text/html"));
+    let value = if is_html {
+        "no-cache".to_string()
+    } else {
+        format!("public, max-age={}", max_age.as_secs())
+    };
+    HeaderValue::from_str(&value).ok()
+}
+
+/// answers requests for unknown paths with the index file, so the routes of the
+/// single page app can be loaded directly
+async fn index_fallback(request: ServiceRequest) -> Result<ServiceResponse, actix_web::Error> {
+    let (request, _) = request.into_parts();
+    let index = NamedFile::open_async(format!("{}/{}", ROOT, INDEX_FILE)).await?;
+    let response = index.into_response(&request);
+    Ok(ServiceResponse::new(request, response))
+}
+
 impl<T: DataProvider + Default> Server<T> for StaticServer<T> {
     type ErrorKind = std::io::Error;
     fn default() -> Self {
         Self {
             port: Self::DEFAULT_PORT,
             host: Self::DEFAULT_HOST.to_string(),
+            cache_max_age: WebServerConfig::DEFAULT_CACHE_MAX_AGE,
             phantom: PhantomData,
         }
     }
@@ -24,6 +68,7 @@ impl<T: DataProvider + Default> Server<T> for StaticServer<T> {
         Self {
             port,
             host,
+            cache_max_age: WebServerConfig::DEFAULT_CACHE_MAX_AGE,
             phantom: PhantomData,
         }
     }
@@ -36,28 +81,51 @@ impl<T: DataProvider + Default> Server<T> for StaticServer<T> {
         Self::default()
     }
 
-    fn from_env(_data_provider: T) -> Self {
-        debug!("env: {:?}", std::env::vars());
-        let port =
-            std::env::var("WEBSERVER_PORT").unwrap_or_else(|_| Self::DEFAULT_PORT.to_string());
-        let host =
-            std::env::var("WEBSERVER_HOST").unwrap_or_else(|_| Self::DEFAULT_HOST.to_string());
-        let port = port.parse::<u16>().unwrap_or(Self::DEFAULT_PORT);
-        Self::new(host, port, T::default())
+    fn from_config(config: &Config, _data_provider: T) -> Self {
+        Self {
+            cache_max_age: config.webserver.cache_max_age,
+            ..Self::new(
+                config.webserver.server.host.clone(),
+                config.webserver.server.port,
+                T::default(),
+            )
+        }
     }
     async fn start(&mut self) -> Result<(), Self::ErrorKind> {
         debug!("Starting static server on {}", self.get_address());
-        HttpServer::new(|| App::new().service(Files::new("/", "./static").index_file("index.html")))
-            .bind(self.get_address())
-            .unwrap()
-            .run()
-            .await
+        let cache_max_age = self.cache_max_age;
+        let server = HttpServer::new(move || {
+            App::new()
+                .wrap_fn(move |request, service| {
+                    let response = service.call(request);
+                    async move {
+                        let mut response = response.await?;
+                        if let Some(cache_control) = cache_control(&response, cache_max_age) {
+                            response.headers_mut().insert(CACHE_CONTROL, cache_control);
+                        }
+                        Ok(response)
+                    }
+                })
+                .service(
+                    Files::new("/", ROOT)
+                        .index_file(INDEX_FILE)
+                        .use_etag(true)
+                        .use_last_modified(true)
+                        .default_handler(fn_service(index_fallback)),
+                )
+        })
+        .bind(self.get_address())?
+        .run();
+        server.await
     }
 }
 
 #[cfg(test)]
 mod test {
-    use reqwest::{Client, StatusCode};
+    use reqwest::{
+        header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH, LAST_MODIFIED},
+        Client, StatusCode,
+    };
 
     use tokio::{
         fs::write,
@@ -101,4 +169,55 @@ mod test {
         // delete the file
         tokio::fs::remove_file("./static/test.txt").await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_cache_headers() {
+        let random_port = rand::random::<u16>();
+        let mut server = StaticServer::<CacheProvider>::new(
+            "127.0.0.1".to_string(),
+            random_port,
+            CacheProvider::default(),
+        );
+        server.cache_max_age = Duration::from_secs(600);
+        let address = server.get_address();
+        write("./static/cache-test.css", "body {}").await.unwrap();
+        spawn(async move {
+            server.start().await.unwrap();
+        });
+        sleep(Duration::from_secs(1)).await;
+
+        let client = Client::new();
+        let url = format!("http://{}/cache-test.css", address);
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CACHE_CONTROL], "public, max-age=600");
+        assert!(response.headers().contains_key(LAST_MODIFIED));
+        let etag = response.headers()[ETAG].clone();
+
+        // the second request only revalidates the asset
+        let response = client
+            .get(&url)
+            .header(IF_NONE_MATCH, etag)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[CACHE_CONTROL], "public, max-age=600");
+        tokio::fs::remove_file("./static/cache-test.css")
+            .await
+            .unwrap();
+
+        // unknown paths are routes of the single page app
+        let index = tokio::fs::read_to_string("./static/index.html")
+            .await
+            .unwrap();
+        let response = client
+            .get(format!("http://{}/games/some-game", address))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CACHE_CONTROL], "no-cache");
+        assert_eq!(response.text().await.unwrap(), index);
+    }
 }
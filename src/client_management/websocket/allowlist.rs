@@ -0,0 +1,150 @@
+use std::{fmt::Display, net::IpAddr, str::FromStr};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AllowlistError {
+    InvalidAddress(String),
+    InvalidPrefixLength(String),
+}
+
+impl Display for AllowlistError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidAddress(address) => write!(f, "invalid ip address: {}", address),
+            Self::InvalidPrefixLength(prefix) => write!(f, "invalid prefix length: {}", prefix),
+        }
+    }
+}
+
+/// a network in CIDR notation, e.g. `10.0.0.0/8` or `::1/128`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Network {
+    pub address: IpAddr,
+    pub prefix_length: u8,
+}
+
+impl Network {
+    /// Returns true if the address lies within the network. IPv4-mapped IPv6
+    /// addresses are treated like their IPv4 counterpart.
+    pub fn contains(&self, address: IpAddr) -> bool {
+        match (self.address.to_canonical(), address.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => Self::matches_prefix(
+                u32::from(network).into(),
+                u32::from(address).into(),
+                self.prefix_length,
+                32,
+            ),
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                Self::matches_prefix(network.into(), address.into(), self.prefix_length, 128)
+            }
+            _ => false,
+        }
+    }
+
+    fn matches_prefix(network: u128, address: u128, prefix_length: u8, bits: u32) -> bool {
+        let host_bits = bits - u32::from(prefix_length);
+        network.checked_shr(host_bits).unwrap_or(0) == address.checked_shr(host_bits).unwrap_or(0)
+    }
+}
+
+impl FromStr for Network {
+    type Err = AllowlistError;
+
+    /// parses `<address>/<prefix length>`. A plain address is a network of that single address
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, prefix_length) = match s.split_once('/') {
+            Some((address, prefix_length)) => (address, Some(prefix_length)),
+            None => (s, None),
+        };
+        let address: IpAddr = address
+            .parse()
+            .map_err(|_| AllowlistError::InvalidAddress(address.to_string()))?;
+        let max_prefix_length = if address.is_ipv4() { 32 } else { 128 };
+        let prefix_length = match prefix_length {
+            Some(prefix_length) => prefix_length
+                .parse::<u8>()
+                .ok()
+                .filter(|length| *length <= max_prefix_length)
+                .ok_or_else(|| AllowlistError::InvalidPrefixLength(prefix_length.to_string()))?,
+            None => max_prefix_length,
+        };
+        Ok(Self {
+            address,
+            prefix_length,
+        })
+    }
+}
+
+/// the networks clients are allowed to connect from
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Allowlist {
+    pub networks: Vec<Network>,
+}
+
+impl Allowlist {
+    /// Parses a comma separated list of networks, e.g. `127.0.0.1/32,10.0.0.0/8`
+    pub fn parse(s: &str) -> Result<Self, AllowlistError> {
+        let networks = s
+            .split(',')
+            .map(str::trim)
+            .filter(|network| !network.is_empty())
+            .map(Network::from_str)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { networks })
+    }
+
+    pub fn allows(&self, address: IpAddr) -> bool {
+        self.networks
+            .iter()
+            .any(|network| network.contains(address))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_allowlist() {
+        let allowlist = Allowlist::parse("127.0.0.1, 10.0.0.0/8,::1/128").unwrap();
+        assert_eq!(
+            allowlist.networks,
+            vec![
+                Network {
+                    address: "127.0.0.1".parse().unwrap(),
+                    prefix_length: 32
+                },
+                Network {
+                    address: "10.0.0.0".parse().unwrap(),
+                    prefix_length: 8
+                },
+                Network {
+                    address: "::1".parse().unwrap(),
+                    prefix_length: 128
+                },
+            ]
+        );
+
+        assert_eq!(
+            Allowlist::parse("localhost"),
+            Err(AllowlistError::InvalidAddress("localhost".to_string()))
+        );
+        assert_eq!(
+            Allowlist::parse("10.0.0.0/33"),
+            Err(AllowlistError::InvalidPrefixLength("33".to_string()))
+        );
+    }
+
+    #[test]
+    fn allows() {
+        let allowlist = Allowlist::parse("10.0.0.0/8,fd00::/8,0.0.0.0/0").unwrap();
+        assert!(allowlist.allows("10.1.2.3".parse().unwrap()));
+        assert!(allowlist.allows("::ffff:10.1.2.3".parse().unwrap()));
+        assert!(allowlist.allows("fd12::1".parse().unwrap()));
+        assert!(allowlist.allows("192.168.0.1".parse().unwrap()));
+        assert!(!allowlist.allows("fe80::1".parse().unwrap()));
+
+        let allowlist = Allowlist::parse("10.0.0.0/8").unwrap();
+        assert!(!allowlist.allows("11.0.0.0".parse().unwrap()));
+        assert!(!Allowlist::default().allows("127.0.0.1".parse().unwrap()));
+    }
+}
@@ -0,0 +1,312 @@
+use std::fmt::Display;
+
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+use super::stream_handler::{Error, IncommingMessage, OutgoingMessage};
+
+/// the game a client asks to be connected to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestedGame {
+    /// `/<game_uuid>`
+    Id(Uuid),
+    /// `/featured`: whatever game is the busiest at connection time
+    Featured,
+}
+
+/// what a client receives on updates, chosen by the path it connects to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subscription {
+    /// `/<game_uuid>`: the full board after every update
+    GameState,
+    /// `/<game_uuid>/moves`: every single move as it is made
+    Moves,
+}
+
+/// how outgoing messages are encoded, chosen by the `format` query parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MessageFormat {
+    /// `?format=json`: one json text frame per message
+    #[default]
+    Json,
+}
+
+impl MessageFormat {
+    pub fn encode(&self, message: &OutgoingMessage) -> Result<Message, Error> {
+        match self {
+            MessageFormat::Json => serde_json::to_string(message)
+                .map(Message::Text)
+                .map_err(|e| Error::CouldNotSerialize(e.to_string())),
+        }
+    }
+
+    /// parses a text or binary frame of the client. Json clients send text frames, so
+    /// their binary frames are rejected.
+    pub fn decode(&self, message: Message) -> Result<IncommingMessage, Error> {
+        match (self, message) {
+            (MessageFormat::Json, Message::Text(text)) => {
+                serde_json::from_str(&text).map_err(|e| Error::InvalidMessage(e.to_string()))
+            }
+            (MessageFormat::Json, _) => Err(Error::UnexpectedBinary),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionRequestError {
+    MissingLeadingSlash,
+    MissingGame,
+    InvalidGame(String),
+    UnknownRole(String),
+    TooManySegments,
+    UnknownParameter(String),
+    UnknownFormat(String),
+    InvalidWait(String),
+    InvalidLinger(String),
+    InvalidHave(String),
+}
+
+impl Display for ConnectionRequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingLeadingSlash => write!(f, "the path must start with a slash"),
+            Self::MissingGame => write!(f, "no game uuid provided"),
+            Self::InvalidGame(game) => write!(f, "invalid game uuid: {}", game),
+            Self::UnknownRole(role) => write!(f, "unknown role: {}", role),
+            Self::TooManySegments => write!(f, "the path has too many segments"),
+            Self::UnknownParameter(parameter) => {
+                write!(f, "unknown query parameter: {}", parameter)
+            }
+            Self::UnknownFormat(format) => write!(f, "unknown format: {}", format),
+            Self::InvalidWait(wait) => write!(f, "wait must be true or false, not {}", wait),
+            Self::InvalidLinger(linger) => {
+                write!(f, "linger must be true or false, not {}", linger)
+            }
+            Self::InvalidHave(have) => write!(f, "have must be a number of moves, not {}", have),
+        }
+    }
+}
+
+/// the parsed request path of a websocket connection:
+/// `/<game_uuid|featured>[/<role>][?format=<format>][&wait=<bool>][&linger=<bool>][&have=<n>]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionRequest {
+    pub game_id: RequestedGame,
+    pub role: Subscription,
+    pub format: MessageFormat,
+    /// `?wait=true`: keep the connection open until the game is created instead of
+    /// rejecting it if the game doesn't exist yet
+    pub wait: bool,
+    /// `?linger=true`: keep the connection open after the game has ended instead
+    /// of closing it after the final update
+    pub linger: bool,
+    /// `?have=<n>`: the client already has the first `n` moves, e.g. when reconnecting,
+    /// so it is only sent the moves after them instead of the full board
+    pub have: Option<usize>,
+}
+
+impl ConnectionRequest {
+    pub fn parse(request: &str) -> Result<Self, ConnectionRequestError> {
+        let (path, query) = request.split_once('?').unwrap_or((request, ""));
+
+        let path = path
+            .strip_prefix('/')
+            .ok_or(ConnectionRequestError::MissingLeadingSlash)?;
+        // a single trailing slash is tolerated
+        let path = path.strip_suffix('/').unwrap_or(path);
+        let mut segments = path.split('/');
+
+        let game_id = match segments.next() {
+            None | Some("") => return Err(ConnectionRequestError::MissingGame),
+            Some("featured") => RequestedGame::Featured,
+            Some(game) => RequestedGame::Id(
+                Uuid::parse_str(game)
+                    .map_err(|_| ConnectionRequestError::InvalidGame(game.to_string()))?,
+            ),
+        };
+
+        let role = match segments.next() {
+            None => Subscription::GameState,
+            Some("moves") => Subscription::Moves,
+            Some(role) => return Err(ConnectionRequestError::UnknownRole(role.to_string())),
+        };
+
+        if segments.next().is_some() {
+            return Err(ConnectionRequestError::TooManySegments);
+        }
+
+        let mut format = MessageFormat::default();
+        let mut wait = false;
+        let mut linger = false;
+        let mut have = None;
+        for parameter in query.split('&').filter(|parameter| !parameter.is_empty()) {
+            match parameter.split_once('=') {
+                Some(("format", "json")) => format = MessageFormat::Json,
+                Some(("format", value)) => {
+                    return Err(ConnectionRequestError::UnknownFormat(value.to_string()))
+                }
+                Some(("wait", value)) => {
+                    wait = value
+                        .parse()
+                        .map_err(|_| ConnectionRequestError::InvalidWait(value.to_string()))?
+                }
+                Some(("linger", value)) => {
+                    linger = value
+                        .parse()
+                        .map_err(|_| ConnectionRequestError::InvalidLinger(value.to_string()))?
+                }
+                Some(("have", value)) => {
+                    have = Some(
+                        value
+                            .parse()
+                            .map_err(|_| ConnectionRequestError::InvalidHave(value.to_string()))?,
+                    )
+                }
+                _ => {
+                    return Err(ConnectionRequestError::UnknownParameter(
+                        parameter.to_string(),
+                    ))
+                }
+            }
+        }
+
+        Ok(Self {
+            game_id,
+            role,
+            format,
+            wait,
+            linger,
+            have,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_valid_requests() {
+        let game_id = Uuid::new_v4();
+        let tests = [
+            (
+                format!("/{}", game_id),
+                RequestedGame::Id(game_id),
+                Subscription::GameState,
+            ),
+            (
+                format!("/{}/", game_id),
+                RequestedGame::Id(game_id),
+                Subscription::GameState,
+            ),
+            (
+                format!("/{}/moves", game_id),
+                RequestedGame::Id(game_id),
+                Subscription::Moves,
+            ),
+            (
+                format!("/{}/moves?format=json", game_id),
+                RequestedGame::Id(game_id),
+                Subscription::Moves,
+            ),
+            (
+                "/featured".to_string(),
+                RequestedGame::Featured,
+                Subscription::GameState,
+            ),
+            (
+                "/featured/moves?".to_string(),
+                RequestedGame::Featured,
+                Subscription::Moves,
+            ),
+        ];
+
+        for (request, expected_game, expected_role) in tests {
+            assert_eq!(
+                ConnectionRequest::parse(&request),
+                Ok(ConnectionRequest {
+                    game_id: expected_game,
+                    role: expected_role,
+                    format: MessageFormat::Json,
+                    wait: false,
+                    linger: false,
+                    have: None,
+                }),
+                "failed to parse {}",
+                request
+            );
+        }
+
+        assert_eq!(
+            ConnectionRequest::parse(&format!(
+                "/{}?wait=true&format=json&linger=true&have=3",
+                game_id
+            )),
+            Ok(ConnectionRequest {
+                game_id: RequestedGame::Id(game_id),
+                role: Subscription::GameState,
+                format: MessageFormat::Json,
+                wait: true,
+                linger: true,
+                have: Some(3),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_invalid_requests() {
+        let game_id = Uuid::new_v4();
+        let tests = [
+            (
+                game_id.to_string(),
+                ConnectionRequestError::MissingLeadingSlash,
+            ),
+            ("/".to_string(), ConnectionRequestError::MissingGame),
+            (
+                "/not-a-uuid".to_string(),
+                ConnectionRequestError::InvalidGame("not-a-uuid".to_string()),
+            ),
+            (
+                format!("/{}/spectators", game_id),
+                ConnectionRequestError::UnknownRole("spectators".to_string()),
+            ),
+            (
+                format!("/{}/moves/extra", game_id),
+                ConnectionRequestError::TooManySegments,
+            ),
+            (
+                format!("/{}//moves", game_id),
+                ConnectionRequestError::UnknownRole("".to_string()),
+            ),
+            (
+                format!("/{}?format=xml", game_id),
+                ConnectionRequestError::UnknownFormat("xml".to_string()),
+            ),
+            (
+                format!("/{}?wait=yes", game_id),
+                ConnectionRequestError::InvalidWait("yes".to_string()),
+            ),
+            (
+                format!("/{}?linger=1", game_id),
+                ConnectionRequestError::InvalidLinger("1".to_string()),
+            ),
+            (
+                format!("/{}?have=-1", game_id),
+                ConnectionRequestError::InvalidHave("-1".to_string()),
+            ),
+            (
+                format!("/{}?token=abc", game_id),
+                ConnectionRequestError::UnknownParameter("token=abc".to_string()),
+            ),
+        ];
+
+        for (request, expected_error) in tests {
+            assert_eq!(
+                ConnectionRequest::parse(&request),
+                Err(expected_error),
+                "expected {} to be rejected",
+                request
+            );
+        }
+    }
+}
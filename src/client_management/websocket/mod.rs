@@ -1,4 +1,10 @@
+mod allowlist;
+mod connection_request;
 mod server;
 mod stream_handler;
-pub use server::WebSocketServer;
-pub use stream_handler::StreamHandler;
+pub use allowlist::{Allowlist, AllowlistError, Network};
+pub use connection_request::{
+    ConnectionRequest, ConnectionRequestError, MessageFormat, RequestedGame, Subscription,
+};
+pub use server::{ConnectionSlot, WebSocketServer};
+pub use stream_handler::{IncommingMessage, OutgoingMessage, StreamHandler};
@@ -1,6 +1,26 @@
-use crate::{websocket::StreamHandler, DataProvider, Server};
-use log::{debug, error};
-use tokio::{net::TcpListener, spawn};
+use crate::{
+    websocket::{Allowlist, StreamHandler},
+    Config, DataProvider, Server, WebSocketConfig,
+};
+use log::{debug, error, info};
+use std::{
+    borrow::Cow,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    spawn,
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time::timeout,
+};
+use tokio_tungstenite::{
+    accept_async,
+    tungstenite::protocol::{frame::coding::CloseCode, CloseFrame},
+};
 
 #[derive(Debug)]
 pub enum ErrorKind {
@@ -13,22 +33,92 @@ pub struct WebSocketServer<T: DataProvider> {
     pub port: u16,
     pub host: String,
     pub data_provider: T,
+    /// if set, only clients from these networks may connect
+    pub allowlist: Option<Allowlist>,
+    /// how long clients connecting with `?wait=true` wait for their game to be created
+    pub wait_timeout: Duration,
+    /// how many clients may be connected at once, further clients are refused
+    pub max_connections: usize,
+    /// if set, the updates of a game are sent to each client at most once per window
+    pub debounce: Option<Duration>,
+    /// how many clients are connected, shared with the clones of the server
+    pub live_connections: Arc<AtomicUsize>,
+}
+
+impl<T: DataProvider> WebSocketServer<T> {
+    pub const DEFAULT_WAIT_TIMEOUT: Duration = WebSocketConfig::DEFAULT_WAIT_TIMEOUT;
+    pub const DEFAULT_MAX_CONNECTIONS: usize = WebSocketConfig::DEFAULT_MAX_CONNECTIONS;
+    /// how long a refused client gets to complete the handshake before it is dropped
+    pub const REFUSAL_TIMEOUT: Duration = Duration::from_secs(1);
+    /// how many refusals may be in flight at once, further clients are dropped without a handshake
+    pub const MAX_PENDING_REFUSALS: usize = 64;
+
+    /// the number of clients currently connected
+    pub fn live_connections(&self) -> usize {
+        self.live_connections.load(Ordering::SeqCst)
+    }
+
+    /// completes the handshake only to tell the client to try again later (1013)
+    /// the permit is held until the refusal is done, bounding the number of pending refusals
+    async fn refuse(
+        stream: TcpStream,
+        peer_addr: std::net::SocketAddr,
+        _permit: OwnedSemaphorePermit,
+    ) {
+        let close_frame = CloseFrame {
+            code: CloseCode::Again,
+            reason: Cow::Borrowed("try again later"),
+        };
+        let refusal = async {
+            match accept_async(stream).await {
+                Ok(mut ws_stream) => {
+                    if let Err(e) = ws_stream.close(Some(close_frame)).await {
+                        debug!("Could not close connection from {}: {:?}", peer_addr, e);
+                    }
+                }
+                Err(e) => debug!("Handshake with refused {} failed: {:?}", peer_addr, e),
+            }
+        };
+        if timeout(Self::REFUSAL_TIMEOUT, refusal).await.is_err() {
+            debug!("Refusal of {} timed out", peer_addr);
+        }
+    }
+}
+
+/// a connection counted against `max_connections`, freed when the task serving it ends
+pub struct ConnectionSlot {
+    _permit: OwnedSemaphorePermit,
+    live_connections: Arc<AtomicUsize>,
+}
+
+impl ConnectionSlot {
+    fn new(permit: OwnedSemaphorePermit, live_connections: Arc<AtomicUsize>) -> Self {
+        live_connections.fetch_add(1, Ordering::SeqCst);
+        Self {
+            _permit: permit,
+            live_connections,
+        }
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.live_connections.fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
 impl<T: DataProvider + Default + 'static> Server<T> for WebSocketServer<T> {
     type ErrorKind = ErrorKind;
-    fn from_env(data_provider: T) -> Self {
-        let host =
-            std::env::var("WEBSOCKET_HOST").unwrap_or_else(|_| Self::DEFAULT_HOST.to_string());
-        let port = std::env::var("WEBSOCKET_PORT")
-            .ok()
-            .and_then(|x| x.parse().ok())
-            .unwrap_or(Self::DEFAULT_PORT);
-
+    fn from_config(config: &Config, data_provider: T) -> Self {
         WebSocketServer {
-            host,
-            port,
+            host: config.websocket.server.host.clone(),
+            port: config.websocket.server.port,
             data_provider,
+            allowlist: config.websocket.allowlist.clone(),
+            wait_timeout: config.websocket.wait_timeout,
+            max_connections: config.websocket.max_connections,
+            debounce: config.websocket.debounce,
+            live_connections: Arc::default(),
         }
     }
     fn new(host: String, port: u16, data_provider: T) -> Self {
@@ -36,6 +126,11 @@ impl<T: DataProvider + Default + 'static> Server<T> for WebSocketServer<T> {
             host,
             port,
             data_provider,
+            allowlist: None,
+            wait_timeout: Self::DEFAULT_WAIT_TIMEOUT,
+            max_connections: Self::DEFAULT_MAX_CONNECTIONS,
+            debounce: None,
+            live_connections: Arc::default(),
         }
     }
 
@@ -48,6 +143,11 @@ impl<T: DataProvider + Default + 'static> Server<T> for WebSocketServer<T> {
             host: Self::DEFAULT_HOST.to_string(),
             port: Self::DEFAULT_PORT,
             data_provider: T::default(),
+            allowlist: None,
+            wait_timeout: Self::DEFAULT_WAIT_TIMEOUT,
+            max_connections: Self::DEFAULT_MAX_CONNECTIONS,
+            debounce: None,
+            live_connections: Arc::default(),
         }
     }
 
@@ -69,17 +169,47 @@ impl<T: DataProvider + Default + 'static> Server<T> for WebSocketServer<T> {
 
         debug!("server started");
 
+        let connection_limit = Arc::new(Semaphore::new(self.max_connections));
+        let refusal_limit = Arc::new(Semaphore::new(Self::MAX_PENDING_REFUSALS));
         loop {
             match server.accept().await {
                 Err(e) => {
                     error!("Error accepting connection: {:?}", e);
                 }
-                Ok((stream, _)) => {
-                    debug!("new connection");
+                Ok((stream, peer_addr)) => {
+                    if let Some(allowlist) = &self.allowlist {
+                        if !allowlist.allows(peer_addr.ip()) {
+                            info!("Rejected connection from {}", peer_addr);
+                            continue;
+                        }
+                    }
+                    let Ok(permit) = connection_limit.clone().try_acquire_owned() else {
+                        info!("Refused connection from {}: too many clients", peer_addr);
+                        match refusal_limit.clone().try_acquire_owned() {
+                            Ok(permit) => {
+                                spawn(Self::refuse(stream, peer_addr, permit));
+                            }
+                            Err(_) => debug!("Dropped {} without a handshake", peer_addr),
+                        }
+                        continue;
+                    };
+                    let slot = ConnectionSlot::new(permit, self.live_connections.clone());
+                    info!("New connection from {}", peer_addr);
                     let data_provider = self.data_provider.clone();
-                    spawn(async {
-                        if let Err(e) = StreamHandler::handle_stream(stream, data_provider).await {
-                            error!("Error handling stream: {:?}", e)
+                    let wait_timeout = self.wait_timeout;
+                    let debounce = self.debounce;
+                    spawn(async move {
+                        if let Err(e) = StreamHandler::handle_stream(
+                            stream,
+                            peer_addr,
+                            wait_timeout,
+                            debounce,
+                            slot,
+                            data_provider,
+                        )
+                        .await
+                        {
+                            error!("Error handling stream from {}: {:?}", peer_addr, e)
                         }
                     });
                 }
@@ -119,19 +249,42 @@ mod test {
 
     use super::*;
     use crate::{
-        websocket::stream_handler::{IncommingMessage, OutgoingMessage},
-        CacheProvider, Move, Player, RedisProvider, RedisProviderArgs,
+        websocket::stream_handler::{Error, IncommingMessage, OutgoingMessage},
+        Board, CacheProvider, CacheProviderArgs, GameData, InvalidMove, Move, Player,
+        RedisProvider, RedisProviderArgs,
     };
-    use futures_util::StreamExt;
+    use futures_util::{SinkExt, StreamExt};
 
     use redis_stack::Redis;
-    use std::time::Duration;
+    use std::{num::NonZeroUsize, time::Duration};
     use testcontainers::clients::Cli as DockerCli;
     use tokio::time::sleep;
     use tokio::time::timeout;
-    use tokio_tungstenite::connect_async;
+    use tokio_tungstenite::{
+        connect_async,
+        tungstenite::{client::IntoClientRequest, Message},
+        MaybeTlsStream, WebSocketStream,
+    };
     use uuid::Uuid;
 
+    #[test]
+    fn making_move_error_reason() {
+        let mut board = Board::new();
+        board.insert_move((4, 4), Player::X).unwrap();
+        let reason = board
+            .validate_move(Move::new((4, 4), Player::O))
+            .unwrap_err();
+        assert_eq!(reason, InvalidMove::FieldOccupied);
+
+        let message = OutgoingMessage::Error {
+            error_message: Error::MakingMove(reason),
+        };
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            serde_json::json!({ "error": { "error_message": { "MakingMove": "field_occupied" } } })
+        );
+    }
+
     #[tokio::test]
     #[ignore = "this is a manual test"]
     async fn test_server_manual() {
@@ -141,7 +294,7 @@ mod test {
             .try_init()
             .expect("Failed to init logger");
 
-        debug!("sample uuid {}", Uuid::new_v4().to_string());
+        debug!("sample uuid {}", Uuid::new_v4());
         debug!(
             "sample ping {}",
             serde_json::to_string(&IncommingMessage::Ping {}).unwrap()
@@ -239,9 +392,927 @@ mod test {
                 // TODO: currently in beta, but assert_matches would be really neat here.
                 assert!(matches!(
                     serde_json::from_str::<OutgoingMessage>(&msg.to_string()),
-                    Ok(OutgoingMessage::GameState { game_state: _ })
+                    Ok(OutgoingMessage::GameState { .. })
                 ))
             }
         }
     }
+    #[tokio::test]
+    async fn test_move_subscription_with_cache() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+
+        let random_port = rand::random::<u16>();
+        let mut server = WebSocketServer::new(
+            WebSocketServer::<CacheProvider>::DEFAULT_HOST.to_string(),
+            random_port,
+            data_provider.clone(),
+        );
+        let server_address = server.get_address();
+        tokio::spawn(async move {
+            server.start().await.unwrap();
+        });
+        sleep(Duration::from_millis(100)).await;
+
+        let (ws_stream, _) = timeout(
+            Duration::from_millis(1000),
+            connect_async(format!("ws://{}/{}/moves", server_address, game_id)),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        let (_write, mut read) = ws_stream.split();
+
+        // wait for the subscription to be registered
+        sleep(Duration::from_millis(100)).await;
+
+        let moves = [
+            Move::new((0, 0), Player::X),
+            Move::new((1, 1), Player::O),
+            Move::new((3, 3), Player::X),
+        ];
+        for new_move in moves {
+            data_provider.add_move(game_id, new_move).unwrap();
+        }
+
+        for (expected_index, expected_move) in moves.into_iter().enumerate() {
+            let msg = timeout(Duration::from_millis(1000), read.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            match serde_json::from_str::<OutgoingMessage>(&msg.to_string()) {
+                Ok(OutgoingMessage::Move { move_, index, .. }) => {
+                    assert_eq!(move_, expected_move);
+                    assert_eq!(index, expected_index);
+                }
+                other => panic!("expected a move message, got {:?}", other),
+            }
+        }
+    }
+    #[tokio::test]
+    async fn test_wait_for_game() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = Uuid::new_v4();
+
+        let random_port = rand::random::<u16>();
+        let mut server =
+            WebSocketServer::new("127.0.0.1".to_string(), random_port, data_provider.clone());
+        let server_address = server.get_address();
+        tokio::spawn(async move {
+            server.start().await.unwrap();
+        });
+        sleep(Duration::from_millis(100)).await;
+
+        let (ws_stream, _) = timeout(
+            Duration::from_millis(1000),
+            connect_async(format!("ws://{}/{}?wait=true", server_address, game_id)),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        let (_write, mut read) = ws_stream.split();
+
+        sleep(Duration::from_millis(200)).await;
+        data_provider.create_game(Some(game_id)).unwrap();
+
+        let msg = timeout(Duration::from_millis(1000), read.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        match serde_json::from_str::<OutgoingMessage>(&msg.to_string()) {
+            Ok(OutgoingMessage::GameState { game_state, .. }) => {
+                assert_eq!(game_state.game_id, game_id)
+            }
+            other => panic!("expected the game state, got {:?}", other),
+        }
+    }
+    #[tokio::test]
+    async fn test_have_moves() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+        let moves = [
+            Move::new((0, 0), Player::X),
+            Move::new((1, 1), Player::O),
+            Move::new((3, 3), Player::X),
+            Move::new((0, 1), Player::O),
+        ];
+        for new_move in moves {
+            data_provider.add_move(game_id, new_move).unwrap();
+        }
+
+        let random_port = rand::random::<u16>();
+        let mut server =
+            WebSocketServer::new("127.0.0.1".to_string(), random_port, data_provider.clone());
+        let server_address = server.get_address();
+        tokio::spawn(async move {
+            server.start().await.unwrap();
+        });
+        sleep(Duration::from_millis(100)).await;
+
+        let connect = |path: String| {
+            let server_address = server_address.clone();
+            async move {
+                let (ws_stream, _) = timeout(
+                    Duration::from_millis(1000),
+                    connect_async(format!("ws://{}/{}", server_address, path)),
+                )
+                .await
+                .unwrap()
+                .unwrap();
+                ws_stream
+            }
+        };
+        async fn next_message(
+            ws_stream: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+        ) -> OutgoingMessage {
+            let msg = timeout(Duration::from_millis(1000), ws_stream.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            serde_json::from_str::<OutgoingMessage>(&msg.to_string()).unwrap()
+        }
+
+        let mut state_client = connect(format!("{}?have=2", game_id)).await;
+        let mut moves_client = connect(format!("{}/moves?have=2", game_id)).await;
+        // a client that claims to have more moves than the game is out of sync
+        let mut desynced_client = connect(format!("{}?have=5", game_id)).await;
+
+        for client in [&mut state_client, &mut moves_client] {
+            match next_message(client).await {
+                OutgoingMessage::Moves {
+                    game_id: moves_game_id,
+                    moves: missing_moves,
+                    base,
+                } => {
+                    assert_eq!(moves_game_id, game_id);
+                    assert_eq!(base, 2);
+                    assert_eq!(missing_moves, moves[2..]);
+                }
+                other => panic!("expected the missing moves, got {:?}", other),
+            }
+        }
+        match next_message(&mut desynced_client).await {
+            OutgoingMessage::GameState { game_state, .. } => {
+                assert_eq!(game_state.moves, moves)
+            }
+            other => panic!("expected the game state, got {:?}", other),
+        }
+
+        // later updates are sent as usual
+        let new_move = Move::new((0, 3), Player::X);
+        data_provider.add_move(game_id, new_move).unwrap();
+        match next_message(&mut state_client).await {
+            OutgoingMessage::GameState { game_state, .. } => {
+                assert_eq!(game_state.moves.len(), moves.len() + 1)
+            }
+            other => panic!("expected the game state, got {:?}", other),
+        }
+        match next_message(&mut moves_client).await {
+            OutgoingMessage::Move { move_, .. } => assert_eq!(move_, new_move),
+            other => panic!("expected a move message, got {:?}", other),
+        }
+    }
+    #[tokio::test]
+    async fn test_get_allowed_moves() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+        data_provider
+            .add_move(game_id, Move::new((4, 4), Player::X))
+            .unwrap();
+
+        let random_port = rand::random::<u16>();
+        let mut server =
+            WebSocketServer::new("127.0.0.1".to_string(), random_port, data_provider.clone());
+        let server_address = server.get_address();
+        tokio::spawn(async move {
+            server.start().await.unwrap();
+        });
+        sleep(Duration::from_millis(100)).await;
+
+        let (mut ws_stream, _) = timeout(
+            Duration::from_millis(1000),
+            connect_async(format!("ws://{}/{}", server_address, game_id)),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        async fn request(
+            ws_stream: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+            message: Message,
+        ) -> OutgoingMessage {
+            ws_stream.send(message).await.unwrap();
+            let msg = timeout(Duration::from_millis(1000), ws_stream.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            serde_json::from_str::<OutgoingMessage>(&msg.to_string()).unwrap()
+        }
+        // the first message is the current game state
+        timeout(Duration::from_millis(1000), ws_stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        let board = data_provider.get_board(game_id).unwrap();
+        let get_allowed_moves = serde_json::to_string(&IncommingMessage::GetAllowedMoves {});
+        match request(&mut ws_stream, Message::Text(get_allowed_moves.unwrap())).await {
+            OutgoingMessage::AllowedMoves {
+                moves,
+                forced_subboard,
+            } => {
+                assert_eq!(moves, board.get_allowed_moves());
+                assert_eq!(forced_subboard, Some((1, 1)));
+            }
+            other => panic!("expected the allowed moves, got {:?}", other),
+        }
+
+        let ping = Message::Text(serde_json::to_string(&IncommingMessage::Ping {}).unwrap());
+        assert!(matches!(
+            request(&mut ws_stream, ping.clone()).await,
+            OutgoingMessage::Pong {}
+        ));
+        assert!(matches!(
+            request(&mut ws_stream, Message::Text("not a message".to_string())).await,
+            OutgoingMessage::Error {
+                error_message: Error::InvalidMessage(_)
+            }
+        ));
+        assert!(matches!(
+            request(&mut ws_stream, Message::Binary(vec![0xff, 0x00])).await,
+            OutgoingMessage::Error {
+                error_message: Error::UnexpectedBinary
+            }
+        ));
+        // the connection survives the malformed messages
+        assert!(matches!(
+            request(&mut ws_stream, ping).await,
+            OutgoingMessage::Pong {}
+        ));
+    }
+    #[tokio::test]
+    async fn test_multiple_games() {
+        let mut data_provider = CacheProvider::default();
+        let first_game = data_provider.create_game(None).unwrap();
+        let second_game = data_provider.create_game(None).unwrap();
+
+        let random_port = rand::random::<u16>();
+        let mut server =
+            WebSocketServer::new("127.0.0.1".to_string(), random_port, data_provider.clone());
+        let server_address = server.get_address();
+        tokio::spawn(async move {
+            server.start().await.unwrap();
+        });
+        sleep(Duration::from_millis(100)).await;
+
+        let (mut ws_stream, _) = timeout(
+            Duration::from_millis(1000),
+            connect_async(format!("ws://{}/{}", server_address, first_game)),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        async fn next_game_state(
+            ws_stream: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+        ) -> (Uuid, usize) {
+            let msg = timeout(Duration::from_millis(1000), ws_stream.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            match serde_json::from_str::<OutgoingMessage>(&msg.to_string()).unwrap() {
+                OutgoingMessage::GameState {
+                    game_id,
+                    game_state,
+                } => {
+                    assert_eq!(game_state.game_id, game_id);
+                    (game_id, game_state.moves.len())
+                }
+                other => panic!("expected a game state, got {:?}", other),
+            }
+        }
+        // the game of the path is the first subscription
+        assert_eq!(next_game_state(&mut ws_stream).await, (first_game, 0));
+
+        let subscribe = IncommingMessage::Subscribe {
+            game_id: second_game,
+        };
+        ws_stream
+            .send(Message::Text(serde_json::to_string(&subscribe).unwrap()))
+            .await
+            .unwrap();
+        assert_eq!(next_game_state(&mut ws_stream).await, (second_game, 0));
+
+        // updates of both games arrive on the same connection
+        data_provider
+            .add_move(second_game, Move::new((4, 4), Player::X))
+            .unwrap();
+        assert_eq!(next_game_state(&mut ws_stream).await, (second_game, 1));
+        data_provider
+            .add_move(first_game, Move::new((0, 0), Player::X))
+            .unwrap();
+        assert_eq!(next_game_state(&mut ws_stream).await, (first_game, 1));
+
+        // after unsubscribing, only the other game is sent
+        let unsubscribe = IncommingMessage::Unsubscribe {
+            game_id: second_game,
+        };
+        ws_stream
+            .send(Message::Text(serde_json::to_string(&unsubscribe).unwrap()))
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(100)).await;
+        data_provider
+            .add_move(second_game, Move::new((3, 3), Player::O))
+            .unwrap();
+        data_provider
+            .add_move(first_game, Move::new((1, 1), Player::O))
+            .unwrap();
+        assert_eq!(next_game_state(&mut ws_stream).await, (first_game, 2));
+
+        // subscribing to a game that doesn't exist is answered with an error
+        let subscribe = IncommingMessage::Subscribe {
+            game_id: Uuid::new_v4(),
+        };
+        ws_stream
+            .send(Message::Text(serde_json::to_string(&subscribe).unwrap()))
+            .await
+            .unwrap();
+        let msg = timeout(Duration::from_millis(1000), ws_stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            serde_json::from_str::<OutgoingMessage>(&msg.to_string()).unwrap(),
+            OutgoingMessage::Error {
+                error_message: Error::GameNotFound
+            }
+        ));
+    }
+    #[tokio::test]
+    async fn test_max_connections() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+
+        let random_port = rand::random::<u16>();
+        let mut server =
+            WebSocketServer::new("127.0.0.1".to_string(), random_port, data_provider.clone());
+        server.max_connections = 2;
+        let server_address = server.get_address();
+        let live_connections = server.live_connections.clone();
+        tokio::spawn(async move {
+            server.start().await.unwrap();
+        });
+        sleep(Duration::from_millis(100)).await;
+
+        let mut clients = vec![];
+        for _ in 0..2 {
+            let (mut ws_stream, _) = timeout(
+                Duration::from_millis(1000),
+                connect_async(format!("ws://{}/{}", server_address, game_id)),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+            // the first message is the current game state
+            timeout(Duration::from_millis(1000), ws_stream.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            clients.push(ws_stream);
+        }
+        assert_eq!(live_connections.load(Ordering::SeqCst), 2);
+
+        let (mut refused, _) = timeout(
+            Duration::from_millis(1000),
+            connect_async(format!("ws://{}/{}", server_address, game_id)),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        match timeout(Duration::from_millis(1000), refused.next())
+            .await
+            .unwrap()
+        {
+            Some(Ok(Message::Close(Some(close_frame)))) => {
+                assert_eq!(close_frame.code, CloseCode::Again)
+            }
+            other => panic!("expected a close frame, got {:?}", other),
+        }
+        assert_eq!(live_connections.load(Ordering::SeqCst), 2);
+    }
+    #[tokio::test]
+    async fn test_stalled_refusal_is_dropped() {
+        let mut data_provider = CacheProvider::default();
+        data_provider.create_game(None).unwrap();
+
+        let random_port = rand::random::<u16>();
+        let mut server =
+            WebSocketServer::new("127.0.0.1".to_string(), random_port, data_provider.clone());
+        server.max_connections = 0;
+        let server_address = server.get_address();
+        tokio::spawn(async move {
+            server.start().await.unwrap();
+        });
+        sleep(Duration::from_millis(100)).await;
+
+        // a client that never sends its handshake is disconnected once the refusal times out
+        let mut stalled = tokio::net::TcpStream::connect(&server_address)
+            .await
+            .unwrap();
+        let mut buffer = [0; 16];
+        let read = timeout(
+            WebSocketServer::<CacheProvider>::REFUSAL_TIMEOUT + Duration::from_millis(1000),
+            tokio::io::AsyncReadExt::read(&mut stalled, &mut buffer),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(read, Ok(0) | Err(_)));
+    }
+    #[tokio::test]
+    async fn test_debounced_subscriber() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+
+        let random_port = rand::random::<u16>();
+        let mut server =
+            WebSocketServer::new("127.0.0.1".to_string(), random_port, data_provider.clone());
+        server.debounce = Some(Duration::from_millis(300));
+        let server_address = server.get_address();
+        tokio::spawn(async move {
+            server.start().await.unwrap();
+        });
+        sleep(Duration::from_millis(100)).await;
+
+        let connect = |url: String| async move {
+            let (ws_stream, _) = timeout(Duration::from_millis(1000), connect_async(url))
+                .await
+                .unwrap()
+                .unwrap();
+            ws_stream
+        };
+        let mut game_states = connect(format!("ws://{}/{}", server_address, game_id)).await;
+        let mut moves = connect(format!("ws://{}/{}/moves", server_address, game_id)).await;
+        sleep(Duration::from_millis(100)).await;
+
+        // the server runs in between the moves, so they aren't coalesced by the channels
+        let mut board = Board::new();
+        let mut made_moves = Vec::new();
+        for _ in 0..12 {
+            let new_move = Move::new(board.get_allowed_moves()[0], board.get_next_player());
+            board
+                .insert_move(new_move.coordinates, new_move.player)
+                .unwrap();
+            data_provider.add_move(game_id, new_move).unwrap();
+            made_moves.push(new_move);
+            sleep(Duration::from_millis(10)).await;
+        }
+
+        async fn received(
+            ws_stream: &mut WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>,
+        ) -> Vec<OutgoingMessage> {
+            let mut messages = Vec::new();
+            while let Ok(Some(msg)) = timeout(Duration::from_millis(800), ws_stream.next()).await {
+                messages.push(serde_json::from_str(&msg.unwrap().to_string()).unwrap());
+            }
+            messages
+        }
+
+        let states = received(&mut game_states).await;
+        assert!(states.len() < made_moves.len());
+        match states.last() {
+            Some(OutgoingMessage::GameState { game_state, .. }) => {
+                assert_eq!(game_state.moves, made_moves)
+            }
+            other => panic!("expected the game state, got {:?}", other),
+        }
+
+        let deltas = received(&mut moves).await;
+        assert!(deltas.len() < made_moves.len());
+        let mut received_moves = Vec::new();
+        for delta in deltas {
+            match delta {
+                OutgoingMessage::Move { move_, index, .. } => {
+                    assert_eq!(index, received_moves.len());
+                    received_moves.push(move_);
+                }
+                OutgoingMessage::Moves { moves, base, .. } => {
+                    assert_eq!(base, received_moves.len());
+                    received_moves.extend(moves);
+                }
+                other => panic!("expected moves, got {:?}", other),
+            }
+        }
+        assert_eq!(received_moves, made_moves);
+    }
+
+    #[tokio::test]
+    async fn test_lagging_move_subscriber() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs {
+            move_buffer: NonZeroUsize::new(1),
+            ..Default::default()
+        })
+        .unwrap();
+        let game_id = data_provider.create_game(None).unwrap();
+
+        let random_port = rand::random::<u16>();
+        let mut server =
+            WebSocketServer::new("127.0.0.1".to_string(), random_port, data_provider.clone());
+        let server_address = server.get_address();
+        tokio::spawn(async move {
+            server.start().await.unwrap();
+        });
+        sleep(Duration::from_millis(100)).await;
+
+        let (mut ws_stream, _) = timeout(
+            Duration::from_millis(1000),
+            connect_async(format!("ws://{}/{}/moves", server_address, game_id)),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        sleep(Duration::from_millis(100)).await;
+
+        // the server can't run in between, so all but the last move overflow the buffer
+        for (coordinates, player) in [
+            ((4, 4), Player::X),
+            ((3, 3), Player::O),
+            ((0, 0), Player::X),
+            ((1, 1), Player::O),
+            ((3, 4), Player::X),
+        ] {
+            data_provider
+                .add_move(game_id, Move::new(coordinates, player))
+                .unwrap();
+        }
+
+        let msg = timeout(Duration::from_millis(1000), ws_stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        match serde_json::from_str::<OutgoingMessage>(&msg.to_string()).unwrap() {
+            OutgoingMessage::GameState { game_state, .. } => {
+                assert_eq!(game_state.moves.len(), 5)
+            }
+            other => panic!("expected the game state, got {:?}", other),
+        }
+        // the move still in the buffer was part of the game state
+        assert!(timeout(Duration::from_millis(200), ws_stream.next())
+            .await
+            .is_err());
+    }
+    #[tokio::test]
+    async fn test_replay() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+        for (coordinates, player) in [
+            ((4, 4), Player::X),
+            ((3, 3), Player::O),
+            ((0, 0), Player::X),
+            ((1, 1), Player::O),
+            ((3, 4), Player::X),
+        ] {
+            data_provider
+                .add_move(game_id, Move::new(coordinates, player))
+                .unwrap();
+        }
+
+        let random_port = rand::random::<u16>();
+        let mut server =
+            WebSocketServer::new("127.0.0.1".to_string(), random_port, data_provider.clone());
+        let server_address = server.get_address();
+        tokio::spawn(async move {
+            server.start().await.unwrap();
+        });
+        sleep(Duration::from_millis(100)).await;
+
+        let (mut ws_stream, _) = timeout(
+            Duration::from_millis(1000),
+            connect_async(format!("ws://{}/{}", server_address, game_id)),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        async fn next_message(
+            ws_stream: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+        ) -> OutgoingMessage {
+            let msg = timeout(Duration::from_millis(1000), ws_stream.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            serde_json::from_str::<OutgoingMessage>(&msg.to_string()).unwrap()
+        }
+        // the first message is the current game state
+        next_message(&mut ws_stream).await;
+
+        let start_replay = |interval_ms| {
+            Message::Text(
+                serde_json::to_string(&IncommingMessage::StartReplay { interval_ms }).unwrap(),
+            )
+        };
+        ws_stream.send(start_replay(10)).await.unwrap();
+        for move_count in 1..=5 {
+            match next_message(&mut ws_stream).await {
+                OutgoingMessage::GameState { game_state, .. } => {
+                    assert_eq!(game_state.moves.len(), move_count)
+                }
+                other => panic!("expected the game state, got {:?}", other),
+            }
+        }
+        // the game hasn't ended, so there is no game over
+        assert!(timeout(Duration::from_millis(200), ws_stream.next())
+            .await
+            .is_err());
+
+        // a stopped replay sends no more states
+        ws_stream.send(start_replay(500)).await.unwrap();
+        next_message(&mut ws_stream).await;
+        let stop_replay = serde_json::to_string(&IncommingMessage::StopReplay {}).unwrap();
+        ws_stream.send(Message::Text(stop_replay)).await.unwrap();
+        assert!(timeout(Duration::from_millis(800), ws_stream.next())
+            .await
+            .is_err());
+    }
+    #[tokio::test]
+    async fn test_private_game() {
+        let mut data_provider = CacheProvider::default();
+        let mut template = GameData::new();
+        template.settings.private = true;
+        let game_id = data_provider.create_game_from(None, template).unwrap();
+        let player_token = Uuid::new_v4();
+        data_provider
+            .register_player(game_id, player_token)
+            .unwrap();
+        let public_game = data_provider.create_game(None).unwrap();
+
+        let random_port = rand::random::<u16>();
+        let mut server =
+            WebSocketServer::new("127.0.0.1".to_string(), random_port, data_provider.clone());
+        let server_address = server.get_address();
+        tokio::spawn(async move {
+            server.start().await.unwrap();
+        });
+        sleep(Duration::from_millis(100)).await;
+
+        let connect = |header: Option<(&'static str, String)>| {
+            let mut request = format!("ws://{}/{}", server_address, game_id)
+                .into_client_request()
+                .unwrap();
+            if let Some((name, value)) = header {
+                request.headers_mut().insert(name, value.parse().unwrap());
+            }
+            async move {
+                let (ws_stream, _) = timeout(Duration::from_millis(1000), connect_async(request))
+                    .await
+                    .unwrap()
+                    .unwrap();
+                ws_stream
+            }
+        };
+        async fn next_message(
+            ws_stream: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+        ) -> Message {
+            timeout(Duration::from_millis(1000), ws_stream.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap()
+        }
+
+        // a registered token may follow the game, sent as a header or as subprotocols
+        for header in [
+            ("Authorization", format!("Bearer {}", player_token)),
+            (
+                "Sec-WebSocket-Protocol",
+                format!("bearer, {}", player_token),
+            ),
+        ] {
+            let mut ws_stream = connect(Some(header)).await;
+            let msg = next_message(&mut ws_stream).await;
+            assert!(matches!(
+                serde_json::from_str::<OutgoingMessage>(&msg.to_string()),
+                Ok(OutgoingMessage::GameState { .. })
+            ));
+
+            // public games can be followed on the same connection
+            let subscribe = IncommingMessage::Subscribe {
+                game_id: public_game,
+            };
+            let subscribe = serde_json::to_string(&subscribe).unwrap();
+            ws_stream.send(Message::Text(subscribe)).await.unwrap();
+            let msg = next_message(&mut ws_stream).await;
+            assert!(matches!(
+                serde_json::from_str::<OutgoingMessage>(&msg.to_string()),
+                Ok(OutgoingMessage::GameState { game_id, .. }) if game_id == public_game
+            ));
+        }
+
+        // a wrong or missing token is refused
+        for header in [
+            Some(("Authorization", format!("Bearer {}", Uuid::new_v4()))),
+            None,
+        ] {
+            let mut ws_stream = connect(header).await;
+            match next_message(&mut ws_stream).await {
+                Message::Close(Some(close_frame)) => {
+                    assert_eq!(u16::from(close_frame.code), 4401)
+                }
+                other => panic!("expected a close frame, got {:?}", other),
+            }
+        }
+
+        // nor can the private game be followed from another game's connection
+        let (mut ws_stream, _) = connect_async(format!("ws://{}/{}", server_address, public_game))
+            .await
+            .unwrap();
+        next_message(&mut ws_stream).await;
+        let subscribe = serde_json::to_string(&IncommingMessage::Subscribe { game_id }).unwrap();
+        ws_stream.send(Message::Text(subscribe)).await.unwrap();
+        let msg = next_message(&mut ws_stream).await;
+        assert!(matches!(
+            serde_json::from_str::<OutgoingMessage>(&msg.to_string()),
+            Ok(OutgoingMessage::Error {
+                error_message: Error::Unauthorized
+            })
+        ));
+    }
+    #[tokio::test]
+    async fn test_unreadable_game_is_refused() {
+        let mut data_provider = CacheProvider::default();
+        let public_game = data_provider.create_game(None).unwrap();
+        let other_game = data_provider.create_game(None).unwrap();
+
+        let random_port = rand::random::<u16>();
+        let mut server =
+            WebSocketServer::new("127.0.0.1".to_string(), random_port, data_provider.clone());
+        let server_address = server.get_address();
+        tokio::spawn(async move {
+            server.start().await.unwrap();
+        });
+        sleep(Duration::from_millis(100)).await;
+
+        let (mut ws_stream, _) = connect_async(format!("ws://{}/{}", server_address, public_game))
+            .await
+            .unwrap();
+        timeout(Duration::from_millis(1000), ws_stream.next())
+            .await
+            .unwrap();
+
+        // from now on, whether the other game is private can't be told
+        let hash_map = data_provider.hash_map.clone();
+        std::thread::spawn(move || {
+            let _guard = hash_map.lock().unwrap();
+            panic!("poisoning the games");
+        })
+        .join()
+        .unwrap_err();
+
+        let subscribe = IncommingMessage::Subscribe {
+            game_id: other_game,
+        };
+        let subscribe = serde_json::to_string(&subscribe).unwrap();
+        ws_stream.send(Message::Text(subscribe)).await.unwrap();
+        let msg = timeout(Duration::from_millis(1000), ws_stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            serde_json::from_str::<OutgoingMessage>(&msg.to_string()),
+            Ok(OutgoingMessage::Error {
+                error_message: Error::Unauthorized
+            })
+        ));
+    }
+    #[tokio::test]
+    async fn test_finished_game() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+        let moves = [
+            ((1, 1), Player::X),
+            ((4, 4), Player::O),
+            ((3, 4), Player::X),
+            ((1, 4), Player::O),
+            ((3, 5), Player::X),
+            ((1, 7), Player::O),
+            ((3, 3), Player::X),
+            ((0, 0), Player::O),
+            ((0, 2), Player::X),
+            ((0, 6), Player::O),
+            ((2, 0), Player::X),
+            ((8, 2), Player::O),
+            ((6, 8), Player::X),
+            ((2, 8), Player::O),
+            ((7, 7), Player::X),
+            ((2, 5), Player::O),
+            ((8, 6), Player::X),
+        ];
+        for (coordinates, player) in moves {
+            data_provider
+                .add_move(game_id, Move::new(coordinates, player))
+                .unwrap();
+        }
+
+        let random_port = rand::random::<u16>();
+        let mut server =
+            WebSocketServer::new("127.0.0.1".to_string(), random_port, data_provider.clone());
+        let server_address = server.get_address();
+        tokio::spawn(async move {
+            server.start().await.unwrap();
+        });
+        sleep(Duration::from_millis(100)).await;
+
+        let (ws_stream, _) = timeout(
+            Duration::from_millis(1000),
+            connect_async(format!("ws://{}/{}", server_address, game_id)),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        let (_write, mut read) = ws_stream.split();
+
+        let msg = timeout(Duration::from_millis(1000), read.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        match serde_json::from_str::<OutgoingMessage>(&msg.to_string()) {
+            Ok(OutgoingMessage::GameState { game_state, .. }) => {
+                assert_eq!(game_state.moves.len(), moves.len())
+            }
+            other => panic!("expected the game state, got {:?}", other),
+        }
+
+        let msg = timeout(Duration::from_millis(1000), read.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        match serde_json::from_str::<OutgoingMessage>(&msg.to_string()) {
+            Ok(OutgoingMessage::GameOver { result, .. }) => {
+                assert_eq!(result.winner, Some(Player::X))
+            }
+            other => panic!("expected the game over message, got {:?}", other),
+        }
+
+        // the server closes the connection after the final state
+        match timeout(Duration::from_millis(1000), read.next())
+            .await
+            .unwrap()
+        {
+            None | Some(Ok(Message::Close(_))) => {}
+            other => panic!("expected the connection to be closed, got {:?}", other),
+        }
+    }
+    #[tokio::test]
+    async fn test_allowlist() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+
+        for (allowlist, expect_connected) in [("127.0.0.0/8", true), ("10.0.0.0/8", false)] {
+            let random_port = rand::random::<u16>();
+            let mut server =
+                WebSocketServer::new("127.0.0.1".to_string(), random_port, data_provider.clone());
+            server.allowlist = Some(Allowlist::parse(allowlist).unwrap());
+            let server_address = server.get_address();
+            tokio::spawn(async move {
+                server.start().await.unwrap();
+            });
+            sleep(Duration::from_millis(100)).await;
+
+            let connection = timeout(
+                Duration::from_millis(1000),
+                connect_async(format!("ws://{}/{}", server_address, game_id)),
+            )
+            .await
+            .unwrap();
+            assert_eq!(
+                connection.is_ok(),
+                expect_connected,
+                "allowlist {} should {}allow localhost",
+                allowlist,
+                if expect_connected { "" } else { "not " }
+            );
+        }
+    }
+    #[tokio::test]
+    async fn test_start_on_used_port() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let mut server =
+            WebSocketServer::new("127.0.0.1".to_string(), port, CacheProvider::default());
+        assert!(matches!(
+            server.start().await,
+            Err(ErrorKind::ErrorListening(_))
+        ));
+    }
 }
@@ -1,33 +1,114 @@
-use crate::{Board, DataProvider};
-use futures_util::{SinkExt, StreamExt};
+use super::{
+    connection_request::{ConnectionRequest, MessageFormat, RequestedGame, Subscription},
+    server::ConnectionSlot,
+};
+use crate::{Board, Coordinates, DataProvider, GameData, GameDelta, GameResult, InvalidMove, Move};
+use futures_util::{
+    future::ready,
+    stream::{self, once, SplitSink},
+    SinkExt, Stream, StreamExt,
+};
 use serde::{Deserialize, Serialize};
-use tokio_stream::wrappers::WatchStream;
-use tokio_tungstenite::{tungstenite::Message, WebSocketStream};
+use tokio_stream::{
+    wrappers::{errors::BroadcastStreamRecvError, BroadcastStream, WatchStream},
+    StreamMap,
+};
+use tokio_tungstenite::WebSocketStream;
 use uuid::Uuid;
 
-use log::debug;
+use log::{debug, warn};
 use std::{
+    borrow::Cow,
+    collections::HashMap,
+    net::SocketAddr,
     ops::Deref,
+    pin::Pin,
     sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    net::TcpStream,
+    select,
+    sync::broadcast::{error::RecvError, Receiver},
+    time::{interval, timeout, timeout_at, Instant},
 };
-use tokio::net::TcpStream;
 use tokio_tungstenite::{
     accept_hdr_async,
-    tungstenite::handshake::server::{Request, Response},
+    tungstenite::{
+        handshake::server::{Request, Response},
+        http::{
+            header::{AUTHORIZATION, SEC_WEBSOCKET_PROTOCOL},
+            HeaderValue,
+        },
+        protocol::{frame::coding::CloseCode, CloseFrame},
+        Message,
+    },
 };
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum OutgoingMessage {
-    Error { error_message: Error },
-    Welcome { game_uuid: Uuid },
-    GameState { game_state: Board },
+    Error {
+        error_message: Error,
+    },
+    Welcome {
+        game_uuid: Uuid,
+    },
+    GameState {
+        game_id: Uuid,
+        game_state: Board,
+    },
+    Move {
+        game_id: Uuid,
+        #[serde(rename = "move")]
+        move_: Move,
+        /// the number of moves made before this one
+        index: usize,
+    },
+    /// the moves after the first `base` moves, sent instead of the full board
+    /// to clients that already have the first `base` moves
+    Moves {
+        game_id: Uuid,
+        moves: Vec<Move>,
+        base: usize,
+    },
+    /// sent before the subscription to the game ends because the game has ended
+    GameOver {
+        game_id: Uuid,
+        result: GameResult,
+    },
     Pong {},
+    /// the answer to `IncommingMessage::GetAllowedMoves`
+    AllowedMoves {
+        moves: Vec<Coordinates>,
+        /// the sub board the next move must be made in, if the next player is limited to one
+        forced_subboard: Option<Coordinates>,
+    },
 }
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum IncommingMessage {
     Ping {},
+    /// asks for the moves the next player may make in the board of the game the
+    /// client connected to
+    GetAllowedMoves {},
+    /// follows another game on the same connection, with the same kind of updates
+    /// as the game the client connected to
+    Subscribe {
+        game_id: Uuid,
+    },
+    /// stops following a game
+    Unsubscribe {
+        game_id: Uuid,
+    },
+    /// replays the game the client connected to: the board after each of its moves is
+    /// sent as a `GameState` every `interval_ms` milliseconds, followed by a `GameOver`
+    /// if the game has ended. A new replay replaces the running one.
+    StartReplay {
+        interval_ms: u64,
+    },
+    /// cancels the running replay
+    StopReplay {},
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -37,100 +118,669 @@ pub enum Error {
     InvalidRole(String),
     HandShake(String),
     CouldNotSerialize(String),
-    MakingMove(String),
+    /// a move was rejected, with the reason it was rejected for
+    MakingMove(InvalidMove),
     Subscribing(String),
     CouldNotSend(String),
+    /// the message couldn't be parsed, with the reason
+    InvalidMessage(String),
+    /// the client sent a binary frame, but its messages are expected as json text
+    UnexpectedBinary,
+    LoadingBoard(String),
+    /// the game is private and the client didn't send the token of one of its players
+    Unauthorized,
+}
+
+/// the messages sent to a client, each with the result of the game once it has ended
+type Updates = Pin<Box<dyn Stream<Item = (OutgoingMessage, Option<GameResult>)> + Send>>;
+
+/// the messages of a running replay, see `IncommingMessage::StartReplay`
+type Replay = Pin<Box<dyn Stream<Item = OutgoingMessage> + Send>>;
+
+/// what a connection was opened with, needed to answer the messages of the client
+#[derive(Clone, Copy, Debug)]
+struct ClientContext {
+    connected_game: Uuid,
+    subscription: Subscription,
+    player_token: Option<Uuid>,
+    /// the window the updates of each subscription are collected in, see `debounce`
+    debounce: Option<Duration>,
 }
 
 pub struct StreamHandler<T: DataProvider> {
     pub stream: WebSocketStream<TcpStream>,
     pub connected_game: Uuid,
+    pub peer_addr: SocketAddr,
+    pub subscription: Subscription,
+    pub format: MessageFormat,
+    /// keep the connection open after the game has ended
+    pub linger: bool,
+    /// the number of moves the client already has
+    pub have: Option<usize>,
+    /// the player token sent with the handshake, see `bearer_token`
+    pub player_token: Option<Uuid>,
     pub data_provider: T,
 }
 
-impl<T: DataProvider> StreamHandler<T> {
-    pub async fn handle_stream(stream: TcpStream, mut data_provider: T) -> Result<(), Error> {
-        let client = Self::accept_connection(stream, data_provider.clone()).await?;
-        debug!("Client accepted");
+impl<T: DataProvider + 'static> StreamHandler<T> {
+    const TOUCH_INTERVAL: Duration = Duration::from_secs(60);
+    /// replays asking for a shorter interval are slowed down to this one
+    const MIN_REPLAY_INTERVAL: Duration = Duration::from_millis(10);
+    /// the close code sent to clients that may not follow a private game
+    const UNAUTHORIZED: u16 = 4401;
+    /// the subprotocol browsers send before the token, as they can't set the
+    /// `Authorization` header of a websocket
+    const BEARER_PROTOCOL: &'static str = "bearer";
 
-        let mut rx = WatchStream::new(
-            data_provider
-                .subscribe_to_game(client.connected_game)
-                .map_err(|e| Error::Subscribing(e.to_string()))?,
+    /// handles a client connection. Clients that asked to wait for their game to be
+    /// created are disconnected if it doesn't appear within `wait_timeout`.
+    /// The slot is held until the connection ends. If a debounce window is given, the
+    /// updates of the games are sent at most once per window.
+    pub async fn handle_stream(
+        stream: TcpStream,
+        peer_addr: SocketAddr,
+        wait_timeout: Duration,
+        debounce: Option<Duration>,
+        slot: ConnectionSlot,
+        mut data_provider: T,
+    ) -> Result<(), Error> {
+        let client =
+            Self::accept_connection(stream, peer_addr, wait_timeout, data_provider.clone()).await?;
+        debug!(
+            "Client {} accepted, connected to game {}",
+            client.peer_addr, client.connected_game
         );
 
-        debug!("sucessfully subscribed to game {}", client.connected_game);
+        let format = client.format;
+        let linger = client.linger;
+        let connected_game = client.connected_game;
+        let subscription = client.subscription;
+        let context = ClientContext {
+            connected_game,
+            subscription,
+            player_token: client.player_token,
+            debounce,
+        };
+        let (mut ws_sender, mut ws_receiver) = client.stream.split();
 
-        let (mut ws_sender, _) = client.stream.split();
+        // the updates of every game the client follows, and the results of the games
+        // that have ended but whose game over hasn't been sent yet
+        let mut subscriptions: StreamMap<Uuid, Updates> = StreamMap::new();
+        let mut finished_games: HashMap<Uuid, GameResult> = HashMap::new();
+        let mut replay: Option<Replay> = None;
+        let (updates, game_over) = Self::subscribe(
+            &mut data_provider,
+            connected_game,
+            subscription,
+            client.have,
+        )?;
+        subscriptions.insert(connected_game, Self::debounce(updates, debounce));
+        finished_games.extend(game_over.map(|result| (connected_game, result)));
 
         tokio::spawn(async move {
+            let _slot = slot;
+            // the games are touched regularly so they don't expire while a client watches them
+            let mut keep_alive = interval(Self::TOUCH_INTERVAL);
             loop {
-                match rx.next().await {
-                    Some(game_data_update) => {
-                        debug!("Received data from DataProvider: Sending game update to client");
-                        ws_sender
-                            .send(Message::Text(
-                                serde_json::to_string(&OutgoingMessage::GameState {
-                                    game_state: Board::from(game_data_update),
-                                })
-                                .map_err(|e| Error::CouldNotSerialize(e.to_string()))
-                                .unwrap(),
-                            ))
-                            .await
-                            .map_err(|e| Error::CouldNotSend(e.to_string()))
-                            .unwrap();
-                        debug!("Data sent, waiting for next message");
+                // a finished game won't receive any more updates, so unless the client
+                // wants to linger, its subscription ends and the connection is closed
+                // once no game is left
+                if !linger {
+                    for (game_id, result) in finished_games.drain() {
+                        debug!("Game {} is over, unsubscribing", game_id);
+                        subscriptions.remove(&game_id);
+                        let game_over = OutgoingMessage::GameOver { game_id, result };
+                        let _ = Self::send(&mut ws_sender, format, &game_over).await;
                     }
-                    None => {
-                        debug!("Received None via rx. Exiting...");
+                    if subscriptions.is_empty() && replay.is_none() {
+                        debug!("No games left, closing connection");
+                        let _ = ws_sender.close().await;
                         break;
                     }
                 }
+                select! {
+                    update = subscriptions.next(), if !subscriptions.is_empty() => {
+                        let Some((game_id, (message, result))) = update else {
+                            debug!("Received None via rx. Exiting...");
+                            break;
+                        };
+                        debug!("Received data from DataProvider: Sending update to client");
+                        if let Err(e) = Self::send(&mut ws_sender, format, &message).await {
+                            debug!("Could not send update to client: {:?}. Exiting...", e);
+                            break;
+                        }
+                        debug!("Data sent, waiting for next message");
+                        finished_games.extend(result.map(|result| (game_id, result)));
+                    }
+                    frame = async { replay.as_mut()?.next().await }, if replay.is_some() => {
+                        let Some(frame) = frame else {
+                            debug!("Replay finished");
+                            replay = None;
+                            continue;
+                        };
+                        if let Err(e) = Self::send(&mut ws_sender, format, &frame).await {
+                            debug!("Could not send replay to client: {:?}. Exiting...", e);
+                            break;
+                        }
+                    }
+                    incoming = ws_receiver.next() => {
+                        let message = match incoming {
+                            Some(Ok(message @ (Message::Text(_) | Message::Binary(_)))) => message,
+                            Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
+                                debug!("Client closed the connection. Exiting...");
+                                break;
+                            }
+                            // pings are answered by tungstenite
+                            Some(Ok(_)) => continue,
+                        };
+                        // a message that can't be parsed is answered with an error, the
+                        // connection stays open for the next one
+                        let answer = match format.decode(message) {
+                            Err(error_message) => Some(OutgoingMessage::Error { error_message }),
+                            Ok(message) => Self::handle_message(
+                                message,
+                                &mut data_provider,
+                                context,
+                                &mut subscriptions,
+                                &mut finished_games,
+                                &mut replay,
+                            ),
+                        };
+                        if let Some(answer) = answer {
+                            if let Err(e) = Self::send(&mut ws_sender, format, &answer).await {
+                                debug!("Could not answer client: {:?}. Exiting...", e);
+                                break;
+                            }
+                        }
+                    }
+                    _ = keep_alive.tick() => {
+                        for game_id in subscriptions.keys() {
+                            if let Err(e) = data_provider.touch(*game_id) {
+                                warn!("Could not touch game {}: {}", game_id, e);
+                            }
+                        }
+                    }
+                }
             }
         });
         debug!("stream ended. Returning");
         Ok(())
     }
 
-    async fn accept_connection(stream: TcpStream, data_provider: T) -> Result<Self, Error> {
+    /// subscribes to the updates of the game, starting with the moves the client doesn't
+    /// have yet if it told how many it has. Returns the result of the game if it has
+    /// already ended and the updates won't tell.
+    fn subscribe(
+        data_provider: &mut T,
+        game_id: Uuid,
+        subscription: Subscription,
+        have: Option<usize>,
+    ) -> Result<(Updates, Option<GameResult>), Error> {
+        if !data_provider.game_exists(game_id).unwrap_or(false) {
+            return Err(Error::GameNotFound);
+        }
+        match subscription {
+            Subscription::GameState => {
+                let mut rx = data_provider
+                    .subscribe_to_game(game_id)
+                    .map_err(|e| Error::Subscribing(e.to_string()))?;
+                debug!("sucessfully subscribed to game {}", game_id);
+
+                let current_game_data = rx.borrow_and_update().clone();
+                // the first update holds the current state, so the game over is detected there
+                let first_update = Self::missing_moves(have, &current_game_data)
+                    .unwrap_or_else(|| Self::game_state_update(current_game_data));
+                let updates = once(ready(first_update))
+                    .chain(WatchStream::from_changes(rx).map(Self::game_state_update));
+                Ok((Box::pin(updates), None))
+            }
+            Subscription::Moves => {
+                let rx = data_provider
+                    .subscribe_to_game_moves(game_id)
+                    .map_err(|e| Error::Subscribing(e.to_string()))?;
+                debug!("sucessfully subscribed to moves of game {}", game_id);
+
+                // later results arrive with the move that ended the game
+                let game_over = data_provider
+                    .get_game_data(game_id)
+                    .ok()
+                    .and_then(|game_data| game_data.result.or_else(|| game_data.compute_result()));
+                let resync_provider = data_provider.clone();
+                // the number of moves the last resync contained, later deltas of them are dropped
+                let mut synced_moves = 0;
+                let updates = BroadcastStream::new(rx).filter_map(move |delta| {
+                    let update = match delta {
+                        Ok(GameDelta { index, .. }) if index < synced_moves => None,
+                        Ok(GameDelta {
+                            move_,
+                            index,
+                            result,
+                        }) => Some((
+                            OutgoingMessage::Move {
+                                game_id,
+                                move_,
+                                index,
+                            },
+                            result,
+                        )),
+                        // the missed moves are gone from the buffer, so the client is sent
+                        // the whole game instead
+                        Err(BroadcastStreamRecvError::Lagged(missed)) => {
+                            debug!(
+                                "Move subscriber of game {} missed {} moves, resyncing",
+                                game_id, missed
+                            );
+                            match resync_provider.get_game_data(game_id) {
+                                Ok(game_data) => {
+                                    synced_moves = game_data.moves.len();
+                                    Some(Self::game_state_update(game_data))
+                                }
+                                Err(e) => {
+                                    warn!("Could not resync game {}: {}", game_id, e);
+                                    None
+                                }
+                            }
+                        }
+                    };
+                    async move { update }
+                });
+                // a client that already has some moves catches up before the new moves
+                let catch_up = have.and_then(|_| {
+                    let game_data = data_provider.get_game_data(game_id).ok()?;
+                    Some(
+                        Self::missing_moves(have, &game_data)
+                            .unwrap_or_else(|| Self::game_state_update(game_data)),
+                    )
+                });
+                match catch_up {
+                    Some(catch_up) => Ok((Box::pin(once(ready(catch_up)).chain(updates)), None)),
+                    None => Ok((Box::pin(updates), game_over)),
+                }
+            }
+        }
+    }
+
+    /// collects the updates that arrive within `window` of the first one and sends them
+    /// as one update, see `coalesce`. Without a window, the updates are sent as they come.
+    fn debounce(updates: Updates, window: Option<Duration>) -> Updates {
+        let Some(window) = window else {
+            return updates;
+        };
+        let updates = stream::unfold(
+            (updates.fuse(), None),
+            move |(mut updates, pending)| async move {
+                let mut batch = match pending {
+                    Some(update) => update,
+                    None => updates.next().await?,
+                };
+                let deadline = Instant::now() + window;
+                let mut pending = None;
+                while let Ok(Some(update)) = timeout_at(deadline, updates.next()).await {
+                    // an update that can't be merged starts the next window
+                    pending = Self::coalesce(&mut batch, update);
+                    if pending.is_some() {
+                        break;
+                    }
+                }
+                Some((batch, (updates, pending)))
+            },
+        );
+        Box::pin(updates)
+    }
+
+    /// merges an update into the batch if one message can tell both: a board replaces
+    /// everything before it and consecutive moves are sent together. Returns the update
+    /// if it has to be sent after the batch instead.
+    fn coalesce(
+        batch: &mut (OutgoingMessage, Option<GameResult>),
+        update: (OutgoingMessage, Option<GameResult>),
+    ) -> Option<(OutgoingMessage, Option<GameResult>)> {
+        let (message, result) = update;
+        if let OutgoingMessage::GameState { .. } = message {
+            *batch = (message, result.or(batch.1));
+            return None;
+        }
+        match (Self::as_moves(&batch.0), Self::as_moves(&message)) {
+            (Some((game_id, base, mut moves)), Some((_, next, later_moves)))
+                if base + moves.len() == next =>
+            {
+                moves.extend(later_moves);
+                let merged = OutgoingMessage::Moves {
+                    game_id,
+                    moves,
+                    base,
+                };
+                *batch = (merged, result.or(batch.1));
+                None
+            }
+            _ => Some((message, result)),
+        }
+    }
+
+    /// the game, the index of the first move and the moves of a message carrying moves
+    fn as_moves(message: &OutgoingMessage) -> Option<(Uuid, usize, Vec<Move>)> {
+        match message {
+            OutgoingMessage::Move {
+                game_id,
+                move_,
+                index,
+            } => Some((*game_id, *index, vec![*move_])),
+            OutgoingMessage::Moves {
+                game_id,
+                moves,
+                base,
+            } => Some((*game_id, *base, moves.clone())),
+            _ => None,
+        }
+    }
+
+    async fn send(
+        ws_sender: &mut SplitSink<WebSocketStream<TcpStream>, Message>,
+        format: MessageFormat,
+        message: &OutgoingMessage,
+    ) -> Result<(), Error> {
+        ws_sender
+            .send(format.encode(message)?)
+            .await
+            .map_err(|e| Error::CouldNotSend(e.to_string()))
+    }
+
+    /// handles a message the client sent and returns the answer to it, if there is one
+    fn handle_message(
+        message: IncommingMessage,
+        data_provider: &mut T,
+        context: ClientContext,
+        subscriptions: &mut StreamMap<Uuid, Updates>,
+        finished_games: &mut HashMap<Uuid, GameResult>,
+        replay: &mut Option<Replay>,
+    ) -> Option<OutgoingMessage> {
+        match message {
+            IncommingMessage::Ping {} => Some(OutgoingMessage::Pong {}),
+            IncommingMessage::GetAllowedMoves {} => {
+                match data_provider.get_board(context.connected_game) {
+                    Ok(board) => Some(OutgoingMessage::AllowedMoves {
+                        moves: board.get_allowed_moves(),
+                        forced_subboard: board.forced_subboard(),
+                    }),
+                    Err(e) => Some(OutgoingMessage::Error {
+                        error_message: Error::LoadingBoard(e.to_string()),
+                    }),
+                }
+            }
+            IncommingMessage::Subscribe { game_id } => {
+                // games that can't be looked up are refused below, as they might be private
+                if let Ok(false) = data_provider.game_exists(game_id) {
+                    return Some(OutgoingMessage::Error {
+                        error_message: Error::GameNotFound,
+                    });
+                }
+                if !Self::is_authorized(data_provider, game_id, context.player_token) {
+                    return Some(OutgoingMessage::Error {
+                        error_message: Error::Unauthorized,
+                    });
+                }
+                match Self::subscribe(data_provider, game_id, context.subscription, None) {
+                    Ok((updates, game_over)) => {
+                        subscriptions.insert(game_id, Self::debounce(updates, context.debounce));
+                        finished_games.extend(game_over.map(|result| (game_id, result)));
+                        None
+                    }
+                    Err(error_message) => Some(OutgoingMessage::Error { error_message }),
+                }
+            }
+            IncommingMessage::Unsubscribe { game_id } => {
+                subscriptions.remove(&game_id);
+                finished_games.remove(&game_id);
+                None
+            }
+            IncommingMessage::StartReplay { interval_ms } => {
+                match data_provider.get_board(context.connected_game) {
+                    Ok(board) => {
+                        let interval = Duration::from_millis(interval_ms);
+                        *replay =
+                            Some(Self::replay(board, interval.max(Self::MIN_REPLAY_INTERVAL)));
+                        None
+                    }
+                    Err(e) => Some(OutgoingMessage::Error {
+                        error_message: Error::LoadingBoard(e.to_string()),
+                    }),
+                }
+            }
+            IncommingMessage::StopReplay {} => {
+                *replay = None;
+                None
+            }
+        }
+    }
+
+    /// the board after each move of the game, one every `period`, followed by the
+    /// game over if the game has ended
+    fn replay(board: Board, period: Duration) -> Replay {
+        let game_id = board.game_id;
+        let result = GameResult::from_state(board.get_state(), board.moves.len());
+        let frames = board
+            .replay()
+            .into_iter()
+            .map(move |game_state| OutgoingMessage::GameState {
+                game_id,
+                game_state,
+            })
+            .chain(result.map(|result| OutgoingMessage::GameOver { game_id, result }));
+        let ticks = stream::unfold(interval(period), |mut ticks| async move {
+            ticks.tick().await;
+            Some(((), ticks))
+        });
+        Box::pin(ticks.zip(stream::iter(frames)).map(|(_, frame)| frame))
+    }
+
+    /// the full board, together with the result of the game once it has ended
+    fn game_state_update(game_data: GameData) -> (OutgoingMessage, Option<GameResult>) {
+        let board = Board::from(game_data);
+        let result = GameResult::from_state(board.get_state(), board.moves.len());
+        (
+            OutgoingMessage::GameState {
+                game_id: board.game_id,
+                game_state: board,
+            },
+            result,
+        )
+    }
+
+    /// the moves the client doesn't have yet. If it claims to have more moves than the
+    /// game, the two are out of sync and it has to be sent the full board instead.
+    fn missing_moves(
+        have: Option<usize>,
+        game_data: &GameData,
+    ) -> Option<(OutgoingMessage, Option<GameResult>)> {
+        let base = have.filter(|have| *have <= game_data.moves.len())?;
+        Some((
+            OutgoingMessage::Moves {
+                game_id: game_data.game_id,
+                moves: game_data.moves[base..].to_vec(),
+                base,
+            },
+            game_data.result,
+        ))
+    }
+
+    // the handshake callback's error type is dictated by tungstenite
+    #[allow(clippy::result_large_err)]
+    async fn accept_connection(
+        stream: TcpStream,
+        peer_addr: SocketAddr,
+        wait_timeout: Duration,
+        data_provider: T,
+    ) -> Result<Self, Error> {
         let request_path = Arc::new(Mutex::new(String::new()));
+        let player_token = Arc::new(Mutex::new(None));
 
-        let ws_stream = accept_hdr_async(stream, |req: &Request, response: Response| {
-            *(request_path.lock().unwrap()) = req.uri().path().to_string();
+        let ws_stream = accept_hdr_async(stream, |req: &Request, mut response: Response| {
+            *(request_path.lock().unwrap()) = req
+                .uri()
+                .path_and_query()
+                .map(|path| path.to_string())
+                .unwrap_or_default();
+            let (token, via_protocol) = Self::bearer_token(req);
+            *(player_token.lock().unwrap()) = token;
+            // a client offering subprotocols fails the handshake unless one is accepted
+            if via_protocol {
+                response.headers_mut().insert(
+                    SEC_WEBSOCKET_PROTOCOL,
+                    HeaderValue::from_static(Self::BEARER_PROTOCOL),
+                );
+            }
             Ok(response)
         })
         .await
         .map_err(|ws_err| Error::HandShake(ws_err.to_string()))?;
 
         let path = request_path.lock().unwrap().deref().clone();
-        debug!("request path: {:?}", path);
-        StreamHandler::from_path(path, ws_stream, data_provider).await
+        let player_token = *player_token.lock().unwrap();
+        debug!("request path of {}: {:?}", peer_addr, path);
+        StreamHandler::from_path(
+            path,
+            peer_addr,
+            wait_timeout,
+            player_token,
+            ws_stream,
+            data_provider,
+        )
+        .await
+    }
+
+    /// reads the player token from the `Authorization: Bearer <token>` header or, for
+    /// browsers, from the subprotocols `bearer, <token>`. Also tells whether it was sent
+    /// as subprotocols, which have to be answered.
+    fn bearer_token(request: &Request) -> (Option<Uuid>, bool) {
+        let headers = request.headers();
+        let authorization = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| Uuid::parse_str(token.trim()).ok());
+        if authorization.is_some() {
+            return (authorization, false);
+        }
+        let protocols: Vec<&str> = headers
+            .get(SEC_WEBSOCKET_PROTOCOL)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.split(',').map(str::trim).collect())
+            .unwrap_or_default();
+        match protocols.as_slice() {
+            [Self::BEARER_PROTOCOL, token, ..] => (Uuid::parse_str(token).ok(), true),
+            _ => (None, false),
+        }
+    }
+
+    /// see `DataProvider::may_view`. If the game can't be loaded, nobody may follow it,
+    /// as it might be private.
+    fn is_authorized(data_provider: &T, game_id: Uuid, player_token: Option<Uuid>) -> bool {
+        data_provider
+            .may_view(game_id, player_token)
+            .unwrap_or_else(|e| {
+                warn!(
+                    "could not load game {} to authorize a client: {}",
+                    game_id, e
+                );
+                false
+            })
+    }
+
+    /// waits until the game is created, false if no more games are announced
+    async fn wait_for_game(
+        data_provider: &mut T,
+        game_id: Uuid,
+        mut new_games: Receiver<Uuid>,
+    ) -> bool {
+        loop {
+            match new_games.recv().await {
+                Ok(created) if created == game_id => return true,
+                Ok(_) => {}
+                // the game may be among the ones that were skipped
+                Err(RecvError::Lagged(_)) => {
+                    if data_provider.game_exists(game_id).unwrap_or(false) {
+                        return true;
+                    }
+                }
+                Err(RecvError::Closed) => return false,
+            }
+        }
     }
 
     async fn from_path(
         path: String,
+        peer_addr: SocketAddr,
+        wait_timeout: Duration,
+        player_token: Option<Uuid>,
         mut stream: WebSocketStream<TcpStream>,
         mut data_provider: T,
     ) -> Result<Self, Error> {
-        // path is made of the game_uuid: /<game_uuid>
-        // parse path
-        let mut path = path.split('/');
-        let game_uuid = path
-            .nth(1)
-            .ok_or_else(|| Error::InvalidUuid("No game uuid provided".to_string()))?;
+        let request = match ConnectionRequest::parse(&path) {
+            Ok(request) => request,
+            Err(e) => {
+                let _ = stream.close(None).await;
+                return Err(Error::HandShake(e.to_string()));
+            }
+        };
 
-        let game_id = Uuid::parse_str(game_uuid)
-            .map_err(|_| Error::InvalidUuid(format!("Invalid game uuid: {}", game_uuid)))?;
+        let game_id = match request.game_id {
+            RequestedGame::Id(game_id) => game_id,
+            RequestedGame::Featured => {
+                let featured_game = data_provider.get_featured_game().ok().flatten();
+                match featured_game {
+                    Some(game_id) => game_id,
+                    None => {
+                        let _ = stream.close(None).await;
+                        return Err(Error::GameNotFound);
+                    }
+                }
+            }
+        };
 
-        // check if uuid exists
-        if !data_provider.game_exists(game_id).unwrap_or(false) {
-            stream.close(None).await.unwrap();
+        // check if uuid exists, waiting for the game to be created if the client asked for it.
+        // The new games are subscribed to first, so a game created in between isn't missed.
+        let new_games = if request.wait {
+            data_provider
+                .subscribe_to_new_games()
+                .map_err(|e| warn!("Could not wait for game {}: {}", game_id, e))
+                .ok()
+        } else {
+            None
+        };
+        let exists = data_provider.game_exists(game_id).unwrap_or(false)
+            || match new_games {
+                Some(new_games) => timeout(
+                    wait_timeout,
+                    Self::wait_for_game(&mut data_provider, game_id, new_games),
+                )
+                .await
+                .unwrap_or(false),
+                None => false,
+            };
+        if !exists {
+            let _ = stream.close(None).await;
             return Err(Error::GameNotFound);
         }
 
+        if !Self::is_authorized(&data_provider, game_id, player_token) {
+            debug!("{} may not follow the private game {}", peer_addr, game_id);
+            let close_frame = CloseFrame {
+                code: CloseCode::from(Self::UNAUTHORIZED),
+                reason: Cow::Borrowed("unauthorized"),
+            };
+            let _ = stream.close(Some(close_frame)).await;
+            return Err(Error::Unauthorized);
+        }
+
         Ok(Self {
             stream,
             connected_game: game_id,
+            peer_addr,
+            subscription: request.role,
+            format: request.format,
+            linger: request.linger,
+            have: request.have,
+            player_token,
             data_provider,
         })
     }
@@ -0,0 +1,779 @@
+use crate::{
+    websocket::{Allowlist, AllowlistError},
+    CacheProviderArgs, Codec, RedisProviderArgs, StorageMode,
+};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    net::{IpAddr, Ipv6Addr},
+    num::{NonZeroU64, NonZeroUsize},
+    time::Duration,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    InvalidPort { name: String, value: String },
+    InvalidHost { name: String, value: String },
+    InvalidValue { name: String, value: String },
+    InvalidAllowlist(AllowlistError),
+    DuplicatePort(u16),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidPort { name, value } => {
+                write!(
+                    f,
+                    "{} must be a port between 1 and 65535, not {}",
+                    name, value
+                )
+            }
+            Self::InvalidHost { name, value } => {
+                write!(
+                    f,
+                    "{} must be a hostname or an ip address, not {}",
+                    name, value
+                )
+            }
+            Self::InvalidValue { name, value } => {
+                write!(f, "invalid value for {}: {}", name, value)
+            }
+            Self::InvalidAllowlist(e) => write!(f, "invalid WEBSOCKET_ALLOWLIST: {}", e),
+            Self::DuplicatePort(port) => write!(f, "port {} is used by more than one server", port),
+        }
+    }
+}
+
+/// the address a server listens on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+}
+
+impl ServerConfig {
+    pub const DEFAULT_PORT: u16 = 3000;
+    pub const DEFAULT_HOST: &'static str = "127.0.0.1";
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: Self::DEFAULT_HOST.to_string(),
+            port: Self::DEFAULT_PORT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebServerConfig {
+    pub server: ServerConfig,
+    /// how long browsers may cache the static assets without asking again
+    pub cache_max_age: Duration,
+}
+
+impl WebServerConfig {
+    pub const DEFAULT_CACHE_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+}
+
+impl Default for WebServerConfig {
+    fn default() -> Self {
+        Self {
+            server: ServerConfig::default(),
+            cache_max_age: Self::DEFAULT_CACHE_MAX_AGE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiConfig {
+    pub server: ServerConfig,
+    /// the largest request body that is accepted, in bytes
+    pub max_body_size: usize,
+    /// whether `POST /api/v1/admin/seed` creates games for load tests
+    pub enable_seed_endpoint: bool,
+    /// the bearer token the admin endpoints require. Without one they refuse every request.
+    pub admin_token: Option<String>,
+    /// the path all endpoints are mounted under, e.g. when a reverse proxy forwards
+    /// `/ttt/api` to the server. It starts with a slash and doesn't end with one.
+    pub base_path: String,
+}
+
+impl ApiConfig {
+    pub const DEFAULT_MAX_BODY_SIZE: usize = 256 * 1024;
+    pub const DEFAULT_BASE_PATH: &'static str = "/api/v1";
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            server: ServerConfig::default(),
+            max_body_size: Self::DEFAULT_MAX_BODY_SIZE,
+            enable_seed_endpoint: false,
+            admin_token: None,
+            base_path: Self::DEFAULT_BASE_PATH.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebSocketConfig {
+    pub server: ServerConfig,
+    /// if set, only clients from these networks may connect
+    pub allowlist: Option<Allowlist>,
+    /// how long clients connecting with `?wait=true` wait for their game to be created
+    pub wait_timeout: Duration,
+    /// how many clients may be connected at once
+    pub max_connections: usize,
+    /// if set, the updates of a subscription are collected for this long and sent
+    /// together, so a fast game is sent at most once per window
+    pub debounce: Option<Duration>,
+}
+
+impl WebSocketConfig {
+    pub const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(60);
+    pub const DEFAULT_MAX_CONNECTIONS: usize = 1024;
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            server: ServerConfig::default(),
+            allowlist: None,
+            wait_timeout: Self::DEFAULT_WAIT_TIMEOUT,
+            max_connections: Self::DEFAULT_MAX_CONNECTIONS,
+            debounce: None,
+        }
+    }
+}
+
+/// the background task that ends abandoned games as a draw, see `Reaper`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReaperConfig {
+    pub enabled: bool,
+    /// how often the games are checked
+    pub interval: Duration,
+    /// how long a game may go without a move before it is abandoned
+    pub idle_timeout: Duration,
+}
+
+impl ReaperConfig {
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+    pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
+}
+
+impl Default for ReaperConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: Self::DEFAULT_INTERVAL,
+            idle_timeout: Self::DEFAULT_IDLE_TIMEOUT,
+        }
+    }
+}
+
+/// the data provider the servers share when they all run in one process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DataProviderKind {
+    /// `DATA_PROVIDER=cache`: the games are kept in memory
+    #[default]
+    Cache,
+    /// `DATA_PROVIDER=redis`: the games are stored in redis
+    Redis,
+}
+
+/// how the binary writes its log lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// `LOG_FORMAT=text`: human readable lines for local development
+    #[default]
+    Text,
+    /// `LOG_FORMAT=json`: one json object per line for log aggregators
+    Json,
+}
+
+/// the configuration of all servers and data providers. It is loaded from the
+/// environment once at startup, every value is validated while loading.
+#[derive(Clone, Default)]
+pub struct Config {
+    pub webserver: WebServerConfig,
+    pub api: ApiConfig,
+    pub websocket: WebSocketConfig,
+    pub cache: CacheProviderArgs,
+    pub redis: RedisProviderArgs,
+    /// the data provider used when all servers are started together
+    pub data_provider: DataProviderKind,
+    /// the data provider used instead of redis if redis can't be reached at startup.
+    /// Only the cache can take its place.
+    pub data_provider_fallback: Option<DataProviderKind>,
+    pub reaper: ReaperConfig,
+    pub log_format: LogFormat,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_vars(&std::env::vars().collect())
+    }
+
+    /// loads the configuration from the given variables instead of the environment
+    pub fn from_vars(vars: &HashMap<String, String>) -> Result<Self, ConfigError> {
+        let vars = Vars(vars);
+        let redis_defaults = RedisProviderArgs::new();
+        // a buffer of 0 can't hold a single move
+        let move_buffer = vars.number::<NonZeroUsize>("MOVE_BUFFER_SIZE")?;
+
+        Ok(Self {
+            webserver: WebServerConfig {
+                server: vars.server("WEBSERVER")?,
+                cache_max_age: vars
+                    .number("WEBSERVER_CACHE_MAX_AGE")?
+                    .map(Duration::from_secs)
+                    .unwrap_or(WebServerConfig::DEFAULT_CACHE_MAX_AGE),
+            },
+            api: ApiConfig {
+                server: vars.server("API")?,
+                // a limit of 0 would refuse every body
+                max_body_size: vars
+                    .number::<NonZeroUsize>("API_MAX_BODY_SIZE")?
+                    .map_or(ApiConfig::DEFAULT_MAX_BODY_SIZE, NonZeroUsize::get),
+                enable_seed_endpoint: vars.flag("ENABLE_SEED_ENDPOINT")?,
+                admin_token: vars
+                    .get("ADMIN_TOKEN")
+                    .map(str::trim)
+                    .filter(|token| !token.is_empty())
+                    .map(str::to_string),
+                base_path: vars
+                    .base_path("API_BASE_PATH")?
+                    .unwrap_or(ApiConfig::DEFAULT_BASE_PATH.to_string()),
+            },
+            websocket: WebSocketConfig {
+                server: vars.server("WEBSOCKET")?,
+                allowlist: vars
+                    .get("WEBSOCKET_ALLOWLIST")
+                    .map(Allowlist::parse)
+                    .transpose()
+                    .map_err(ConfigError::InvalidAllowlist)?,
+                wait_timeout: vars
+                    .number("WEBSOCKET_WAIT_TIMEOUT")?
+                    .map(Duration::from_secs)
+                    .unwrap_or(WebSocketConfig::DEFAULT_WAIT_TIMEOUT),
+                // a limit of 0 would refuse every client
+                max_connections: vars
+                    .number::<NonZeroUsize>("WEBSOCKET_MAX_CONNECTIONS")?
+                    .map_or(WebSocketConfig::DEFAULT_MAX_CONNECTIONS, NonZeroUsize::get),
+                debounce: vars
+                    .number::<NonZeroU64>("WEBSOCKET_DEBOUNCE_MS")?
+                    .map(|window| Duration::from_millis(window.get())),
+            },
+            cache: CacheProviderArgs {
+                max_entries: vars.number("CACHE_MAX_ENTRIES")?,
+                move_buffer,
+                ..Default::default()
+            },
+            redis: RedisProviderArgs {
+                server_hostname: vars
+                    .host("REDIS_SERVER_HOSTNAME")?
+                    .unwrap_or(redis_defaults.server_hostname),
+                server_port: vars
+                    .port("REDIS_SERVER_PORT")?
+                    .unwrap_or(redis_defaults.server_port),
+                username: vars.get("REDIS_USERNAME").map(str::to_string),
+                password: vars.get("REDIS_PASSWORD").map(str::to_string),
+                game_ttl: vars.number("REDIS_GAME_TTL")?,
+                use_tls: vars.flag("REDIS_TLS")?,
+                cluster: vars.flag("REDIS_CLUSTER")?,
+                storage_mode: vars.storage_mode("REDIS_STORAGE", "REDIS_COMPRESS")?,
+                move_buffer,
+            },
+            data_provider: vars.data_provider_kind("DATA_PROVIDER")?,
+            data_provider_fallback: vars.data_provider_fallback("DATA_PROVIDER_FALLBACK")?,
+            reaper: ReaperConfig {
+                enabled: vars.flag("ENABLE_REAPER")?,
+                // an interval of 0 would check the games all the time
+                interval: vars
+                    .number::<NonZeroU64>("REAPER_INTERVAL")?
+                    .map_or(ReaperConfig::DEFAULT_INTERVAL, |interval| {
+                        Duration::from_secs(interval.get())
+                    }),
+                idle_timeout: vars
+                    .number("REAPER_IDLE_TIMEOUT")?
+                    .map(Duration::from_secs)
+                    .unwrap_or(ReaperConfig::DEFAULT_IDLE_TIMEOUT),
+            },
+            log_format: vars.log_format("LOG_FORMAT")?,
+        })
+    }
+
+    /// checks that the servers listen on different ports, which is required when
+    /// they are all started in the same process
+    pub fn check_distinct_ports(&self) -> Result<(), ConfigError> {
+        let ports = [
+            self.webserver.server.port,
+            self.api.server.port,
+            self.websocket.server.port,
+        ];
+        for (index, port) in ports.iter().enumerate() {
+            if ports[..index].contains(port) {
+                return Err(ConfigError::DuplicatePort(*port));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// the raw variables, with a parser for each kind of value
+struct Vars<'a>(&'a HashMap<String, String>);
+
+impl Vars<'_> {
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    fn invalid_value(name: &str, value: &str) -> ConfigError {
+        ConfigError::InvalidValue {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    /// reads `<prefix>_HOST` and `<prefix>_PORT`
+    fn server(&self, prefix: &str) -> Result<ServerConfig, ConfigError> {
+        Ok(ServerConfig {
+            host: self
+                .host(&format!("{}_HOST", prefix))?
+                .unwrap_or(ServerConfig::DEFAULT_HOST.to_string()),
+            port: self
+                .port(&format!("{}_PORT", prefix))?
+                .unwrap_or(ServerConfig::DEFAULT_PORT),
+        })
+    }
+
+    fn port(&self, name: &str) -> Result<Option<u16>, ConfigError> {
+        self.get(name)
+            .map(|value| {
+                value
+                    .parse::<u16>()
+                    .ok()
+                    .filter(|port| *port != 0)
+                    .ok_or_else(|| ConfigError::InvalidPort {
+                        name: name.to_string(),
+                        value: value.to_string(),
+                    })
+            })
+            .transpose()
+    }
+
+    /// a host must be usable in `<host>:<port>`, so ipv6 addresses need brackets
+    fn host(&self, name: &str) -> Result<Option<String>, ConfigError> {
+        self.get(name)
+            .map(|value| {
+                let is_valid = match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                    Some(address) => address.parse::<Ipv6Addr>().is_ok(),
+                    None => {
+                        matches!(value.parse::<IpAddr>(), Ok(IpAddr::V4(_)))
+                            || is_valid_hostname(value)
+                    }
+                };
+                if is_valid {
+                    Ok(value.to_string())
+                } else {
+                    Err(ConfigError::InvalidHost {
+                        name: name.to_string(),
+                        value: value.to_string(),
+                    })
+                }
+            })
+            .transpose()
+    }
+
+    /// a path starting with a slash, without the trailing slash. `/` mounts the
+    /// endpoints at the root, which is the empty path.
+    fn base_path(&self, name: &str) -> Result<Option<String>, ConfigError> {
+        self.get(name)
+            .map(|value| {
+                let is_valid = value.starts_with('/')
+                    && !value.contains("//")
+                    && value
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "/-._~".contains(c));
+                if is_valid {
+                    Ok(value.trim_end_matches('/').to_string())
+                } else {
+                    Err(Self::invalid_value(name, value))
+                }
+            })
+            .transpose()
+    }
+
+    fn number<N: std::str::FromStr>(&self, name: &str) -> Result<Option<N>, ConfigError> {
+        self.get(name)
+            .map(|value| {
+                value
+                    .parse::<N>()
+                    .map_err(|_| Self::invalid_value(name, value))
+            })
+            .transpose()
+    }
+
+    fn flag(&self, name: &str) -> Result<bool, ConfigError> {
+        match self.get(name).map(str::to_lowercase).as_deref() {
+            None | Some("0" | "false" | "no") => Ok(false),
+            Some("1" | "true" | "yes") => Ok(true),
+            Some(value) => Err(Self::invalid_value(name, value)),
+        }
+    }
+
+    /// the storage mode named by `name`, or compressed strings if a codec is named by
+    /// `codec_name`. Compressed games can't be modified by RedisJSON, so a codec can't
+    /// be combined with the json mode.
+    fn storage_mode(
+        &self,
+        name: &str,
+        codec_name: &str,
+    ) -> Result<Option<StorageMode>, ConfigError> {
+        let codec_value = self.get(codec_name);
+        let codec = match codec_value.map(str::to_lowercase).as_deref() {
+            None => None,
+            Some("gzip") => Some(Codec::Gzip),
+            Some("zstd") => Some(Codec::Zstd),
+            Some(value) => return Err(Self::invalid_value(codec_name, value)),
+        };
+        match (self.get(name).map(str::to_lowercase).as_deref(), codec) {
+            (None, None) => Ok(None),
+            (Some("json"), None) => Ok(Some(StorageMode::RedisJson)),
+            (Some("string"), None) => Ok(Some(StorageMode::String)),
+            (None | Some("string"), Some(codec)) => Ok(Some(StorageMode::Compressed(codec))),
+            (Some("json"), Some(_)) => Err(Self::invalid_value(
+                codec_name,
+                codec_value.unwrap_or_default(),
+            )),
+            (Some(value), _) => Err(Self::invalid_value(name, value)),
+        }
+    }
+
+    fn data_provider_kind(&self, name: &str) -> Result<DataProviderKind, ConfigError> {
+        match self.get(name).map(str::to_lowercase).as_deref() {
+            None | Some("cache") => Ok(DataProviderKind::Cache),
+            Some("redis") => Ok(DataProviderKind::Redis),
+            Some(value) => Err(Self::invalid_value(name, value)),
+        }
+    }
+
+    fn data_provider_fallback(&self, name: &str) -> Result<Option<DataProviderKind>, ConfigError> {
+        match self.get(name).map(str::to_lowercase).as_deref() {
+            None => Ok(None),
+            Some("cache") => Ok(Some(DataProviderKind::Cache)),
+            Some(value) => Err(Self::invalid_value(name, value)),
+        }
+    }
+
+    fn log_format(&self, name: &str) -> Result<LogFormat, ConfigError> {
+        match self.get(name).map(str::to_lowercase).as_deref() {
+            None | Some("text") => Ok(LogFormat::Text),
+            Some("json") => Ok(LogFormat::Json),
+            Some(value) => Err(Self::invalid_value(name, value)),
+        }
+    }
+}
+
+fn is_valid_hostname(host: &str) -> bool {
+    host.len() <= 253
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn vars(vars: &[(&str, &str)]) -> HashMap<String, String> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn defaults() {
+        let config = Config::from_vars(&HashMap::new()).unwrap();
+        assert_eq!(config.webserver, WebServerConfig::default());
+        assert_eq!(config.api, ApiConfig::default());
+        assert_eq!(config.websocket, WebSocketConfig::default());
+        assert_eq!(config.cache.max_entries, None);
+        assert_eq!(config.cache.move_buffer, None);
+        assert_eq!(config.redis.server_hostname, "localhost");
+        assert_eq!(config.redis.server_port, 6379);
+        assert!(!config.redis.use_tls);
+        assert_eq!(config.data_provider, DataProviderKind::Cache);
+        assert_eq!(config.data_provider_fallback, None);
+        assert_eq!(config.reaper, ReaperConfig::default());
+        assert_eq!(config.log_format, LogFormat::Text);
+
+        // every server uses the default port, which is only fine in separate processes
+        assert_eq!(
+            config.check_distinct_ports(),
+            Err(ConfigError::DuplicatePort(ServerConfig::DEFAULT_PORT))
+        );
+    }
+
+    #[test]
+    fn valid_config() {
+        let config = Config::from_vars(&vars(&[
+            ("WEBSERVER_PORT", "3000"),
+            ("WEBSERVER_HOST", "[::]"),
+            ("WEBSERVER_CACHE_MAX_AGE", "600"),
+            ("API_PORT", "4000"),
+            ("API_HOST", "0.0.0.0"),
+            ("API_MAX_BODY_SIZE", "1024"),
+            ("ENABLE_SEED_ENDPOINT", "1"),
+            ("ADMIN_TOKEN", "secret"),
+            ("API_BASE_PATH", "/ttt/api/"),
+            ("WEBSOCKET_PORT", "5000"),
+            ("WEBSOCKET_HOST", "localhost"),
+            ("WEBSOCKET_ALLOWLIST", "10.0.0.0/8"),
+            ("WEBSOCKET_WAIT_TIMEOUT", "5"),
+            ("WEBSOCKET_MAX_CONNECTIONS", "10"),
+            ("WEBSOCKET_DEBOUNCE_MS", "50"),
+            ("CACHE_MAX_ENTRIES", "100"),
+            ("MOVE_BUFFER_SIZE", "16"),
+            ("REDIS_SERVER_HOSTNAME", "redis.example.com"),
+            ("REDIS_SERVER_PORT", "6380"),
+            ("REDIS_GAME_TTL", "3600"),
+            ("REDIS_TLS", "true"),
+            ("REDIS_STORAGE", "string"),
+            ("DATA_PROVIDER", "redis"),
+            ("DATA_PROVIDER_FALLBACK", "cache"),
+            ("ENABLE_REAPER", "true"),
+            ("REAPER_INTERVAL", "30"),
+            ("REAPER_IDLE_TIMEOUT", "3600"),
+            ("LOG_FORMAT", "JSON"),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            config.webserver.server,
+            ServerConfig {
+                host: "[::]".to_string(),
+                port: 3000
+            }
+        );
+        assert_eq!(config.webserver.cache_max_age, Duration::from_secs(600));
+        assert_eq!(config.api.server.port, 4000);
+        assert_eq!(config.api.max_body_size, 1024);
+        assert!(config.api.enable_seed_endpoint);
+        assert_eq!(config.api.admin_token.as_deref(), Some("secret"));
+        assert_eq!(config.api.base_path, "/ttt/api");
+        assert_eq!(config.websocket.server.host, "localhost");
+        assert_eq!(
+            config.websocket.allowlist,
+            Some(Allowlist::parse("10.0.0.0/8").unwrap())
+        );
+        assert_eq!(config.websocket.wait_timeout, Duration::from_secs(5));
+        assert_eq!(config.websocket.max_connections, 10);
+        assert_eq!(config.websocket.debounce, Some(Duration::from_millis(50)));
+        assert_eq!(config.cache.max_entries, Some(100));
+        assert_eq!(config.cache.move_buffer, NonZeroUsize::new(16));
+        assert_eq!(config.redis.move_buffer, NonZeroUsize::new(16));
+        assert_eq!(config.redis.get_url(), "rediss://redis.example.com:6380");
+        assert_eq!(config.redis.game_ttl, Some(3600));
+        assert!(!config.redis.cluster);
+        assert_eq!(config.redis.storage_mode, Some(StorageMode::String));
+        assert_eq!(config.data_provider, DataProviderKind::Redis);
+        assert_eq!(config.data_provider_fallback, Some(DataProviderKind::Cache));
+        assert_eq!(
+            config.reaper,
+            ReaperConfig {
+                enabled: true,
+                interval: Duration::from_secs(30),
+                idle_timeout: Duration::from_secs(3600),
+            }
+        );
+        assert_eq!(config.log_format, LogFormat::Json);
+        assert_eq!(config.check_distinct_ports(), Ok(()));
+    }
+
+    #[test]
+    fn invalid_config() {
+        for (name, value, expected_error) in [
+            (
+                "API_PORT",
+                "70000",
+                ConfigError::InvalidPort {
+                    name: "API_PORT".to_string(),
+                    value: "70000".to_string(),
+                },
+            ),
+            (
+                "WEBSOCKET_PORT",
+                "0",
+                ConfigError::InvalidPort {
+                    name: "WEBSOCKET_PORT".to_string(),
+                    value: "0".to_string(),
+                },
+            ),
+            (
+                "WEBSERVER_HOST",
+                "::",
+                ConfigError::InvalidHost {
+                    name: "WEBSERVER_HOST".to_string(),
+                    value: "::".to_string(),
+                },
+            ),
+            (
+                "REDIS_SERVER_HOSTNAME",
+                "not a host",
+                ConfigError::InvalidHost {
+                    name: "REDIS_SERVER_HOSTNAME".to_string(),
+                    value: "not a host".to_string(),
+                },
+            ),
+            (
+                "CACHE_MAX_ENTRIES",
+                "-1",
+                ConfigError::InvalidValue {
+                    name: "CACHE_MAX_ENTRIES".to_string(),
+                    value: "-1".to_string(),
+                },
+            ),
+            (
+                "WEBSOCKET_MAX_CONNECTIONS",
+                "0",
+                ConfigError::InvalidValue {
+                    name: "WEBSOCKET_MAX_CONNECTIONS".to_string(),
+                    value: "0".to_string(),
+                },
+            ),
+            (
+                "WEBSOCKET_DEBOUNCE_MS",
+                "0",
+                ConfigError::InvalidValue {
+                    name: "WEBSOCKET_DEBOUNCE_MS".to_string(),
+                    value: "0".to_string(),
+                },
+            ),
+            (
+                "MOVE_BUFFER_SIZE",
+                "0",
+                ConfigError::InvalidValue {
+                    name: "MOVE_BUFFER_SIZE".to_string(),
+                    value: "0".to_string(),
+                },
+            ),
+            (
+                "API_MAX_BODY_SIZE",
+                "0",
+                ConfigError::InvalidValue {
+                    name: "API_MAX_BODY_SIZE".to_string(),
+                    value: "0".to_string(),
+                },
+            ),
+            (
+                "API_BASE_PATH",
+                "api/v1",
+                ConfigError::InvalidValue {
+                    name: "API_BASE_PATH".to_string(),
+                    value: "api/v1".to_string(),
+                },
+            ),
+            (
+                "REAPER_INTERVAL",
+                "0",
+                ConfigError::InvalidValue {
+                    name: "REAPER_INTERVAL".to_string(),
+                    value: "0".to_string(),
+                },
+            ),
+            (
+                "REDIS_CLUSTER",
+                "maybe",
+                ConfigError::InvalidValue {
+                    name: "REDIS_CLUSTER".to_string(),
+                    value: "maybe".to_string(),
+                },
+            ),
+            (
+                "DATA_PROVIDER",
+                "postgres",
+                ConfigError::InvalidValue {
+                    name: "DATA_PROVIDER".to_string(),
+                    value: "postgres".to_string(),
+                },
+            ),
+            (
+                "DATA_PROVIDER_FALLBACK",
+                "redis",
+                ConfigError::InvalidValue {
+                    name: "DATA_PROVIDER_FALLBACK".to_string(),
+                    value: "redis".to_string(),
+                },
+            ),
+            (
+                "LOG_FORMAT",
+                "xml",
+                ConfigError::InvalidValue {
+                    name: "LOG_FORMAT".to_string(),
+                    value: "xml".to_string(),
+                },
+            ),
+            (
+                "WEBSOCKET_ALLOWLIST",
+                "10.0.0.0/33",
+                ConfigError::InvalidAllowlist(AllowlistError::InvalidPrefixLength(
+                    "33".to_string(),
+                )),
+            ),
+        ] {
+            assert_eq!(
+                Config::from_vars(&vars(&[(name, value)])).err(),
+                Some(expected_error)
+            );
+        }
+    }
+
+    #[test]
+    fn compressed_storage_mode() {
+        for (storage, codec, expected_mode) in [
+            (None, None, Ok(None)),
+            (
+                None,
+                Some("gzip"),
+                Ok(Some(StorageMode::Compressed(Codec::Gzip))),
+            ),
+            (
+                Some("string"),
+                Some("ZSTD"),
+                Ok(Some(StorageMode::Compressed(Codec::Zstd))),
+            ),
+            (Some("json"), None, Ok(Some(StorageMode::RedisJson))),
+            (
+                Some("json"),
+                Some("zstd"),
+                Err(ConfigError::InvalidValue {
+                    name: "REDIS_COMPRESS".to_string(),
+                    value: "zstd".to_string(),
+                }),
+            ),
+            (
+                None,
+                Some("lz4"),
+                Err(ConfigError::InvalidValue {
+                    name: "REDIS_COMPRESS".to_string(),
+                    value: "lz4".to_string(),
+                }),
+            ),
+        ] {
+            let mut pairs = vec![];
+            pairs.extend(storage.map(|storage| ("REDIS_STORAGE", storage)));
+            pairs.extend(codec.map(|codec| ("REDIS_COMPRESS", codec)));
+            assert_eq!(
+                Config::from_vars(&vars(&pairs)).map(|config| config.redis.storage_mode),
+                expected_mode
+            );
+        }
+    }
+}
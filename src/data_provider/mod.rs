@@ -1,12 +1,17 @@
 mod factory;
 mod providers;
+mod reaper;
 
 pub use factory::DataProviderFactory;
 pub use providers::*;
+pub use reaper::Reaper;
 use serde::{Deserialize, Serialize};
 
-use crate::{Board, GameData, Move};
-use core::fmt::Debug;
+use crate::{
+    Board, GameData, GameDelta, GameResult, GlobalStats, InvalidGameData, InvalidMove, Move,
+    PlayerStats, Tournament,
+};
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 /// DataProvider is a trait that defines the interface for a data provider.
@@ -14,21 +19,188 @@ use uuid::Uuid;
 /// be for example in redis, in a file or in memory.
 pub trait DataProvider: Send + Clone {
     type Args: Clone;
-    type ErrorKind: Debug + Clone + PartialEq + Eq + ToString + Deserialize<'static> + Serialize;
+    type ErrorKind: std::error::Error
+        + Clone
+        + PartialEq
+        + Eq
+        + Deserialize<'static>
+        + Serialize
+        + From<InvalidMove>
+        + From<InvalidGameData>;
 
+    /// returns the ids of all stored games, in no particular order.
     fn get_games(&self) -> Result<Vec<Uuid>, Self::ErrorKind>;
 
+    /// returns the ids of the games created after the given time, oldest first.
+    /// Clients polling for new games only need to ask for the games created since
+    /// their last poll instead of fetching every id.
+    fn list_games_since(&self, after: DateTime<Utc>) -> Result<Vec<Uuid>, Self::ErrorKind>;
+
+    /// returns the ids of the games, newest first, together with their results, which
+    /// are `None` while a game is in progress. The first `offset` games are skipped and
+    /// at most `limit` games are returned, so an archive can be paged through without
+    /// fetching every game.
+    fn list_results(
+        &self,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<(Uuid, Option<GameResult>)>, Self::ErrorKind>;
+
     /// returns the game data for a given game id.
     /// This means that it will have  to fetch the data from its source,
     /// serialize it if needed and return it.
     fn get_game_data(&self, game_id: Uuid) -> Result<GameData, Self::ErrorKind>;
 
-    /// adds a move to the game for a given game id.
+    /// returns the number of moves of the game, for when the moves themselves aren't needed
+    fn move_count(&self, game_id: Uuid) -> Result<usize, Self::ErrorKind> {
+        Ok(self.get_game_data(game_id)?.moves.len())
+    }
+
+    /// returns the index and the last move of the game, or None if no move was made yet,
+    /// for clients that sync a game move by move
+    fn get_last_move(&self, game_id: Uuid) -> Result<Option<(usize, Move)>, Self::ErrorKind> {
+        let moves = self.get_game_data(game_id)?.moves;
+        Ok(moves.last().map(|last_move| (moves.len() - 1, *last_move)))
+    }
+
+    /// returns the board for a given game id.
+    /// The game data is fetched and its moves are replayed once, an invalid
+    /// move in the stored data is reported as the provider's error kind.
+    fn get_board(&self, game_id: Uuid) -> Result<Board, Self::ErrorKind> {
+        Ok(Board::try_from_game_data(self.get_game_data(game_id)?)?)
+    }
+
+    /// whether the game may be viewed by a client with the given player token.
+    /// Everyone may view a game that isn't private, a private game only the player
+    /// tokens registered in it.
+    fn may_view(&self, game_id: Uuid, player_token: Option<Uuid>) -> Result<bool, Self::ErrorKind> {
+        if !self.get_game_data(game_id)?.settings.private {
+            return Ok(true);
+        }
+        match player_token {
+            Some(player_token) => Ok(self.get_player_games(player_token)?.contains(&game_id)),
+            None => Ok(false),
+        }
+    }
+
+    /// returns the id of the in-progress game with the most moves, if there is any.
+    /// Private games are never featured, and neither are games that can't be loaded,
+    /// e.g. because they have expired during the scan. This scans all games, so it is
+    /// meant for occasional use like demos.
+    fn get_featured_game(&self) -> Result<Option<Uuid>, Self::ErrorKind> {
+        let mut featured_game = None;
+        for game_id in self.get_games()? {
+            let Ok(game_data) = self.get_game_data(game_id) else {
+                continue;
+            };
+            if game_data.settings.private {
+                continue;
+            }
+            // a stored result, e.g. of an abandoned game, ends the game before its board does
+            let has_result = game_data.result.is_some();
+            let Ok(board) = Board::try_from_game_data(game_data) else {
+                continue;
+            };
+            if has_result || board.is_over() {
+                continue;
+            }
+            if featured_game.is_none_or(|(_, move_count)| board.moves.len() > move_count) {
+                featured_game = Some((game_id, board.moves.len()));
+            }
+        }
+        Ok(featured_game.map(|(game_id, _)| game_id))
+    }
+
+    /// ends the game as an abandoned draw, see `GameResult::abandoned`, and sends it to
+    /// the subscribers of the game. Returns false if the game already has a result,
+    /// which is never replaced.
+    fn abandon_game(&mut self, game_id: Uuid) -> Result<bool, Self::ErrorKind>;
+
+    /// abandons every game without a result whose last activity, see
+    /// `GameData::last_activity`, was before `idle_since`, in milliseconds since the unix
+    /// epoch, and returns the ids of the abandoned games. This scans all games, so it is
+    /// meant to run every now and then, see `Reaper`.
+    fn abandon_idle_games(&mut self, idle_since: u64) -> Result<Vec<Uuid>, Self::ErrorKind> {
+        let mut abandoned = vec![];
+        for game_id in self.get_games()? {
+            let game_data = self.get_game_data(game_id)?;
+            if game_data.result.is_some()
+                || game_data.compute_result().is_some()
+                || game_data.last_activity() >= idle_since
+            {
+                continue;
+            }
+            if self.abandon_game(game_id)? {
+                abandoned.push(game_id);
+            }
+        }
+        Ok(abandoned)
+    }
+
+    /// adds a move to the game for a given game id. Games that have a result are refused
+    /// with `InvalidMove::GameEnded`, unless their board is full already.
     fn add_move(&mut self, game_id: Uuid, new_move: Move) -> Result<(), Self::ErrorKind>;
 
+    /// adds a move to the game for a given game id, but only if the game
+    /// currently has exactly `expected_count` moves. Otherwise the game has
+    /// been changed in the meantime and a conflict error is returned.
+    fn add_move_if_count(
+        &mut self,
+        game_id: Uuid,
+        expected_count: usize,
+        new_move: Move,
+    ) -> Result<(), Self::ErrorKind>;
+
+    /// takes back every move of the game after the first `move_number` moves and returns
+    /// the removed moves in the order they were made. Subscribers of the game receive the
+    /// truncated game data, there is no message for removed moves on the move subscription.
+    /// Games that have ended are refused with `InvalidMove::GameEnded`, their result is final.
+    fn truncate_game(
+        &mut self,
+        game_id: Uuid,
+        move_number: usize,
+    ) -> Result<Vec<Move>, Self::ErrorKind>;
+
+    /// returns a copy of the game data that can be handed to `restore` later on,
+    /// e.g. to undo an edit of the game.
+    fn snapshot(&self, game_id: Uuid) -> Result<GameData, Self::ErrorKind> {
+        self.get_game_data(game_id)
+    }
+
+    /// replaces the data of an existing game with a snapshot at once. The game keeps its
+    /// id, creation time and creator. The snapshot is validated like an imported game first.
+    /// Subscribers of the game receive the restored game data, there
+    /// is no message for changed moves on the move subscription.
+    fn restore(&mut self, game_id: Uuid, snapshot: GameData) -> Result<(), Self::ErrorKind>;
+
     /// creates a new game and returns the game id.
     fn create_game(&mut self, uuid: Option<Uuid>) -> Result<Uuid, Self::ErrorKind>;
 
+    /// creates a new game with X's opening move already made and returns the game id.
+    /// The move is validated against an empty board, the game is only created if it is
+    /// a legal opening and both are stored at once, so no other move can get in between.
+    fn create_game_with_move(
+        &mut self,
+        uuid: Option<Uuid>,
+        first: Move,
+    ) -> Result<Uuid, Self::ErrorKind>;
+
+    /// creates a new game from the given game data, e.g. to attribute it with
+    /// `GameData::created_by`, and returns the game id. The game gets the given id or a
+    /// new one and is created now. Its moves are checked with `GameData::validate` first,
+    /// the game is only created if they are valid.
+    fn create_game_from(
+        &mut self,
+        uuid: Option<Uuid>,
+        template: GameData,
+    ) -> Result<Uuid, Self::ErrorKind>;
+
+    /// stores the game data as it is, keeping its id and creation time, e.g. to move
+    /// games to another provider. Like `create_game_from` the moves are validated first,
+    /// and an existing game with the same id is never replaced. The result of the game
+    /// isn't counted in the statistics again.
+    fn import_game(&mut self, game_data: GameData) -> Result<Uuid, Self::ErrorKind>;
+
     fn new(args: Self::Args) -> Result<Self, Self::ErrorKind>
     where
         Self: Sized;
@@ -38,9 +210,81 @@ pub trait DataProvider: Send + Clone {
         game_id: Uuid,
     ) -> Result<tokio::sync::watch::Receiver<GameData>, Self::ErrorKind>;
 
-    /// checks if a game exists for a given game id.
+    /// subscribes to every single move made in a game.
+    /// Unlike `subscribe_to_game`, which only keeps the latest game data, each
+    /// move is delivered in order together with its index, starting with the first
+    /// move made after subscribing.
+    fn subscribe_to_game_moves(
+        &mut self,
+        game_id: Uuid,
+    ) -> Result<tokio::sync::broadcast::Receiver<GameDelta>, Self::ErrorKind>;
+
+    /// subscribes to the ids of the games created from now on, e.g. to wait for a game
+    /// that doesn't exist yet. A game created right before subscribing is missed, so
+    /// check whether it exists after subscribing.
+    fn subscribe_to_new_games(
+        &mut self,
+    ) -> Result<tokio::sync::broadcast::Receiver<Uuid>, Self::ErrorKind>;
+
+    /// removes every game together with its players and the subscriptions to it, and
+    /// returns how many games were removed. Statistics and tournaments are kept.
+    fn clear_all(&mut self) -> Result<usize, Self::ErrorKind>;
+
+    /// the name of the storage, e.g. `cache` or `redis`
+    fn kind(&self) -> &'static str;
+
+    /// how the storage keeps the games, for storages that can keep them in more than one
+    /// way, e.g. the `StorageMode` of redis
+    fn storage_description(&self) -> Option<String> {
+        None
+    }
+
+    /// checks that the storage can be reached
+    fn ping(&self) -> Result<(), Self::ErrorKind>;
+
+    /// stops every background task serving subscriptions and drops their channels, so
+    /// all receivers handed out by `subscribe_to_game` and `subscribe_to_game_moves`
+    /// are closed. Meant for shutting down, though later subscriptions work as before.
+    fn close(&mut self) -> Result<(), Self::ErrorKind>;
+
+    /// checks if a game exists for a given game id. This must take constant time and
+    /// must not read the game itself, as it is called before most operations on a game.
+    /// Callers that only need to know whether a game exists use it instead of
+    /// `get_game_data`.
     fn game_exists(&mut self, game_id: Uuid) -> Result<bool, Self::ErrorKind>;
 
+    /// checks at once whether another player can join the game: it exists, it has no
+    /// result yet and fewer than two player tokens, for X and O, are registered in it.
+    /// Providers answer this without reading the whole game where they can.
+    fn is_joinable(&self, game_id: Uuid) -> Result<bool, Self::ErrorKind>;
+
+    /// marks a game as recently used without modifying it, e.g. to keep it
+    /// from expiring or from being evicted while clients are connected.
+    fn touch(&mut self, game_id: Uuid) -> Result<(), Self::ErrorKind>;
+
+    /// registers a player token as a participant of the game with the given id.
+    /// The token is added to a reverse index so that the games of a player can
+    /// be looked up later on. The first two tokens registered in a game play X and
+    /// O, in that order, which decides whom its result counts towards.
+    fn register_player(&mut self, game_id: Uuid, player_token: Uuid)
+        -> Result<(), Self::ErrorKind>;
+
+    /// returns the ids of all games the given player token is registered in.
+    fn get_player_games(&self, player_token: Uuid) -> Result<Vec<Uuid>, Self::ErrorKind>;
+
+    /// returns how many of all finished games were won by either player or drawn.
+    fn get_global_stats(&self) -> Result<GlobalStats, Self::ErrorKind>;
+
+    /// returns how many of the finished games the given player token took part in it
+    /// won, lost or drew.
+    fn get_player_stats(&self, player_token: Uuid) -> Result<PlayerStats, Self::ErrorKind>;
+
+    /// stores a tournament, replacing the tournament with the same id if there is one.
+    fn store_tournament(&mut self, tournament: &Tournament) -> Result<(), Self::ErrorKind>;
+
+    /// returns the tournament with the given id.
+    fn get_tournament(&self, tournament_id: Uuid) -> Result<Tournament, Self::ErrorKind>;
+
     /// syncs the board with the data provider.
     /// This means check if there are any remote moves that are not in the board
     /// and add them to the board, and check if there are any local moves that
@@ -1,97 +1,586 @@
 use std::{
-    collections::{hash_map::Entry, HashMap},
-    sync::{Arc, Mutex},
+    collections::{hash_map::Entry, BTreeSet, HashMap, HashSet},
+    fmt::Display,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
 };
 
+use chrono::{DateTime, Utc};
+use log::debug;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::{DataProvider, GameData};
+use crate::{
+    Board, Config, DataProvider, GameData, GameDelta, GameResult, GlobalStats, InvalidGameData,
+    InvalidMove, Move, Player, PlayerStats, Tournament,
+};
 
-#[derive(Clone)]
-pub struct CacheProviderArgs {}
+#[derive(Clone, Default)]
+pub struct CacheProviderArgs {
+    /// the maximum number of games kept in memory. If it is exceeded, the
+    /// least recently accessed game is evicted. `None` means unlimited.
+    pub max_entries: Option<usize>,
+    /// seeds the generation of game ids, which makes them reproducible.
+    /// `None` uses random v4 uuids.
+    pub seed: Option<u64>,
+    /// how many moves a move subscriber may fall behind before it misses moves.
+    /// `None` uses the default of 64.
+    pub move_buffer: Option<NonZeroUsize>,
+}
+
+impl CacheProviderArgs {
+    pub fn from_env() -> Self {
+        Config::from_env()
+            .unwrap_or_else(|e| panic!("Invalid configuration: {}", e))
+            .cache
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub enum CacheProviderErrorKind {
     LockError,
     KeyNotFound,
     GameExists,
+    InvalidMove(InvalidMove),
+    /// the moves of a game to store don't form a valid game
+    InvalidGameData(InvalidGameData),
+    Conflict,
+    BoardFull,
+    TooFast,
+    /// the game has fewer moves than should be kept of it
+    NotEnoughMoves,
+}
+
+impl From<InvalidMove> for CacheProviderErrorKind {
+    fn from(invalid_move: InvalidMove) -> Self {
+        Self::InvalidMove(invalid_move)
+    }
+}
+
+impl From<InvalidGameData> for CacheProviderErrorKind {
+    fn from(invalid_game_data: InvalidGameData) -> Self {
+        Self::InvalidGameData(invalid_game_data)
+    }
 }
-impl ToString for CacheProviderErrorKind {
-    fn to_string(&self) -> String {
+impl Display for CacheProviderErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            CacheProviderErrorKind::GameExists => "the game allready exists",
-            CacheProviderErrorKind::KeyNotFound => "the game does not exist",
-            CacheProviderErrorKind::LockError => "could not aquire lock on hashmap",
+            CacheProviderErrorKind::GameExists => write!(f, "the game allready exists"),
+            CacheProviderErrorKind::KeyNotFound => write!(f, "the game does not exist"),
+            CacheProviderErrorKind::LockError => write!(f, "could not aquire lock on hashmap"),
+            CacheProviderErrorKind::Conflict => {
+                write!(f, "the game has changed since the move count was read")
+            }
+            CacheProviderErrorKind::TooFast => {
+                write!(f, "the player's previous move was made too recently")
+            }
+            CacheProviderErrorKind::BoardFull => {
+                write!(f, "the game already has a move for every field")
+            }
+            CacheProviderErrorKind::NotEnoughMoves => {
+                write!(f, "the game has fewer moves than should be kept")
+            }
+            CacheProviderErrorKind::InvalidMove(invalid_move) => {
+                write!(f, "the game contains an invalid move: {:?}", invalid_move)
+            }
+            CacheProviderErrorKind::InvalidGameData(invalid_game_data) => {
+                write!(f, "the game is invalid: {}", invalid_game_data)
+            }
         }
-        .to_string()
     }
 }
 
+impl std::error::Error for CacheProviderErrorKind {}
+
 #[derive(Clone)]
 pub struct CacheProvider {
     pub hash_map: Arc<Mutex<HashMap<Uuid, GameData>>>,
     pub channels: Arc<Mutex<HashMap<Uuid, Vec<tokio::sync::watch::Sender<GameData>>>>>,
+    pub players: Arc<Mutex<HashMap<Uuid, HashSet<Uuid>>>>,
+    pub move_channels: Arc<Mutex<HashMap<Uuid, tokio::sync::broadcast::Sender<GameDelta>>>>,
+    pub tournaments: Arc<Mutex<HashMap<Uuid, Tournament>>>,
+    /// the player tokens of every game in the order they were registered
+    game_players: Arc<Mutex<HashMap<Uuid, Vec<Uuid>>>>,
+    global_stats: Arc<Mutex<GlobalStats>>,
+    player_stats: Arc<Mutex<HashMap<Uuid, PlayerStats>>>,
+    pub max_entries: Option<usize>,
+    /// the ids of all games, sorted by their creation time
+    creation_index: Arc<Mutex<BTreeSet<(u64, Uuid)>>>,
+    last_accesses: Arc<Mutex<HashMap<Uuid, u64>>>,
+    access_counter: Arc<AtomicU64>,
+    rng: Option<Arc<Mutex<StdRng>>>,
+    /// the capacity of the move channels
+    move_buffer: usize,
+    /// the ids of the games as they are created, see `subscribe_to_new_games`
+    new_games: tokio::sync::broadcast::Sender<Uuid>,
+}
+
+impl CacheProvider {
+    const DEFAULT_MOVE_BUFFER: usize = 64;
+    /// how many created games a subscriber to new games may fall behind
+    const NEW_GAME_BUFFER: usize = 64;
+
+    /// counts the result of a game that has just ended. The first two registered
+    /// player tokens of the game played X and O.
+    fn record_result(
+        &self,
+        game_id: Uuid,
+        result: &GameResult,
+    ) -> Result<(), CacheProviderErrorKind> {
+        self.global_stats
+            .lock()
+            .map_err(|_| CacheProviderErrorKind::LockError)?
+            .record(result);
+        let game_players = self
+            .game_players
+            .lock()
+            .map_err(|_| CacheProviderErrorKind::LockError)?;
+        let mut player_stats = self
+            .player_stats
+            .lock()
+            .map_err(|_| CacheProviderErrorKind::LockError)?;
+        let tokens = game_players.get(&game_id).into_iter().flatten();
+        for (token, player) in tokens.zip([Player::X, Player::O]) {
+            player_stats
+                .entry(*token)
+                .or_default()
+                .record(result, player);
+        }
+        Ok(())
+    }
+
+    /// creates a provider whose game ids are derived from the given seed
+    pub fn with_seed(seed: u64) -> Self {
+        Self::new(CacheProviderArgs {
+            seed: Some(seed),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    /// returns a new game id, taken from the seeded rng if there is one
+    fn next_game_id(&self) -> Result<Uuid, CacheProviderErrorKind> {
+        match &self.rng {
+            Some(rng) => {
+                let mut rng = rng.lock().map_err(|_| CacheProviderErrorKind::LockError)?;
+                Ok(uuid::Builder::from_random_bytes(rng.gen()).into_uuid())
+            }
+            None => Ok(Uuid::new_v4()),
+        }
+    }
+
+    /// marks the game as the most recently accessed one
+    fn mark_accessed(&self, game_id: Uuid) -> Result<(), CacheProviderErrorKind> {
+        let access = self.access_counter.fetch_add(1, Ordering::Relaxed);
+        self.last_accesses
+            .lock()
+            .map_err(|_| CacheProviderErrorKind::LockError)?
+            .insert(game_id, access);
+        Ok(())
+    }
+
+    /// evicts the least recently accessed games until `max_entries` is respected.
+    /// The subscription channels of an evicted game are dropped, which closes them
+    /// for all of its subscribers.
+    fn evict(&self, hash_map: &mut HashMap<Uuid, GameData>) -> Result<(), CacheProviderErrorKind> {
+        let Some(max_entries) = self.max_entries else {
+            return Ok(());
+        };
+        let mut last_accesses = self
+            .last_accesses
+            .lock()
+            .map_err(|_| CacheProviderErrorKind::LockError)?;
+
+        while hash_map.len() > max_entries {
+            let Some(game_id) = hash_map
+                .keys()
+                .min_by_key(|game_id| last_accesses.get(game_id).copied().unwrap_or_default())
+                .copied()
+            else {
+                break;
+            };
+            debug!("Evicting game {} from cache", game_id);
+            if let Some(game_data) = hash_map.remove(&game_id) {
+                self.creation_index
+                    .lock()
+                    .map_err(|_| CacheProviderErrorKind::LockError)?
+                    .remove(&(game_data.created_at, game_id));
+            }
+            last_accesses.remove(&game_id);
+            self.channels
+                .lock()
+                .map_err(|_| CacheProviderErrorKind::LockError)?
+                .remove(&game_id);
+            self.move_channels
+                .lock()
+                .map_err(|_| CacheProviderErrorKind::LockError)?
+                .remove(&game_id);
+            self.players
+                .lock()
+                .map_err(|_| CacheProviderErrorKind::LockError)?
+                .values_mut()
+                .for_each(|game_ids| {
+                    game_ids.remove(&game_id);
+                });
+        }
+        Ok(())
+    }
+
+    /// inserts the game data of a new game, failing if the id is already taken
+    fn insert_game(&self, game_data: GameData) -> Result<Uuid, CacheProviderErrorKind> {
+        let game_id = game_data.game_id;
+        let mut hash_map = self
+            .hash_map
+            .lock()
+            .map_err(|_| CacheProviderErrorKind::LockError)?;
+
+        match hash_map.entry(game_id) {
+            Entry::Occupied(_) => Err(CacheProviderErrorKind::GameExists),
+            Entry::Vacant(entry) => {
+                self.creation_index
+                    .lock()
+                    .map_err(|_| CacheProviderErrorKind::LockError)?
+                    .insert((game_data.created_at, game_id));
+                entry.insert(game_data);
+                self.mark_accessed(game_id)?;
+                self.evict(&mut hash_map)?;
+                // sending only fails if there are no receivers, which is fine
+                let _ = self.new_games.send(game_id);
+                Ok(game_id)
+            }
+        }
+    }
+
+    /// sends the game data and the new move to the subscribers of the game. Callers
+    /// hold the lock on `hash_map` until this returns, so concurrent changes to a game
+    /// reach its subscribers in the order they were made.
+    fn notify_subscribers(
+        &self,
+        game_data: &GameData,
+        new_move: Move,
+    ) -> Result<(), CacheProviderErrorKind> {
+        self.notify_game_subscribers(game_data)?;
+
+        if let Some(move_channel) = self
+            .move_channels
+            .lock()
+            .map_err(|_| CacheProviderErrorKind::LockError)?
+            .get(&game_data.game_id)
+        {
+            // sending only fails if there are no receivers, which is fine
+            let _ = move_channel.send(GameDelta {
+                move_: new_move,
+                index: game_data.moves.len() - 1,
+                result: game_data.result,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// sends the updated game data to all subscribers of the game
+    fn notify_game_subscribers(&self, game_data: &GameData) -> Result<(), CacheProviderErrorKind> {
+        if let Some(channels) = self
+            .channels
+            .lock()
+            .map_err(|_| CacheProviderErrorKind::LockError)?
+            .get_mut(&game_data.game_id)
+        {
+            // sending fails once the subscriber went away, so the channel can be dropped
+            channels.retain(|channel| channel.send(game_data.clone()).is_ok());
+        };
+        Ok(())
+    }
 }
 
 impl Default for CacheProvider {
     fn default() -> Self {
-        Self::new(CacheProviderArgs {}).unwrap()
+        Self::new(CacheProviderArgs::default()).unwrap()
     }
 }
 
 impl DataProvider for CacheProvider {
     type Args = CacheProviderArgs;
     type ErrorKind = CacheProviderErrorKind;
-    fn add_move(&mut self, game_id: Uuid, new_move: crate::Move) -> Result<(), Self::ErrorKind> {
+    fn add_move(&mut self, game_id: Uuid, new_move: Move) -> Result<(), Self::ErrorKind> {
         let mut hash_map = self
             .hash_map
             .lock()
             .map_err(|_| Self::ErrorKind::LockError)?;
-        hash_map
-            .entry(game_id)
-            .and_modify(|game_data| game_data.moves.push(new_move));
+        let game_data = hash_map
+            .get_mut(&game_id)
+            .ok_or(Self::ErrorKind::KeyNotFound)?;
+        if game_data.moves.len() >= game_data.settings.board_size.max_moves() {
+            return Err(Self::ErrorKind::BoardFull);
+        }
+        if game_data.result.is_some() {
+            return Err(InvalidMove::GameEnded.into());
+        }
+        let now = GameData::now();
+        if game_data.is_too_fast(new_move.player, now) {
+            return Err(Self::ErrorKind::TooFast);
+        }
+        let had_result = game_data.result.is_some();
+        game_data.add_move_at(new_move, now);
+        if let (false, Some(result)) = (had_result, game_data.result) {
+            self.record_result(game_id, &result)?;
+        }
+        self.mark_accessed(game_id)?;
 
-        let new_game_data = hash_map
-            .get(&game_id)
-            .ok_or(Self::ErrorKind::KeyNotFound)?
-            .clone();
+        self.notify_subscribers(game_data, new_move)
+    }
+    fn add_move_if_count(
+        &mut self,
+        game_id: Uuid,
+        expected_count: usize,
+        new_move: Move,
+    ) -> Result<(), Self::ErrorKind> {
+        let mut hash_map = self
+            .hash_map
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?;
+        let game_data = hash_map
+            .get_mut(&game_id)
+            .ok_or(Self::ErrorKind::KeyNotFound)?;
+        if game_data.moves.len() != expected_count {
+            return Err(Self::ErrorKind::Conflict);
+        }
+        if game_data.moves.len() >= game_data.settings.board_size.max_moves() {
+            return Err(Self::ErrorKind::BoardFull);
+        }
+        if game_data.result.is_some() {
+            return Err(InvalidMove::GameEnded.into());
+        }
+        let now = GameData::now();
+        if game_data.is_too_fast(new_move.player, now) {
+            return Err(Self::ErrorKind::TooFast);
+        }
+        let had_result = game_data.result.is_some();
+        game_data.add_move_at(new_move, now);
+        if let (false, Some(result)) = (had_result, game_data.result) {
+            self.record_result(game_id, &result)?;
+        }
+        self.mark_accessed(game_id)?;
 
-        if let Some(channels) = self
-            .channels
+        self.notify_subscribers(game_data, new_move)
+    }
+    fn truncate_game(
+        &mut self,
+        game_id: Uuid,
+        move_number: usize,
+    ) -> Result<Vec<Move>, Self::ErrorKind> {
+        let mut hash_map = self
+            .hash_map
             .lock()
-            .map_err(|_| Self::ErrorKind::LockError)?
-            .get(&game_id)
-        {
-            channels.iter().for_each(|channel| {
-                channel.send(new_game_data.clone()).unwrap();
-            })
+            .map_err(|_| Self::ErrorKind::LockError)?;
+        let game_data = hash_map
+            .get_mut(&game_id)
+            .ok_or(Self::ErrorKind::KeyNotFound)?;
+        if game_data.result.is_some() {
+            return Err(InvalidMove::GameEnded.into());
+        }
+        if move_number > game_data.moves.len() {
+            return Err(Self::ErrorKind::NotEnoughMoves);
+        }
+        let removed_moves = game_data.truncate_moves(move_number);
+        self.mark_accessed(game_id)?;
+
+        self.notify_game_subscribers(game_data)?;
+        Ok(removed_moves)
+    }
+    fn restore(&mut self, game_id: Uuid, snapshot: GameData) -> Result<(), Self::ErrorKind> {
+        snapshot.validate()?;
+        let mut hash_map = self
+            .hash_map
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?;
+        let game_data = hash_map
+            .get_mut(&game_id)
+            .ok_or(Self::ErrorKind::KeyNotFound)?;
+        *game_data = GameData {
+            game_id,
+            created_at: game_data.created_at,
+            created_by: game_data.created_by.take(),
+            ..snapshot
         };
+        self.mark_accessed(game_id)?;
 
+        self.notify_game_subscribers(game_data)?;
         Ok(())
     }
-    fn create_game(&mut self, game_id: Option<Uuid>) -> Result<Uuid, Self::ErrorKind> {
-        let game_id = game_id.unwrap_or_else(Uuid::new_v4);
+    fn abandon_game(&mut self, game_id: Uuid) -> Result<bool, Self::ErrorKind> {
         let mut hash_map = self
             .hash_map
             .lock()
             .map_err(|_| Self::ErrorKind::LockError)?;
-
-        match hash_map.entry(game_id) {
-            Entry::Occupied(_) => Err(Self::ErrorKind::GameExists),
-            Entry::Vacant(entry) => {
-                entry.insert(GameData::new_with_id(game_id));
-                Ok(game_id)
-            }
+        let game_data = hash_map
+            .get_mut(&game_id)
+            .ok_or(Self::ErrorKind::KeyNotFound)?;
+        if game_data.result.is_some() {
+            return Ok(false);
         }
+        let result = GameResult::abandoned(game_data.moves.len());
+        game_data.result = Some(result);
+        self.record_result(game_id, &result)?;
+
+        self.notify_game_subscribers(game_data)?;
+        Ok(true)
+    }
+    fn create_game(&mut self, game_id: Option<Uuid>) -> Result<Uuid, Self::ErrorKind> {
+        let game_id = match game_id {
+            Some(game_id) => game_id,
+            None => self.next_game_id()?,
+        };
+        self.insert_game(GameData::new_with_id(game_id))
+    }
+    fn create_game_with_move(
+        &mut self,
+        game_id: Option<Uuid>,
+        first: Move,
+    ) -> Result<Uuid, Self::ErrorKind> {
+        Board::new().validate_move(first)?;
+
+        let game_id = match game_id {
+            Some(game_id) => game_id,
+            None => self.next_game_id()?,
+        };
+        let mut game_data = GameData::new_with_id(game_id);
+        game_data.add_move(first);
+        self.insert_game(game_data)
+    }
+    fn create_game_from(
+        &mut self,
+        game_id: Option<Uuid>,
+        template: GameData,
+    ) -> Result<Uuid, Self::ErrorKind> {
+        template.validate()?;
+
+        let game_id = match game_id {
+            Some(game_id) => game_id,
+            None => self.next_game_id()?,
+        };
+        let mut game_data = GameData {
+            game_id,
+            created_at: GameData::now(),
+            ..template
+        };
+        game_data.update_result();
+        self.insert_game(game_data)
+    }
+    fn import_game(&mut self, mut game_data: GameData) -> Result<Uuid, Self::ErrorKind> {
+        game_data.validate()?;
+        game_data.update_result();
+        self.insert_game(game_data)
+    }
+    fn kind(&self) -> &'static str {
+        "cache"
+    }
+    fn ping(&self) -> Result<(), Self::ErrorKind> {
+        // the games are in memory, they can only become unreachable through a poisoned lock
+        self.hash_map
+            .lock()
+            .map(|_| ())
+            .map_err(|_| Self::ErrorKind::LockError)
     }
     fn game_exists(&mut self, game_id: Uuid) -> Result<bool, Self::ErrorKind> {
-        let mut hash_map = self
+        let hash_map = self
             .hash_map
             .lock()
             .map_err(|_| Self::ErrorKind::LockError)?;
 
-        Ok(matches!(hash_map.entry(game_id), Entry::Occupied(_)))
+        Ok(hash_map.contains_key(&game_id))
+    }
+    fn is_joinable(&self, game_id: Uuid) -> Result<bool, Self::ErrorKind> {
+        let in_progress = self
+            .hash_map
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?
+            .get(&game_id)
+            .is_some_and(|game_data| game_data.result.is_none());
+        if !in_progress {
+            return Ok(false);
+        }
+        let players = self
+            .game_players
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?
+            .get(&game_id)
+            .map_or(0, Vec::len);
+        Ok(players < 2)
+    }
+    fn touch(&mut self, game_id: Uuid) -> Result<(), Self::ErrorKind> {
+        if !self.game_exists(game_id)? {
+            return Err(Self::ErrorKind::KeyNotFound);
+        }
+        self.mark_accessed(game_id)
+    }
+    fn register_player(
+        &mut self,
+        game_id: Uuid,
+        player_token: Uuid,
+    ) -> Result<(), Self::ErrorKind> {
+        if !self.game_exists(game_id)? {
+            return Err(Self::ErrorKind::KeyNotFound);
+        }
+        self.players
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?
+            .entry(player_token)
+            .or_default()
+            .insert(game_id);
+        let mut game_players = self
+            .game_players
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?;
+        let tokens = game_players.entry(game_id).or_default();
+        if !tokens.contains(&player_token) {
+            tokens.push(player_token);
+        }
+        Ok(())
+    }
+    fn get_player_games(&self, player_token: Uuid) -> Result<Vec<Uuid>, Self::ErrorKind> {
+        let players = self
+            .players
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?;
+        Ok(players
+            .get(&player_token)
+            .map(|game_ids| game_ids.iter().cloned().collect())
+            .unwrap_or_default())
+    }
+    fn get_global_stats(&self) -> Result<GlobalStats, Self::ErrorKind> {
+        Ok(*self
+            .global_stats
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?)
+    }
+    fn get_player_stats(&self, player_token: Uuid) -> Result<PlayerStats, Self::ErrorKind> {
+        Ok(self
+            .player_stats
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?
+            .get(&player_token)
+            .copied()
+            .unwrap_or_default())
+    }
+    fn store_tournament(&mut self, tournament: &Tournament) -> Result<(), Self::ErrorKind> {
+        self.tournaments
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?
+            .insert(tournament.tournament_id, tournament.clone());
+        Ok(())
+    }
+    fn get_tournament(&self, tournament_id: Uuid) -> Result<Tournament, Self::ErrorKind> {
+        self.tournaments
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?
+            .get(&tournament_id)
+            .cloned()
+            .ok_or(Self::ErrorKind::KeyNotFound)
     }
     fn get_game_data(&self, game_id: Uuid) -> Result<crate::GameData, Self::ErrorKind> {
         let mut hash_map = self
@@ -99,17 +588,49 @@ impl DataProvider for CacheProvider {
             .lock()
             .map_err(|_| Self::ErrorKind::LockError)?;
         match hash_map.entry(game_id) {
-            Entry::Occupied(entry) => Ok(entry.get().clone()),
+            Entry::Occupied(entry) => {
+                self.mark_accessed(game_id)?;
+                Ok(entry.get().clone())
+            }
             Entry::Vacant(_) => Err(Self::ErrorKind::KeyNotFound),
         }
     }
-    fn new(_args: Self::Args) -> Result<Self, Self::ErrorKind>
+    fn get_last_move(&self, game_id: Uuid) -> Result<Option<(usize, Move)>, Self::ErrorKind> {
+        let hash_map = self
+            .hash_map
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?;
+        let moves = &hash_map
+            .get(&game_id)
+            .ok_or(Self::ErrorKind::KeyNotFound)?
+            .moves;
+        self.mark_accessed(game_id)?;
+        Ok(moves.last().map(|last_move| (moves.len() - 1, *last_move)))
+    }
+    fn new(args: Self::Args) -> Result<Self, Self::ErrorKind>
     where
         Self: Sized,
     {
         Ok(Self {
             hash_map: Arc::new(Mutex::new(HashMap::new())),
             channels: Arc::new(Mutex::new(HashMap::new())),
+            players: Arc::new(Mutex::new(HashMap::new())),
+            move_channels: Arc::new(Mutex::new(HashMap::new())),
+            tournaments: Arc::new(Mutex::new(HashMap::new())),
+            game_players: Arc::new(Mutex::new(HashMap::new())),
+            global_stats: Arc::new(Mutex::new(GlobalStats::default())),
+            player_stats: Arc::new(Mutex::new(HashMap::new())),
+            creation_index: Arc::new(Mutex::new(BTreeSet::new())),
+            max_entries: args.max_entries,
+            last_accesses: Arc::new(Mutex::new(HashMap::new())),
+            access_counter: Arc::new(AtomicU64::new(0)),
+            rng: args
+                .seed
+                .map(|seed| Arc::new(Mutex::new(StdRng::seed_from_u64(seed)))),
+            move_buffer: args
+                .move_buffer
+                .map_or(Self::DEFAULT_MOVE_BUFFER, NonZeroUsize::get),
+            new_games: tokio::sync::broadcast::channel(Self::NEW_GAME_BUFFER).0,
         })
     }
     fn sync_board(&mut self, _game: &mut crate::Board) -> Result<(), Self::ErrorKind> {
@@ -123,6 +644,43 @@ impl DataProvider for CacheProvider {
         Ok(hash_map.keys().cloned().collect())
     }
 
+    fn list_games_since(&self, after: DateTime<Utc>) -> Result<Vec<Uuid>, Self::ErrorKind> {
+        let after = u64::try_from(after.timestamp_millis()).unwrap_or_default();
+        let creation_index = self
+            .creation_index
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?;
+        Ok(creation_index
+            .range((after.saturating_add(1), Uuid::nil())..)
+            .map(|(_, game_id)| *game_id)
+            .collect())
+    }
+
+    fn list_results(
+        &self,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<(Uuid, Option<GameResult>)>, Self::ErrorKind> {
+        let hash_map = self
+            .hash_map
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?;
+        let creation_index = self
+            .creation_index
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?;
+        Ok(creation_index
+            .iter()
+            .rev()
+            .skip(offset)
+            .take(limit)
+            .filter_map(|(_, game_id)| {
+                let game_data = hash_map.get(game_id)?;
+                Some((*game_id, game_data.result))
+            })
+            .collect())
+    }
+
     fn subscribe_to_game(
         &mut self,
         game_id: Uuid,
@@ -145,4 +703,980 @@ impl DataProvider for CacheProvider {
 
         Ok(rx)
     }
+
+    fn subscribe_to_new_games(
+        &mut self,
+    ) -> Result<tokio::sync::broadcast::Receiver<Uuid>, Self::ErrorKind> {
+        Ok(self.new_games.subscribe())
+    }
+
+    fn subscribe_to_game_moves(
+        &mut self,
+        game_id: Uuid,
+    ) -> Result<tokio::sync::broadcast::Receiver<GameDelta>, Self::ErrorKind> {
+        if !self.game_exists(game_id)? {
+            return Err(Self::ErrorKind::KeyNotFound);
+        }
+        let rx = self
+            .move_channels
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?
+            .entry(game_id)
+            .or_insert_with(|| tokio::sync::broadcast::channel(self.move_buffer).0)
+            .subscribe();
+
+        Ok(rx)
+    }
+
+    fn clear_all(&mut self) -> Result<usize, Self::ErrorKind> {
+        let removed = {
+            let mut hash_map = self
+                .hash_map
+                .lock()
+                .map_err(|_| Self::ErrorKind::LockError)?;
+            let removed = hash_map.len();
+            hash_map.clear();
+            removed
+        };
+        self.players
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?
+            .clear();
+        self.game_players
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?
+            .clear();
+        self.creation_index
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?
+            .clear();
+        self.last_accesses
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?
+            .clear();
+        // closes the subscriptions of the removed games
+        self.close()?;
+        Ok(removed)
+    }
+
+    fn close(&mut self) -> Result<(), Self::ErrorKind> {
+        // there are no tasks, dropping the senders closes every subscription
+        self.channels
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?
+            .clear();
+        self.move_channels
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?
+            .clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{BoardSize, GameResult, GameState, Player};
+    use std::{thread::sleep, time::Duration};
+
+    #[test]
+    fn get_board() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+
+        for new_move in [
+            Move::new((0, 0), Player::X),
+            Move::new((1, 1), Player::O),
+            Move::new((3, 3), Player::X),
+        ] {
+            data_provider.add_move(game_id, new_move).unwrap();
+        }
+
+        let board = data_provider.get_board(game_id).unwrap();
+        assert_eq!(board.game_id, game_id);
+        assert_eq!(board.moves.len(), 3);
+        assert_eq!(
+            board.get_state(),
+            GameState::InProgress {
+                next_player: Player::O
+            }
+        );
+    }
+
+    #[test]
+    fn get_board_with_invalid_move() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+
+        data_provider
+            .add_move(game_id, Move::new((0, 0), Player::O))
+            .unwrap();
+
+        assert_eq!(
+            data_provider.get_board(game_id),
+            Err(CacheProviderErrorKind::InvalidMove(
+                InvalidMove::NotYourTurn
+            ))
+        );
+    }
+    #[tokio::test]
+    async fn subscribe_to_new_games() {
+        let mut data_provider = CacheProvider::default();
+        let existing_game = data_provider.create_game(None).unwrap();
+        let mut rx = data_provider.subscribe_to_new_games().unwrap();
+
+        let game_id = data_provider.create_game(None).unwrap();
+        assert_eq!(rx.recv().await.unwrap(), game_id);
+        // games created before subscribing aren't sent
+        assert_ne!(game_id, existing_game);
+        assert!(rx.try_recv().is_err());
+    }
+    #[tokio::test]
+    async fn subscribe_to_game_moves() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+        let mut rx = data_provider.subscribe_to_game_moves(game_id).unwrap();
+
+        let moves = [
+            Move::new((0, 0), Player::X),
+            Move::new((1, 1), Player::O),
+            Move::new((3, 3), Player::X),
+            Move::new((0, 1), Player::O),
+        ];
+        for new_move in moves {
+            data_provider.add_move(game_id, new_move).unwrap();
+        }
+
+        for (index, expected_move) in moves.into_iter().enumerate() {
+            assert_eq!(
+                rx.recv().await.unwrap(),
+                GameDelta {
+                    move_: expected_move,
+                    index,
+                    result: None,
+                }
+            );
+        }
+        // exactly one delta is sent per move
+        assert!(rx.try_recv().is_err());
+    }
+    #[tokio::test]
+    async fn game_ending_delta_has_result() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+        let mut rx = data_provider.subscribe_to_game_moves(game_id).unwrap();
+
+        let moves = [
+            ((1, 1), Player::X),
+            ((4, 4), Player::O),
+            ((3, 4), Player::X),
+            ((1, 4), Player::O),
+            ((3, 5), Player::X),
+            ((1, 7), Player::O),
+            ((3, 3), Player::X),
+            ((0, 0), Player::O),
+            ((0, 2), Player::X),
+            ((0, 6), Player::O),
+            ((2, 0), Player::X),
+            ((8, 2), Player::O),
+            ((6, 8), Player::X),
+            ((2, 8), Player::O),
+            ((7, 7), Player::X),
+            ((2, 5), Player::O),
+            ((8, 6), Player::X),
+        ];
+        for (coordinates, player) in moves {
+            data_provider
+                .add_move(game_id, Move::new(coordinates, player))
+                .unwrap();
+        }
+
+        for index in 0..moves.len() - 1 {
+            assert_eq!(rx.recv().await.unwrap().result, None, "move {}", index);
+        }
+        assert_eq!(
+            rx.recv().await.unwrap().result,
+            Some(GameResult {
+                winner: Some(Player::X),
+                move_count: moves.len(),
+                abandoned: false,
+            })
+        );
+    }
+    #[test]
+    fn concurrent_moves_broadcast_in_order() {
+        const THREADS: usize = 8;
+        const MOVES_PER_THREAD: usize = 10;
+        let total = THREADS * MOVES_PER_THREAD;
+        let mut data_provider = CacheProvider::new(CacheProviderArgs {
+            move_buffer: NonZeroUsize::new(Board::MAX_MOVES),
+            ..Default::default()
+        })
+        .unwrap();
+        let game_id = data_provider.create_game(None).unwrap();
+        let mut rx = data_provider.subscribe_to_game(game_id).unwrap();
+        let mut moves_rx = data_provider.subscribe_to_game_moves(game_id).unwrap();
+
+        // the subscriber must never see the number of moves go down
+        let watcher = std::thread::spawn(move || {
+            let mut last_count = 0;
+            while last_count < total {
+                let count = rx.borrow_and_update().moves.len();
+                assert!(
+                    count >= last_count,
+                    "saw {} moves after {}",
+                    count,
+                    last_count
+                );
+                last_count = count;
+            }
+        });
+        let barrier = std::sync::Arc::new(std::sync::Barrier::new(THREADS));
+        let handles: Vec<_> = (0..THREADS)
+            .map(|thread| {
+                let mut data_provider = data_provider.clone();
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    for i in 0..MOVES_PER_THREAD {
+                        let field = thread * MOVES_PER_THREAD + i;
+                        let player = [Player::X, Player::O][field % 2];
+                        data_provider
+                            .add_move(game_id, Move::new((field / 9, field % 9), player))
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .for_each(|handle| handle.join().unwrap());
+        watcher.join().unwrap();
+
+        // the deltas arrive in the order the moves were appended
+        let moves = data_provider.get_game_data(game_id).unwrap().moves;
+        for (index, game_move) in moves.iter().enumerate() {
+            assert_eq!(
+                moves_rx.try_recv().unwrap(),
+                GameDelta {
+                    move_: *game_move,
+                    index,
+                    result: None,
+                }
+            );
+        }
+        assert_eq!(moves.len(), total);
+        assert!(moves_rx.try_recv().is_err());
+    }
+    #[test]
+    fn add_move_if_count_race() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+        data_provider
+            .add_move(game_id, Move::new((0, 0), Player::X))
+            .unwrap();
+
+        // both threads read the move count before either of them makes a move
+        let barrier = std::sync::Arc::new(std::sync::Barrier::new(2));
+        let handles = [(0, 1), (1, 1)].map(|coordinates| {
+            let mut data_provider = data_provider.clone();
+            let barrier = barrier.clone();
+            std::thread::spawn(move || {
+                barrier.wait();
+                data_provider.add_move_if_count(game_id, 1, Move::new(coordinates, Player::O))
+            })
+        });
+        let results = handles.map(|handle| handle.join().unwrap());
+
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        assert!(results.contains(&Err(CacheProviderErrorKind::Conflict)));
+        assert_eq!(data_provider.get_game_data(game_id).unwrap().moves.len(), 2);
+    }
+    #[tokio::test]
+    async fn clear_all() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+        data_provider.create_game(None).unwrap();
+        data_provider
+            .register_player(game_id, Uuid::new_v4())
+            .unwrap();
+        let mut rx = data_provider.subscribe_to_game(game_id).unwrap();
+        let mut moves_rx = data_provider.subscribe_to_game_moves(game_id).unwrap();
+
+        assert_eq!(data_provider.clear_all(), Ok(2));
+        assert_eq!(data_provider.get_games(), Ok(vec![]));
+        assert_eq!(
+            data_provider.list_games_since(DateTime::<Utc>::MIN_UTC),
+            Ok(vec![])
+        );
+        assert!(rx.changed().await.is_err());
+        assert_eq!(
+            moves_rx.recv().await,
+            Err(tokio::sync::broadcast::error::RecvError::Closed)
+        );
+        assert_eq!(data_provider.clear_all(), Ok(0));
+
+        // the provider keeps working
+        let game_id = data_provider.create_game(None).unwrap();
+        assert_eq!(data_provider.get_games(), Ok(vec![game_id]));
+    }
+    #[tokio::test]
+    async fn evict_least_recently_used() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs {
+            max_entries: Some(2),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let oldest_game = data_provider.create_game(None).unwrap();
+        let accessed_game = data_provider.create_game(None).unwrap();
+        let mut rx = data_provider.subscribe_to_game(oldest_game).unwrap();
+
+        // accessing the second game makes the first one the least recently used
+        data_provider.get_game_data(accessed_game).unwrap();
+        let newest_game = data_provider.create_game(None).unwrap();
+
+        assert!(!data_provider.game_exists(oldest_game).unwrap());
+        assert!(data_provider.game_exists(accessed_game).unwrap());
+        assert!(data_provider.game_exists(newest_game).unwrap());
+
+        // the subscription of the evicted game is closed
+        assert!(rx.changed().await.is_err());
+    }
+    #[test]
+    fn seeded_game_ids() {
+        let create_games = |mut data_provider: CacheProvider| {
+            (0..3)
+                .map(|_| data_provider.create_game(None).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let game_ids = create_games(CacheProvider::with_seed(42));
+        assert_eq!(game_ids, create_games(CacheProvider::with_seed(42)));
+        assert_ne!(game_ids, create_games(CacheProvider::with_seed(43)));
+    }
+    #[test]
+    fn get_last_move() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+        assert_eq!(data_provider.get_last_move(game_id), Ok(None));
+
+        data_provider
+            .add_move(game_id, Move::new((0, 0), Player::X))
+            .unwrap();
+        data_provider
+            .add_move(game_id, Move::new((1, 1), Player::O))
+            .unwrap();
+        assert_eq!(
+            data_provider.get_last_move(game_id),
+            Ok(Some((1, Move::new((1, 1), Player::O))))
+        );
+        assert_eq!(
+            data_provider.get_last_move(Uuid::new_v4()),
+            Err(CacheProviderErrorKind::KeyNotFound)
+        );
+    }
+    #[tokio::test]
+    async fn truncate_game() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+        let moves = [
+            Move::new((0, 0), Player::X),
+            Move::new((1, 1), Player::O),
+            Move::new((3, 3), Player::X),
+            Move::new((0, 1), Player::O),
+        ];
+        for new_move in moves {
+            data_provider.add_move(game_id, new_move).unwrap();
+        }
+        let mut rx = data_provider.subscribe_to_game(game_id).unwrap();
+
+        assert_eq!(data_provider.truncate_game(game_id, 2).unwrap(), moves[2..]);
+        assert_eq!(
+            data_provider.get_game_data(game_id).unwrap().moves,
+            moves[..2]
+        );
+        rx.changed().await.unwrap();
+        assert_eq!(rx.borrow().moves, moves[..2]);
+
+        // the game continues from the truncated state
+        data_provider
+            .add_move(game_id, Move::new((4, 4), Player::X))
+            .unwrap();
+        assert_eq!(data_provider.get_board(game_id).unwrap().moves.len(), 3);
+
+        assert_eq!(
+            data_provider.truncate_game(game_id, 4),
+            Err(CacheProviderErrorKind::NotEnoughMoves)
+        );
+        assert_eq!(
+            data_provider.truncate_game(Uuid::new_v4(), 0),
+            Err(CacheProviderErrorKind::KeyNotFound)
+        );
+
+        // a game that has ended keeps its moves and its result
+        data_provider.abandon_game(game_id).unwrap();
+        assert_eq!(
+            data_provider.truncate_game(game_id, 0),
+            Err(CacheProviderErrorKind::InvalidMove(InvalidMove::GameEnded))
+        );
+        let game_data = data_provider.get_game_data(game_id).unwrap();
+        assert_eq!(game_data.moves.len(), 3);
+        assert_eq!(game_data.result, Some(GameResult::abandoned(3)));
+    }
+    #[tokio::test]
+    async fn restore() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+        data_provider
+            .add_move(game_id, Move::new((0, 0), Player::X))
+            .unwrap();
+        let snapshot = data_provider.snapshot(game_id).unwrap();
+
+        data_provider
+            .add_move(game_id, Move::new((1, 1), Player::O))
+            .unwrap();
+        data_provider
+            .add_move(game_id, Move::new((3, 3), Player::X))
+            .unwrap();
+        let mut rx = data_provider.subscribe_to_game(game_id).unwrap();
+
+        data_provider.restore(game_id, snapshot.clone()).unwrap();
+        assert_eq!(data_provider.get_game_data(game_id).unwrap(), snapshot);
+        rx.changed().await.unwrap();
+        assert_eq!(*rx.borrow(), snapshot);
+
+        // the game continues from the restored state
+        data_provider
+            .add_move(game_id, Move::new((1, 1), Player::O))
+            .unwrap();
+        assert_eq!(data_provider.get_board(game_id).unwrap().moves.len(), 2);
+
+        // a snapshot that isn't a valid game is refused, the game is left as it is
+        let mut invalid_snapshot = snapshot.clone();
+        invalid_snapshot.moves.push(Move::new((0, 0), Player::O));
+        assert_eq!(
+            data_provider.restore(game_id, invalid_snapshot),
+            Err(CacheProviderErrorKind::InvalidGameData(
+                InvalidGameData::DuplicateCoordinates { index: 1, first: 0 }
+            ))
+        );
+        assert_eq!(data_provider.get_board(game_id).unwrap().moves.len(), 2);
+
+        assert_eq!(
+            data_provider.restore(Uuid::new_v4(), snapshot),
+            Err(CacheProviderErrorKind::KeyNotFound)
+        );
+    }
+    #[test]
+    fn is_joinable() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+        assert_eq!(data_provider.is_joinable(game_id), Ok(true));
+
+        // X and O have joined
+        data_provider
+            .register_player(game_id, Uuid::new_v4())
+            .unwrap();
+        assert_eq!(data_provider.is_joinable(game_id), Ok(true));
+        data_provider
+            .register_player(game_id, Uuid::new_v4())
+            .unwrap();
+        assert_eq!(data_provider.is_joinable(game_id), Ok(false));
+
+        let finished_game = data_provider.create_game(None).unwrap();
+        data_provider.abandon_game(finished_game).unwrap();
+        assert_eq!(data_provider.is_joinable(finished_game), Ok(false));
+
+        assert_eq!(data_provider.is_joinable(Uuid::new_v4()), Ok(false));
+    }
+
+    #[tokio::test]
+    async fn abandon_idle_games() {
+        let mut data_provider = CacheProvider::default();
+        let mut stale_game = GameData::new();
+        stale_game.created_at = 1_000;
+        stale_game.add_move_at(Move::new((4, 4), Player::X), 2_000);
+        let stale_game = data_provider.import_game(stale_game).unwrap();
+        let fresh_game = data_provider.create_game(None).unwrap();
+        let mut rx = data_provider.subscribe_to_game(stale_game).unwrap();
+
+        assert_eq!(
+            data_provider.abandon_idle_games(GameData::now() - 60_000),
+            Ok(vec![stale_game])
+        );
+        let expected_result = Some(GameResult::abandoned(1));
+        assert_eq!(
+            data_provider.get_game_data(stale_game).unwrap().result,
+            expected_result
+        );
+        assert_eq!(
+            data_provider.get_game_data(fresh_game).unwrap().result,
+            None
+        );
+        rx.changed().await.unwrap();
+        assert_eq!(rx.borrow().result, expected_result);
+        assert_eq!(data_provider.get_global_stats().unwrap().draws, 1);
+
+        // the result is only set once
+        assert_eq!(data_provider.abandon_game(stale_game), Ok(false));
+        assert_eq!(
+            data_provider.abandon_idle_games(GameData::now() + 1),
+            Ok(vec![fresh_game])
+        );
+        assert_eq!(data_provider.get_global_stats().unwrap().draws, 2);
+        assert_eq!(
+            data_provider.abandon_game(Uuid::new_v4()),
+            Err(CacheProviderErrorKind::KeyNotFound)
+        );
+    }
+    #[tokio::test]
+    async fn close() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+        let mut rx = data_provider.subscribe_to_game(game_id).unwrap();
+        let mut move_rx = data_provider.subscribe_to_game_moves(game_id).unwrap();
+
+        data_provider.close().unwrap();
+        assert!(rx.changed().await.is_err());
+        assert_eq!(
+            move_rx.recv().await,
+            Err(tokio::sync::broadcast::error::RecvError::Closed)
+        );
+
+        // the games are kept and can be subscribed to again
+        let mut rx = data_provider.subscribe_to_game(game_id).unwrap();
+        data_provider
+            .add_move(game_id, Move::new((0, 0), Player::X))
+            .unwrap();
+        rx.changed().await.unwrap();
+        assert_eq!(rx.borrow().moves.len(), 1);
+    }
+    #[test]
+    fn error_kind_is_error() {
+        fn get_missing_game() -> Result<GameData, Box<dyn std::error::Error>> {
+            Ok(CacheProvider::default().get_game_data(Uuid::new_v4())?)
+        }
+        let error = get_missing_game().unwrap_err();
+        assert_eq!(error.to_string(), "the game does not exist");
+        assert_eq!(
+            format!(
+                "{}",
+                CacheProviderErrorKind::from(InvalidMove::FieldOccupied)
+            ),
+            "the game contains an invalid move: FieldOccupied"
+        );
+    }
+    #[test]
+    fn get_games_through_trait() {
+        fn count_games<T: DataProvider>(data_provider: &T) -> usize {
+            data_provider.get_games().unwrap().len()
+        }
+
+        let mut data_provider = CacheProvider::default();
+        assert_eq!(count_games(&data_provider), 0);
+        let game_ids = [
+            data_provider.create_game(None).unwrap(),
+            data_provider.create_game(None).unwrap(),
+        ];
+        assert_eq!(count_games(&data_provider), game_ids.len());
+        let listed_games = data_provider.get_games().unwrap();
+        assert!(game_ids
+            .iter()
+            .all(|game_id| listed_games.contains(game_id)));
+    }
+    #[test]
+    fn list_games_since() {
+        let mut data_provider = CacheProvider::default();
+        let old_game = data_provider.create_game(None).unwrap();
+
+        sleep(Duration::from_millis(10));
+        let since = Utc::now();
+        sleep(Duration::from_millis(10));
+
+        let new_games = [
+            data_provider.create_game(None).unwrap(),
+            data_provider.create_game(None).unwrap(),
+        ];
+        let listed_games = data_provider.list_games_since(since).unwrap();
+        assert_eq!(listed_games.len(), new_games.len());
+        assert!(new_games
+            .iter()
+            .all(|game_id| listed_games.contains(game_id)));
+        assert!(!listed_games.contains(&old_game));
+
+        assert_eq!(data_provider.list_games_since(Utc::now()).unwrap(), vec![]);
+    }
+    #[test]
+    fn list_results() {
+        let mut data_provider = CacheProvider::default();
+        let finished_game = data_provider.create_game(None).unwrap();
+        for (coordinates, player) in [
+            ((1, 1), Player::X),
+            ((4, 4), Player::O),
+            ((3, 4), Player::X),
+            ((1, 4), Player::O),
+            ((3, 5), Player::X),
+            ((1, 7), Player::O),
+            ((3, 3), Player::X),
+            ((0, 0), Player::O),
+            ((0, 2), Player::X),
+            ((0, 6), Player::O),
+            ((2, 0), Player::X),
+            ((8, 2), Player::O),
+            ((6, 8), Player::X),
+            ((2, 8), Player::O),
+            ((7, 7), Player::X),
+            ((2, 5), Player::O),
+            ((8, 6), Player::X),
+        ] {
+            data_provider
+                .add_move(finished_game, Move::new(coordinates, player))
+                .unwrap();
+        }
+        sleep(Duration::from_millis(2));
+        let running_game = data_provider.create_game(None).unwrap();
+        data_provider
+            .add_move(running_game, Move::new((4, 4), Player::X))
+            .unwrap();
+        sleep(Duration::from_millis(2));
+        let new_game = data_provider.create_game(None).unwrap();
+
+        let expected_result = Some(GameResult {
+            winner: Some(Player::X),
+            move_count: 17,
+            abandoned: false,
+        });
+        assert_eq!(
+            data_provider.list_results(10, 0).unwrap(),
+            vec![
+                (new_game, None),
+                (running_game, None),
+                (finished_game, expected_result)
+            ]
+        );
+        assert_eq!(
+            data_provider.list_results(1, 2).unwrap(),
+            vec![(finished_game, expected_result)]
+        );
+        assert_eq!(data_provider.list_results(0, 0).unwrap(), vec![]);
+        assert_eq!(data_provider.list_results(10, 3).unwrap(), vec![]);
+    }
+    #[test]
+    fn touch() {
+        let mut data_provider = CacheProvider::new(CacheProviderArgs {
+            max_entries: Some(2),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let touched_game = data_provider.create_game(None).unwrap();
+        let untouched_game = data_provider.create_game(None).unwrap();
+        data_provider.touch(touched_game).unwrap();
+        data_provider.create_game(None).unwrap();
+
+        assert!(data_provider.game_exists(touched_game).unwrap());
+        assert!(!data_provider.game_exists(untouched_game).unwrap());
+        assert_eq!(
+            data_provider.touch(untouched_game),
+            Err(CacheProviderErrorKind::KeyNotFound)
+        );
+    }
+    #[test]
+    fn game_result() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+        let moves = [
+            ((1, 1), Player::X),
+            ((4, 4), Player::O),
+            ((3, 4), Player::X),
+            ((1, 4), Player::O),
+            ((3, 5), Player::X),
+            ((1, 7), Player::O),
+            ((3, 3), Player::X),
+            ((0, 0), Player::O),
+            ((0, 2), Player::X),
+            ((0, 6), Player::O),
+            ((2, 0), Player::X),
+            ((8, 2), Player::O),
+            ((6, 8), Player::X),
+            ((2, 8), Player::O),
+            ((7, 7), Player::X),
+            ((2, 5), Player::O),
+        ];
+        for (coordinates, player) in moves {
+            data_provider
+                .add_move(game_id, Move::new(coordinates, player))
+                .unwrap();
+        }
+        assert_eq!(data_provider.get_game_data(game_id).unwrap().result, None);
+
+        data_provider
+            .add_move(game_id, Move::new((8, 6), Player::X))
+            .unwrap();
+        let expected_result = Some(GameResult {
+            winner: Some(Player::X),
+            move_count: 17,
+            abandoned: false,
+        });
+        assert_eq!(
+            data_provider.get_game_data(game_id).unwrap().result,
+            expected_result
+        );
+
+        // the game has ended, no more moves are made
+        assert_eq!(
+            data_provider.add_move(game_id, Move::new((8, 8), Player::O)),
+            Err(CacheProviderErrorKind::InvalidMove(InvalidMove::GameEnded))
+        );
+        assert_eq!(
+            data_provider.get_game_data(game_id).unwrap().result,
+            expected_result
+        );
+    }
+    #[test]
+    fn board_full() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+        for index in 0..Board::MAX_MOVES {
+            let player = if index % 2 == 0 { Player::X } else { Player::O };
+            data_provider
+                .add_move(game_id, Move::new((index / 9, index % 9), player))
+                .unwrap();
+        }
+
+        assert_eq!(
+            data_provider.add_move(game_id, Move::new((0, 0), Player::O)),
+            Err(CacheProviderErrorKind::BoardFull)
+        );
+        assert_eq!(
+            data_provider.add_move_if_count(
+                game_id,
+                Board::MAX_MOVES,
+                Move::new((0, 0), Player::O)
+            ),
+            Err(CacheProviderErrorKind::BoardFull)
+        );
+        assert_eq!(
+            data_provider.get_game_data(game_id).unwrap().moves.len(),
+            Board::MAX_MOVES
+        );
+    }
+    #[test]
+    fn board_full_by_board_size() {
+        let mut data_provider = CacheProvider::default();
+        let mut game_data = GameData::new();
+        game_data.settings.board_size = BoardSize {
+            rows: 1,
+            columns: 1,
+        };
+        let game_id = data_provider.create_game_from(None, game_data).unwrap();
+        // a drawn game filling the only sub board
+        let moves = [
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (1, 1),
+            (1, 0),
+            (1, 2),
+            (2, 1),
+            (2, 0),
+            (2, 2),
+        ];
+        for (index, coordinates) in moves.into_iter().enumerate() {
+            let player = if index % 2 == 0 { Player::X } else { Player::O };
+            data_provider
+                .add_move(game_id, Move::new(coordinates, player))
+                .unwrap();
+        }
+
+        assert_eq!(
+            data_provider.add_move(game_id, Move::new((0, 0), Player::O)),
+            Err(CacheProviderErrorKind::BoardFull)
+        );
+        assert_eq!(
+            data_provider.get_game_data(game_id).unwrap().moves.len(),
+            moves.len()
+        );
+    }
+    #[test]
+    fn move_after_result() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider
+            .create_game_with_move(None, Move::new((4, 4), Player::X))
+            .unwrap();
+        assert!(data_provider.abandon_game(game_id).unwrap());
+
+        let ended = Err(CacheProviderErrorKind::InvalidMove(InvalidMove::GameEnded));
+        assert_eq!(
+            data_provider.add_move(game_id, Move::new((3, 3), Player::O)),
+            ended
+        );
+        assert_eq!(
+            data_provider.add_move_if_count(game_id, 1, Move::new((3, 3), Player::O)),
+            ended
+        );
+        assert_eq!(data_provider.get_game_data(game_id).unwrap().moves.len(), 1);
+    }
+    #[test]
+    fn create_game_with_move() {
+        let mut data_provider = CacheProvider::default();
+
+        let opening = Move::new((4, 4), Player::X);
+        let game_id = data_provider.create_game_with_move(None, opening).unwrap();
+        assert_eq!(
+            data_provider.get_game_data(game_id).unwrap().moves,
+            vec![opening]
+        );
+
+        let game_id = Uuid::new_v4();
+        assert_eq!(
+            data_provider.create_game_with_move(Some(game_id), Move::new((4, 4), Player::O)),
+            Err(CacheProviderErrorKind::InvalidMove(
+                InvalidMove::NotYourTurn
+            ))
+        );
+        assert_eq!(
+            data_provider.create_game_with_move(Some(game_id), Move::new((9, 0), Player::X)),
+            Err(CacheProviderErrorKind::InvalidMove(
+                InvalidMove::OutOfBounds
+            ))
+        );
+        assert!(!data_provider.game_exists(game_id).unwrap());
+    }
+    #[test]
+    fn create_game_from() {
+        let mut data_provider = CacheProvider::default();
+
+        let mut template = GameData::new();
+        template.created_by = Some("alice".to_string());
+        template.moves.push(Move::new((4, 4), Player::X));
+        let game_id = data_provider
+            .create_game_from(None, template.clone())
+            .unwrap();
+        let game_data = data_provider.get_game_data(game_id).unwrap();
+        assert_eq!(game_data.game_id, game_id);
+        assert_eq!(game_data.created_by, Some("alice".to_string()));
+        assert_eq!(game_data.moves, template.moves);
+
+        template.moves.push(Move::new((4, 4), Player::O));
+        assert_eq!(
+            data_provider.create_game_from(None, template.clone()),
+            Err(CacheProviderErrorKind::InvalidGameData(
+                InvalidGameData::DuplicateCoordinates { index: 1, first: 0 }
+            ))
+        );
+        template.moves[1] = Move::new((4, 3), Player::X);
+        assert_eq!(
+            data_provider.import_game(template),
+            Err(CacheProviderErrorKind::InvalidGameData(
+                InvalidGameData::SamePlayerTwice { index: 1 }
+            ))
+        );
+        assert_eq!(data_provider.get_games().unwrap(), vec![game_id]);
+    }
+    #[test]
+    fn too_fast() {
+        let mut data_provider = CacheProvider::default();
+        let game_id = data_provider.create_game(None).unwrap();
+        data_provider
+            .hash_map
+            .lock()
+            .unwrap()
+            .get_mut(&game_id)
+            .unwrap()
+            .settings
+            .min_move_interval = Some(60_000);
+
+        data_provider
+            .add_move(game_id, Move::new((0, 0), Player::X))
+            .unwrap();
+        data_provider
+            .add_move(game_id, Move::new((0, 1), Player::O))
+            .unwrap();
+        assert_eq!(
+            data_provider.add_move(game_id, Move::new((0, 3), Player::X)),
+            Err(CacheProviderErrorKind::TooFast)
+        );
+        assert_eq!(data_provider.get_game_data(game_id).unwrap().moves.len(), 2);
+    }
+    #[test]
+    fn stats() {
+        let mut data_provider = CacheProvider::default();
+        let moves = [
+            ((1, 1), Player::X),
+            ((4, 4), Player::O),
+            ((3, 4), Player::X),
+            ((1, 4), Player::O),
+            ((3, 5), Player::X),
+            ((1, 7), Player::O),
+            ((3, 3), Player::X),
+            ((0, 0), Player::O),
+            ((0, 2), Player::X),
+            ((0, 6), Player::O),
+            ((2, 0), Player::X),
+            ((8, 2), Player::O),
+            ((6, 8), Player::X),
+            ((2, 8), Player::O),
+            ((7, 7), Player::X),
+            ((2, 5), Player::O),
+            ((8, 6), Player::X),
+        ];
+        let first_token = Uuid::new_v4();
+        let second_token = Uuid::new_v4();
+        let spectator_token = Uuid::new_v4();
+
+        // the first registered token plays X, X wins both games
+        for tokens in [
+            [first_token, second_token, spectator_token],
+            [second_token, first_token, spectator_token],
+        ] {
+            let game_id = data_provider.create_game(None).unwrap();
+            for token in tokens {
+                data_provider.register_player(game_id, token).unwrap();
+            }
+            for (coordinates, player) in moves {
+                data_provider
+                    .add_move(game_id, Move::new(coordinates, player))
+                    .unwrap();
+            }
+            // moves after the end of the game are refused, the result is counted once
+            assert!(data_provider
+                .add_move(game_id, Move::new((8, 8), Player::O))
+                .is_err());
+        }
+        // games without players only count towards the global stats
+        let game_id = data_provider.create_game(None).unwrap();
+        for (move_count, (coordinates, player)) in moves.into_iter().enumerate() {
+            data_provider
+                .add_move_if_count(game_id, move_count, Move::new(coordinates, player))
+                .unwrap();
+        }
+
+        assert_eq!(
+            data_provider.get_global_stats().unwrap(),
+            GlobalStats {
+                x_wins: 3,
+                o_wins: 0,
+                draws: 0
+            }
+        );
+        let expected_stats = PlayerStats {
+            wins: 1,
+            losses: 1,
+            draws: 0,
+        };
+        assert_eq!(
+            data_provider.get_player_stats(first_token).unwrap(),
+            expected_stats
+        );
+        assert_eq!(
+            data_provider.get_player_stats(second_token).unwrap(),
+            expected_stats
+        );
+        assert_eq!(
+            data_provider.get_player_stats(spectator_token).unwrap(),
+            PlayerStats::default()
+        );
+    }
 }
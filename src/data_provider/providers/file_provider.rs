@@ -0,0 +1,824 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt::Display,
+    fs, io,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    Board, DataProvider, GameData, GameDelta, GameResult, GlobalStats, InvalidGameData,
+    InvalidMove, Move, Player, PlayerStats, Tournament,
+};
+
+#[derive(Clone, Debug)]
+pub struct FileProviderArgs {
+    /// the directory the games are stored in, it is created if it doesn't exist
+    pub dir: PathBuf,
+    /// how many moves a move subscriber may fall behind before it misses moves.
+    /// `None` uses the default of 64.
+    pub move_buffer: Option<NonZeroUsize>,
+}
+
+impl FileProviderArgs {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            move_buffer: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum FileProviderErrorKind {
+    LockError,
+    KeyNotFound,
+    GameExists,
+    InvalidMove(InvalidMove),
+    /// the moves of a game to store don't form a valid game
+    InvalidGameData(InvalidGameData),
+    Conflict,
+    BoardFull,
+    TooFast,
+    /// the game has fewer moves than should be kept of it
+    NotEnoughMoves,
+    /// a file couldn't be read or written, with the reason
+    Io(String),
+    /// a file doesn't hold what it should, with the reason
+    InvalidData(String),
+    /// the directory couldn't be watched for changes, with the reason
+    Watch(String),
+}
+
+impl From<InvalidMove> for FileProviderErrorKind {
+    fn from(invalid_move: InvalidMove) -> Self {
+        Self::InvalidMove(invalid_move)
+    }
+}
+
+impl From<InvalidGameData> for FileProviderErrorKind {
+    fn from(invalid_game_data: InvalidGameData) -> Self {
+        Self::InvalidGameData(invalid_game_data)
+    }
+}
+
+impl From<io::Error> for FileProviderErrorKind {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::NotFound => Self::KeyNotFound,
+            _ => Self::Io(e.to_string()),
+        }
+    }
+}
+
+impl Display for FileProviderErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileProviderErrorKind::GameExists => write!(f, "the game allready exists"),
+            FileProviderErrorKind::KeyNotFound => write!(f, "the game does not exist"),
+            FileProviderErrorKind::LockError => write!(f, "could not aquire lock on the files"),
+            FileProviderErrorKind::Conflict => {
+                write!(f, "the game has changed since the move count was read")
+            }
+            FileProviderErrorKind::TooFast => {
+                write!(f, "the player's previous move was made too recently")
+            }
+            FileProviderErrorKind::BoardFull => {
+                write!(f, "the game already has a move for every field")
+            }
+            FileProviderErrorKind::NotEnoughMoves => {
+                write!(f, "the game has fewer moves than should be kept")
+            }
+            FileProviderErrorKind::InvalidMove(invalid_move) => {
+                write!(f, "the game contains an invalid move: {:?}", invalid_move)
+            }
+            FileProviderErrorKind::InvalidGameData(invalid_game_data) => {
+                write!(f, "the game is invalid: {}", invalid_game_data)
+            }
+            FileProviderErrorKind::Io(reason) => write!(f, "could not access a file: {}", reason),
+            FileProviderErrorKind::InvalidData(reason) => {
+                write!(f, "a file holds invalid data: {}", reason)
+            }
+            FileProviderErrorKind::Watch(reason) => {
+                write!(f, "could not watch the directory: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FileProviderErrorKind {}
+
+/// the players and statistics of the games, kept in `index.json` next to the games
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+struct FileIndex {
+    /// the player tokens of every game in the order they were registered
+    game_players: HashMap<Uuid, Vec<Uuid>>,
+    global_stats: GlobalStats,
+    player_stats: HashMap<Uuid, PlayerStats>,
+}
+
+type Channels = Arc<Mutex<HashMap<Uuid, Vec<tokio::sync::watch::Sender<GameData>>>>>;
+
+/// stores every game as `{dir}/{game_id}.json`, e.g. for fixtures and offline analysis.
+/// The players and statistics are kept in `{dir}/index.json` and the tournaments in
+/// `{dir}/tournaments`. Files are replaced at once by writing a temporary file first,
+/// and the directory is watched, so subscribers of a game also receive the changes
+/// made to its file from outside.
+/// Listing the games reads every file, so it is meant for a moderate number of games.
+#[derive(Clone)]
+pub struct FileProvider {
+    dir: PathBuf,
+    /// held while a file is read, changed and written again
+    lock: Arc<Mutex<()>>,
+    channels: Channels,
+    move_channels: Arc<Mutex<HashMap<Uuid, tokio::sync::broadcast::Sender<GameDelta>>>>,
+    /// stops watching the directory once the last clone of the provider is dropped
+    _watcher: Arc<Mutex<RecommendedWatcher>>,
+    /// the capacity of the move channels
+    move_buffer: usize,
+    /// the ids of the games as they are created, by the provider or from outside
+    new_games: tokio::sync::broadcast::Sender<Uuid>,
+}
+
+impl FileProvider {
+    const DEFAULT_MOVE_BUFFER: usize = 64;
+    /// how many created games a subscriber to new games may fall behind
+    const NEW_GAME_BUFFER: usize = 64;
+    const INDEX_FILE: &'static str = "index.json";
+    const TOURNAMENT_DIR: &'static str = "tournaments";
+
+    /// the directory the games are stored in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn game_path(&self, game_id: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.json", game_id))
+    }
+
+    fn tournament_path(&self, tournament_id: Uuid) -> PathBuf {
+        self.dir
+            .join(Self::TOURNAMENT_DIR)
+            .join(format!("{}.json", tournament_id))
+    }
+
+    /// the id of the game stored in the file, if it is the file of a game
+    fn game_id_of(path: &Path) -> Option<Uuid> {
+        if path.extension()? != "json" {
+            return None;
+        }
+        Uuid::parse_str(path.file_stem()?.to_str()?).ok()
+    }
+
+    fn read<D: DeserializeOwned>(path: &Path) -> Result<D, FileProviderErrorKind> {
+        let content = fs::read(path)?;
+        serde_json::from_slice(&content)
+            .map_err(|e| FileProviderErrorKind::InvalidData(format!("{}: {}", path.display(), e)))
+    }
+
+    /// writes the value to a temporary file first and moves it in place, so readers
+    /// never see a partly written file
+    fn write<S: Serialize>(path: &Path, value: &S) -> Result<(), FileProviderErrorKind> {
+        let content = serde_json::to_vec_pretty(value)
+            .map_err(|e| FileProviderErrorKind::InvalidData(e.to_string()))?;
+        let file_name = path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .unwrap_or_default();
+        let temp_path = path.with_file_name(format!(".{}.tmp", file_name));
+        fs::write(&temp_path, content)?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, ()>, FileProviderErrorKind> {
+        self.lock
+            .lock()
+            .map_err(|_| FileProviderErrorKind::LockError)
+    }
+
+    fn read_index(&self) -> Result<FileIndex, FileProviderErrorKind> {
+        match Self::read(&self.dir.join(Self::INDEX_FILE)) {
+            Err(FileProviderErrorKind::KeyNotFound) => Ok(FileIndex::default()),
+            index => index,
+        }
+    }
+
+    /// reads every game in the directory
+    fn read_games(&self) -> Result<Vec<GameData>, FileProviderErrorKind> {
+        self.get_games()?
+            .into_iter()
+            .map(|game_id| self.get_game_data(game_id))
+            .collect()
+    }
+
+    /// writes the game data of a new game, failing if the id is already taken
+    fn insert_game(&self, game_data: GameData) -> Result<Uuid, FileProviderErrorKind> {
+        let _lock = self.lock()?;
+        let path = self.game_path(game_data.game_id);
+        if path.exists() {
+            return Err(FileProviderErrorKind::GameExists);
+        }
+        Self::write(&path, &game_data)?;
+        // sending only fails if there are no receivers, which is fine
+        let _ = self.new_games.send(game_data.game_id);
+        Ok(game_data.game_id)
+    }
+
+    /// reads the game, changes it and writes it back, returning what the change
+    /// returned and the changed game. The game isn't written if the change fails.
+    fn update_game<R>(
+        &self,
+        game_id: Uuid,
+        change: impl FnOnce(&mut GameData) -> Result<R, FileProviderErrorKind>,
+    ) -> Result<(R, GameData), FileProviderErrorKind> {
+        let _lock = self.lock()?;
+        let path = self.game_path(game_id);
+        let mut game_data: GameData = Self::read(&path)?;
+        let changed = change(&mut game_data)?;
+        Self::write(&path, &game_data)?;
+        Ok((changed, game_data))
+    }
+
+    /// counts the result of a game that has just ended. The first two registered
+    /// player tokens of the game played X and O.
+    fn record_result(
+        &self,
+        game_id: Uuid,
+        result: &GameResult,
+    ) -> Result<(), FileProviderErrorKind> {
+        let _lock = self.lock()?;
+        let mut index = self.read_index()?;
+        index.global_stats.record(result);
+        let tokens = index.game_players.get(&game_id).into_iter().flatten();
+        for (token, player) in tokens.zip([Player::X, Player::O]) {
+            index
+                .player_stats
+                .entry(*token)
+                .or_default()
+                .record(result, player);
+        }
+        Self::write(&self.dir.join(Self::INDEX_FILE), &index)
+    }
+
+    /// adds the move, checking the move count first if one is expected
+    fn add_move_with(
+        &mut self,
+        game_id: Uuid,
+        expected_count: Option<usize>,
+        new_move: Move,
+    ) -> Result<(), FileProviderErrorKind> {
+        let now = GameData::now();
+        let (new_result, game_data) = self.update_game(game_id, |game_data| {
+            if expected_count.is_some_and(|expected_count| game_data.moves.len() != expected_count)
+            {
+                return Err(FileProviderErrorKind::Conflict);
+            }
+            if game_data.moves.len() >= game_data.settings.board_size.max_moves() {
+                return Err(FileProviderErrorKind::BoardFull);
+            }
+            if game_data.result.is_some() {
+                return Err(InvalidMove::GameEnded.into());
+            }
+            if game_data.is_too_fast(new_move.player, now) {
+                return Err(FileProviderErrorKind::TooFast);
+            }
+            let had_result = game_data.result.is_some();
+            game_data.add_move_at(new_move, now);
+            Ok(game_data.result.filter(|_| !had_result))
+        })?;
+        if let Some(result) = new_result {
+            self.record_result(game_id, &result)?;
+        }
+        Self::notify_game_subscribers(&self.channels, &game_data)?;
+
+        if let Some(move_channel) = self
+            .move_channels
+            .lock()
+            .map_err(|_| FileProviderErrorKind::LockError)?
+            .get(&game_id)
+        {
+            // sending only fails if there are no receivers, which is fine
+            let _ = move_channel.send(GameDelta {
+                move_: new_move,
+                index: game_data.moves.len() - 1,
+                result: game_data.result,
+            });
+        }
+        Ok(())
+    }
+
+    /// sends the game data to all subscribers of the game that don't have it yet. Both
+    /// the provider and the watcher of the directory send the changes it makes.
+    fn notify_game_subscribers(
+        channels: &Channels,
+        game_data: &GameData,
+    ) -> Result<(), FileProviderErrorKind> {
+        if let Some(channels) = channels
+            .lock()
+            .map_err(|_| FileProviderErrorKind::LockError)?
+            .get_mut(&game_data.game_id)
+        {
+            // sending fails once the subscriber went away, so the channel can be dropped
+            channels.retain(|channel| {
+                channel.send_if_modified(|current| {
+                    let modified = current != game_data;
+                    if modified {
+                        *current = game_data.clone();
+                    }
+                    modified
+                });
+                !channel.is_closed()
+            });
+        };
+        Ok(())
+    }
+
+    /// watches the directory for changes to the files of the games and sends them to
+    /// the subscribers of the games
+    fn watch(
+        dir: &Path,
+        channels: Channels,
+        new_games: tokio::sync::broadcast::Sender<Uuid>,
+    ) -> Result<RecommendedWatcher, FileProviderErrorKind> {
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => return warn!("Could not watch the games: {}", e),
+                };
+                if matches!(event.kind, EventKind::Access(_) | EventKind::Remove(_)) {
+                    return;
+                }
+                for path in event.paths {
+                    let Some(game_id) = Self::game_id_of(&path) else {
+                        continue;
+                    };
+                    // the file may be gone again or be written by another program right now
+                    let Ok(game_data) = Self::read::<GameData>(&path) else {
+                        continue;
+                    };
+                    debug!("Game {} changed on disk", game_id);
+                    // the provider writes games by renaming a temporary file, so only
+                    // games written by other programs are created in place
+                    if matches!(event.kind, EventKind::Create(_)) {
+                        let _ = new_games.send(game_id);
+                    }
+                    if let Err(e) = Self::notify_game_subscribers(&channels, &game_data) {
+                        warn!("Could not send the changes of game {}: {}", game_id, e);
+                    }
+                }
+            })
+            .map_err(|e| FileProviderErrorKind::Watch(e.to_string()))?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|e| FileProviderErrorKind::Watch(e.to_string()))?;
+        Ok(watcher)
+    }
+}
+
+impl DataProvider for FileProvider {
+    type Args = FileProviderArgs;
+    type ErrorKind = FileProviderErrorKind;
+    fn add_move(&mut self, game_id: Uuid, new_move: Move) -> Result<(), Self::ErrorKind> {
+        self.add_move_with(game_id, None, new_move)
+    }
+    fn add_move_if_count(
+        &mut self,
+        game_id: Uuid,
+        expected_count: usize,
+        new_move: Move,
+    ) -> Result<(), Self::ErrorKind> {
+        self.add_move_with(game_id, Some(expected_count), new_move)
+    }
+    fn truncate_game(
+        &mut self,
+        game_id: Uuid,
+        move_number: usize,
+    ) -> Result<Vec<Move>, Self::ErrorKind> {
+        let (removed_moves, game_data) = self.update_game(game_id, |game_data| {
+            if game_data.result.is_some() {
+                return Err(InvalidMove::GameEnded.into());
+            }
+            if move_number > game_data.moves.len() {
+                return Err(FileProviderErrorKind::NotEnoughMoves);
+            }
+            Ok(game_data.truncate_moves(move_number))
+        })?;
+        Self::notify_game_subscribers(&self.channels, &game_data)?;
+        Ok(removed_moves)
+    }
+    fn restore(&mut self, game_id: Uuid, snapshot: GameData) -> Result<(), Self::ErrorKind> {
+        snapshot.validate()?;
+        let ((), game_data) = self.update_game(game_id, |game_data| {
+            *game_data = GameData {
+                game_id,
+                created_at: game_data.created_at,
+                created_by: game_data.created_by.take(),
+                ..snapshot
+            };
+            Ok(())
+        })?;
+        Self::notify_game_subscribers(&self.channels, &game_data)
+    }
+    fn abandon_game(&mut self, game_id: Uuid) -> Result<bool, Self::ErrorKind> {
+        let game_data = self.get_game_data(game_id)?;
+        if game_data.result.is_some() {
+            return Ok(false);
+        }
+        let result = GameResult::abandoned(game_data.moves.len());
+        let (abandoned, game_data) = self.update_game(game_id, |game_data| {
+            // a move may have ended the game in the meantime
+            if game_data.result.is_some() {
+                return Ok(false);
+            }
+            game_data.result = Some(result);
+            Ok(true)
+        })?;
+        if abandoned {
+            self.record_result(game_id, &result)?;
+            Self::notify_game_subscribers(&self.channels, &game_data)?;
+        }
+        Ok(abandoned)
+    }
+    fn create_game(&mut self, game_id: Option<Uuid>) -> Result<Uuid, Self::ErrorKind> {
+        self.insert_game(GameData::new_with_id(game_id.unwrap_or_else(Uuid::new_v4)))
+    }
+    fn create_game_with_move(
+        &mut self,
+        game_id: Option<Uuid>,
+        first: Move,
+    ) -> Result<Uuid, Self::ErrorKind> {
+        Board::new().validate_move(first)?;
+
+        let mut game_data = GameData::new_with_id(game_id.unwrap_or_else(Uuid::new_v4));
+        game_data.add_move(first);
+        self.insert_game(game_data)
+    }
+    fn create_game_from(
+        &mut self,
+        game_id: Option<Uuid>,
+        template: GameData,
+    ) -> Result<Uuid, Self::ErrorKind> {
+        template.validate()?;
+
+        let mut game_data = GameData {
+            game_id: game_id.unwrap_or_else(Uuid::new_v4),
+            created_at: GameData::now(),
+            ..template
+        };
+        game_data.update_result();
+        self.insert_game(game_data)
+    }
+    fn import_game(&mut self, mut game_data: GameData) -> Result<Uuid, Self::ErrorKind> {
+        game_data.validate()?;
+        game_data.update_result();
+        self.insert_game(game_data)
+    }
+    fn kind(&self) -> &'static str {
+        "file"
+    }
+    fn ping(&self) -> Result<(), Self::ErrorKind> {
+        if fs::metadata(&self.dir)?.is_dir() {
+            Ok(())
+        } else {
+            Err(Self::ErrorKind::Io(format!(
+                "{} is not a directory",
+                self.dir.display()
+            )))
+        }
+    }
+    fn game_exists(&mut self, game_id: Uuid) -> Result<bool, Self::ErrorKind> {
+        Ok(self.game_path(game_id).is_file())
+    }
+    fn is_joinable(&self, game_id: Uuid) -> Result<bool, Self::ErrorKind> {
+        match self.get_game_data(game_id) {
+            Ok(game_data) if game_data.result.is_none() => {}
+            Ok(_) | Err(FileProviderErrorKind::KeyNotFound) => return Ok(false),
+            Err(e) => return Err(e),
+        }
+        let index = self.read_index()?;
+        Ok(index.game_players.get(&game_id).map_or(0, Vec::len) < 2)
+    }
+    fn touch(&mut self, game_id: Uuid) -> Result<(), Self::ErrorKind> {
+        // the games are kept until they are removed, there is nothing to keep alive
+        if !self.game_exists(game_id)? {
+            return Err(Self::ErrorKind::KeyNotFound);
+        }
+        Ok(())
+    }
+    fn register_player(
+        &mut self,
+        game_id: Uuid,
+        player_token: Uuid,
+    ) -> Result<(), Self::ErrorKind> {
+        if !self.game_exists(game_id)? {
+            return Err(Self::ErrorKind::KeyNotFound);
+        }
+        let _lock = self.lock()?;
+        let mut index = self.read_index()?;
+        let tokens = index.game_players.entry(game_id).or_default();
+        if tokens.contains(&player_token) {
+            return Ok(());
+        }
+        tokens.push(player_token);
+        Self::write(&self.dir.join(Self::INDEX_FILE), &index)
+    }
+    fn get_player_games(&self, player_token: Uuid) -> Result<Vec<Uuid>, Self::ErrorKind> {
+        Ok(self
+            .read_index()?
+            .game_players
+            .into_iter()
+            .filter(|(_, tokens)| tokens.contains(&player_token))
+            .map(|(game_id, _)| game_id)
+            .collect())
+    }
+    fn get_global_stats(&self) -> Result<GlobalStats, Self::ErrorKind> {
+        Ok(self.read_index()?.global_stats)
+    }
+    fn get_player_stats(&self, player_token: Uuid) -> Result<PlayerStats, Self::ErrorKind> {
+        Ok(self
+            .read_index()?
+            .player_stats
+            .get(&player_token)
+            .copied()
+            .unwrap_or_default())
+    }
+    fn store_tournament(&mut self, tournament: &Tournament) -> Result<(), Self::ErrorKind> {
+        fs::create_dir_all(self.dir.join(Self::TOURNAMENT_DIR))?;
+        Self::write(&self.tournament_path(tournament.tournament_id), tournament)
+    }
+    fn get_tournament(&self, tournament_id: Uuid) -> Result<Tournament, Self::ErrorKind> {
+        Self::read(&self.tournament_path(tournament_id))
+    }
+    fn get_game_data(&self, game_id: Uuid) -> Result<GameData, Self::ErrorKind> {
+        Self::read(&self.game_path(game_id))
+    }
+    fn new(args: Self::Args) -> Result<Self, Self::ErrorKind>
+    where
+        Self: Sized,
+    {
+        fs::create_dir_all(&args.dir)?;
+        let channels = Channels::default();
+        let new_games = tokio::sync::broadcast::channel(Self::NEW_GAME_BUFFER).0;
+        let watcher = Self::watch(&args.dir, channels.clone(), new_games.clone())?;
+        Ok(Self {
+            dir: args.dir,
+            lock: Arc::default(),
+            channels,
+            move_channels: Arc::default(),
+            _watcher: Arc::new(Mutex::new(watcher)),
+            move_buffer: args
+                .move_buffer
+                .map_or(Self::DEFAULT_MOVE_BUFFER, NonZeroUsize::get),
+            new_games,
+        })
+    }
+    fn sync_board(&mut self, _game: &mut Board) -> Result<(), Self::ErrorKind> {
+        Ok(())
+    }
+    fn get_games(&self) -> Result<Vec<Uuid>, Self::ErrorKind> {
+        let mut games = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.is_file() {
+                games.extend(Self::game_id_of(&path));
+            }
+        }
+        Ok(games)
+    }
+
+    fn list_games_since(&self, after: DateTime<Utc>) -> Result<Vec<Uuid>, Self::ErrorKind> {
+        let after = u64::try_from(after.timestamp_millis()).unwrap_or_default();
+        let mut games: Vec<(u64, Uuid)> = self
+            .read_games()?
+            .into_iter()
+            .filter(|game_data| game_data.created_at > after)
+            .map(|game_data| (game_data.created_at, game_data.game_id))
+            .collect();
+        games.sort();
+        Ok(games.into_iter().map(|(_, game_id)| game_id).collect())
+    }
+
+    fn list_results(
+        &self,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<(Uuid, Option<GameResult>)>, Self::ErrorKind> {
+        let mut games = self.read_games()?;
+        games.sort_by_key(|game_data| (game_data.created_at, game_data.game_id));
+        Ok(games
+            .into_iter()
+            .rev()
+            .skip(offset)
+            .take(limit)
+            .map(|game_data| (game_data.game_id, game_data.result))
+            .collect())
+    }
+
+    fn subscribe_to_game(
+        &mut self,
+        game_id: Uuid,
+    ) -> Result<tokio::sync::watch::Receiver<GameData>, Self::ErrorKind> {
+        let (tx, rx) = tokio::sync::watch::channel(self.get_game_data(game_id)?);
+        self.channels
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?
+            .entry(game_id)
+            .or_default()
+            .push(tx);
+
+        Ok(rx)
+    }
+
+    fn subscribe_to_new_games(
+        &mut self,
+    ) -> Result<tokio::sync::broadcast::Receiver<Uuid>, Self::ErrorKind> {
+        Ok(self.new_games.subscribe())
+    }
+
+    fn subscribe_to_game_moves(
+        &mut self,
+        game_id: Uuid,
+    ) -> Result<tokio::sync::broadcast::Receiver<GameDelta>, Self::ErrorKind> {
+        if !self.game_exists(game_id)? {
+            return Err(Self::ErrorKind::KeyNotFound);
+        }
+        let rx = match self
+            .move_channels
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?
+            .entry(game_id)
+        {
+            Entry::Occupied(entry) => entry.get().subscribe(),
+            Entry::Vacant(entry) => {
+                let (tx, rx) = tokio::sync::broadcast::channel(self.move_buffer);
+                entry.insert(tx);
+                rx
+            }
+        };
+
+        Ok(rx)
+    }
+
+    fn clear_all(&mut self) -> Result<usize, Self::ErrorKind> {
+        let removed = {
+            let _lock = self.lock()?;
+            let games = self.get_games()?;
+            for game_id in &games {
+                fs::remove_file(self.game_path(*game_id))?;
+            }
+            let mut index = self.read_index()?;
+            index.game_players.clear();
+            Self::write(&self.dir.join(Self::INDEX_FILE), &index)?;
+            games.len()
+        };
+        // closes the subscriptions of the removed games
+        self.close()?;
+        Ok(removed)
+    }
+
+    fn close(&mut self) -> Result<(), Self::ErrorKind> {
+        // the watcher keeps running for later subscriptions, dropping the senders
+        // closes every subscription
+        self.channels
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?
+            .clear();
+        self.move_channels
+            .lock()
+            .map_err(|_| Self::ErrorKind::LockError)?
+            .clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::GameState;
+    use std::time::Duration;
+
+    #[test]
+    fn reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut data_provider = FileProvider::new(FileProviderArgs::new(dir.path())).unwrap();
+        let game_id = data_provider.create_game(None).unwrap();
+        let player_token = Uuid::new_v4();
+        data_provider
+            .register_player(game_id, player_token)
+            .unwrap();
+        for new_move in [
+            Move::new((0, 0), Player::X),
+            Move::new((1, 1), Player::O),
+            Move::new((3, 3), Player::X),
+        ] {
+            data_provider.add_move(game_id, new_move).unwrap();
+        }
+        let other_game = data_provider
+            .create_game_with_move(None, Move::new((4, 4), Player::X))
+            .unwrap();
+        assert!(data_provider.abandon_game(other_game).unwrap());
+        assert_eq!(
+            data_provider.add_move(other_game, Move::new((3, 3), Player::O)),
+            Err(FileProviderErrorKind::InvalidMove(InvalidMove::GameEnded))
+        );
+        assert_eq!(
+            data_provider.create_game(Some(game_id)),
+            Err(FileProviderErrorKind::GameExists)
+        );
+        drop(data_provider);
+
+        // every file was moved in place, none is left behind
+        let mut files: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        let mut expected = vec![
+            format!("{}.json", game_id),
+            format!("{}.json", other_game),
+            "index.json".to_string(),
+        ];
+        expected.sort();
+        assert_eq!(files, expected);
+
+        let mut data_provider = FileProvider::new(FileProviderArgs::new(dir.path())).unwrap();
+        let mut games = data_provider.get_games().unwrap();
+        games.sort();
+        let mut expected = vec![game_id, other_game];
+        expected.sort();
+        assert_eq!(games, expected);
+        assert!(data_provider.game_exists(game_id).unwrap());
+        assert!(!data_provider.game_exists(Uuid::new_v4()).unwrap());
+
+        let board = data_provider.get_board(game_id).unwrap();
+        assert_eq!(board.moves.len(), 3);
+        assert_eq!(
+            board.get_state(),
+            GameState::InProgress {
+                next_player: Player::O
+            }
+        );
+        assert_eq!(
+            data_provider.get_player_games(player_token).unwrap(),
+            vec![game_id]
+        );
+        assert_eq!(
+            data_provider.get_game_data(other_game).unwrap().result,
+            Some(GameResult::abandoned(1))
+        );
+        assert_eq!(data_provider.get_global_stats().unwrap().draws, 1);
+        assert_eq!(data_provider.is_joinable(game_id), Ok(true));
+        assert_eq!(data_provider.is_joinable(other_game), Ok(false));
+
+        assert_eq!(data_provider.clear_all().unwrap(), 2);
+        assert!(data_provider.get_games().unwrap().is_empty());
+        assert_eq!(
+            data_provider.get_game_data(game_id),
+            Err(FileProviderErrorKind::KeyNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn external_edit() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut data_provider = FileProvider::new(FileProviderArgs::new(dir.path())).unwrap();
+        let game_id = data_provider.create_game(None).unwrap();
+        let mut rx = data_provider.subscribe_to_game(game_id).unwrap();
+
+        // the moves made through the provider are sent once
+        data_provider
+            .add_move(game_id, Move::new((4, 4), Player::X))
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(5), rx.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(rx.borrow_and_update().moves.len(), 1);
+
+        // another program adds a move to the file
+        let mut game_data = data_provider.get_game_data(game_id).unwrap();
+        game_data.add_move(Move::new((3, 3), Player::O));
+        fs::write(
+            dir.path().join(format!("{}.json", game_id)),
+            serde_json::to_string(&game_data).unwrap(),
+        )
+        .unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while rx.borrow_and_update().moves.len() < 2 {
+                rx.changed().await.unwrap();
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(*rx.borrow(), game_data);
+    }
+}
@@ -0,0 +1,104 @@
+use log::{info, warn};
+use std::fmt::Display;
+use uuid::Uuid;
+
+use crate::DataProvider;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationError {
+    /// the games of the source could not be listed
+    ListingGames(String),
+    /// a valid game could not be stored in the target
+    Importing { game_id: Uuid, message: String },
+}
+
+impl Display for MigrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ListingGames(message) => write!(f, "the games could not be listed: {}", message),
+            Self::Importing { game_id, message } => {
+                write!(f, "game {} could not be imported: {}", game_id, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+/// Copies every game of `from` to `to` with `DataProvider::import_game` and returns how
+/// many were copied. Games that expired while migrating or whose moves are invalid are
+/// skipped and reported in the log. Any other failure to import stops the migration,
+/// the games copied until then are kept.
+pub fn migrate(
+    from: &impl DataProvider,
+    to: &mut impl DataProvider,
+) -> Result<usize, MigrationError> {
+    let game_ids = from
+        .get_games()
+        .map_err(|e| MigrationError::ListingGames(e.to_string()))?;
+
+    let mut migrated = 0;
+    for game_id in game_ids {
+        let game_data = match from.get_game_data(game_id) {
+            Ok(game_data) => game_data,
+            Err(e) => {
+                warn!("Skipping game {}, it could not be read: {}", game_id, e);
+                continue;
+            }
+        };
+        if let Err(e) = game_data.validate() {
+            warn!("Skipping game {}, it is invalid: {}", game_id, e);
+            continue;
+        }
+        to.import_game(game_data)
+            .map_err(|e| MigrationError::Importing {
+                game_id,
+                message: e.to_string(),
+            })?;
+        migrated += 1;
+    }
+    info!("Migrated {} games", migrated);
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{CacheProvider, Move, Player};
+
+    #[test]
+    fn migrate_games() {
+        let mut from = CacheProvider::default();
+        let empty_game = from.create_game(None).unwrap();
+        let started_game = from
+            .create_game_with_move(None, Move::new((4, 4), Player::X))
+            .unwrap();
+        let invalid_game = from.create_game(None).unwrap();
+        from.hash_map
+            .lock()
+            .unwrap()
+            .get_mut(&invalid_game)
+            .unwrap()
+            .moves = vec![Move::new((0, 0), Player::X), Move::new((0, 0), Player::O)];
+
+        let mut to = CacheProvider::default();
+        assert_eq!(migrate(&from, &mut to), Ok(2));
+        let mut migrated_games = to.get_games().unwrap();
+        migrated_games.sort();
+        let mut expected_games = vec![empty_game, started_game];
+        expected_games.sort();
+        assert_eq!(migrated_games, expected_games);
+        for game_id in [empty_game, started_game] {
+            assert_eq!(
+                to.get_game_data(game_id).unwrap(),
+                from.get_game_data(game_id).unwrap()
+            );
+        }
+
+        // games that already exist in the target are not replaced
+        assert!(matches!(
+            migrate(&from, &mut to),
+            Err(MigrationError::Importing { .. })
+        ));
+    }
+}
@@ -1,8 +1,14 @@
 mod cache_provider;
+#[cfg(feature = "file")]
+mod file_provider;
+mod migration;
 mod redis_provider;
 
 pub use cache_provider::{CacheProvider, CacheProviderArgs};
-pub use redis_provider::{RedisProvider, RedisProviderArgs};
+#[cfg(feature = "file")]
+pub use file_provider::{FileProvider, FileProviderArgs, FileProviderErrorKind};
+pub use migration::{migrate, MigrationError};
+pub use redis_provider::{Codec, RedisProvider, RedisProviderArgs, StorageMode};
 
 pub enum Provider {
     Redis(RedisProvider),
@@ -1,10 +1,26 @@
-use crate::{Board, DataProvider, GameData, Move};
-
-use log::debug;
-use redis::Client;
-use redis_async::{client::pubsub::pubsub_connect, resp::FromResp};
-use serde::{Deserialize, Serialize};
+use crate::{
+    Board, Config, DataProvider, GameData, GameDelta, GameResult, GlobalStats, InvalidGameData,
+    InvalidMove, Move, Player, PlayerStats, Tournament,
+};
+
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use redis::{cluster::ClusterClient, Client, ConnectionLike, FromRedisValue, RedisResult};
+use redis_async::{client::ConnectionBuilder, resp::FromResp};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{from_str, to_string};
+use std::{
+    fmt::Display,
+    future::Future,
+    io::{Read, Write},
+    num::NonZeroUsize,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+    time::Duration,
+};
+use tokio::{
+    task::{spawn_blocking, JoinHandle},
+    time::sleep,
+};
 use tokio_stream::StreamExt;
 use uuid::Uuid;
 
@@ -12,7 +28,213 @@ use uuid::Uuid;
 pub struct RedisProvider {
     _args: RedisProviderArgs,
 
-    redis_client: Client,
+    redis_client: RedisClient,
+    /// set on first use, either from the args or by probing the server
+    storage_mode: Arc<OnceLock<StorageMode>>,
+    /// the tasks listening to the pubsub channels of subscribed games
+    tasks: Arc<Mutex<Vec<JoinHandle<()>>>>,
+}
+
+/// how games are stored in redis
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageMode {
+    /// as JSON documents that are modified in place by the RedisJSON module
+    RedisJson,
+    /// as serialized strings that are read, modified and written back, for servers
+    /// without the RedisJSON module. Modifications use WATCH/MULTI, which redis
+    /// clusters only support on a single node.
+    String,
+    /// as compressed serialized strings, to save memory. Like `String`, every move
+    /// reads, decompresses, modifies, compresses and writes back the whole game, so
+    /// it costs more cpu and more round trips than RedisJSON.
+    Compressed(Codec),
+}
+
+impl StorageMode {
+    /// the command that reads a whole game
+    fn get_command(self) -> &'static str {
+        match self {
+            Self::RedisJson => "JSON.GET",
+            Self::String | Self::Compressed(_) => "GET",
+        }
+    }
+
+    /// turns a serialized game into the value that is stored
+    fn encode(self, serialized_game: &str) -> Result<Vec<u8>, ErrorKind> {
+        match self {
+            Self::RedisJson | Self::String => Ok(serialized_game.as_bytes().to_vec()),
+            Self::Compressed(codec) => {
+                codec
+                    .compress(serialized_game.as_bytes())
+                    .map_err(|e| ErrorKind::Serialize {
+                        message: format!("{}", e),
+                    })
+            }
+        }
+    }
+
+    /// turns a stored value back into a serialized game
+    fn decode(self, value: Vec<u8>) -> Result<String, ErrorKind> {
+        let value = match self {
+            Self::RedisJson | Self::String => value,
+            Self::Compressed(codec) => {
+                codec
+                    .decompress(&value)
+                    .map_err(|e| ErrorKind::Deserialize {
+                        message: format!("{}", e),
+                    })?
+            }
+        };
+        String::from_utf8(value).map_err(|e| ErrorKind::Deserialize {
+            message: format!("{}", e),
+        })
+    }
+}
+
+impl Display for StorageMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RedisJson => write!(f, "RedisJSON documents"),
+            Self::String => write!(f, "serialized strings"),
+            Self::Compressed(codec) => write!(f, "{} compressed strings", codec),
+        }
+    }
+}
+
+/// how games are compressed in `StorageMode::Compressed`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Gzip,
+    Zstd,
+}
+
+impl Codec {
+    /// the default level of zstd, which favours speed over size
+    const ZSTD_LEVEL: i32 = 0;
+
+    fn compress(self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Self::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Self::Zstd => zstd::encode_all(data, Self::ZSTD_LEVEL),
+        }
+    }
+
+    fn decompress(self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Self::Gzip => {
+                let mut decompressed = Vec::new();
+                flate2::read::GzDecoder::new(data).read_to_end(&mut decompressed)?;
+                Ok(decompressed)
+            }
+            Self::Zstd => zstd::decode_all(data),
+        }
+    }
+}
+
+impl Display for Codec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Gzip => write!(f, "gzip"),
+            Self::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+/// a client for either a single redis server or a redis cluster
+#[derive(Clone)]
+enum RedisClient {
+    Single(Client),
+    Cluster(ClusterClient),
+}
+
+/// a connection to either a single redis server or a redis cluster, so that the
+/// same commands can be sent regardless of the deployment
+enum RedisConnection {
+    Single(redis::Connection),
+    Cluster(redis::cluster::ClusterConnection),
+}
+
+impl RedisConnection {
+    fn inner(&mut self) -> &mut dyn ConnectionLike {
+        match self {
+            Self::Single(connection) => connection,
+            Self::Cluster(connection) => connection,
+        }
+    }
+
+    /// sends commands whose keys may live in different cluster slots. A single server
+    /// gets them as one pipeline, while a cluster pipeline sends every command to the
+    /// node of its key. A plain pipeline would send all of them to the node of the
+    /// first key.
+    fn query_each<T: FromRedisValue>(&mut self, commands: Vec<redis::Cmd>) -> RedisResult<T> {
+        match self {
+            Self::Single(connection) => {
+                let mut pipe = redis::pipe();
+                for command in commands {
+                    pipe.add_command(command);
+                }
+                pipe.query(connection)
+            }
+            Self::Cluster(connection) => {
+                let mut pipe = redis::cluster::cluster_pipe();
+                for command in commands {
+                    pipe.add_command(command);
+                }
+                pipe.query(connection)
+            }
+        }
+    }
+}
+
+impl ConnectionLike for RedisConnection {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> redis::RedisResult<redis::Value> {
+        self.inner().req_packed_command(cmd)
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> redis::RedisResult<Vec<redis::Value>> {
+        self.inner().req_packed_commands(cmd, offset, count)
+    }
+
+    // the cluster connection routes commands by their keys, which only works
+    // when the whole command is passed on
+    fn req_command(&mut self, cmd: &redis::Cmd) -> redis::RedisResult<redis::Value> {
+        self.inner().req_command(cmd)
+    }
+
+    fn supports_pipelining(&self) -> bool {
+        match self {
+            Self::Single(connection) => connection.supports_pipelining(),
+            Self::Cluster(connection) => connection.supports_pipelining(),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        match self {
+            Self::Single(connection) => connection.get_db(),
+            Self::Cluster(connection) => connection.get_db(),
+        }
+    }
+
+    fn check_connection(&mut self) -> bool {
+        self.inner().check_connection()
+    }
+
+    fn is_open(&self) -> bool {
+        match self {
+            Self::Single(connection) => connection.is_open(),
+            Self::Cluster(connection) => connection.is_open(),
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -22,6 +244,23 @@ pub struct RedisProviderArgs {
 
     pub username: Option<String>,
     pub password: Option<String>,
+
+    /// the number of seconds a game is kept after it was created or last touched.
+    /// `None` keeps games forever.
+    pub game_ttl: Option<u64>,
+
+    /// connect with TLS (`rediss://`), as required by most managed redis offerings
+    pub use_tls: bool,
+    /// treat the server as a node of a redis cluster
+    pub cluster: bool,
+
+    /// how games are stored. `None` uses RedisJSON if the server has the module
+    /// and falls back to serialized strings otherwise. Compressed games are never
+    /// detected, they have to be configured.
+    pub storage_mode: Option<StorageMode>,
+    /// how many moves a move subscriber may fall behind before it misses moves.
+    /// `None` uses the default of 64.
+    pub move_buffer: Option<NonZeroUsize>,
 }
 
 impl Default for RedisProviderArgs {
@@ -40,26 +279,24 @@ impl RedisProviderArgs {
             server_port: Self::DEFAULT_SERVER_PORT,
             username: None,
             password: None,
+            game_ttl: None,
+            use_tls: false,
+            cluster: false,
+            storage_mode: None,
+            move_buffer: None,
         }
     }
 
     pub fn from_env() -> Self {
-        let server_hostname = std::env::var("REDIS_SERVER_HOSTNAME")
-            .unwrap_or(Self::DEFAULT_SERVER_HOSTNAME.to_string());
-        let server_port = std::env::var("REDIS_SERVER_PORT")
-            .unwrap_or(Self::DEFAULT_SERVER_PORT.to_string())
-            .parse::<u16>()
-            .expect("Failed to parse REDIS_SERVER_PORT");
-
-        let username = std::env::var("REDIS_USERNAME").ok();
-        let password = std::env::var("REDIS_PASSWORD").ok();
+        Config::from_env()
+            .unwrap_or_else(|e| panic!("Invalid configuration: {}", e))
+            .redis
+    }
 
-        Self {
-            server_hostname,
-            server_port,
-            username,
-            password,
-        }
+    /// the url of the redis server, `rediss://` if TLS is used
+    pub fn get_url(&self) -> String {
+        let scheme = if self.use_tls { "rediss" } else { "redis" };
+        format!("{}://{}:{}", scheme, self.server_hostname, self.server_port)
     }
 }
 
@@ -71,173 +308,1319 @@ impl Default for RedisProvider {
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub enum ErrorKind {
-    Connection { message: String },
-    Query { message: String },
-    Deserialize { message: String },
-    Serialize { message: String },
+    Connection {
+        message: String,
+    },
+    Query {
+        message: String,
+    },
+    Deserialize {
+        message: String,
+    },
+    Serialize {
+        message: String,
+    },
+    KeyNotFound,
+    /// a game with the id to create already exists
+    GameExists,
+    InvalidMove {
+        invalid_move: InvalidMove,
+    },
+    /// the moves of a game to store don't form a valid game
+    InvalidGameData {
+        invalid_game_data: InvalidGameData,
+    },
+    Conflict,
+    BoardFull,
+    TooFast,
+    /// the game has fewer moves than should be kept of it
+    NotEnoughMoves,
 }
 
-impl ToString for ErrorKind {
-    fn to_string(&self) -> String {
+impl From<InvalidMove> for ErrorKind {
+    fn from(invalid_move: InvalidMove) -> Self {
+        Self::InvalidMove { invalid_move }
+    }
+}
+
+impl From<InvalidGameData> for ErrorKind {
+    fn from(invalid_game_data: InvalidGameData) -> Self {
+        Self::InvalidGameData { invalid_game_data }
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Connection { message } => format!(
+            Self::Connection { message } => write!(
+                f,
                 "the connection to redis could not be established: {}",
                 message
             ),
             Self::Deserialize { message } => {
-                format!("the string from redis could not be serialized: {}", message)
+                write!(
+                    f,
+                    "the string from redis could not be serialized: {}",
+                    message
+                )
             }
-            Self::Query { message } => format!("there was an error querying redis: {}", message),
+            Self::Query { message } => write!(f, "there was an error querying redis: {}", message),
             Self::Serialize { message } => {
-                format!("the local object could not be serialized: {}", message)
+                write!(f, "the local object could not be serialized: {}", message)
+            }
+            Self::KeyNotFound => write!(f, "the game does not exist"),
+            Self::GameExists => write!(f, "the game already exists"),
+            Self::Conflict => write!(f, "the game has changed since the move count was read"),
+            Self::BoardFull => write!(f, "the game already has a move for every field"),
+            Self::TooFast => write!(f, "the player's previous move was made too recently"),
+            Self::NotEnoughMoves => write!(f, "the game has fewer moves than should be kept"),
+            Self::InvalidMove { invalid_move } => {
+                write!(f, "the game contains an invalid move: {:?}", invalid_move)
+            }
+            Self::InvalidGameData { invalid_game_data } => {
+                write!(f, "the game is invalid: {}", invalid_game_data)
             }
         }
     }
 }
 
+impl std::error::Error for ErrorKind {}
+
 impl RedisProvider {
-    fn get_connection(&self) -> Result<redis::Connection, ErrorKind> {
-        self.redis_client
-            .get_connection()
-            .map_err(|e| ErrorKind::Connection {
-                message: format!("{}", e),
-            })
+    fn get_connection(&self) -> Result<RedisConnection, ErrorKind> {
+        match &self.redis_client {
+            RedisClient::Single(client) => client.get_connection().map(RedisConnection::Single),
+            RedisClient::Cluster(client) => client.get_connection().map(RedisConnection::Cluster),
+        }
+        .map_err(|e| ErrorKind::Connection {
+            message: format!("{}", e),
+        })
+    }
+
+    /// returns how games are stored. Unless it is configured, the server is probed on
+    /// first use: a server without the RedisJSON module rejects the `JSON.GET` command.
+    fn get_storage_mode(&self, connection: &mut RedisConnection) -> Result<StorageMode, ErrorKind> {
+        if let Some(storage_mode) = self.storage_mode.get() {
+            return Ok(*storage_mode);
+        }
+        let storage_mode = match self._args.storage_mode {
+            Some(storage_mode) => storage_mode,
+            None => match redis::cmd("JSON.GET")
+                .arg(Self::STORAGE_PROBE_KEY)
+                .query::<Option<String>>(connection)
+            {
+                Ok(_) => StorageMode::RedisJson,
+                Err(e) if e.kind() == redis::ErrorKind::ResponseError => StorageMode::String,
+                Err(e) => {
+                    return Err(ErrorKind::Query {
+                        message: format!("{}", e),
+                    })
+                }
+            },
+        };
+        if self.storage_mode.set(storage_mode).is_ok() {
+            info!("Storing games in redis as {}", storage_mode);
+        }
+        Ok(storage_mode)
+    }
+
+    /// the storage mode, if it has been determined yet
+    pub fn storage_mode(&self) -> Option<StorageMode> {
+        self.storage_mode.get().copied()
     }
 }
 
-impl DataProvider for RedisProvider {
-    type Args = RedisProviderArgs;
-    type ErrorKind = ErrorKind;
-    fn get_game_data(&self, game_id: Uuid) -> Result<GameData, ErrorKind> {
-        debug!("Getting game data for game {}", game_id);
-        let mut connection = self.get_connection()?;
-        let remote_move_count = (redis::cmd("JSON.ARRLEN")
-            .arg(game_id.to_string())
-            .arg("$.moves")
-            .query(&mut connection) as Result<Vec<usize>, _>)
-            .map_err(|e| ErrorKind::Query {
-                message: format!("{}", e),
-            })?
-            .remove(0);
+impl RedisProvider {
+    const INITIAL_RECONNECT_DELAY: Duration = Duration::from_millis(100);
+    const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+    /// how often a move subscription checks whether it still has receivers, broadcast
+    /// senders can't wait for that
+    const RECEIVER_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+    const DEFAULT_MOVE_BUFFER: usize = 64;
+    /// how many created games a subscriber to new games may fall behind
+    const NEW_GAME_BUFFER: usize = 64;
+
+    /// the pubsub channel the ids of new games are published to
+    const NEW_GAMES_CHANNEL: &'static str = "games:new";
+
+    /// a key that is never written, used to check whether the RedisJSON module is loaded
+    const STORAGE_PROBE_KEY: &'static str = "storage:probe";
+
+    /// listens to the pubsub channel of a game and passes every update to `on_update`,
+    /// see `listen`. Once subscribed, `on_update` receives the current game data to
+    /// catch up with the messages published before.
+    fn listen_to_game<F>(
+        &self,
+        game_id: Uuid,
+        closed: impl Future<Output = ()> + Send + 'static,
+        on_update: F,
+    ) where
+        F: FnMut(GameData) -> bool + Send + 'static,
+    {
+        self.listen(
+            game_id.to_string(),
+            move |provider| {
+                provider
+                    .get_game_data(game_id)
+                    .map(|game_data| vec![game_data])
+            },
+            closed,
+            on_update,
+        );
+    }
 
-        if remote_move_count == 0 {
-            return Ok(GameData::new_with_id(game_id));
-        }
+    /// listens to the pubsub channel and passes every message to `on_message`.
+    /// The subscription is made in the background, so every time it has been made
+    /// `on_message` receives what `catch_up` returns to make up for the messages that
+    /// were published before. If the connection is lost it is re-established with an
+    /// exponential backoff. `catch_up` runs on a blocking thread, so
+    /// the runtime isn't held up by redis. Listening stops once `on_message` returns false
+    /// or `closed` completes, which unsubscribes from the channel. Without `closed`, a
+    /// subscriber that went away would only be noticed with the next message.
+    fn listen<M, C, F>(
+        &self,
+        channel: String,
+        catch_up: C,
+        closed: impl Future<Output = ()> + Send + 'static,
+        mut on_message: F,
+    ) where
+        M: DeserializeOwned + Send + 'static,
+        C: Fn(&Self) -> Result<Vec<M>, ErrorKind> + Send + Sync + 'static,
+        F: FnMut(M) -> bool + Send + 'static,
+    {
+        let provider = self.clone();
+        let catch_up = Arc::new(catch_up);
+        let task = tokio::spawn(async move {
+            tokio::pin!(closed);
+            let args = &provider._args;
+            let mut reconnect_delay = Self::INITIAL_RECONNECT_DELAY;
+            loop {
+                let mut builder =
+                    match ConnectionBuilder::new(&args.server_hostname, args.server_port) {
+                        Ok(builder) => builder,
+                        Err(e) => {
+                            warn!("Invalid redis pubsub address: {}", e);
+                            return;
+                        }
+                    };
+                if args.use_tls {
+                    builder.tls();
+                }
+                match builder.pubsub_connect().await {
+                    Err(e) => warn!("Could not connect to redis pubsub: {}", e),
+                    Ok(connection) => match connection.subscribe(&channel).await {
+                        Err(e) => warn!("Could not subscribe to {}: {}", channel, e),
+                        Ok(mut stream) => {
+                            reconnect_delay = Self::INITIAL_RECONNECT_DELAY;
+
+                            let catch_up_provider = provider.clone();
+                            let catch_up = catch_up.clone();
+                            let messages =
+                                spawn_blocking(move || catch_up(&catch_up_provider)).await;
+                            match messages {
+                                Err(e) => warn!("Could not catch up with {}: {}", channel, e),
+                                Ok(Ok(messages)) => {
+                                    for message in messages {
+                                        if !on_message(message) {
+                                            debug!("Stopped listening to {}", channel);
+                                            return;
+                                        }
+                                    }
+                                }
+                                Ok(Err(e)) => {
+                                    warn!("Could not catch up with {}: {}", channel, e)
+                                }
+                            }
+
+                            loop {
+                                let msg = tokio::select! {
+                                    msg = stream.next() => msg,
+                                    () = &mut closed => {
+                                        debug!("Stopped listening to {}", channel);
+                                        return;
+                                    }
+                                };
+                                let Some(msg) = msg else {
+                                    break;
+                                };
+                                let msg = match msg.map(String::from_resp) {
+                                    Ok(Ok(msg)) => msg,
+                                    Ok(Err(e)) => {
+                                        warn!("Skipping malformed pubsub message: {}", e);
+                                        continue;
+                                    }
+                                    Err(e) => {
+                                        warn!("Lost pubsub connection for {}: {}", channel, e);
+                                        break;
+                                    }
+                                };
+                                debug!("Received pubsub message: {:?}", msg);
+                                let message: M = match from_str(&msg) {
+                                    Ok(message) => message,
+                                    Err(e) => {
+                                        warn!("Skipping malformed pubsub message: {}", e);
+                                        continue;
+                                    }
+                                };
+                                if !on_message(message) {
+                                    debug!("Stopped listening to {}", channel);
+                                    return;
+                                }
+                            }
+                        }
+                    },
+                }
 
-        let serialized_game: String = redis::cmd("JSON.GET")
-            .arg(game_id.to_string())
-            .query(&mut connection)
-            .map_err(|e| ErrorKind::Query {
-                message: format!("{}", e),
-            })?;
+                debug!(
+                    "Reconnecting to pubsub of {} in {:?}",
+                    channel, reconnect_delay
+                );
+                tokio::select! {
+                    () = sleep(reconnect_delay) => {}
+                    () = &mut closed => {
+                        debug!("Stopped listening to {}", channel);
+                        return;
+                    }
+                }
+                reconnect_delay = (reconnect_delay * 2).min(Self::MAX_RECONNECT_DELAY);
+            }
+        });
 
-        debug!("Deserializing game data: {}", serialized_game);
-        let game_data: GameData =
-            from_str(&serialized_game).map_err(|e| ErrorKind::Deserialize {
-                message: format!("{}", e),
+        let mut tasks = self.tasks.lock().unwrap_or_else(PoisonError::into_inner);
+        // listeners that stopped are dropped, so the registry doesn't grow with every subscription
+        tasks.retain(|task| !task.is_finished());
+        tasks.push(task);
+    }
+
+    /// appends the move in ARGV[1], made by ARGV[4] at ARGV[3], to the game in KEYS[1]
+    /// if it has less than ARGV[2] moves and exactly ARGV[5] moves. See
+    /// `GameData::is_too_fast` for the check of the minimum move interval.
+    /// The number of moves is read from and written to the counter in KEYS[2], which
+    /// expires together with the game. Games stored before the counter was kept fall
+    /// back to the length of their moves.
+    /// If the move ends the game, its result is given in ARGV[6] and set unless the game
+    /// already has one. ARGV[6] is empty otherwise.
+    /// The game with the new move and its result is published from within the script, so
+    /// subscribers receive the published games in the order the moves were made.
+    /// Returns -1 if the game doesn't exist, -2 if it is full, -3 if the move is too fast,
+    /// -4 if the game already has a result, 0 on a conflict, 2 if the result was set and
+    /// 1 on any other success.
+    const ADD_MOVE_SCRIPT: &'static str = r"
+        if redis.call('EXISTS', KEYS[1]) == 0 then
+            return -1
+        end
+        local count = tonumber(redis.call('GET', KEYS[2]))
+        if not count then
+            count = redis.call('JSON.ARRLEN', KEYS[1], '$.moves')[1]
+        end
+        if count ~= tonumber(ARGV[5]) then
+            return 0
+        end
+        if count >= tonumber(ARGV[2]) then
+            return -2
+        end
+        local result = cjson.decode(redis.call('JSON.GET', KEYS[1], '$.result'))[1]
+        if result and result ~= cjson.null then
+            return -4
+        end
+        local interval = cjson.decode(
+            redis.call('JSON.GET', KEYS[1], '$.settings.min_move_interval'))[1]
+        if interval then
+            local players = cjson.decode(redis.call('JSON.GET', KEYS[1], '$.moves[*].player'))
+            local times = cjson.decode(redis.call('JSON.GET', KEYS[1], '$.move_times'))[1] or {}
+            local offset = #players - #times
+            for i = #times, 1, -1 do
+                if players[i + offset] == ARGV[4] then
+                    if tonumber(ARGV[3]) - times[i] < interval then
+                        return -3
+                    end
+                    break
+                end
+            end
+        end
+        redis.call('JSON.ARRAPPEND', KEYS[1], '$.moves', ARGV[1])
+        redis.call('JSON.ARRAPPEND', KEYS[1], '$.move_times', ARGV[3])
+        redis.call('SET', KEYS[2], count + 1)
+        local ttl = redis.call('PTTL', KEYS[1])
+        if ttl > 0 then
+            redis.call('PEXPIRE', KEYS[2], ttl)
+        end
+        local stored = 1
+        if ARGV[6] ~= '' and redis.call('JSON.SET', KEYS[1], '$.result', ARGV[6], 'NX') then
+            stored = 2
+        end
+        redis.call('PUBLISH', KEYS[1], redis.call('JSON.GET', KEYS[1]))
+        return stored
+    ";
+
+    /// sets the result in ARGV[1] of the game in KEYS[1] unless it already has one and
+    /// publishes the game if it was set. Returns 1 if the result was set, 0 otherwise.
+    const STORE_RESULT_SCRIPT: &'static str = r"
+        if redis.call('JSON.SET', KEYS[1], '$.result', ARGV[1], 'NX') then
+            redis.call('PUBLISH', KEYS[1], redis.call('JSON.GET', KEYS[1]))
+            return 1
+        end
+        return 0
+    ";
+
+    /// returns the number of moves and the last move of the game in KEYS[1] as a json
+    /// array holding at most the one move, or nil if the game doesn't exist
+    const LAST_MOVE_SCRIPT: &'static str = r"
+        if redis.call('EXISTS', KEYS[1]) == 0 then
+            return nil
+        end
+        return {
+            redis.call('JSON.ARRLEN', KEYS[1], '$.moves')[1],
+            redis.call('JSON.GET', KEYS[1], '$.moves[-1]')
+        }
+    ";
+
+    /// appends the player token in ARGV[1] to the players of the game in KEYS[2], kept
+    /// in KEYS[1], unless it is already one of them or the game has two players already.
+    /// The list expires together with the game.
+    const REGISTER_SIDE_SCRIPT: &'static str = r"
+        if not redis.call('LPOS', KEYS[1], ARGV[1]) and redis.call('LLEN', KEYS[1]) < 2 then
+            redis.call('RPUSH', KEYS[1], ARGV[1])
+            local ttl = redis.call('PTTL', KEYS[2])
+            if ttl > 0 then
+                redis.call('PEXPIRE', KEYS[1], ttl)
+            end
+        end
+    ";
+
+    /// returns 1 if the game in KEYS[1] exists, has no result and fewer than two players
+    /// in KEYS[2], 0 otherwise. Only the result of the game is read.
+    const JOINABLE_SCRIPT: &'static str = r"
+        if redis.call('EXISTS', KEYS[1]) == 0 then
+            return 0
+        end
+        local result = cjson.decode(redis.call('JSON.GET', KEYS[1], '$.result'))[1]
+        if result and result ~= cjson.null then
+            return 0
+        end
+        if redis.call('LLEN', KEYS[2]) >= 2 then
+            return 0
+        end
+        return 1
+    ";
+
+    /// atomically appends the move, see `ADD_MOVE_SCRIPT`, and returns the result of the
+    /// game if the move has ended it. The result is stored and published together with
+    /// the move. Without an expected count, the move is retried if another move was
+    /// made after the game was read.
+    fn append_move(
+        &self,
+        connection: &mut RedisConnection,
+        game_id: Uuid,
+        new_move: Move,
+        expected_count: Option<usize>,
+    ) -> Result<Option<GameResult>, ErrorKind> {
+        let now = GameData::now();
+        if self.get_storage_mode(connection)? != StorageMode::RedisJson {
+            let mut new_result = None;
+            self.update_game(connection, game_id, |game_data| {
+                if expected_count
+                    .is_some_and(|expected_count| expected_count != game_data.moves.len())
+                {
+                    return Err(ErrorKind::Conflict);
+                }
+                if game_data.moves.len() >= game_data.settings.board_size.max_moves() {
+                    return Err(ErrorKind::BoardFull);
+                }
+                if game_data.result.is_some() {
+                    return Err(InvalidMove::GameEnded.into());
+                }
+                if game_data.is_too_fast(new_move.player, now) {
+                    return Err(ErrorKind::TooFast);
+                }
+                let had_result = game_data.result.is_some();
+                game_data.add_move_at(new_move, now);
+                new_result = game_data.result.filter(|_| !had_result);
+                Ok(())
             })?;
+            return Ok(new_result);
+        }
 
-        Ok(game_data)
+        let stringified_move = to_string(&new_move).map_err(|e| ErrorKind::Serialize {
+            message: format!("{}", e),
+        })?;
+        loop {
+            // the result is computed from the moves the move is appended to, the script
+            // only appends it if no other move was made in the meantime
+            let mut game_data = self.get_game_data(game_id)?;
+            let count = game_data.moves.len();
+            if expected_count.is_some_and(|expected_count| expected_count != count) {
+                return Err(ErrorKind::Conflict);
+            }
+            let had_result = game_data.result.is_some();
+            game_data.add_move_at(new_move, now);
+            let new_result = game_data.result.filter(|_| !had_result);
+            let serialized_result = new_result
+                .map(|result| to_string(&result))
+                .transpose()
+                .map_err(|e| ErrorKind::Serialize {
+                    message: format!("{}", e),
+                })?
+                .unwrap_or_default();
+
+            let stored: i64 = redis::Script::new(Self::ADD_MOVE_SCRIPT)
+                .key(game_id.to_string())
+                .key(Self::get_count_key(game_id))
+                .arg(&stringified_move)
+                .arg(game_data.settings.board_size.max_moves())
+                .arg(now)
+                .arg(new_move.player.to_string())
+                .arg(count)
+                .arg(&serialized_result)
+                .invoke(connection)
+                .map_err(|e| ErrorKind::Query {
+                    message: format!("{}", e),
+                })?;
+
+            match stored {
+                -1 => return Err(ErrorKind::KeyNotFound),
+                -2 => return Err(ErrorKind::BoardFull),
+                -3 => return Err(ErrorKind::TooFast),
+                -4 => return Err(InvalidMove::GameEnded.into()),
+                0 if expected_count.is_some() => return Err(ErrorKind::Conflict),
+                0 => continue,
+                1 => {
+                    debug!("Added move {:?} to game {}", new_move, game_id);
+                    return Ok(None);
+                }
+                _ => {
+                    debug!("Added move {:?} to game {}", new_move, game_id);
+                    Self::index_result(connection, game_id, Some(&serialized_result))?;
+                    return Ok(new_result);
+                }
+            }
+        }
     }
 
-    fn get_games(&self) -> Result<Vec<Uuid>, Self::ErrorKind> {
-        let mut connection = self.get_connection()?;
-        let game_ids: Vec<String> =
-            redis::cmd("KEYS")
-                .arg("*")
-                .query(&mut connection)
+    /// reads the whole game, applies `update` and writes it back. If the game is changed
+    /// in the meantime, the update is retried with the new game data. The updated game
+    /// is published and its move counter is set in the same transaction, so no other
+    /// update can be published in between. Only keys in the cluster slot of the game
+    /// may be part of the transaction, the results index is updated afterwards.
+    fn update_game<F>(
+        &self,
+        connection: &mut RedisConnection,
+        game_id: Uuid,
+        mut update: F,
+    ) -> Result<(), ErrorKind>
+    where
+        F: FnMut(&mut GameData) -> Result<(), ErrorKind>,
+    {
+        let storage_mode = self.get_storage_mode(connection)?;
+        let key = game_id.to_string();
+        let count_key = Self::get_count_key(game_id);
+        let mut written_result = None;
+        redis::transaction(connection, &[&key], |connection, pipe| {
+            let stored_game: Option<Vec<u8>> = redis::cmd(storage_mode.get_command())
+                .arg(&key)
+                .query(connection)?;
+            let updated_game = stored_game
+                .ok_or(ErrorKind::KeyNotFound)
+                .and_then(|stored_game| storage_mode.decode(stored_game))
+                .and_then(|serialized_game| {
+                    from_str::<GameData>(&serialized_game).map_err(|e| ErrorKind::Deserialize {
+                        message: format!("{}", e),
+                    })
+                })
+                .and_then(|mut game_data| {
+                    update(&mut game_data)?;
+                    let serialized_game =
+                        to_string(&game_data).map_err(|e| ErrorKind::Serialize {
+                            message: format!("{}", e),
+                        })?;
+                    let stored_game = storage_mode.encode(&serialized_game)?;
+                    let serialized_result = game_data
+                        .result
+                        .map(|result| to_string(&result))
+                        .transpose()
+                        .map_err(|e| ErrorKind::Serialize {
+                            message: format!("{}", e),
+                        })?;
+                    Ok((
+                        serialized_game,
+                        stored_game,
+                        game_data.moves.len(),
+                        serialized_result,
+                    ))
+                });
+            match updated_game {
+                Err(e) => Ok(Some(Err(e))),
+                // None means the game was changed since WATCH, so the update is retried
+                Ok((serialized_game, stored_game, move_count, serialized_result)) => {
+                    let ttl: i64 = redis::cmd("PTTL").arg(&key).query(connection)?;
+                    match storage_mode {
+                        StorageMode::RedisJson => {
+                            pipe.cmd("JSON.SET").arg(&key).arg("$").arg(&stored_game)
+                        }
+                        StorageMode::String | StorageMode::Compressed(_) => {
+                            pipe.cmd("SET").arg(&key).arg(&stored_game).arg("KEEPTTL")
+                        }
+                    };
+                    pipe.ignore()
+                        .cmd("SET")
+                        .arg(&count_key)
+                        .arg(move_count)
+                        .ignore();
+                    if ttl > 0 {
+                        pipe.cmd("PEXPIRE").arg(&count_key).arg(ttl).ignore();
+                    }
+                    written_result = serialized_result;
+                    pipe.cmd("PUBLISH")
+                        .arg(&key)
+                        .arg(&serialized_game)
+                        .ignore()
+                        .query::<Option<()>>(connection)
+                        .map(|written| written.map(Ok))
+                }
+            }
+        })
+        .map_err(|e| ErrorKind::Query {
+            message: format!("{}", e),
+        })??;
+        Self::index_result(connection, game_id, written_result.as_deref())
+    }
+
+    /// keeps the serialized result of the game in the results index, or removes the game
+    /// from the index if it has no result. The index is a single key, which is in
+    /// another cluster slot than the game, so it is written after the game.
+    fn index_result(
+        connection: &mut RedisConnection,
+        game_id: Uuid,
+        serialized_result: Option<&str>,
+    ) -> Result<(), ErrorKind> {
+        match serialized_result {
+            Some(serialized_result) => redis::cmd("HSET")
+                .arg(Self::RESULTS_INDEX_KEY)
+                .arg(game_id.to_string())
+                .arg(serialized_result)
+                .clone(),
+            None => redis::cmd("HDEL")
+                .arg(Self::RESULTS_INDEX_KEY)
+                .arg(game_id.to_string())
+                .clone(),
+        }
+        .query::<()>(connection)
+        .map_err(|e| ErrorKind::Query {
+            message: format!("{}", e),
+        })
+    }
+
+    /// (re)applies the configured ttl to the game, its move counter and its players, if
+    /// there is one
+    fn apply_ttl(&self, connection: &mut RedisConnection, game_id: Uuid) -> Result<(), ErrorKind> {
+        if let Some(game_ttl) = self._args.game_ttl {
+            redis::pipe()
+                .cmd("EXPIRE")
+                .arg(game_id.to_string())
+                .arg(game_ttl)
+                .ignore()
+                .cmd("EXPIRE")
+                .arg(Self::get_count_key(game_id))
+                .arg(game_ttl)
+                .ignore()
+                .cmd("EXPIRE")
+                .arg(Self::get_game_players_key(game_id))
+                .arg(game_ttl)
+                .ignore()
+                .query::<()>(connection)
                 .map_err(|e| ErrorKind::Query {
                     message: format!("{}", e),
                 })?;
+        }
+        Ok(())
+    }
 
-        let game_ids: Vec<Uuid> = game_ids
-            .into_iter()
-            .filter_map(|x| Uuid::parse_str(&x).ok())
-            .collect();
+    /// stores the given result unless the game already has one, publishes the game with
+    /// it and counts it in the statistics. Returns true if the result was stored.
+    fn set_result(
+        &self,
+        connection: &mut RedisConnection,
+        game_id: Uuid,
+        result: GameResult,
+    ) -> Result<bool, ErrorKind> {
+        let stored = if self.get_storage_mode(connection)? != StorageMode::RedisJson {
+            let mut stored = false;
+            self.update_game(connection, game_id, |game_data| {
+                stored = game_data.result.is_none();
+                if stored {
+                    game_data.result = Some(result);
+                }
+                Ok(())
+            })?;
+            stored
+        } else {
+            let serialized_result = to_string(&result).map_err(|e| ErrorKind::Serialize {
+                message: format!("{}", e),
+            })?;
 
-        Ok(game_ids)
+            let stored = redis::Script::new(Self::STORE_RESULT_SCRIPT)
+                .key(game_id.to_string())
+                .arg(&serialized_result)
+                .invoke::<bool>(connection)
+                .map_err(|e| ErrorKind::Query {
+                    message: format!("{}", e),
+                })?;
+            if stored {
+                Self::index_result(connection, game_id, Some(&serialized_result))?;
+            }
+            stored
+        };
+        if !stored {
+            return Ok(false);
+        }
+
+        debug!("Stored result {:?} of game {}", result, game_id);
+        self.record_result(connection, game_id, &result)?;
+        Ok(true)
     }
 
-    fn game_exists(&mut self, game_id: Uuid) -> Result<bool, Self::ErrorKind> {
-        let mut connection = self.get_connection()?;
+    /// counts the result stored by `append_move`, if the move has ended the game
+    fn record_new_result(
+        &self,
+        connection: &mut RedisConnection,
+        game_id: Uuid,
+        new_result: Option<GameResult>,
+    ) -> Result<(), ErrorKind> {
+        let Some(result) = new_result else {
+            return Ok(());
+        };
+        debug!("Stored result {:?} of game {}", result, game_id);
+        self.record_result(connection, game_id, &result)
+    }
 
-        let exists: bool = redis::cmd("EXISTS")
-            .arg(game_id.to_string())
-            .query(&mut connection)
+    /// counts the result of a game that has just ended. The first two registered
+    /// player tokens of the game played X and O.
+    fn record_result(
+        &self,
+        connection: &mut RedisConnection,
+        game_id: Uuid,
+        result: &GameResult,
+    ) -> Result<(), ErrorKind> {
+        let tokens: Vec<String> = redis::cmd("LRANGE")
+            .arg(Self::get_game_players_key(game_id))
+            .arg(0)
+            .arg(1)
+            .query(connection)
             .map_err(|e| ErrorKind::Query {
                 message: format!("{}", e),
             })?;
 
-        Ok(exists)
+        // the counters are in different cluster slots
+        let mut commands = vec![redis::cmd("HINCRBY")
+            .arg(Self::GLOBAL_STATS_KEY)
+            .arg(GlobalStats::field_for(result))
+            .arg(1)
+            .clone()];
+        let tokens = tokens
+            .iter()
+            .filter_map(|token| Uuid::parse_str(token).ok());
+        for (token, player) in tokens.zip([Player::X, Player::O]) {
+            commands.push(
+                redis::cmd("HINCRBY")
+                    .arg(Self::get_player_stats_key(token))
+                    .arg(PlayerStats::field_for(result, player))
+                    .arg(1)
+                    .clone(),
+            );
+        }
+        connection
+            .query_each::<()>(commands)
+            .map_err(|e| ErrorKind::Query {
+                message: format!("{}", e),
+            })
     }
 
-    fn add_move(&mut self, game_id: Uuid, new_move: Move) -> Result<(), ErrorKind> {
+    /// reads a hash of counters, missing counters are 0
+    fn get_counters(&self, key: &str) -> Result<Vec<(String, u64)>, ErrorKind> {
         let mut connection = self.get_connection()?;
-
-        let stringified_move = to_string(&new_move).map_err(|e| ErrorKind::Serialize {
-            message: format!("{}", e),
-        })?;
-
-        redis::cmd("JSON.ARRAPPEND")
-            .arg(game_id.to_string())
-            .arg("$.moves")
-            .arg(stringified_move)
+        redis::cmd("HGETALL")
+            .arg(key)
             .query(&mut connection)
             .map_err(|e| ErrorKind::Query {
                 message: format!("{}", e),
-            })?;
+            })
+    }
 
-        debug!("Added move {:?} to game {}", new_move, game_id);
+    /// stores the game data of a new game with a single command and applies the ttl
+    fn store_new_game(&self, game: GameData) -> Result<Uuid, ErrorKind> {
+        let mut connection = self.get_connection()?;
 
-        debug!("Publishing game data to channel {}", game_id);
-        let game_data = self.get_game_data(game_id)?;
-        let serialized_game_data = to_string(&game_data).map_err(|e| ErrorKind::Serialize {
+        let serialized_game = to_string(&game).map_err(|e| ErrorKind::Serialize {
             message: format!("{}", e),
         })?;
 
-        redis::cmd("PUBLISH")
-            .arg(game_id.to_string())
-            .arg(serialized_game_data)
-            .query(&mut connection)
+        let storage_mode = self.get_storage_mode(&mut connection)?;
+        let stored_game = storage_mode.encode(&serialized_game)?;
+        // an existing game is never overwritten
+        let set = match storage_mode {
+            StorageMode::RedisJson => redis::cmd("JSON.SET")
+                .arg(game.game_id.to_string())
+                .arg("$")
+                .arg(stored_game)
+                .arg("NX")
+                .clone(),
+            StorageMode::String | StorageMode::Compressed(_) => redis::cmd("SET")
+                .arg(game.game_id.to_string())
+                .arg(stored_game)
+                .arg("NX")
+                .clone(),
+        };
+        let stored: Option<String> = set.query(&mut connection).map_err(|e| ErrorKind::Query {
+            message: format!("{}", e),
+        })?;
+        if stored.is_none() {
+            return Err(ErrorKind::GameExists);
+        }
+        redis::cmd("SET")
+            .arg(Self::get_count_key(game.game_id))
+            .arg(game.moves.len())
+            .query::<()>(&mut connection)
             .map_err(|e| ErrorKind::Query {
                 message: format!("{}", e),
             })?;
 
-        Ok(())
-    }
-
-    fn create_game(&mut self, uuid: Option<Uuid>) -> Result<Uuid, ErrorKind> {
-        let mut connection = self.get_connection()?;
-        let uuid = uuid.unwrap_or(Uuid::new_v4());
+        self.apply_ttl(&mut connection, game.game_id)?;
 
-        let game = GameData::new_with_id(uuid);
+        redis::cmd("ZADD")
+            .arg(Self::CREATION_INDEX_KEY)
+            .arg(game.created_at)
+            .arg(game.game_id.to_string())
+            .query::<()>(&mut connection)
+            .map_err(|e| ErrorKind::Query {
+                message: format!("{}", e),
+            })?;
+        // games created from finished game data already have a result
+        if let Some(result) = game.result {
+            let serialized_result = to_string(&result).map_err(|e| ErrorKind::Serialize {
+                message: format!("{}", e),
+            })?;
+            Self::index_result(&mut connection, game.game_id, Some(&serialized_result))?;
+        }
 
-        let serialized_game = to_string(&game).map_err(|e| ErrorKind::Serialize {
+        let serialized_game_id = to_string(&game.game_id).map_err(|e| ErrorKind::Serialize {
             message: format!("{}", e),
         })?;
-
-        redis::cmd("JSON.SET")
-            .arg(uuid.to_string())
-            .arg("$")
-            .arg(serialized_game)
-            .query(&mut connection)
+        redis::cmd("PUBLISH")
+            .arg(Self::NEW_GAMES_CHANNEL)
+            .arg(serialized_game_id)
+            .query::<()>(&mut connection)
             .map_err(|e| ErrorKind::Query {
                 message: format!("{}", e),
             })?;
 
-        debug!("Created game {}", uuid);
-        Ok(uuid)
+        debug!("Created game {}", game.game_id);
+        Ok(game.game_id)
     }
 
-    fn new(args: Self::Args) -> Result<Self, ErrorKind> {
-        let redis_client = Client::open(format!(
-            "redis://{}:{}",
-            args.server_hostname, args.server_port
-        ))
-        .expect("Failed to create Redis client");
-        Ok(Self {
-            _args: args.clone(),
+    /// the key of the sorted set holding all game ids, scored by their creation time
+    const CREATION_INDEX_KEY: &'static str = "games:created";
+
+    /// the key of the hash holding the serialized results of the finished games by their id
+    const RESULTS_INDEX_KEY: &'static str = "games:results";
+
+    /// the key of the number of moves of the game, kept next to the game so the count
+    /// can be read without reading the game. Its hash tag puts it into the cluster slot
+    /// of the game, so scripts can use both.
+    fn get_count_key(game_id: Uuid) -> String {
+        format!("{{{}}}:count", game_id)
+    }
+
+    /// the key of the set holding the game ids a player token participates in
+    fn get_player_key(player_token: Uuid) -> String {
+        format!("player:{}", player_token)
+    }
+
+    /// matches the keys of `get_player_key`
+    const PLAYER_KEY_PATTERN: &'static str = "player:*";
+
+    /// the key of the list holding the player tokens of a game in the order they were
+    /// registered, in the cluster slot of the game like `get_count_key`
+    fn get_game_players_key(game_id: Uuid) -> String {
+        format!("{{{}}}:players", game_id)
+    }
+
+    /// the key of the hash counting the results of all games
+    const GLOBAL_STATS_KEY: &'static str = "stats:global";
+
+    /// the key of the hash counting the results of the games of a player token
+    fn get_player_stats_key(player_token: Uuid) -> String {
+        format!("stats:player:{}", player_token)
+    }
+
+    /// the key of the serialized tournament
+    fn get_tournament_key(tournament_id: Uuid) -> String {
+        format!("tournament:{}", tournament_id)
+    }
+}
+
+impl DataProvider for RedisProvider {
+    type Args = RedisProviderArgs;
+    type ErrorKind = ErrorKind;
+    fn get_game_data(&self, game_id: Uuid) -> Result<GameData, ErrorKind> {
+        debug!("Getting game data for game {}", game_id);
+        let mut connection = self.get_connection()?;
+        let storage_mode = self.get_storage_mode(&mut connection)?;
+        let stored_game: Option<Vec<u8>> = redis::cmd(storage_mode.get_command())
+            .arg(game_id.to_string())
+            .query(&mut connection)
+            .map_err(|e| ErrorKind::Query {
+                message: format!("{}", e),
+            })?;
+        let serialized_game = storage_mode.decode(stored_game.ok_or(ErrorKind::KeyNotFound)?)?;
+
+        debug!("Deserializing game data: {}", serialized_game);
+        let game_data: GameData =
+            from_str(&serialized_game).map_err(|e| ErrorKind::Deserialize {
+                message: format!("{}", e),
+            })?;
+
+        Ok(game_data)
+    }
+
+    fn move_count(&self, game_id: Uuid) -> Result<usize, ErrorKind> {
+        let mut connection = self.get_connection()?;
+        let count: Option<usize> = redis::cmd("GET")
+            .arg(Self::get_count_key(game_id))
+            .query(&mut connection)
+            .map_err(|e| ErrorKind::Query {
+                message: format!("{}", e),
+            })?;
+        match count {
+            Some(count) => Ok(count),
+            // games stored before the counter was kept
+            None => Ok(self.get_game_data(game_id)?.moves.len()),
+        }
+    }
+
+    fn get_last_move(&self, game_id: Uuid) -> Result<Option<(usize, Move)>, ErrorKind> {
+        let mut connection = self.get_connection()?;
+        if self.get_storage_mode(&mut connection)? != StorageMode::RedisJson {
+            let moves = self.get_game_data(game_id)?.moves;
+            return Ok(moves.last().map(|last_move| (moves.len() - 1, *last_move)));
+        }
+        let last_move: Option<(usize, String)> = redis::Script::new(Self::LAST_MOVE_SCRIPT)
+            .key(game_id.to_string())
+            .invoke(&mut connection)
+            .map_err(|e| ErrorKind::Query {
+                message: format!("{}", e),
+            })?;
+        let (move_count, last_move) = last_move.ok_or(ErrorKind::KeyNotFound)?;
+        // the path matches no move in a game without moves
+        let last_move: Vec<Move> = from_str(&last_move).map_err(|e| ErrorKind::Deserialize {
+            message: format!("{}", e),
+        })?;
+        Ok(last_move
+            .first()
+            .map(|last_move| (move_count - 1, *last_move)))
+    }
+
+    fn get_games(&self) -> Result<Vec<Uuid>, Self::ErrorKind> {
+        let mut connection = self.get_connection()?;
+        let game_ids: Vec<String> =
+            redis::cmd("KEYS")
+                .arg("*")
+                .query(&mut connection)
+                .map_err(|e| ErrorKind::Query {
+                    message: format!("{}", e),
+                })?;
+
+        let game_ids: Vec<Uuid> = game_ids
+            .into_iter()
+            .filter_map(|x| Uuid::parse_str(&x).ok())
+            .collect();
+
+        Ok(game_ids)
+    }
+
+    fn list_games_since(&self, after: DateTime<Utc>) -> Result<Vec<Uuid>, Self::ErrorKind> {
+        let mut connection = self.get_connection()?;
+        let game_ids: Vec<String> = redis::cmd("ZRANGEBYSCORE")
+            .arg(Self::CREATION_INDEX_KEY)
+            .arg(format!("({}", after.timestamp_millis()))
+            .arg("+inf")
+            .query(&mut connection)
+            .map_err(|e| ErrorKind::Query {
+                message: format!("{}", e),
+            })?;
+
+        // expired games are still in the index, they are removed once they are found
+        let mut listed_games = Vec::new();
+        for game_id in game_ids {
+            let exists: bool = redis::cmd("EXISTS")
+                .arg(&game_id)
+                .query(&mut connection)
+                .map_err(|e| ErrorKind::Query {
+                    message: format!("{}", e),
+                })?;
+            if !exists {
+                redis::cmd("ZREM")
+                    .arg(Self::CREATION_INDEX_KEY)
+                    .arg(&game_id)
+                    .query::<()>(&mut connection)
+                    .map_err(|e| ErrorKind::Query {
+                        message: format!("{}", e),
+                    })?;
+                continue;
+            }
+            if let Ok(game_id) = Uuid::parse_str(&game_id) {
+                listed_games.push(game_id);
+            }
+        }
+        Ok(listed_games)
+    }
+
+    fn list_results(
+        &self,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<(Uuid, Option<GameResult>)>, Self::ErrorKind> {
+        if limit == 0 {
+            return Ok(vec![]);
+        }
+        let mut connection = self.get_connection()?;
+        // expired games are still in the indices until they are found. The index is walked
+        // from the newest game on and expired games are removed on the way, so that the
+        // offset and the limit only count games that still exist.
+        let mut live_games = Vec::new();
+        let mut start = 0;
+        while live_games.len() < offset + limit {
+            let missing = offset + limit - live_games.len();
+            let game_ids: Vec<String> = redis::cmd("ZREVRANGE")
+                .arg(Self::CREATION_INDEX_KEY)
+                .arg(start)
+                .arg(start + missing - 1)
+                .query(&mut connection)
+                .map_err(|e| ErrorKind::Query {
+                    message: format!("{}", e),
+                })?;
+            if game_ids.is_empty() {
+                break;
+            }
+            let exists: Vec<bool> = connection
+                .query_each(
+                    game_ids
+                        .iter()
+                        .map(|game_id| redis::cmd("EXISTS").arg(game_id).clone())
+                        .collect(),
+                )
+                .map_err(|e| ErrorKind::Query {
+                    message: format!("{}", e),
+                })?;
+            for (game_id, exists) in game_ids.into_iter().zip(exists) {
+                if !exists {
+                    connection
+                        .query_each::<()>(vec![
+                            redis::cmd("ZREM")
+                                .arg(Self::CREATION_INDEX_KEY)
+                                .arg(&game_id)
+                                .clone(),
+                            redis::cmd("HDEL")
+                                .arg(Self::RESULTS_INDEX_KEY)
+                                .arg(&game_id)
+                                .clone(),
+                        ])
+                        .map_err(|e| ErrorKind::Query {
+                            message: format!("{}", e),
+                        })?;
+                    continue;
+                }
+                // the removed games no longer take up a rank in the index
+                start += 1;
+                if let Ok(game_id) = Uuid::parse_str(&game_id) {
+                    live_games.push(game_id);
+                }
+            }
+        }
+        let page: Vec<Uuid> = live_games.into_iter().skip(offset).collect();
+        if page.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let serialized_results: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(Self::RESULTS_INDEX_KEY)
+            .arg(
+                page.iter()
+                    .map(|game_id| game_id.to_string())
+                    .collect::<Vec<_>>(),
+            )
+            .query(&mut connection)
+            .map_err(|e| ErrorKind::Query {
+                message: format!("{}", e),
+            })?;
+        page.into_iter()
+            .zip(serialized_results)
+            .map(|(game_id, serialized_result)| {
+                let result = serialized_result
+                    .map(|serialized_result| from_str(&serialized_result))
+                    .transpose()
+                    .map_err(|e| ErrorKind::Deserialize {
+                        message: format!("{}", e),
+                    })?;
+                Ok((game_id, result))
+            })
+            .collect()
+    }
+
+    fn game_exists(&mut self, game_id: Uuid) -> Result<bool, Self::ErrorKind> {
+        let mut connection = self.get_connection()?;
+
+        let exists: bool = redis::cmd("EXISTS")
+            .arg(game_id.to_string())
+            .query(&mut connection)
+            .map_err(|e| ErrorKind::Query {
+                message: format!("{}", e),
+            })?;
+
+        Ok(exists)
+    }
+
+    fn is_joinable(&self, game_id: Uuid) -> Result<bool, ErrorKind> {
+        let mut connection = self.get_connection()?;
+        if self.get_storage_mode(&mut connection)? != StorageMode::RedisJson {
+            // without RedisJSON, the whole game has to be read for its result
+            match self.get_game_data(game_id) {
+                Ok(game_data) if game_data.result.is_none() => {}
+                Ok(_) | Err(ErrorKind::KeyNotFound) => return Ok(false),
+                Err(e) => return Err(e),
+            }
+            let players: usize = redis::cmd("LLEN")
+                .arg(Self::get_game_players_key(game_id))
+                .query(&mut connection)
+                .map_err(|e| ErrorKind::Query {
+                    message: format!("{}", e),
+                })?;
+            return Ok(players < 2);
+        }
+        redis::Script::new(Self::JOINABLE_SCRIPT)
+            .key(game_id.to_string())
+            .key(Self::get_game_players_key(game_id))
+            .invoke::<bool>(&mut connection)
+            .map_err(|e| ErrorKind::Query {
+                message: format!("{}", e),
+            })
+    }
+
+    fn touch(&mut self, game_id: Uuid) -> Result<(), ErrorKind> {
+        if !self.game_exists(game_id)? {
+            return Err(ErrorKind::KeyNotFound);
+        }
+        let mut connection = self.get_connection()?;
+        self.apply_ttl(&mut connection, game_id)
+    }
+
+    fn register_player(&mut self, game_id: Uuid, player_token: Uuid) -> Result<(), ErrorKind> {
+        if !self.game_exists(game_id)? {
+            return Err(ErrorKind::KeyNotFound);
+        }
+        let mut connection = self.get_connection()?;
+
+        redis::cmd("SADD")
+            .arg(Self::get_player_key(player_token))
+            .arg(game_id.to_string())
+            .query::<()>(&mut connection)
+            .map_err(|e| ErrorKind::Query {
+                message: format!("{}", e),
+            })?;
+        redis::Script::new(Self::REGISTER_SIDE_SCRIPT)
+            .key(Self::get_game_players_key(game_id))
+            .key(game_id.to_string())
+            .arg(player_token.to_string())
+            .invoke::<()>(&mut connection)
+            .map_err(|e| ErrorKind::Query {
+                message: format!("{}", e),
+            })?;
+
+        debug!("Registered player {} in game {}", player_token, game_id);
+        Ok(())
+    }
+
+    fn get_player_games(&self, player_token: Uuid) -> Result<Vec<Uuid>, ErrorKind> {
+        let mut connection = self.get_connection()?;
+        let game_ids: Vec<String> = redis::cmd("SMEMBERS")
+            .arg(Self::get_player_key(player_token))
+            .query(&mut connection)
+            .map_err(|e| ErrorKind::Query {
+                message: format!("{}", e),
+            })?;
+
+        Ok(game_ids
+            .into_iter()
+            .filter_map(|x| Uuid::parse_str(&x).ok())
+            .collect())
+    }
+
+    fn get_global_stats(&self) -> Result<GlobalStats, ErrorKind> {
+        let mut stats = GlobalStats::default();
+        for (field, count) in self.get_counters(Self::GLOBAL_STATS_KEY)? {
+            stats.increment(&field, count);
+        }
+        Ok(stats)
+    }
+
+    fn get_player_stats(&self, player_token: Uuid) -> Result<PlayerStats, ErrorKind> {
+        let mut stats = PlayerStats::default();
+        for (field, count) in self.get_counters(&Self::get_player_stats_key(player_token))? {
+            stats.increment(&field, count);
+        }
+        Ok(stats)
+    }
+
+    fn store_tournament(&mut self, tournament: &Tournament) -> Result<(), ErrorKind> {
+        let mut connection = self.get_connection()?;
+        let serialized_tournament = to_string(tournament).map_err(|e| ErrorKind::Serialize {
+            message: format!("{}", e),
+        })?;
+
+        redis::cmd("SET")
+            .arg(Self::get_tournament_key(tournament.tournament_id))
+            .arg(serialized_tournament)
+            .query::<()>(&mut connection)
+            .map_err(|e| ErrorKind::Query {
+                message: format!("{}", e),
+            })?;
+
+        debug!("Stored tournament {}", tournament.tournament_id);
+        Ok(())
+    }
+
+    fn get_tournament(&self, tournament_id: Uuid) -> Result<Tournament, ErrorKind> {
+        let mut connection = self.get_connection()?;
+        let serialized_tournament: Option<String> = redis::cmd("GET")
+            .arg(Self::get_tournament_key(tournament_id))
+            .query(&mut connection)
+            .map_err(|e| ErrorKind::Query {
+                message: format!("{}", e),
+            })?;
+
+        from_str(&serialized_tournament.ok_or(ErrorKind::KeyNotFound)?).map_err(|e| {
+            ErrorKind::Deserialize {
+                message: format!("{}", e),
+            }
+        })
+    }
+
+    fn add_move(&mut self, game_id: Uuid, new_move: Move) -> Result<(), ErrorKind> {
+        let mut connection = self.get_connection()?;
+        let new_result = self.append_move(&mut connection, game_id, new_move, None)?;
+        self.record_new_result(&mut connection, game_id, new_result)
+    }
+
+    fn add_move_if_count(
+        &mut self,
+        game_id: Uuid,
+        expected_count: usize,
+        new_move: Move,
+    ) -> Result<(), ErrorKind> {
+        let mut connection = self.get_connection()?;
+        let new_result =
+            self.append_move(&mut connection, game_id, new_move, Some(expected_count))?;
+        self.record_new_result(&mut connection, game_id, new_result)
+    }
+
+    fn truncate_game(&mut self, game_id: Uuid, move_number: usize) -> Result<Vec<Move>, ErrorKind> {
+        let mut connection = self.get_connection()?;
+        let mut removed_moves = Vec::new();
+        self.update_game(&mut connection, game_id, |game_data| {
+            if game_data.result.is_some() {
+                return Err(InvalidMove::GameEnded.into());
+            }
+            if move_number > game_data.moves.len() {
+                return Err(ErrorKind::NotEnoughMoves);
+            }
+            removed_moves = game_data.truncate_moves(move_number);
+            Ok(())
+        })?;
+
+        Ok(removed_moves)
+    }
+
+    fn restore(&mut self, game_id: Uuid, snapshot: GameData) -> Result<(), ErrorKind> {
+        snapshot.validate()?;
+        let mut connection = self.get_connection()?;
+        self.update_game(&mut connection, game_id, |game_data| {
+            *game_data = GameData {
+                game_id,
+                created_at: game_data.created_at,
+                created_by: game_data.created_by.take(),
+                ..snapshot.clone()
+            };
+            Ok(())
+        })
+    }
+
+    fn abandon_game(&mut self, game_id: Uuid) -> Result<bool, ErrorKind> {
+        let game_data = self.get_game_data(game_id)?;
+        if game_data.result.is_some() {
+            return Ok(false);
+        }
+        let mut connection = self.get_connection()?;
+        self.set_result(
+            &mut connection,
+            game_id,
+            GameResult::abandoned(game_data.moves.len()),
+        )
+    }
+
+    fn create_game(&mut self, uuid: Option<Uuid>) -> Result<Uuid, ErrorKind> {
+        let uuid = uuid.unwrap_or(Uuid::new_v4());
+        self.store_new_game(GameData::new_with_id(uuid))
+    }
+
+    fn create_game_with_move(
+        &mut self,
+        uuid: Option<Uuid>,
+        first: Move,
+    ) -> Result<Uuid, ErrorKind> {
+        Board::new().validate_move(first)?;
+
+        let mut game = GameData::new_with_id(uuid.unwrap_or(Uuid::new_v4()));
+        game.add_move(first);
+        self.store_new_game(game)
+    }
+
+    fn create_game_from(
+        &mut self,
+        uuid: Option<Uuid>,
+        template: GameData,
+    ) -> Result<Uuid, ErrorKind> {
+        template.validate()?;
+
+        let mut game = GameData {
+            game_id: uuid.unwrap_or(Uuid::new_v4()),
+            created_at: GameData::now(),
+            ..template
+        };
+        game.update_result();
+        self.store_new_game(game)
+    }
+
+    fn import_game(&mut self, mut game_data: GameData) -> Result<Uuid, ErrorKind> {
+        game_data.validate()?;
+        game_data.update_result();
+        self.store_new_game(game_data)
+    }
+
+    fn new(args: Self::Args) -> Result<Self, ErrorKind> {
+        let redis_client = if args.cluster {
+            RedisClient::Cluster(
+                ClusterClient::new(vec![args.get_url()]).expect("Failed to create Redis client"),
+            )
+        } else {
+            RedisClient::Single(
+                Client::open(args.get_url()).expect("Failed to create Redis client"),
+            )
+        };
+        Ok(Self {
+            _args: args.clone(),
             redis_client,
+            storage_mode: Arc::new(OnceLock::new()),
+            tasks: Arc::new(Mutex::new(Vec::new())),
         })
     }
 
@@ -245,7 +1628,7 @@ impl DataProvider for RedisProvider {
         debug!("Syncing board {}", game.game_id);
 
         // test if remote game data exists
-        if self.get_game_data(game.game_id).is_err() {
+        if !self.game_exists(game.game_id)? {
             debug!(
                 "Remote game data for {} doesn't exist. Creating...",
                 game.game_id
@@ -308,59 +1691,177 @@ impl DataProvider for RedisProvider {
         &mut self,
         game_id: Uuid,
     ) -> Result<tokio::sync::watch::Receiver<GameData>, Self::ErrorKind> {
-        // TODO: This is a very naive implementation. It should be thoroughly tested
-
         debug!("Subscribing to game {}", game_id);
-        // let mut connection = self.get_connection()?;
-        let (tx, rx) = tokio::sync::watch::channel(GameData::new_with_id(game_id));
-        let args = self._args.clone();
-        tokio::spawn(async move {
-            let connection = pubsub_connect(args.server_hostname, args.server_port)
-                .await
-                .unwrap();
-
-            // let mut pubsub = connection.as_pubsub();
-            let mut stream = connection.subscribe(&game_id.to_string()).await.unwrap();
-
-            while let Some(Ok(msg)) = stream.next().await {
-                let msg = String::from_resp(msg).unwrap();
-                debug!("Received pubsub message: {:?}", msg);
-                let game_data: GameData = from_str(&msg).unwrap();
-                debug!("Sending new game data to subscribers: {:?}", game_data);
-                tx.send(game_data).unwrap();
-            }
-            // loop {
-            //     let msg = pubsub.get_message().unwrap();
-            //     debug!("Received pubsub message: {:?}", msg);
-            //     let payload: String = msg.get_payload().unwrap();
-            //     let game_data: GameData = from_str(&payload).unwrap();
-            //     debug!("Sending new game data to subscribers: {:?}", game_data);
-            //     tx.send(game_data).unwrap();
-
-            //     // debug:
-            //     // for _ in 0..5 {
-            //     //     tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-            //     //     let game_data = GameData::default();
-            //     //     tx.send(game_data).unwrap()
-            //     // }
-            // }
+        // the channel starts with the stored game, so subscribers see its moves right away
+        // instead of an empty board until the next update
+        let (tx, rx) = tokio::sync::watch::channel(self.get_game_data(game_id)?);
+        let tx = Arc::new(tx);
+        // dropping the last receiver stops listening right away
+        let closed = {
+            let tx = tx.clone();
+            async move { tx.closed().await }
+        };
+        self.listen_to_game(game_id, closed, move |game_data| {
+            debug!("Sending new game data to subscribers: {:?}", game_data);
+            tx.send_if_modified(|current| {
+                if *current != game_data {
+                    *current = game_data;
+                    true
+                } else {
+                    false
+                }
+            });
+            !tx.is_closed()
         });
 
         Ok(rx)
     }
-}
-
-#[cfg(test)]
-pub mod test {
 
-    pub mod redis_stack {
-        use testcontainers::{core::WaitFor, Image};
-        // docker image: redis-stack-server
+    fn subscribe_to_new_games(
+        &mut self,
+    ) -> Result<tokio::sync::broadcast::Receiver<Uuid>, Self::ErrorKind> {
+        let (tx, rx) = tokio::sync::broadcast::channel(Self::NEW_GAME_BUFFER);
+        let tx = Arc::new(tx);
+        let closed = {
+            let tx = tx.clone();
+            async move {
+                while tx.receiver_count() > 0 {
+                    sleep(Self::RECEIVER_CHECK_INTERVAL).await;
+                }
+            }
+        };
+        // the games created while the connection was lost are read from the creation
+        // index, which may repeat games that were already sent
+        let subscribed_at = Utc::now();
+        self.listen(
+            Self::NEW_GAMES_CHANNEL.to_string(),
+            move |provider| provider.list_games_since(subscribed_at),
+            closed,
+            move |game_id| tx.send(game_id).is_ok(),
+        );
 
-        const NAME: &str = "redis/redis-stack-server";
-        const TAG: &str = "latest";
+        Ok(rx)
+    }
 
-        #[derive(Debug, Default)]
+    fn subscribe_to_game_moves(
+        &mut self,
+        game_id: Uuid,
+    ) -> Result<tokio::sync::broadcast::Receiver<GameDelta>, Self::ErrorKind> {
+        debug!("Subscribing to moves of game {}", game_id);
+        let mut sent_moves = self.move_count(game_id)?;
+        let capacity = self
+            ._args
+            .move_buffer
+            .map_or(Self::DEFAULT_MOVE_BUFFER, NonZeroUsize::get);
+        let (tx, rx) = tokio::sync::broadcast::channel(capacity);
+        let tx = Arc::new(tx);
+        let closed = {
+            let tx = tx.clone();
+            async move {
+                while tx.receiver_count() > 0 {
+                    sleep(Self::RECEIVER_CHECK_INTERVAL).await;
+                }
+            }
+        };
+        // every published game data contains the full history, so the moves
+        // that are new since the last update can be derived from it
+        self.listen_to_game(game_id, closed, move |game_data| {
+            for (index, new_move) in game_data.moves.iter().enumerate().skip(sent_moves) {
+                let delta = GameDelta {
+                    move_: *new_move,
+                    index,
+                    // the game is published together with the result of its last move
+                    result: game_data
+                        .result
+                        .filter(|_| index + 1 == game_data.moves.len()),
+                };
+                if tx.send(delta).is_err() {
+                    return false;
+                }
+            }
+            // a truncated game continues with the moves after the truncation
+            sent_moves = game_data.moves.len();
+            tx.receiver_count() > 0
+        });
+
+        Ok(rx)
+    }
+
+    fn clear_all(&mut self) -> Result<usize, ErrorKind> {
+        let game_ids = self.get_games()?;
+        let mut connection = self.get_connection()?;
+        let player_keys: Vec<String> = redis::cmd("KEYS")
+            .arg(Self::PLAYER_KEY_PATTERN)
+            .query(&mut connection)
+            .map_err(|e| ErrorKind::Query {
+                message: format!("{}", e),
+            })?;
+        // only the keys of the games are deleted, so other data in the database survives.
+        // Every key is deleted on its own, the keys of a cluster live on different nodes.
+        let keys = game_ids
+            .iter()
+            .flat_map(|game_id| {
+                [
+                    game_id.to_string(),
+                    Self::get_count_key(*game_id),
+                    Self::get_game_players_key(*game_id),
+                ]
+            })
+            .chain(player_keys)
+            .chain([Self::CREATION_INDEX_KEY, Self::RESULTS_INDEX_KEY].map(str::to_string));
+        connection
+            .query_each::<()>(keys.map(|key| redis::cmd("DEL").arg(key).clone()).collect())
+            .map_err(|e| ErrorKind::Query {
+                message: format!("{}", e),
+            })?;
+        // closes the subscriptions of the removed games
+        self.close()?;
+        Ok(game_ids.len())
+    }
+
+    fn kind(&self) -> &'static str {
+        "redis"
+    }
+
+    fn storage_description(&self) -> Option<String> {
+        // the storage mode is probed unless it is configured or known already
+        let mut connection = self.get_connection().ok()?;
+        let storage_mode = self.get_storage_mode(&mut connection).ok()?;
+        Some(storage_mode.to_string())
+    }
+
+    fn ping(&self) -> Result<(), ErrorKind> {
+        let mut connection = self.get_connection()?;
+        redis::cmd("PING")
+            .query::<String>(&mut connection)
+            .map(|_| ())
+            .map_err(|e| ErrorKind::Query {
+                message: format!("{}", e),
+            })
+    }
+
+    fn close(&mut self) -> Result<(), ErrorKind> {
+        let tasks = std::mem::take(&mut *self.tasks.lock().unwrap_or_else(PoisonError::into_inner));
+        debug!("Stopping {} subscription tasks", tasks.len());
+        // the senders are owned by the tasks, so aborting them closes the receivers
+        for task in tasks {
+            task.abort();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+
+    pub mod redis_stack {
+        use testcontainers::{core::WaitFor, Image};
+        // docker image: redis-stack-server
+
+        const NAME: &str = "redis/redis-stack-server";
+        const TAG: &str = "latest";
+
+        #[derive(Debug, Default)]
         pub struct Redis;
 
         impl Image for Redis {
@@ -379,12 +1880,79 @@ pub mod test {
             }
         }
     }
+    pub mod plain_redis {
+        use testcontainers::{core::WaitFor, Image};
+        // docker image: redis, without any modules
+
+        const NAME: &str = "redis";
+        const TAG: &str = "7";
+
+        #[derive(Debug, Default)]
+        pub struct PlainRedis;
+
+        impl Image for PlainRedis {
+            type Args = ();
+
+            fn name(&self) -> String {
+                NAME.to_owned()
+            }
+
+            fn tag(&self) -> String {
+                TAG.to_owned()
+            }
+
+            fn ready_conditions(&self) -> Vec<WaitFor> {
+                vec![WaitFor::message_on_stdout("Ready to accept connections")]
+            }
+        }
+    }
     use super::*;
     use crate::{DataProviderFactory, Player};
 
+    use plain_redis::PlainRedis;
     use redis::Client;
     use redis_stack::Redis;
-    use testcontainers::clients::Cli as DockerCli;
+    use testcontainers::{clients::Cli as DockerCli, Container, RunnableImage};
+
+    #[test]
+    fn url_for_flags() {
+        for (use_tls, cluster, expected_url) in [
+            (false, false, "redis://redis.example.com:6380"),
+            (true, false, "rediss://redis.example.com:6380"),
+            (false, true, "redis://redis.example.com:6380"),
+            (true, true, "rediss://redis.example.com:6380"),
+        ] {
+            let args = RedisProviderArgs {
+                server_hostname: "redis.example.com".to_string(),
+                server_port: 6380,
+                use_tls,
+                cluster,
+                ..Default::default()
+            };
+            assert_eq!(args.get_url(), expected_url);
+
+            // creating the client doesn't connect yet, but validates the url
+            let data_provider = RedisProvider::new(args).unwrap();
+            assert_eq!(
+                matches!(data_provider.redis_client, RedisClient::Cluster(_)),
+                cluster
+            );
+        }
+    }
+
+    #[test]
+    fn game_keys_share_a_slot() {
+        use redis::cluster_routing::get_slot;
+
+        let game_id = Uuid::new_v4();
+        let slot = get_slot(game_id.to_string().as_bytes());
+        for key in [
+            RedisProvider::get_count_key(game_id),
+            RedisProvider::get_game_players_key(game_id),
+        ] {
+            assert_eq!(get_slot(key.as_bytes()), slot, "{} is in another slot", key);
+        }
+    }
 
     #[tokio::test]
     async fn start_redis_server() {
@@ -438,6 +2006,11 @@ pub mod test {
             server_port: redis_port,
             username: None,
             password: None,
+            game_ttl: None,
+            use_tls: false,
+            cluster: false,
+            storage_mode: None,
+            move_buffer: None,
         };
 
         let mut data_provider = DataProviderFactory::create::<RedisProvider>(args)
@@ -579,4 +2152,1325 @@ pub mod test {
             );
         }
     }
+    #[tokio::test]
+    async fn test_subscription_reconnect() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let redis_port = redis_container.get_host_port_ipv4(6379);
+
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_port,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let game_id = data_provider.create_game(None).unwrap();
+        let mut rx = data_provider.subscribe_to_game(game_id).unwrap();
+
+        // wait for the subscription to be established
+        sleep(Duration::from_millis(500)).await;
+
+        // drop every pubsub connection, as a redis restart would
+        let mut connection = data_provider.get_connection().unwrap();
+        let killed: usize = redis::cmd("CLIENT")
+            .arg("KILL")
+            .arg("TYPE")
+            .arg("pubsub")
+            .query(&mut connection)
+            .unwrap();
+        assert!(killed > 0, "expected the subscription to be connected");
+
+        sleep(Duration::from_millis(500)).await;
+
+        let new_move = Move::new((0, 0), Player::X);
+        data_provider.add_move(game_id, new_move).unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), rx.changed())
+            .await
+            .expect("subscription did not resume after reconnect")
+            .unwrap();
+        assert_eq!(rx.borrow().moves, vec![new_move]);
+    }
+    #[tokio::test]
+    async fn test_subscription_resumes_after_restart() {
+        // the port has to stay the same when the container is started again
+        let redis_port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let docker_cli = DockerCli::default();
+        let redis_container =
+            docker_cli.run(RunnableImage::from(Redis).with_mapped_port((redis_port, 6379)));
+
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_port,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let game_id = data_provider.create_game(None).unwrap();
+        let mut rx = data_provider.subscribe_to_game(game_id).unwrap();
+
+        // wait for the subscription to be established
+        sleep(Duration::from_millis(500)).await;
+
+        redis_container.stop();
+        redis_container.start();
+        let mut restarted = false;
+        for _ in 0..100 {
+            if data_provider.ping().is_ok() {
+                restarted = true;
+                break;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+        assert!(restarted, "redis did not come back after the restart");
+
+        // the server kept nothing, so the game is created again
+        data_provider.create_game(Some(game_id)).unwrap();
+        // the reconnect delay has only doubled a few times while the server was down
+        sleep(Duration::from_secs(4)).await;
+
+        let new_move = Move::new((0, 0), Player::X);
+        data_provider.add_move(game_id, new_move).unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while rx.borrow_and_update().moves != vec![new_move] {
+                rx.changed().await.unwrap();
+            }
+        })
+        .await
+        .expect("subscription did not resume after the restart");
+    }
+    #[tokio::test]
+    async fn test_clear_all() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let game_id = data_provider.create_game(None).unwrap();
+        data_provider.create_game(None).unwrap();
+        data_provider
+            .register_player(game_id, Uuid::new_v4())
+            .unwrap();
+        let mut rx = data_provider.subscribe_to_game(game_id).unwrap();
+        let mut connection = data_provider.get_connection().unwrap();
+        redis::cmd("SET")
+            .arg("unrelated")
+            .arg("kept")
+            .query::<()>(&mut connection)
+            .unwrap();
+
+        assert_eq!(data_provider.clear_all(), Ok(2));
+        assert_eq!(data_provider.get_games(), Ok(vec![]));
+        assert!(rx.changed().await.is_err());
+        let keys: Vec<String> = redis::cmd("KEYS").arg("*").query(&mut connection).unwrap();
+        assert_eq!(keys, vec!["unrelated".to_string()]);
+    }
+    #[tokio::test]
+    async fn test_ping() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(data_provider.kind(), "redis");
+        assert_eq!(data_provider.ping(), Ok(()));
+
+        // nothing listens on the port once the container is gone
+        drop(redis_container);
+        assert!(matches!(
+            data_provider.ping(),
+            Err(ErrorKind::Connection { .. })
+        ));
+    }
+    #[tokio::test]
+    async fn test_import_game() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let mut game_data = GameData::new();
+        game_data.created_at = 42;
+        game_data.add_move(Move::new((4, 4), Player::X));
+        let game_id = data_provider.import_game(game_data.clone()).unwrap();
+        assert_eq!(game_id, game_data.game_id);
+        assert_eq!(data_provider.get_game_data(game_id).unwrap(), game_data);
+        assert_eq!(
+            data_provider.import_game(game_data),
+            Err(ErrorKind::GameExists)
+        );
+
+        let mut invalid_game = GameData::new();
+        invalid_game.add_move(Move::new((4, 4), Player::X));
+        invalid_game.add_move(Move::new((4, 3), Player::X));
+        assert_eq!(
+            data_provider.import_game(invalid_game.clone()),
+            Err(ErrorKind::InvalidGameData {
+                invalid_game_data: InvalidGameData::SamePlayerTwice { index: 1 }
+            })
+        );
+        assert!(!data_provider.game_exists(invalid_game.game_id).unwrap());
+    }
+    #[tokio::test]
+    async fn test_create_existing_game() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let game_id = data_provider.create_game(None).unwrap();
+        data_provider
+            .add_move(game_id, Move::new((4, 4), Player::X))
+            .unwrap();
+        assert_eq!(
+            data_provider.create_game(Some(game_id)),
+            Err(ErrorKind::GameExists)
+        );
+        assert_eq!(data_provider.get_game_data(game_id).unwrap().moves.len(), 1);
+    }
+    #[tokio::test]
+    async fn test_subscription_initial_value() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let game_id = data_provider.create_game(None).unwrap();
+        let moves = [Move::new((0, 0), Player::X), Move::new((1, 1), Player::O)];
+        for new_move in moves {
+            data_provider.add_move(game_id, new_move).unwrap();
+        }
+
+        let rx = data_provider.subscribe_to_game(game_id).unwrap();
+        assert_eq!(rx.borrow().moves, moves);
+        assert_eq!(
+            data_provider.subscribe_to_game(Uuid::new_v4()).err(),
+            Some(ErrorKind::KeyNotFound)
+        );
+    }
+    #[tokio::test]
+    async fn test_subscribe_to_new_games() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let mut rx = data_provider.subscribe_to_new_games().unwrap();
+        // the subscription is made in the background
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let game_id = data_provider.create_game(None).unwrap();
+        let created = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("the new game wasn't announced")
+            .unwrap();
+        assert_eq!(created, game_id);
+    }
+    #[tokio::test]
+    async fn test_add_move_if_count() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let redis_port = redis_container.get_host_port_ipv4(6379);
+
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_port,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let game_id = data_provider.create_game(None).unwrap();
+        data_provider
+            .add_move_if_count(game_id, 0, Move::new((0, 0), Player::X))
+            .unwrap();
+
+        // two clients that both believe the game has one move
+        let results: Vec<_> = [(0, 1), (1, 1)]
+            .into_iter()
+            .map(|coordinates| {
+                data_provider.add_move_if_count(game_id, 1, Move::new(coordinates, Player::O))
+            })
+            .collect();
+
+        assert_eq!(results[0], Ok(()));
+        assert_eq!(results[1], Err(ErrorKind::Conflict));
+        assert_eq!(
+            data_provider.add_move_if_count(Uuid::new_v4(), 0, Move::new((0, 0), Player::X)),
+            Err(ErrorKind::KeyNotFound)
+        );
+        assert_eq!(data_provider.get_game_data(game_id).unwrap().moves.len(), 2);
+    }
+    #[tokio::test]
+    async fn test_board_full() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let redis_port = redis_container.get_host_port_ipv4(6379);
+
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_port,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let game_id = data_provider.create_game(None).unwrap();
+        for index in 0..Board::MAX_MOVES {
+            let player = if index % 2 == 0 { Player::X } else { Player::O };
+            data_provider
+                .add_move(game_id, Move::new((index / 9, index % 9), player))
+                .unwrap();
+        }
+
+        assert_eq!(
+            data_provider.add_move(game_id, Move::new((0, 0), Player::O)),
+            Err(ErrorKind::BoardFull)
+        );
+        assert_eq!(
+            data_provider.get_game_data(game_id).unwrap().moves.len(),
+            Board::MAX_MOVES
+        );
+    }
+    #[tokio::test]
+    async fn test_move_after_result() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let redis_port = redis_container.get_host_port_ipv4(6379);
+
+        // RedisJSON games are checked by the script, the others by the transaction
+        for storage_mode in [StorageMode::RedisJson, StorageMode::String] {
+            let mut data_provider = RedisProvider::new(RedisProviderArgs {
+                server_port: redis_port,
+                storage_mode: Some(storage_mode),
+                ..Default::default()
+            })
+            .unwrap();
+            let game_id = data_provider
+                .create_game_with_move(None, Move::new((4, 4), Player::X))
+                .unwrap();
+            assert!(data_provider.abandon_game(game_id).unwrap());
+
+            let ended = Err(ErrorKind::InvalidMove {
+                invalid_move: InvalidMove::GameEnded,
+            });
+            assert_eq!(
+                data_provider.add_move(game_id, Move::new((3, 3), Player::O)),
+                ended,
+                "{}",
+                storage_mode
+            );
+            assert_eq!(
+                data_provider.add_move_if_count(game_id, 1, Move::new((3, 3), Player::O)),
+                ended,
+                "{}",
+                storage_mode
+            );
+            assert_eq!(data_provider.move_count(game_id), Ok(1), "{}", storage_mode);
+        }
+    }
+    #[tokio::test]
+    async fn test_list_games_since() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let redis_port = redis_container.get_host_port_ipv4(6379);
+
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_port,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let old_game = data_provider.create_game(None).unwrap();
+        sleep(Duration::from_millis(10)).await;
+        let since = Utc::now();
+        sleep(Duration::from_millis(10)).await;
+        let new_game = data_provider.create_game(None).unwrap();
+
+        assert_eq!(
+            data_provider.list_games_since(since).unwrap(),
+            vec![new_game]
+        );
+        assert_eq!(
+            data_provider
+                .list_games_since(DateTime::<Utc>::MIN_UTC)
+                .unwrap(),
+            vec![old_game, new_game]
+        );
+    }
+    #[tokio::test]
+    async fn test_list_results() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let finished_game = data_provider.create_game(None).unwrap();
+        for (coordinates, player) in [
+            ((1, 1), Player::X),
+            ((4, 4), Player::O),
+            ((3, 4), Player::X),
+            ((1, 4), Player::O),
+            ((3, 5), Player::X),
+            ((1, 7), Player::O),
+            ((3, 3), Player::X),
+            ((0, 0), Player::O),
+            ((0, 2), Player::X),
+            ((0, 6), Player::O),
+            ((2, 0), Player::X),
+            ((8, 2), Player::O),
+            ((6, 8), Player::X),
+            ((2, 8), Player::O),
+            ((7, 7), Player::X),
+            ((2, 5), Player::O),
+            ((8, 6), Player::X),
+        ] {
+            data_provider
+                .add_move(finished_game, Move::new(coordinates, player))
+                .unwrap();
+        }
+        sleep(Duration::from_millis(2)).await;
+        let running_game = data_provider.create_game(None).unwrap();
+        data_provider
+            .add_move(running_game, Move::new((4, 4), Player::X))
+            .unwrap();
+
+        let expected_result = Some(GameResult {
+            winner: Some(Player::X),
+            move_count: 17,
+            abandoned: false,
+        });
+        assert_eq!(
+            data_provider.list_results(10, 0).unwrap(),
+            vec![(running_game, None), (finished_game, expected_result)]
+        );
+        assert_eq!(
+            data_provider.list_results(1, 1).unwrap(),
+            vec![(finished_game, expected_result)]
+        );
+
+        // expired games are left out, a page in the middle of which a game has expired
+        // is filled up with the next older game
+        sleep(Duration::from_millis(2)).await;
+        let newest_game = data_provider.create_game(None).unwrap();
+        let mut connection = data_provider.get_connection().unwrap();
+        redis::cmd("DEL")
+            .arg(running_game.to_string())
+            .query::<()>(&mut connection)
+            .unwrap();
+        assert_eq!(
+            data_provider.list_results(2, 0).unwrap(),
+            vec![(newest_game, None), (finished_game, expected_result)]
+        );
+        assert_eq!(
+            data_provider.list_results(1, 1).unwrap(),
+            vec![(finished_game, expected_result)]
+        );
+        assert_eq!(data_provider.list_results(10, 2).unwrap(), vec![]);
+    }
+    #[tokio::test]
+    async fn test_storage_mode_detection() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(data_provider.storage_mode(), None);
+        data_provider.get_games().unwrap();
+        data_provider.get_game_data(Uuid::new_v4()).unwrap_err();
+        assert_eq!(data_provider.storage_mode(), Some(StorageMode::RedisJson));
+
+        // the health info probes the server itself
+        let data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            data_provider.storage_description().as_deref(),
+            Some("RedisJSON documents")
+        );
+    }
+    #[tokio::test]
+    async fn test_string_fallback() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(PlainRedis);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            game_ttl: Some(3600),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let game_id = data_provider.create_game(None).unwrap();
+        assert_eq!(data_provider.storage_mode(), Some(StorageMode::String));
+        assert_eq!(
+            data_provider.storage_description().as_deref(),
+            Some("serialized strings")
+        );
+
+        let moves = [
+            Move::new((0, 0), Player::X),
+            Move::new((1, 1), Player::O),
+            Move::new((3, 3), Player::X),
+        ];
+        for new_move in moves {
+            data_provider.add_move(game_id, new_move).unwrap();
+        }
+        assert_eq!(
+            data_provider.get_game_data(game_id).unwrap().moves,
+            moves.to_vec()
+        );
+        assert_eq!(
+            data_provider.add_move_if_count(game_id, 2, Move::new((0, 4), Player::O)),
+            Err(ErrorKind::Conflict)
+        );
+        assert_eq!(
+            data_provider.add_move(Uuid::new_v4(), Move::new((0, 0), Player::X)),
+            Err(ErrorKind::KeyNotFound)
+        );
+
+        // rewriting the game keeps its ttl
+        let mut connection = data_provider.get_connection().unwrap();
+        let ttl: i64 = redis::cmd("TTL")
+            .arg(game_id.to_string())
+            .query(&mut connection)
+            .unwrap();
+        assert!(ttl > 0, "the game lost its ttl: {}", ttl);
+    }
+    #[test]
+    fn codec_round_trip() {
+        let serialized_game = to_string(&GameData::new()).unwrap();
+        for codec in [Codec::Gzip, Codec::Zstd] {
+            let storage_mode = StorageMode::Compressed(codec);
+            let stored_game = storage_mode.encode(&serialized_game).unwrap();
+            assert_ne!(stored_game, serialized_game.as_bytes());
+            assert_eq!(storage_mode.decode(stored_game).unwrap(), serialized_game);
+            assert!(matches!(
+                storage_mode.decode(serialized_game.as_bytes().to_vec()),
+                Err(ErrorKind::Deserialize { .. })
+            ));
+        }
+    }
+    #[tokio::test]
+    async fn test_compressed_storage() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(PlainRedis);
+        for codec in [Codec::Gzip, Codec::Zstd] {
+            let mut data_provider = RedisProvider::new(RedisProviderArgs {
+                server_port: redis_container.get_host_port_ipv4(6379),
+                storage_mode: Some(StorageMode::Compressed(codec)),
+                ..Default::default()
+            })
+            .unwrap();
+
+            let game_id = data_provider.create_game(None).unwrap();
+            let moves = [
+                Move::new((0, 0), Player::X),
+                Move::new((1, 1), Player::O),
+                Move::new((3, 3), Player::X),
+            ];
+            for new_move in moves {
+                data_provider.add_move(game_id, new_move).unwrap();
+            }
+            assert_eq!(
+                data_provider.get_game_data(game_id).unwrap().moves,
+                moves.to_vec()
+            );
+            assert_eq!(
+                data_provider.add_move_if_count(game_id, 2, Move::new((0, 4), Player::O)),
+                Err(ErrorKind::Conflict)
+            );
+            data_provider.truncate_game(game_id, 1).unwrap();
+            assert_eq!(data_provider.move_count(game_id).unwrap(), 1);
+
+            // the stored value is compressed, not the serialized game
+            let mut connection = data_provider.get_connection().unwrap();
+            let stored_game: Vec<u8> = redis::cmd("GET")
+                .arg(game_id.to_string())
+                .query(&mut connection)
+                .unwrap();
+            assert_eq!(
+                from_str::<GameData>(&StorageMode::Compressed(codec).decode(stored_game).unwrap())
+                    .unwrap(),
+                data_provider.get_game_data(game_id).unwrap()
+            );
+        }
+    }
+    #[tokio::test]
+    async fn test_truncate_game() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let game_id = data_provider.create_game(None).unwrap();
+        let moves = [
+            Move::new((0, 0), Player::X),
+            Move::new((1, 1), Player::O),
+            Move::new((3, 3), Player::X),
+        ];
+        for new_move in moves {
+            data_provider.add_move(game_id, new_move).unwrap();
+        }
+
+        assert_eq!(data_provider.truncate_game(game_id, 1).unwrap(), moves[1..]);
+        let game_data = data_provider.get_game_data(game_id).unwrap();
+        assert_eq!(game_data.moves, moves[..1]);
+        assert_eq!(game_data.move_times.len(), 1);
+        assert_eq!(
+            data_provider.truncate_game(game_id, 2),
+            Err(ErrorKind::NotEnoughMoves)
+        );
+
+        // a game that has ended keeps its moves and its result
+        data_provider.abandon_game(game_id).unwrap();
+        assert_eq!(
+            data_provider.truncate_game(game_id, 0),
+            Err(ErrorKind::InvalidMove {
+                invalid_move: InvalidMove::GameEnded
+            })
+        );
+        let game_data = data_provider.get_game_data(game_id).unwrap();
+        assert_eq!(game_data.moves, moves[..1]);
+        assert_eq!(game_data.result, Some(GameResult::abandoned(1)));
+    }
+    #[tokio::test]
+    async fn test_get_last_move() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        for storage_mode in [StorageMode::RedisJson, StorageMode::String] {
+            let mut data_provider = RedisProvider::new(RedisProviderArgs {
+                server_port: redis_container.get_host_port_ipv4(6379),
+                storage_mode: Some(storage_mode),
+                ..Default::default()
+            })
+            .unwrap();
+
+            let game_id = data_provider.create_game(None).unwrap();
+            assert_eq!(data_provider.get_last_move(game_id), Ok(None));
+
+            data_provider
+                .add_move(game_id, Move::new((0, 0), Player::X))
+                .unwrap();
+            data_provider
+                .add_move(game_id, Move::new((1, 1), Player::O))
+                .unwrap();
+            assert_eq!(
+                data_provider.get_last_move(game_id),
+                Ok(Some((1, Move::new((1, 1), Player::O))))
+            );
+            assert_eq!(
+                data_provider.get_last_move(Uuid::new_v4()),
+                Err(ErrorKind::KeyNotFound)
+            );
+        }
+    }
+    #[tokio::test]
+    async fn test_move_counter() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+        let mut connection = data_provider.get_connection().unwrap();
+
+        let game_id = data_provider.create_game(None).unwrap();
+        let moves = [
+            Move::new((0, 0), Player::X),
+            Move::new((1, 1), Player::O),
+            Move::new((3, 3), Player::X),
+        ];
+        for (move_count, new_move) in moves.into_iter().enumerate() {
+            let counter: usize = redis::cmd("GET")
+                .arg(RedisProvider::get_count_key(game_id))
+                .query(&mut connection)
+                .unwrap();
+            let array_length: Vec<usize> = redis::cmd("JSON.ARRLEN")
+                .arg(game_id.to_string())
+                .arg("$.moves")
+                .query(&mut connection)
+                .unwrap();
+            assert_eq!(counter, move_count);
+            assert_eq!(array_length, vec![move_count]);
+            assert_eq!(data_provider.move_count(game_id).unwrap(), move_count);
+
+            data_provider.add_move(game_id, new_move).unwrap();
+        }
+        assert_eq!(data_provider.move_count(game_id).unwrap(), moves.len());
+
+        // rewriting the game keeps the counter in sync
+        data_provider.truncate_game(game_id, 1).unwrap();
+        assert_eq!(data_provider.move_count(game_id).unwrap(), 1);
+
+        // games stored before the counter was kept are counted from their moves
+        redis::cmd("DEL")
+            .arg(RedisProvider::get_count_key(game_id))
+            .query::<()>(&mut connection)
+            .unwrap();
+        assert_eq!(data_provider.move_count(game_id).unwrap(), 1);
+        data_provider
+            .add_move_if_count(game_id, 1, Move::new((1, 0), Player::O))
+            .unwrap();
+        assert_eq!(data_provider.move_count(game_id).unwrap(), 2);
+    }
+    #[tokio::test]
+    async fn test_restore() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let game_id = data_provider.create_game(None).unwrap();
+        data_provider
+            .add_move(game_id, Move::new((0, 0), Player::X))
+            .unwrap();
+        let snapshot = data_provider.snapshot(game_id).unwrap();
+        data_provider
+            .add_move(game_id, Move::new((1, 1), Player::O))
+            .unwrap();
+        let mut rx = data_provider.subscribe_to_game(game_id).unwrap();
+
+        data_provider.restore(game_id, snapshot.clone()).unwrap();
+        assert_eq!(data_provider.get_game_data(game_id).unwrap(), snapshot);
+        tokio::time::timeout(Duration::from_secs(5), rx.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(*rx.borrow(), snapshot);
+
+        assert_eq!(
+            data_provider.restore(Uuid::new_v4(), snapshot),
+            Err(ErrorKind::KeyNotFound)
+        );
+    }
+    #[tokio::test]
+    async fn test_abandon_idle_games() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let mut stale_game = GameData::new();
+        stale_game.created_at = 1_000;
+        stale_game.add_move_at(Move::new((4, 4), Player::X), 2_000);
+        let stale_game = data_provider.import_game(stale_game).unwrap();
+        let fresh_game = data_provider.create_game(None).unwrap();
+        let mut rx = data_provider.subscribe_to_game(stale_game).unwrap();
+
+        assert_eq!(
+            data_provider.abandon_idle_games(GameData::now() - 60_000),
+            Ok(vec![stale_game])
+        );
+        let expected_result = Some(GameResult::abandoned(1));
+        assert_eq!(
+            data_provider.get_game_data(stale_game).unwrap().result,
+            expected_result
+        );
+        assert_eq!(
+            data_provider.get_game_data(fresh_game).unwrap().result,
+            None
+        );
+        tokio::time::timeout(Duration::from_secs(5), rx.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(rx.borrow().result, expected_result);
+        assert_eq!(data_provider.get_global_stats().unwrap().draws, 1);
+
+        // the result is only set once
+        assert_eq!(data_provider.abandon_game(stale_game), Ok(false));
+        assert_eq!(
+            data_provider.abandon_idle_games(GameData::now() + 1),
+            Ok(vec![fresh_game])
+        );
+        assert_eq!(data_provider.get_global_stats().unwrap().draws, 2);
+    }
+    #[tokio::test]
+    async fn test_create_game_from() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let mut template = GameData::new();
+        template.created_by = Some("alice".to_string());
+        template.moves.push(Move::new((4, 4), Player::X));
+        let game_id = data_provider
+            .create_game_from(None, template.clone())
+            .unwrap();
+        let game_data = data_provider.get_game_data(game_id).unwrap();
+        assert_eq!(game_data.created_by, Some("alice".to_string()));
+        assert_eq!(game_data.moves, template.moves);
+
+        template.moves.push(Move::new((4, 4), Player::O));
+        assert!(matches!(
+            data_provider.create_game_from(None, template),
+            Err(ErrorKind::InvalidMove { .. })
+        ));
+    }
+    #[tokio::test]
+    async fn test_close() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let game_id = data_provider.create_game(None).unwrap();
+        let mut rx = data_provider.subscribe_to_game(game_id).unwrap();
+        let mut move_rx = data_provider.subscribe_to_game_moves(game_id).unwrap();
+
+        data_provider.close().unwrap();
+        let changed = tokio::time::timeout(Duration::from_secs(5), rx.changed())
+            .await
+            .unwrap();
+        assert!(changed.is_err());
+        let received = tokio::time::timeout(Duration::from_secs(5), move_rx.recv())
+            .await
+            .unwrap();
+        assert_eq!(
+            received,
+            Err(tokio::sync::broadcast::error::RecvError::Closed)
+        );
+    }
+    #[tokio::test]
+    async fn test_dropped_subscription_stops_listening() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+        let subscribers = |data_provider: &RedisProvider, game_id: Uuid| {
+            let (_, count): (String, usize) = redis::cmd("PUBSUB")
+                .arg("NUMSUB")
+                .arg(game_id.to_string())
+                .query(&mut data_provider.get_connection().unwrap())
+                .unwrap();
+            count
+        };
+
+        let game_id = data_provider.create_game(None).unwrap();
+        let rx = data_provider.subscribe_to_game(game_id).unwrap();
+        let move_rx = data_provider.subscribe_to_game_moves(game_id).unwrap();
+        // wait for the subscriptions to be registered
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(subscribers(&data_provider, game_id), 2);
+
+        // the tasks stop without waiting for another update of the game
+        drop(rx);
+        drop(move_rx);
+        tokio::time::sleep(RedisProvider::RECEIVER_CHECK_INTERVAL + Duration::from_millis(500))
+            .await;
+        assert!(data_provider
+            .tasks
+            .lock()
+            .unwrap()
+            .iter()
+            .all(JoinHandle::is_finished));
+        assert_eq!(subscribers(&data_provider, game_id), 0);
+    }
+    #[tokio::test]
+    async fn test_game_ending_move_is_published_with_result() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let redis_port = redis_container.get_host_port_ipv4(6379);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_port,
+            ..Default::default()
+        })
+        .unwrap();
+        let game_id = data_provider.create_game(None).unwrap();
+        let mut move_rx = data_provider.subscribe_to_game_moves(game_id).unwrap();
+
+        let mut pubsub_connection = Client::open(format!("redis://localhost:{}", redis_port))
+            .unwrap()
+            .get_connection()
+            .unwrap();
+        let mut pubsub = pubsub_connection.as_pubsub();
+        pubsub.subscribe(game_id.to_string()).unwrap();
+        pubsub
+            .set_read_timeout(Some(Duration::from_millis(500)))
+            .unwrap();
+        // wait for the subscription of the provider to be registered
+        sleep(Duration::from_millis(500)).await;
+
+        let moves = [
+            ((1, 1), Player::X),
+            ((4, 4), Player::O),
+            ((3, 4), Player::X),
+            ((1, 4), Player::O),
+            ((3, 5), Player::X),
+            ((1, 7), Player::O),
+            ((3, 3), Player::X),
+            ((0, 0), Player::O),
+            ((0, 2), Player::X),
+            ((0, 6), Player::O),
+            ((2, 0), Player::X),
+            ((8, 2), Player::O),
+            ((6, 8), Player::X),
+            ((2, 8), Player::O),
+            ((7, 7), Player::X),
+            ((2, 5), Player::O),
+            ((8, 6), Player::X),
+        ];
+        for (coordinates, player) in moves {
+            data_provider
+                .add_move(game_id, Move::new(coordinates, player))
+                .unwrap();
+        }
+        let expected_result = Some(GameResult {
+            winner: Some(Player::X),
+            move_count: moves.len(),
+            abandoned: false,
+        });
+
+        // the game is published once per move, the last time together with its result
+        let mut published = vec![];
+        while let Ok(msg) = pubsub.get_message() {
+            let payload: String = msg.get_payload().unwrap();
+            published.push(from_str::<GameData>(&payload).unwrap());
+        }
+        assert_eq!(published.len(), moves.len());
+        assert_eq!(published.last().unwrap().result, expected_result);
+
+        for index in 0..moves.len() {
+            let delta = tokio::time::timeout(Duration::from_millis(500), move_rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(delta.index, index);
+            let expected = expected_result.filter(|_| index + 1 == moves.len());
+            assert_eq!(delta.result, expected);
+        }
+        assert_eq!(
+            data_provider.list_results(1, 0).unwrap(),
+            vec![(game_id, expected_result)]
+        );
+    }
+    #[tokio::test]
+    async fn test_published_games_are_ordered() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let game_id = data_provider.clone().create_game(None).unwrap();
+        let mut rx = data_provider.clone().subscribe_to_game(game_id).unwrap();
+        // wait for the subscription to be registered
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // two writers add moves at the same time, each on its own row
+        let writers: Vec<_> = [(0, Player::X), (1, Player::O)]
+            .into_iter()
+            .map(|(row, player)| {
+                let mut data_provider = data_provider.clone();
+                std::thread::spawn(move || {
+                    for column in 0..9 {
+                        data_provider
+                            .add_move(game_id, Move::new((row, column), player))
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        let mut seen_move_counts = vec![];
+        while seen_move_counts.last() != Some(&18) {
+            tokio::time::timeout(Duration::from_secs(5), rx.changed())
+                .await
+                .unwrap()
+                .unwrap();
+            seen_move_counts.push(rx.borrow_and_update().moves.len());
+        }
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert!(
+            seen_move_counts
+                .windows(2)
+                .all(|counts| counts[0] <= counts[1]),
+            "subscribers saw the moves out of order: {:?}",
+            seen_move_counts
+        );
+    }
+    #[test]
+    fn test_error_kind_is_error() {
+        let error: Box<dyn std::error::Error> = Box::new(ErrorKind::Query {
+            message: "ERR unknown command".to_string(),
+        });
+        assert_eq!(
+            error.to_string(),
+            "there was an error querying redis: ERR unknown command"
+        );
+        assert_eq!(
+            format!("{}", ErrorKind::KeyNotFound),
+            "the game does not exist"
+        );
+    }
+    #[tokio::test]
+    async fn test_touch() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let redis_port = redis_container.get_host_port_ipv4(6379);
+
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_port,
+            game_ttl: Some(3600),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let game_id = data_provider.create_game(None).unwrap();
+
+        let mut connection = data_provider.get_connection().unwrap();
+        redis::cmd("EXPIRE")
+            .arg(game_id.to_string())
+            .arg(10)
+            .query::<()>(&mut connection)
+            .unwrap();
+
+        data_provider.touch(game_id).unwrap();
+
+        let ttl: i64 = redis::cmd("TTL")
+            .arg(game_id.to_string())
+            .query(&mut connection)
+            .unwrap();
+        assert!(ttl > 10, "touching didn't extend the ttl: {}", ttl);
+
+        assert_eq!(
+            data_provider.touch(Uuid::new_v4()),
+            Err(ErrorKind::KeyNotFound)
+        );
+    }
+    #[tokio::test]
+    async fn test_game_exists_without_reading() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            storage_mode: Some(StorageMode::String),
+            ..Default::default()
+        })
+        .unwrap();
+
+        // a game that can't be deserialized still exists, as it is never read
+        let game_id = Uuid::new_v4();
+        let mut connection = data_provider.get_connection().unwrap();
+        redis::cmd("SET")
+            .arg(game_id.to_string())
+            .arg("not a game")
+            .query::<()>(&mut connection)
+            .unwrap();
+        assert!(matches!(
+            data_provider.get_game_data(game_id),
+            Err(ErrorKind::Deserialize { .. })
+        ));
+        assert_eq!(data_provider.game_exists(game_id), Ok(true));
+        assert_eq!(data_provider.touch(game_id), Ok(()));
+        assert_eq!(data_provider.game_exists(Uuid::new_v4()), Ok(false));
+    }
+    #[tokio::test]
+    async fn test_is_joinable() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+        let game_id = data_provider.create_game(None).unwrap();
+        assert_eq!(data_provider.is_joinable(game_id), Ok(true));
+
+        // X and O have joined
+        data_provider
+            .register_player(game_id, Uuid::new_v4())
+            .unwrap();
+        assert_eq!(data_provider.is_joinable(game_id), Ok(true));
+        data_provider
+            .register_player(game_id, Uuid::new_v4())
+            .unwrap();
+        assert_eq!(data_provider.is_joinable(game_id), Ok(false));
+
+        let finished_game = data_provider.create_game(None).unwrap();
+        data_provider.abandon_game(finished_game).unwrap();
+        assert_eq!(data_provider.is_joinable(finished_game), Ok(false));
+
+        // the result is read from the game, not from the results index
+        redis::cmd("HDEL")
+            .arg(RedisProvider::RESULTS_INDEX_KEY)
+            .arg(finished_game.to_string())
+            .query::<()>(&mut data_provider.get_connection().unwrap())
+            .unwrap();
+        assert_eq!(data_provider.is_joinable(finished_game), Ok(false));
+
+        assert_eq!(data_provider.is_joinable(Uuid::new_v4()), Ok(false));
+    }
+    #[tokio::test]
+    async fn test_is_joinable_without_json() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(PlainRedis);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+        let game_id = data_provider.create_game(None).unwrap();
+        assert_eq!(data_provider.is_joinable(game_id), Ok(true));
+        data_provider
+            .register_player(game_id, Uuid::new_v4())
+            .unwrap();
+        data_provider
+            .register_player(game_id, Uuid::new_v4())
+            .unwrap();
+        assert_eq!(data_provider.is_joinable(game_id), Ok(false));
+
+        let finished_game = data_provider.create_game(None).unwrap();
+        data_provider.abandon_game(finished_game).unwrap();
+        assert_eq!(data_provider.is_joinable(finished_game), Ok(false));
+
+        assert_eq!(data_provider.is_joinable(Uuid::new_v4()), Ok(false));
+    }
+    #[tokio::test]
+    async fn test_stats() {
+        let docker_cli = DockerCli::default();
+        let redis_container = docker_cli.run(Redis);
+        let mut data_provider = RedisProvider::new(RedisProviderArgs {
+            server_port: redis_container.get_host_port_ipv4(6379),
+            ..Default::default()
+        })
+        .unwrap();
+        let moves = [
+            ((1, 1), Player::X),
+            ((4, 4), Player::O),
+            ((3, 4), Player::X),
+            ((1, 4), Player::O),
+            ((3, 5), Player::X),
+            ((1, 7), Player::O),
+            ((3, 3), Player::X),
+            ((0, 0), Player::O),
+            ((0, 2), Player::X),
+            ((0, 6), Player::O),
+            ((2, 0), Player::X),
+            ((8, 2), Player::O),
+            ((6, 8), Player::X),
+            ((2, 8), Player::O),
+            ((7, 7), Player::X),
+            ((2, 5), Player::O),
+            ((8, 6), Player::X),
+        ];
+        let first_token = Uuid::new_v4();
+        let second_token = Uuid::new_v4();
+        let spectator_token = Uuid::new_v4();
+
+        // the first registered token plays X, X wins both games
+        for tokens in [
+            [first_token, second_token, spectator_token],
+            [second_token, first_token, spectator_token],
+        ] {
+            let game_id = data_provider.create_game(None).unwrap();
+            for token in tokens {
+                data_provider.register_player(game_id, token).unwrap();
+            }
+            // registering twice doesn't change the sides
+            data_provider.register_player(game_id, tokens[0]).unwrap();
+            for (coordinates, player) in moves {
+                data_provider
+                    .add_move(game_id, Move::new(coordinates, player))
+                    .unwrap();
+            }
+        }
+
+        assert_eq!(
+            data_provider.get_global_stats().unwrap(),
+            GlobalStats {
+                x_wins: 2,
+                o_wins: 0,
+                draws: 0
+            }
+        );
+        let expected_stats = PlayerStats {
+            wins: 1,
+            losses: 1,
+            draws: 0,
+        };
+        assert_eq!(
+            data_provider.get_player_stats(first_token).unwrap(),
+            expected_stats
+        );
+        assert_eq!(
+            data_provider.get_player_stats(second_token).unwrap(),
+            expected_stats
+        );
+        assert_eq!(
+            data_provider.get_player_stats(spectator_token).unwrap(),
+            PlayerStats::default()
+        );
+    }
+
+    /// starts a redis stack server as the only node of a cluster, serving every slot.
+    /// Like the nodes of a real cluster, it refuses transactions, scripts and pipelines
+    /// whose keys are in different slots, so these are caught without several nodes.
+    fn start_single_node_cluster(docker_cli: &DockerCli) -> (Container<'_, Redis>, u16) {
+        // the node announces the port it is reachable at, so it is mapped to a known one
+        let redis_port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let redis_container = docker_cli.run(
+            RunnableImage::from(Redis)
+                .with_mapped_port((redis_port, 6379))
+                .with_env_var((
+                    "REDIS_ARGS",
+                    format!(
+                        "--cluster-enabled yes --cluster-announce-ip 127.0.0.1 \
+                         --cluster-announce-port {}",
+                        redis_port
+                    ),
+                )),
+        );
+        let mut connection = Client::open(format!("redis://localhost:{}", redis_port))
+            .unwrap()
+            .get_connection()
+            .unwrap();
+        redis::cmd("CLUSTER")
+            .arg("ADDSLOTSRANGE")
+            .arg(0)
+            .arg(16383)
+            .query::<()>(&mut connection)
+            .unwrap();
+        while !redis::cmd("CLUSTER")
+            .arg("INFO")
+            .query::<String>(&mut connection)
+            .unwrap()
+            .contains("cluster_state:ok")
+        {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        (redis_container, redis_port)
+    }
+
+    #[tokio::test]
+    async fn test_cluster() {
+        let docker_cli = DockerCli::default();
+        let (_redis_container, redis_port) = start_single_node_cluster(&docker_cli);
+        let moves = [
+            ((1, 1), Player::X),
+            ((4, 4), Player::O),
+            ((3, 4), Player::X),
+            ((1, 4), Player::O),
+            ((3, 5), Player::X),
+            ((1, 7), Player::O),
+            ((3, 3), Player::X),
+            ((0, 0), Player::O),
+            ((0, 2), Player::X),
+            ((0, 6), Player::O),
+            ((2, 0), Player::X),
+            ((8, 2), Player::O),
+            ((6, 8), Player::X),
+            ((2, 8), Player::O),
+            ((7, 7), Player::X),
+            ((2, 5), Player::O),
+            ((8, 6), Player::X),
+        ]
+        .map(|(coordinates, player)| Move::new(coordinates, player));
+
+        // RedisJSON games are changed by scripts, the others by transactions
+        for storage_mode in [StorageMode::RedisJson, StorageMode::String] {
+            let mut data_provider = RedisProvider::new(RedisProviderArgs {
+                server_port: redis_port,
+                cluster: true,
+                storage_mode: Some(storage_mode),
+                game_ttl: Some(60),
+                ..Default::default()
+            })
+            .unwrap();
+            let player_token = Uuid::new_v4();
+
+            // the move ending the game sets the result and counts it in the statistics
+            let won_game = data_provider.create_game(None).unwrap();
+            data_provider
+                .register_player(won_game, player_token)
+                .unwrap();
+            for new_move in moves {
+                data_provider.add_move(won_game, new_move).unwrap();
+            }
+            let won_result = data_provider.get_game_data(won_game).unwrap().result;
+            assert!(won_result.is_some(), "{}", storage_mode);
+            assert_eq!(
+                data_provider.get_player_stats(player_token).unwrap().wins,
+                1,
+                "{}",
+                storage_mode
+            );
+
+            let abandoned_game = data_provider.create_game(None).unwrap();
+            assert!(data_provider.abandon_game(abandoned_game).unwrap());
+
+            let game_id = data_provider.create_game(None).unwrap();
+            data_provider.add_move(game_id, moves[0]).unwrap();
+            let snapshot = data_provider.get_game_data(game_id).unwrap();
+            data_provider.add_move(game_id, moves[1]).unwrap();
+            assert_eq!(
+                data_provider.truncate_game(game_id, 1).unwrap(),
+                vec![moves[1]]
+            );
+            data_provider.restore(game_id, snapshot).unwrap();
+
+            // games created within the same millisecond are listed in any order
+            let mut results = data_provider.list_results(10, 0).unwrap();
+            results.sort_by_key(|(game_id, _)| *game_id);
+            let mut expected_results = vec![
+                (game_id, None),
+                (abandoned_game, Some(GameResult::abandoned(0))),
+                (won_game, won_result),
+            ];
+            expected_results.sort_by_key(|(game_id, _)| *game_id);
+            assert_eq!(results, expected_results, "{}", storage_mode);
+            assert_eq!(data_provider.clear_all().unwrap(), 3, "{}", storage_mode);
+        }
+    }
 }
@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use log::{error, info};
+use uuid::Uuid;
+
+use crate::{Config, DataProvider, GameData};
+
+/// ends games that went without a move for too long as an abandoned draw, so they don't
+/// stay in progress forever. It only runs if it is enabled with `ENABLE_REAPER`.
+pub struct Reaper<T: DataProvider> {
+    data_provider: T,
+    /// how often the games are checked
+    interval: Duration,
+    /// how long a game may go without a move before it is abandoned
+    idle_timeout: Duration,
+}
+
+impl<T: DataProvider> Reaper<T> {
+    pub fn new(data_provider: T, interval: Duration, idle_timeout: Duration) -> Self {
+        Self {
+            data_provider,
+            interval,
+            idle_timeout,
+        }
+    }
+
+    pub fn from_config(config: &Config, data_provider: T) -> Self {
+        Self::new(
+            data_provider,
+            config.reaper.interval,
+            config.reaper.idle_timeout,
+        )
+    }
+
+    /// abandons the games that are idle right now and returns their ids
+    pub fn reap(&mut self) -> Result<Vec<Uuid>, T::ErrorKind> {
+        let idle_timeout = self.idle_timeout.as_millis() as u64;
+        self.data_provider
+            .abandon_idle_games(GameData::now().saturating_sub(idle_timeout))
+    }
+
+    /// checks the games once every interval, starting right away. A failed check is
+    /// logged and tried again at the next interval.
+    pub async fn run(mut self) {
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            interval.tick().await;
+            match self.reap() {
+                Ok(abandoned) if abandoned.is_empty() => {}
+                Ok(abandoned) => info!("Abandoned {} idle games", abandoned.len()),
+                Err(e) => error!("Could not abandon the idle games: {}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{CacheProvider, GameResult, Move, Player};
+
+    #[tokio::test]
+    async fn reap_stale_game() {
+        let mut data_provider = CacheProvider::default();
+        let mut stale_game = GameData::new();
+        stale_game.add_move_at(Move::new((4, 4), Player::X), GameData::now() - 120_000);
+        let stale_game = data_provider.import_game(stale_game).unwrap();
+        let fresh_game = data_provider.create_game(None).unwrap();
+        data_provider
+            .add_move(fresh_game, Move::new((4, 4), Player::X))
+            .unwrap();
+        let mut rx = data_provider.subscribe_to_game(stale_game).unwrap();
+
+        let reaper = Reaper::new(
+            data_provider.clone(),
+            Duration::from_millis(10),
+            Duration::from_secs(60),
+        );
+        let task = tokio::spawn(reaper.run());
+        tokio::time::timeout(Duration::from_secs(5), rx.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        task.abort();
+
+        let game_data = data_provider.get_game_data(stale_game).unwrap();
+        assert_eq!(game_data.result, Some(GameResult::abandoned(1)));
+        assert!(rx.borrow().result.is_some_and(|result| result.abandoned));
+        assert_eq!(
+            data_provider.get_game_data(fresh_game).unwrap().result,
+            None
+        );
+    }
+}
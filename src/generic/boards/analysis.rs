@@ -0,0 +1,206 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{Board, GameState, Move, Player};
+
+/// the result of searching a position for the best moves
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct Analysis {
+    /// positive if the position favours X, negative if it favours O.
+    /// Scores of at least `Board::WIN_SCORE` mean a forced win.
+    pub score: i32,
+    /// the best moves of both players from the analysed position on
+    pub principal_variation: Vec<Move>,
+}
+
+impl Board {
+    /// the deepest search `analyze` runs, deeper searches take too long to be useful
+    pub const MAX_ANALYSIS_DEPTH: usize = 4;
+    pub const WIN_SCORE: i32 = 1_000;
+    const SUB_BOARD_SCORE: i32 = 10;
+
+    /// Searches the position `depth` moves deep (at most `MAX_ANALYSIS_DEPTH`) with
+    /// minimax and alpha-beta pruning and returns the score with the principal variation.
+    /// Positions at the search horizon are scored by the sub boards each player has won.
+    pub fn analyze(&self, depth: usize) -> Analysis {
+        let depth = depth.min(Self::MAX_ANALYSIS_DEPTH);
+        let (score, principal_variation) = self.negamax(depth, -i32::MAX, i32::MAX);
+        Analysis {
+            score: match self.get_next_player() {
+                Player::X => score,
+                Player::O => -score,
+            },
+            principal_variation,
+        }
+    }
+
+    /// scores the position for the player to move, returning the best line of moves
+    fn negamax(&self, depth: usize, mut alpha: i32, beta: i32) -> (i32, Vec<Move>) {
+        let player = self.get_next_player();
+        match self.get_state() {
+            GameState::Won { winner } => {
+                // the more depth is left, the sooner the game was won
+                let score = Self::WIN_SCORE + depth as i32;
+                return (if winner == player { score } else { -score }, vec![]);
+            }
+            GameState::Draw => return (0, vec![]),
+            GameState::InProgress { .. } => {}
+        }
+
+        let allowed_moves = self.get_allowed_moves();
+        if depth == 0 || allowed_moves.is_empty() {
+            return (self.evaluate(player), vec![]);
+        }
+
+        let mut best = (-i32::MAX, vec![]);
+        for coordinates in allowed_moves {
+            let new_move = Move::new(coordinates, player);
+            let (score, mut line) =
+                self.with_allowed_move(new_move)
+                    .negamax(depth - 1, -beta, -alpha);
+            let score = -score;
+            if score > best.0 {
+                line.insert(0, new_move);
+                best = (score, line);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+
+    /// Counts the distinct games that continue from this position for `depth` more moves,
+    /// like perft in chess. Games that end earlier count once. Comparing the counts with
+    /// known values checks the allowed moves and the forced sub board rules.
+    pub fn perft(&self, depth: u8) -> u64 {
+        if depth == 0 || self.is_over() {
+            return 1;
+        }
+        let player = self.get_next_player();
+        let allowed_moves = self.get_allowed_moves();
+        if depth == 1 {
+            return allowed_moves.len() as u64;
+        }
+        allowed_moves
+            .into_iter()
+            .map(|coordinates| {
+                self.with_allowed_move(Move::new(coordinates, player))
+                    .perft(depth - 1)
+            })
+            .sum()
+    }
+
+    /// a copy of the board with a move from `get_allowed_moves` made. The move is not
+    /// validated again.
+    fn with_allowed_move(&self, new_move: Move) -> Board {
+        let mut board = self.clone();
+        board.moves.push(new_move);
+        board
+            .render_move(&new_move)
+            .expect("allowed moves are always in bounds");
+        board
+    }
+
+    /// the sub boards won by the player minus the sub boards won by the opponent
+    fn evaluate(&self, player: Player) -> i32 {
+        self.data
+            .iter()
+            .map(|sub_board| match sub_board.get_state(player) {
+                GameState::Won { winner } if winner == player => Self::SUB_BOARD_SCORE,
+                GameState::Won { .. } => -Self::SUB_BOARD_SCORE,
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn perft() {
+        let board = Board::new();
+        // no sub board can be decided within four moves, so these follow from the
+        // forced sub board rule alone
+        for (depth, count) in [(0, 1), (1, 81), (2, 720), (3, 6_336), (4, 55_080)] {
+            assert_eq!(board.perft(depth), count, "depth {}", depth);
+        }
+
+        // a finished game has no continuations besides itself
+        let mut board = Board::new();
+        for (coordinates, player) in [
+            ((1, 1), Player::X),
+            ((4, 4), Player::O),
+            ((3, 4), Player::X),
+            ((1, 4), Player::O),
+            ((3, 5), Player::X),
+            ((1, 7), Player::O),
+            ((3, 3), Player::X),
+            ((0, 0), Player::O),
+            ((0, 2), Player::X),
+            ((0, 6), Player::O),
+            ((2, 0), Player::X),
+            ((8, 2), Player::O),
+            ((6, 8), Player::X),
+            ((2, 8), Player::O),
+            ((7, 7), Player::X),
+            ((2, 5), Player::O),
+            ((8, 6), Player::X),
+        ] {
+            board.insert_move(coordinates, player).unwrap();
+        }
+        assert_eq!(board.perft(3), 1);
+    }
+
+    #[test]
+    fn analyze_winning_position() {
+        // the sample game of the board tests without its winning move, X to play
+        let moves = [
+            ((1, 1), Player::X),
+            ((4, 4), Player::O),
+            ((3, 4), Player::X),
+            ((1, 4), Player::O),
+            ((3, 5), Player::X),
+            ((1, 7), Player::O),
+            ((3, 3), Player::X),
+            ((0, 0), Player::O),
+            ((0, 2), Player::X),
+            ((0, 6), Player::O),
+            ((2, 0), Player::X),
+            ((8, 2), Player::O),
+            ((6, 8), Player::X),
+            ((2, 8), Player::O),
+            ((7, 7), Player::X),
+            ((2, 5), Player::O),
+        ];
+        let mut board = Board::new();
+        for (coordinates, player) in moves {
+            board.insert_move(coordinates, player).unwrap();
+        }
+
+        let analysis = board.analyze(2);
+        assert!(analysis.score >= Board::WIN_SCORE);
+        let best_move = analysis.principal_variation[0];
+        assert_eq!(board.validate_move(best_move), Ok(()));
+        board
+            .insert_move(best_move.coordinates, best_move.player)
+            .unwrap();
+        assert!(board.is_won_by(Player::X));
+
+        // seen from O, who has already lost
+        assert_eq!(board.analyze(2).score, Board::WIN_SCORE + 2);
+    }
+
+    #[test]
+    fn analyze_caps_depth() {
+        let analysis = Board::new().analyze(usize::MAX);
+        assert_eq!(
+            analysis.principal_variation.len(),
+            Board::MAX_ANALYSIS_DEPTH
+        );
+        assert_eq!(analysis.score, 0);
+    }
+}
@@ -0,0 +1,52 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{Board, Coordinates, GameState, Move, Player};
+
+/// a move together with what it did to the sub boards, see `Board::annotated_moves`
+#[derive(Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AnnotatedMove {
+    #[serde(rename = "move")]
+    pub move_: Move,
+    /// the sub board the move was made in
+    pub subboard: Coordinates,
+    /// the player who won the sub board with this move, if it did
+    pub captured_subboard: Option<Player>,
+    /// the sub board the next player is sent to. If it is already decided, the next
+    /// player may move in any sub board instead.
+    pub sent_to: Coordinates,
+}
+
+impl Board {
+    /// Returns the moves in the order they were made, each with the sub board it was
+    /// made in, whether it captured that sub board and the sub board it sent the next
+    /// player to. The moves are replayed once.
+    pub fn annotated_moves(&self) -> Vec<AnnotatedMove> {
+        let mut board = self.empty_copy();
+        self.moves
+            .iter()
+            .map(|m| {
+                let (subboard_index, field_index) = board
+                    .get_subboard_for_move(m.coordinates.into())
+                    .expect("the moves of a board are in bounds");
+                let was_won = matches!(
+                    board.sub_board(subboard_index).get_state(m.player),
+                    GameState::Won { .. }
+                );
+                board
+                    .render_move(m)
+                    .expect("the moves of a board are in bounds");
+                let captured_subboard = match board.sub_board(subboard_index).get_state(m.player) {
+                    GameState::Won { winner } if !was_won => Some(winner),
+                    _ => None,
+                };
+                AnnotatedMove {
+                    move_: *m,
+                    subboard: subboard_index.into(),
+                    captured_subboard,
+                    sent_to: field_index.target_sub_board().into(),
+                }
+            })
+            .collect()
+    }
+}
@@ -1,29 +1,111 @@
 use itertools::Itertools;
 use log::debug;
 use ndarray::Array2;
+use rand::{seq::SliceRandom, Rng};
 use serde::{Deserialize, Serialize};
-use std::fmt::Debug;
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+    sync::OnceLock,
+};
 use uuid::Uuid;
 
 use crate::{
-    generic::boards::check_matrix, Coordinates, Field, GameData, GameState, Move, Player, SubBoard,
+    generic::boards::{check_matrix, check_matrix_with_shared_draws, rules::Rules},
+    BoardSize, CellIndex, CompactBoard, Coordinates, DrawRule, Field, GameData, GameResult,
+    GameSettings, GameState, GlobalCoord, Move, Player, RuleSet, RuleSetKind, StandardRules,
+    SubBoard, SubBoardIndex, Symmetry,
 };
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// why a move was rejected. The reasons are serialized in snake_case, like
+/// `field_occupied`, so clients can match on them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum InvalidMove {
     FieldOccupied,
     SubBoardNotActive,
     GameEnded,
     OutOfBounds,
     NotYourTurn,
+    /// rejected by a house rule of the board's rule set
+    ForbiddenByRules,
+}
+
+impl Display for InvalidMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidMove::FieldOccupied => write!(f, "the field is already occupied"),
+            InvalidMove::SubBoardNotActive => {
+                write!(f, "the move has to be made in another sub board")
+            }
+            InvalidMove::GameEnded => write!(f, "the game has already ended"),
+            InvalidMove::OutOfBounds => write!(f, "the field is not on the board"),
+            InvalidMove::NotYourTurn => write!(f, "it is not the player's turn"),
+            InvalidMove::ForbiddenByRules => write!(f, "the move is forbidden by the rules"),
+        }
+    }
+}
+
+/// why a grid of fields can't be a position of a game, see `Board::from_cells`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvalidBoard {
+    /// the grid doesn't have `Board::FIELD_SIZE` fields
+    WrongSize,
+    /// a field is disabled, only sub boards can be
+    DisabledField,
+    /// X moves first, so X has as many fields as O if it is X's turn and one more if
+    /// it is O's turn
+    ImplausibleCounts { x: usize, o: usize },
+    /// the sub board has a line of both players, or the board has if it isn't given
+    WonByBoth { subboard: Option<Coordinates> },
+    /// the forced sub board is out of bounds or already decided
+    InvalidForcedSubBoard,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Board {
+    /// the sub boards. Moves are made with `insert_move`, changing the sub boards
+    /// directly after the state of the board was queried leaves the cached
+    /// states of the sub boards behind. Sub boards are replaced with ones built by
+    /// `SubBoard::from_cells`, whose state matches their fields.
     pub data: Array2<SubBoard>,
     pub moves: Vec<Move>,
     pub game_id: Uuid,
+    /// how drawn sub boards count towards the lines of the board
+    #[serde(default)]
+    pub draw_rule: DrawRule,
+    #[serde(skip)]
+    pub(crate) abstracted_board: AbstractedBoardCache,
+    /// decides which moves are valid, the standard rules unless set with `with_rules`
+    #[serde(skip)]
+    pub(crate) rules: Rules,
+    /// whose turn it is in a board built with `from_cells`, as long as it has no moves
+    /// to tell
+    #[serde(skip)]
+    pub(crate) position: Option<GridPosition>,
+}
+
+/// the turn of a board built from a grid of fields, see `Board::from_cells`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct GridPosition {
+    next_player: Player,
+    forced: Option<SubBoardIndex>,
 }
 
+/// the states of the sub boards as returned by `Board::get_abstracted_board`, computed
+/// once per move instead of once per query. Being a cache, it is neither serialized
+/// nor compared.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AbstractedBoardCache(OnceLock<Array2<Field>>);
+
+impl PartialEq for AbstractedBoardCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for AbstractedBoardCache {}
+
 impl Default for Board {
     fn default() -> Self {
         Self::new()
@@ -32,32 +114,87 @@ impl Default for Board {
 
 impl From<GameData> for Board {
     fn from(game_data: GameData) -> Self {
-        let mut board = Board::new_with_id(game_data.game_id);
-        for m in game_data.moves {
-            board
-                .insert_move(m.coordinates, m.player)
-                .expect("Invalid move in game data");
-        }
-        board
+        Board::try_from_game_data(game_data).expect("Invalid move in game data")
     }
 }
 
 impl From<Board> for GameData {
     fn from(val: Board) -> Self {
+        let result = GameResult::from_state(val.get_state(), val.moves.len());
         Self {
             moves: val.moves,
             game_id: val.game_id,
+            result,
+            // boards don't know when their moves were made
+            move_times: vec![],
+            settings: GameSettings {
+                draw_rule: val.draw_rule,
+                board_size: val.data.dim().into(),
+                ..Default::default()
+            },
+            // nor when and by whom they were created
+            created_at: 0,
+            created_by: None,
         }
     }
 }
 
 impl Board {
+    /// the number of rows and columns of sub boards of a board with the default
+    /// `BoardSize`. Boards of games with other settings are sized by `with_settings`.
     pub const SIZE: Coordinates = (3, 3);
+    /// the number of rows and columns of fields of the default board, see `get_field_size`
+    pub const FIELD_SIZE: Coordinates = (
+        Self::SIZE.0 * SubBoard::SIZE.0,
+        Self::SIZE.1 * SubBoard::SIZE.1,
+    );
+    /// the most moves a game on the default board can have, see `max_moves`
+    pub const MAX_MOVES: usize = Self::FIELD_SIZE.0 * Self::FIELD_SIZE.1;
+
     pub fn new() -> Self {
         Self {
             data: Array2::from_elem((Self::SIZE.0, Self::SIZE.1), SubBoard::new()),
             moves: Vec::new(),
             game_id: Uuid::new_v4(),
+            draw_rule: DrawRule::default(),
+            abstracted_board: AbstractedBoardCache::default(),
+            rules: Rules::default(),
+            position: None,
+        }
+    }
+
+    /// the board with its moves validated by the given rule set instead of the standard
+    /// rules. The moves already made are kept as they are.
+    pub fn with_rules(mut self, rules: impl RuleSet + 'static) -> Self {
+        self.rules = Rules::new(rules);
+        self
+    }
+
+    /// the board played with the board size, the draw rule and the rule set of the
+    /// settings. A board of another size starts over with empty sub boards, so the
+    /// settings are applied before the moves are made.
+    pub fn with_settings(mut self, settings: &GameSettings) -> Self {
+        let size = Coordinates::from(settings.board_size);
+        if self.data.dim() != size {
+            self.data = Array2::from_elem(size, SubBoard::new());
+            self.abstracted_board = AbstractedBoardCache::default();
+        }
+        self.draw_rule = settings.draw_rule;
+        match settings.rules {
+            RuleSetKind::Standard => self.with_rules(StandardRules),
+        }
+    }
+
+    /// an empty board of the same game, size and rules, e.g. to replay the moves on
+    pub(crate) fn empty_copy(&self) -> Self {
+        Self {
+            data: Array2::from_elem(self.data.dim(), SubBoard::new()),
+            moves: Vec::new(),
+            game_id: self.game_id,
+            draw_rule: self.draw_rule,
+            abstracted_board: AbstractedBoardCache::default(),
+            rules: self.rules.clone(),
+            position: None,
         }
     }
 
@@ -66,57 +203,151 @@ impl Board {
             data: Array2::from_elem((Self::SIZE.0, Self::SIZE.1), SubBoard::new()),
             moves: Vec::new(),
             game_id: id,
+            draw_rule: DrawRule::default(),
+            abstracted_board: AbstractedBoardCache::default(),
+            rules: Rules::default(),
+            position: None,
+        }
+    }
+
+    /// Builds the board of a position from its fields and whose turn it is, without the
+    /// moves that lead there, e.g. for puzzles. `forced` is the sub board the next move
+    /// must be made in, if the next player is limited to one.
+    /// The grid is checked to be a position a game could reach: X moves first, so the
+    /// counts of the players have to fit whose turn it is, no sub board may be won by
+    /// both players and the forced sub board has to be undecided.
+    /// As the board has no moves, it can't be undone and it can't be stored as game
+    /// data, so it can't be synced to clients either.
+    pub fn from_cells(
+        cells: Array2<Field>,
+        next_player: Player,
+        forced: Option<Coordinates>,
+    ) -> Result<Board, InvalidBoard> {
+        if cells.dim() != Self::FIELD_SIZE {
+            return Err(InvalidBoard::WrongSize);
+        }
+        if cells.iter().any(|field| *field == Field::Disabled) {
+            return Err(InvalidBoard::DisabledField);
+        }
+        let count = |player| {
+            cells
+                .iter()
+                .filter(|field| **field == Field::Occupied { player })
+                .count()
+        };
+        let (x, o) = (count(Player::X), count(Player::O));
+        let plausible = match next_player {
+            Player::X => x == o,
+            Player::O => x == o + 1,
+        };
+        if !plausible {
+            return Err(InvalidBoard::ImplausibleCounts { x, o });
+        }
+
+        let mut board = Board::new();
+        for ((row, column), field) in cells.indexed_iter() {
+            let (subboard_index, field_index) = GlobalCoord::new(row, column).split();
+            board
+                .sub_board_mut(subboard_index)
+                .set_cell(field_index, *field);
+        }
+        if let Some((subboard, _)) = board
+            .data
+            .indexed_iter()
+            .find(|(_, sub_board)| Self::won_by_both(&sub_board.data))
+        {
+            return Err(InvalidBoard::WonByBoth {
+                subboard: Some(subboard),
+            });
+        }
+        if Self::won_by_both(board.get_abstracted_board()) {
+            return Err(InvalidBoard::WonByBoth { subboard: None });
+        }
+        if let Some(forced) = forced {
+            let undecided = board
+                .get_abstracted_board()
+                .get(forced)
+                .is_some_and(Field::is_vacant);
+            if !undecided {
+                return Err(InvalidBoard::InvalidForcedSubBoard);
+            }
         }
+        board.position = Some(GridPosition {
+            next_player,
+            forced: forced.map(SubBoardIndex::from),
+        });
+        Ok(board)
     }
 
+    /// true if the fields have a line of each player, which no game can reach
+    fn won_by_both(fields: &Array2<Field>) -> bool {
+        let GameState::Won { winner } = check_matrix(fields, Player::X) else {
+            return false;
+        };
+        let mut others = fields.clone();
+        others.map_inplace(|field| {
+            if *field == (Field::Occupied { player: winner }) {
+                *field = Field::Vacant;
+            }
+        });
+        matches!(check_matrix(&others, Player::X), GameState::Won { .. })
+    }
+
+    /// replays the moves of the given game data, failing on the first invalid move
+    pub fn try_from_game_data(game_data: GameData) -> Result<Self, InvalidMove> {
+        let mut board = Board::new_with_id(game_data.game_id).with_settings(&game_data.settings);
+        for m in game_data.moves {
+            board.insert_move(m.coordinates, m.player)?;
+        }
+        Ok(board)
+    }
+
+    /// Returns the player who has to make the next move. It only depends on the number of
+    /// moves made: X starts (unless the board was built from a position) and the players
+    /// alternate, whatever player the moves claim to be made by.
+    pub fn expected_player_for_next_move(&self) -> Player {
+        let first_player = self
+            .position
+            .map_or(Player::X, |position| position.next_player);
+        if self.moves.len().is_multiple_of(2) {
+            first_player
+        } else {
+            first_player.other()
+        }
+    }
+
+    /// the player whose turn it is, see `expected_player_for_next_move`
     pub fn get_next_player(&self) -> Player {
-        self.moves
-            .last()
-            .map(|last_move| last_move.player.other())
-            .unwrap_or(Player::X)
+        self.expected_player_for_next_move()
     }
 
+    /// Returns the fields the next move may be made in, which are none once the game is over
     pub fn get_allowed_moves(&self) -> Vec<Coordinates> {
         let current_states = self.get_abstracted_board();
+        if !self.state_of(current_states).is_in_progress() {
+            return Vec::new();
+        }
+        let limiting_subboard = self.forced_subboard_of(current_states);
 
         let mut allowed_moves = Vec::new();
 
-        let limiting_subboard: Option<(usize, usize)> =
-            if let Some((_subboard_index, field_index)) = self
-                .moves
-                .last()
-                .and_then(|last_move| self.get_subboard_for_move(last_move.coordinates))
-            {
-                // the index of the last move in the field in the subboard is the index of the subboard
-                // where the next move must be made. If this subboard is not vacand, the next move can
-                // be made anywhere
-                if current_states[field_index] == Field::Vacant {
-                    debug!("limiting subboard to {:?}", field_index);
-                    Some(field_index)
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
-
-        let full_size = (
-            0..Self::SIZE.0 * SubBoard::SIZE.0,
-            0..Self::SIZE.1 * SubBoard::SIZE.1,
-        );
+        let size = self.get_field_size();
+        let full_size = (0..size.0, 0..size.1);
         for (row, column) in full_size.0.cartesian_product(full_size.1) {
             let coordinates = (row, column);
-            let (subboard_index, field_index) = self
-                .get_subboard_for_move(coordinates)
-                .expect("This should never happen");
+            let Some((subboard_index, field_index)) =
+                self.get_subboard_for_move(coordinates.into())
+            else {
+                continue;
+            };
 
             // if the subboard is not the limiting subboard, skip it
             if Some(subboard_index) != limiting_subboard && limiting_subboard.is_some() {
                 continue;
             }
 
-            if current_states[subboard_index] == Field::Vacant
-                && self.data[subboard_index].data[field_index] == Field::Vacant
+            if current_states[Coordinates::from(subboard_index)].is_vacant()
+                && self.sub_board(subboard_index).cell(field_index).is_vacant()
             {
                 allowed_moves.push(coordinates);
             }
@@ -126,93 +357,309 @@ impl Board {
         allowed_moves
     }
 
-    fn get_abstracted_board(&self) -> Array2<Field> {
-        let next_player = self.get_next_player();
-        let shape = self.data.shape();
-        let mut data = Array2::from_elem((shape[0], shape[1]), Field::Vacant);
-        for (row_index, row) in self.data.rows().into_iter().enumerate() {
-            for (column_index, sub_board) in row.iter().enumerate() {
-                data[(row_index, column_index)] = match sub_board.get_state(next_player) {
-                    GameState::InProgress { .. } => Field::Vacant,
-                    GameState::Draw => Field::Disabled,
-                    GameState::Won { winner } => Field::Occupied { player: winner },
-                };
+    /// Returns the allowed moves by the sub board they are in. Only sub boards with an
+    /// allowed move are listed, so there is a single one if the next move is forced.
+    pub fn allowed_moves_by_subboard(&self) -> HashMap<Coordinates, Vec<Coordinates>> {
+        let mut allowed_moves: HashMap<Coordinates, Vec<Coordinates>> = HashMap::new();
+        for coordinates in self.get_allowed_moves() {
+            let (subboard_index, _) = GlobalCoord::from(coordinates).split();
+            allowed_moves
+                .entry(subboard_index.into())
+                .or_default()
+                .push(coordinates);
+        }
+        allowed_moves
+    }
+
+    /// Returns the sub board the next move must be made in, if the next player is limited to one
+    pub fn forced_subboard(&self) -> Option<Coordinates> {
+        self.forced_subboard_of(self.get_abstracted_board())
+            .map(Coordinates::from)
+    }
+
+    /// the forced sub board, given the states of the sub boards as returned by `get_abstracted_board`
+    pub(crate) fn forced_subboard_of(
+        &self,
+        current_states: &Array2<Field>,
+    ) -> Option<SubBoardIndex> {
+        let target = match self.moves.last() {
+            Some(last_move) => {
+                let (_subboard_index, field_index) =
+                    self.get_subboard_for_move(last_move.coordinates.into())?;
+                // the index of the last move in the field in the subboard is the index of the
+                // subboard where the next move must be made. If this subboard is not vacand, or
+                // not on a board with fewer sub boards, the next move can be made anywhere
+                field_index.target_sub_board()
             }
+            None => self.position?.forced?,
+        };
+        if current_states
+            .get(Coordinates::from(target))
+            .is_some_and(Field::is_vacant)
+        {
+            debug!("limiting subboard to {:?}", target);
+            Some(target)
+        } else {
+            None
         }
-        data
+    }
+
+    /// the state of every sub board as a field: vacant while it is in progress, disabled
+    /// once it is drawn and occupied by its winner. It is assembled from the states the
+    /// sub boards keep on first use after a move and cached until the next one.
+    fn get_abstracted_board(&self) -> &Array2<Field> {
+        self.abstracted_board
+            .0
+            .get_or_init(|| self.data.map(|sub_board| sub_board.state))
     }
 
     pub fn get_state(&self) -> GameState {
+        self.state_of(self.get_abstracted_board())
+    }
+
+    /// the state of the game, given the states of the sub boards as returned by `get_abstracted_board`
+    pub(crate) fn state_of(&self, current_states: &Array2<Field>) -> GameState {
         let next_player = self.get_next_player();
-        let data = self.get_abstracted_board();
+        match self.draw_rule {
+            DrawRule::Neither => check_matrix(current_states, next_player),
+            DrawRule::Both => check_matrix_with_shared_draws(current_states, next_player),
+        }
+    }
 
-        check_matrix(&data, next_player)
+    /// Returns true if the game has been won by the given player
+    pub fn is_won_by(&self, player: Player) -> bool {
+        self.winner() == Some(player)
     }
 
-    /// Returns the subboard that the given move is in, and the coordinates of the move in that subboard
-    /// Example: (5,1) -> ((1,0), (2,1))  1*3+2 = 5, 0*3+1 = 1
-    fn get_subboard_for_move(
-        &self,
-        _coordinates: Coordinates,
-    ) -> Option<(Coordinates, Coordinates)> {
-        let (row, column) = _coordinates;
+    /// Returns the winner of the game, if there is one
+    pub fn winner(&self) -> Option<Player> {
+        match self.get_state() {
+            GameState::Won { winner } => Some(winner),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the game has ended, either by a win or a draw
+    pub fn is_over(&self) -> bool {
+        !self.get_state().is_in_progress()
+    }
 
-        let subboard_row = row / SubBoard::SIZE.0;
-        let subboard_column = column / SubBoard::SIZE.1;
+    /// Checks if the coordinates lie on the board, based on the actual dimensions of the board
+    pub fn in_bounds(&self, coordinates: Coordinates) -> bool {
+        let size = self.get_field_size();
+        coordinates.0 < size.0 && coordinates.1 < size.1
+    }
+
+    /// Returns the subboard that the given move is in, and the coordinates of the move in that subboard,
+    /// see `GlobalCoord::split`
+    /// Returns None if the coordinates are out of bounds
+    pub(crate) fn get_subboard_for_move(
+        &self,
+        coordinates: GlobalCoord,
+    ) -> Option<(SubBoardIndex, CellIndex)> {
+        if !self.in_bounds(coordinates.into()) {
+            return None;
+        }
+        Some(coordinates.split())
+    }
 
-        let field_row = row - (subboard_row * SubBoard::SIZE.0);
-        let field_column = column - (subboard_column * SubBoard::SIZE.1);
+    /// the sub board at the given index
+    pub fn sub_board(&self, index: SubBoardIndex) -> &SubBoard {
+        &self.data[Coordinates::from(index)]
+    }
 
-        Some(((subboard_row, subboard_column), (field_row, field_column)))
+    fn sub_board_mut(&mut self, index: SubBoardIndex) -> &mut SubBoard {
+        &mut self.data[Coordinates::from(index)]
     }
 
+    /// makes a move for the given player, or for the player whose turn it is if none is
+    /// given. A given player whose turn it isn't is rejected with `NotYourTurn`.
     pub fn insert_move(
         &mut self,
         coordinates: Coordinates,
-        player: Player,
+        player: impl Into<Option<Player>>,
     ) -> Result<(), InvalidMove> {
+        let player = player
+            .into()
+            .unwrap_or_else(|| self.expected_player_for_next_move());
         let new_move = Move::new(coordinates, player);
         self.validate_move(new_move)?;
-        self.moves.push(new_move);
+        // the move is only recorded once it is on the board, so a failure can't leave them apart
         self.render_move(&new_move)?;
+        self.moves.push(new_move);
         Ok(())
     }
 
+    /// a copy of the board with the move made, e.g. to preview which sub board the
+    /// opponent is sent to. The board itself is left as it is.
+    pub fn preview(&self, m: Move) -> Result<Board, InvalidMove> {
+        let mut board = self.clone();
+        board.insert_move(m.coordinates, m.player)?;
+        Ok(board)
+    }
+
+    /// checks the move against the rule set of the board
     pub fn validate_move(&self, new_move: Move) -> Result<(), InvalidMove> {
-        // NotYourTurn
-        if self.get_next_player() != new_move.player {
-            return Err(InvalidMove::NotYourTurn);
+        self.rules.validate(self, new_move)
+    }
+
+    /// Returns the size of the full grid of fields (rows, columns)
+    pub fn get_field_size(&self) -> Coordinates {
+        BoardSize::from(self.data.dim()).field_size()
+    }
+
+    /// every field can be played at most once, so the board can't take more moves than
+    /// it has fields
+    pub fn max_moves(&self) -> usize {
+        BoardSize::from(self.data.dim()).max_moves()
+    }
+
+    /// Returns the compact representation of the board after applying the symmetry
+    pub fn to_compact(&self, symmetry: Symmetry) -> CompactBoard {
+        let size = self.get_field_size();
+        let mut cells = vec![0; size.0 * size.1];
+        for coordinates in (0..size.0).cartesian_product(0..size.1) {
+            let (subboard_index, field_index) = self
+                .get_subboard_for_move(coordinates.into())
+                .expect("coordinates are in bounds");
+            let (row, column) = symmetry.apply(coordinates, size);
+            cells[row * size.1 + column] =
+                CompactBoard::encode_field(self.sub_board(subboard_index).cell(field_index));
         }
-        // GameEnded
-        if !self.get_state().is_in_progress() {
-            return Err(InvalidMove::GameEnded);
+        CompactBoard {
+            cells,
+            last_move: self
+                .moves
+                .last()
+                .map(|last_move| symmetry.apply(last_move.coordinates, size)),
         }
-        // OutOfBounds
-        if new_move.coordinates.0 >= Self::SIZE.0 * SubBoard::SIZE.0
-            || new_move.coordinates.1 >= Self::SIZE.1 * SubBoard::SIZE.1
-        {
-            return Err(InvalidMove::OutOfBounds);
+    }
+
+    /// Returns the board with the symmetry applied to its fields and its moves. The outer
+    /// grid and every sub board are transformed the same way, so the moves still form a
+    /// valid game that leads to the transformed fields.
+    /// The symmetry has to be applicable to the grid, see `Symmetry::is_applicable`.
+    pub fn transform(&self, symmetry: Symmetry) -> Board {
+        let size = self.get_field_size();
+        let sub_boards = self.data.dim();
+        let mut data = self.data.clone();
+        for (subboard_index, sub_board) in self.data.indexed_iter() {
+            let mut transformed = sub_board.clone();
+            for (field_index, field) in sub_board.data.indexed_iter() {
+                transformed.data[symmetry.apply(field_index, SubBoard::SIZE)] = *field;
+            }
+            data[symmetry.apply(subboard_index, sub_boards)] = transformed;
         }
-        // FieldOccupied
-        if let Some((_subboard_index, field_index)) =
-            self.get_subboard_for_move(new_move.coordinates)
-        {
-            if self.data[_subboard_index].data[field_index] != Field::Vacant {
-                return Err(InvalidMove::FieldOccupied);
+        Board {
+            data,
+            moves: self
+                .moves
+                .iter()
+                .map(|m| Move::new(symmetry.apply(m.coordinates, size), m.player))
+                .collect(),
+            game_id: self.game_id,
+            draw_rule: self.draw_rule,
+            abstracted_board: AbstractedBoardCache::default(),
+            rules: self.rules.clone(),
+            position: self.position.map(|position| GridPosition {
+                forced: position
+                    .forced
+                    .map(|forced| symmetry.apply(forced.into(), sub_boards).into()),
+                ..position
+            }),
+        }
+    }
+
+    /// Returns the board rotated clockwise by 90 degrees
+    pub fn rotate90(&self) -> Board {
+        self.transform(Symmetry::Rotate90)
+    }
+
+    /// Returns the board rotated by 180 degrees
+    pub fn rotate180(&self) -> Board {
+        self.transform(Symmetry::Rotate180)
+    }
+
+    /// Returns the board rotated clockwise by 270 degrees
+    pub fn rotate270(&self) -> Board {
+        self.transform(Symmetry::Rotate270)
+    }
+
+    /// Returns the board with its columns mirrored
+    pub fn mirror_horizontal(&self) -> Board {
+        self.transform(Symmetry::FlipHorizontal)
+    }
+
+    /// Returns the board with its rows mirrored
+    pub fn mirror_vertical(&self) -> Board {
+        self.transform(Symmetry::FlipVertical)
+    }
+
+    /// Returns the lexicographically smallest compact representation across all
+    /// symmetries of the board, so that symmetric positions share the same canonical form
+    pub fn canonical(&self) -> CompactBoard {
+        let size = self.get_field_size();
+        Symmetry::ALL
+            .iter()
+            .filter(|symmetry| symmetry.is_applicable(size))
+            .map(|symmetry| self.to_compact(*symmetry))
+            .min()
+            .expect("the identity is always applicable")
+    }
+
+    /// Plays uniformly random legal moves on a copy of the board until the game ends
+    /// and returns the terminal state. If no legal move is left, the game counts as a draw.
+    pub fn random_playout(&self, rng: &mut impl Rng) -> GameState {
+        let mut board = self.clone();
+        board.play_random_moves(usize::MAX, rng);
+        match board.get_state() {
+            state if state.is_in_progress() => GameState::Draw,
+            state => state,
+        }
+    }
+
+    /// makes up to `count` uniformly random legal moves and returns how many were made.
+    /// Fewer moves are made if the game ends or no legal move is left before.
+    pub fn play_random_moves(&mut self, count: usize, rng: &mut impl Rng) -> usize {
+        for made in 0..count {
+            if !self.get_state().is_in_progress() {
+                return made;
             }
+            let Some(&coordinates) = self.get_allowed_moves().choose(rng) else {
+                return made;
+            };
+            // the move is taken from the allowed moves, so validating it again is not needed
+            let new_move = Move::new(coordinates, self.get_next_player());
+            self.moves.push(new_move);
+            self.render_move(&new_move)
+                .expect("allowed moves are always in bounds");
+        }
+        count
+    }
+
+    /// takes back every move after the first `move_number` moves and returns the
+    /// removed moves in the order they were made. The fields are rendered again from
+    /// the remaining moves, so boards built with `from_cells` can't be undone.
+    pub fn undo_to(&mut self, move_number: usize) -> Result<Vec<Move>, InvalidMove> {
+        if move_number > self.moves.len() || self.position.is_some() {
+            return Err(InvalidMove::OutOfBounds);
         }
-        // SubBoardNotActive
-        if !self.get_allowed_moves().contains(&new_move.coordinates) {
-            return Err(InvalidMove::SubBoardNotActive);
+        let removed_moves = self.moves.split_off(move_number);
+        self.data = Array2::from_elem(self.data.dim(), SubBoard::new());
+        self.abstracted_board = AbstractedBoardCache::default();
+        for m in self.moves.clone() {
+            self.render_move(&m)?;
         }
-        Ok(())
+        Ok(removed_moves)
     }
 
     pub fn render_move(&mut self, m: &Move) -> Result<(), InvalidMove> {
         let (subboard_index, field_index) = self
-            .get_subboard_for_move(m.coordinates)
+            .get_subboard_for_move(m.coordinates.into())
             .ok_or(InvalidMove::OutOfBounds)?;
-        self.data[subboard_index].data[field_index] = Field::Occupied { player: m.player };
+        self.sub_board_mut(subboard_index)
+            .set_cell(field_index, Field::Occupied { player: m.player });
+        self.abstracted_board = AbstractedBoardCache::default();
         Ok(())
     }
 }
@@ -221,8 +668,9 @@ impl Board {
 mod test {
 
     use super::*;
-
-    use env_logger;
+    use crate::AnnotatedMove;
+    use ndarray::array;
+    use proptest::prelude::*;
 
     // O   X |       | O
     //   X   |   O   |   O
@@ -257,18 +705,255 @@ mod test {
         ]
     }
 
+    /// a move given by the fuzzer: either one of the allowed moves, which lets games get
+    /// deep enough to end, or arbitrary coordinates and player
+    #[derive(Debug, Clone)]
+    enum FuzzMove {
+        Allowed(usize),
+        Arbitrary(Coordinates, bool),
+    }
+
+    /// the states of the sub boards checked from their fields, without the states they keep
+    fn computed_abstracted_board(board: &Board) -> Array2<Field> {
+        board.data.map(
+            |sub_board| match sub_board.get_state(board.get_next_player()) {
+                GameState::InProgress { .. } => Field::Vacant,
+                GameState::Draw => Field::Disabled,
+                GameState::Won { winner } => Field::Occupied { player: winner },
+            },
+        )
+    }
+
+    fn fuzz_moves() -> impl Strategy<Value = Vec<FuzzMove>> {
+        let fuzz_move = prop_oneof![
+            3 => any::<usize>().prop_map(FuzzMove::Allowed),
+            1 => (0..12usize, 0..12usize, any::<bool>())
+                .prop_map(|(row, column, is_x)| FuzzMove::Arbitrary((row, column), is_x)),
+        ];
+        prop::collection::vec(fuzz_move, 0..100)
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(2048))]
+
+        #[test]
+        fn insert_move_never_panics(fuzz_moves in fuzz_moves()) {
+            let mut board = Board::new();
+            for fuzz_move in fuzz_moves {
+                let new_move = match fuzz_move {
+                    FuzzMove::Allowed(index) => {
+                        let allowed_moves = board.get_allowed_moves();
+                        if allowed_moves.is_empty() {
+                            continue;
+                        }
+                        Move::new(allowed_moves[index % allowed_moves.len()], board.get_next_player())
+                    }
+                    FuzzMove::Arbitrary(coordinates, is_x) => {
+                        Move::new(coordinates, if is_x { Player::X } else { Player::O })
+                    }
+                };
+                let moves_before = board.moves.len();
+                match board.insert_move(new_move.coordinates, new_move.player) {
+                    Ok(()) => prop_assert_eq!(board.moves.len(), moves_before + 1),
+                    // a rejected move leaves the board untouched
+                    Err(_) => prop_assert_eq!(board.moves.len(), moves_before),
+                }
+            }
+
+            // the accepted moves are replayed to the same board
+            let game_data: GameData = board.clone().into();
+            let replayed_board = Board::try_from_game_data(game_data);
+            prop_assert_eq!(replayed_board.as_ref(), Ok(&board));
+
+            let move_count = board.moves.len();
+            let removed_moves = board.undo_to(move_count / 2).unwrap();
+            prop_assert_eq!(removed_moves.len(), move_count - move_count / 2);
+        }
+
+        #[test]
+        fn sub_board_states_stay_current(fuzz_moves in fuzz_moves()) {
+            let mut board = Board::new();
+            for fuzz_move in fuzz_moves {
+                let new_move = match fuzz_move {
+                    FuzzMove::Allowed(index) => {
+                        let allowed_moves = board.get_allowed_moves();
+                        if allowed_moves.is_empty() {
+                            continue;
+                        }
+                        Move::new(allowed_moves[index % allowed_moves.len()], board.get_next_player())
+                    }
+                    FuzzMove::Arbitrary(coordinates, is_x) => {
+                        Move::new(coordinates, if is_x { Player::X } else { Player::O })
+                    }
+                };
+                let _ = board.insert_move(new_move.coordinates, new_move.player);
+                prop_assert_eq!(board.get_abstracted_board(), &computed_abstracted_board(&board));
+            }
+
+            for symmetry in Symmetry::ALL {
+                let transformed = board.transform(symmetry);
+                prop_assert_eq!(
+                    transformed.get_abstracted_board(),
+                    &computed_abstracted_board(&transformed)
+                );
+            }
+            let move_count = board.moves.len();
+            board.undo_to(move_count / 2).unwrap();
+            prop_assert_eq!(board.get_abstracted_board(), &computed_abstracted_board(&board));
+        }
+    }
+
+    #[test]
+    fn undo_to() {
+        let sample_game = get_sample_game();
+        let mut board = Board::new();
+        for new_move in sample_game.iter() {
+            board
+                .insert_move(new_move.coordinates, new_move.player)
+                .unwrap();
+        }
+        assert_eq!(board.get_state(), GameState::Won { winner: Player::X });
+
+        let removed_moves = board.undo_to(10).unwrap();
+        assert_eq!(removed_moves, sample_game[10..]);
+        assert_eq!(board.moves, sample_game[..10]);
+        assert_eq!(
+            board.get_state(),
+            GameState::InProgress {
+                next_player: Player::X
+            }
+        );
+
+        // the board is the same as if only the first moves had been made
+        let mut expected_board = Board::new_with_id(board.game_id);
+        for new_move in sample_game[..10].iter() {
+            expected_board
+                .insert_move(new_move.coordinates, new_move.player)
+                .unwrap();
+        }
+        assert_eq!(board, expected_board);
+
+        assert_eq!(board.undo_to(11), Err(InvalidMove::OutOfBounds));
+        assert_eq!(board.undo_to(0).unwrap(), sample_game[..10]);
+        assert_eq!(board, Board::new_with_id(board.game_id));
+    }
+
+    #[test]
+    fn allowed_moves_by_subboard() {
+        let mut board = Board::new();
+        let allowed_moves = board.allowed_moves_by_subboard();
+        assert_eq!(allowed_moves.len(), 9);
+        assert!(allowed_moves.values().all(|moves| moves.len() == 9));
+
+        // X sends O to the center
+        board.insert_move((4, 4), Player::X).unwrap();
+        let allowed_moves = board.allowed_moves_by_subboard();
+        assert_eq!(allowed_moves.len(), 1);
+        assert_eq!(allowed_moves[&(1, 1)].len(), 8);
+        assert!(!allowed_moves[&(1, 1)].contains(&(4, 4)));
+
+        // the 15th move of the sample game sends O to the center, which X has already
+        // won, so O may play in any of the six sub boards that are still open
+        let mut board = Board::new();
+        for new_move in &get_sample_game()[..15] {
+            board
+                .insert_move(new_move.coordinates, new_move.player)
+                .unwrap();
+        }
+        let allowed_moves = board.allowed_moves_by_subboard();
+        assert_eq!(allowed_moves.len(), 6);
+        for won in [(0, 0), (0, 2), (1, 1)] {
+            assert!(!allowed_moves.contains_key(&won));
+        }
+        let mut flattened: Vec<_> = allowed_moves.into_values().flatten().collect();
+        flattened.sort();
+        assert_eq!(flattened, board.get_allowed_moves());
+    }
+
+    #[test]
+    fn annotated_moves() {
+        let mut board = Board::new();
+        for new_move in get_sample_game() {
+            board
+                .insert_move(new_move.coordinates, new_move.player)
+                .unwrap();
+        }
+        let annotated_moves = board.annotated_moves();
+        assert_eq!(
+            annotated_moves
+                .iter()
+                .map(|annotated| annotated.move_)
+                .collect::<Vec<_>>(),
+            get_sample_game()
+        );
+
+        // X takes the center first, then the top left, O the top right and X wins
+        // with the bottom right
+        let captures: Vec<_> = annotated_moves
+            .iter()
+            .enumerate()
+            .filter_map(|(index, annotated)| Some((index, annotated.captured_subboard?)))
+            .collect();
+        assert_eq!(
+            captures,
+            vec![
+                (6, Player::X),
+                (10, Player::X),
+                (13, Player::O),
+                (16, Player::X)
+            ]
+        );
+
+        // (3, 3) is the top left field of the center sub board and sends O to the top left
+        assert_eq!(
+            annotated_moves[6],
+            AnnotatedMove {
+                move_: Move::new((3, 3), Player::X),
+                subboard: (1, 1),
+                captured_subboard: Some(Player::X),
+                sent_to: (0, 0),
+            }
+        );
+        assert_eq!(annotated_moves[0].subboard, (0, 0));
+        assert_eq!(annotated_moves[0].sent_to, (1, 1));
+        assert_eq!(Board::new().annotated_moves(), vec![]);
+    }
+
     #[test]
     fn get_subboard_for_move() {
         let board = Board::new();
-        assert_eq!(board.get_subboard_for_move((0, 0)), Some(((0, 0), (0, 0))));
-        assert_eq!(board.get_subboard_for_move((1, 1)), Some(((0, 0), (1, 1))));
-        assert_eq!(board.get_subboard_for_move((2, 2)), Some(((0, 0), (2, 2))));
-        assert_eq!(board.get_subboard_for_move((3, 3)), Some(((1, 1), (0, 0))));
-        assert_eq!(board.get_subboard_for_move((4, 4)), Some(((1, 1), (1, 1))));
-        assert_eq!(board.get_subboard_for_move((5, 5)), Some(((1, 1), (2, 2))));
-        assert_eq!(board.get_subboard_for_move((6, 6)), Some(((2, 2), (0, 0))));
-        assert_eq!(board.get_subboard_for_move((7, 7)), Some(((2, 2), (1, 1))));
-        assert_eq!(board.get_subboard_for_move((8, 8)), Some(((2, 2), (2, 2))));
+        let split = |coordinates: Coordinates| board.get_subboard_for_move(coordinates.into());
+        let expected = |subboard_index: Coordinates, field_index: Coordinates| {
+            Some((subboard_index.into(), field_index.into()))
+        };
+        assert_eq!(split((0, 0)), expected((0, 0), (0, 0)));
+        assert_eq!(split((1, 1)), expected((0, 0), (1, 1)));
+        assert_eq!(split((2, 2)), expected((0, 0), (2, 2)));
+        assert_eq!(split((3, 3)), expected((1, 1), (0, 0)));
+        assert_eq!(split((4, 4)), expected((1, 1), (1, 1)));
+        assert_eq!(split((5, 5)), expected((1, 1), (2, 2)));
+        assert_eq!(split((6, 6)), expected((2, 2), (0, 0)));
+        assert_eq!(split((7, 7)), expected((2, 2), (1, 1)));
+        assert_eq!(split((8, 8)), expected((2, 2), (2, 2)));
+        assert_eq!(split((5, 1)), expected((1, 0), (2, 1)));
+        assert_eq!(split((9, 0)), None);
+        assert_eq!(split((0, 9)), None);
+    }
+
+    #[test]
+    fn render_move_out_of_bounds() {
+        let mut board = Board::new();
+        assert!(board.in_bounds((8, 8)));
+        assert!(!board.in_bounds((9, 8)));
+        assert_eq!(
+            board.render_move(&Move::new((9, 8), Player::X)),
+            Err(InvalidMove::OutOfBounds)
+        );
+        assert_eq!(
+            board.render_move(&Move::new((0, usize::MAX), Player::X)),
+            Err(InvalidMove::OutOfBounds)
+        );
+        assert_eq!(board, Board::new_with_id(board.game_id));
     }
 
     #[test]
@@ -351,13 +1036,15 @@ mod test {
         let moves = get_sample_game();
         for (move_index, m) in moves.iter().enumerate() {
             let expected_abstraction = &expected_results[move_index];
-            game_board.insert_move(m.coordinates, m.player).expect(
-                format!("expected move {} to be valid, but it was not", move_index).as_str(),
-            );
+            game_board
+                .insert_move(m.coordinates, m.player)
+                .unwrap_or_else(|_| {
+                    panic!("expected move {} to be valid, but it was not", move_index)
+                });
             if let Some(expected_abstraction) = expected_abstraction {
                 assert_eq!(
                     game_board.get_abstracted_board(),
-                    Array2::from_shape_vec(
+                    &Array2::from_shape_vec(
                         (3, 3),
                         expected_abstraction
                             .iter()
@@ -381,6 +1068,28 @@ mod test {
         );
     }
 
+    #[test]
+    fn preview() {
+        let mut board = Board::new();
+        board.insert_move((4, 4), Player::X).unwrap();
+        let original = board.clone();
+
+        let preview = board.preview(Move::new((3, 5), Player::O)).unwrap();
+        assert_eq!(preview.moves.len(), 2);
+        assert_eq!(preview.forced_subboard(), Some((0, 2)));
+        assert_eq!(preview.get_next_player(), Player::X);
+        // the board itself is unchanged
+        assert_eq!(board, original);
+        assert_eq!(board.forced_subboard(), Some((1, 1)));
+        assert_eq!(board.get_next_player(), Player::O);
+
+        assert_eq!(
+            board.preview(Move::new((0, 0), Player::O)),
+            Err(InvalidMove::SubBoardNotActive)
+        );
+        assert_eq!(board, original);
+    }
+
     #[test]
     fn validate_move() {
         // things to test:
@@ -390,7 +1099,7 @@ mod test {
         // InvalidMove::OutOfBounds;
         // InvalidMove::SubBoardNotActive;
 
-        let tests = vec![
+        let tests = [
             vec![
                 (Move::new((0, 0), Player::X), None),
                 (
@@ -401,7 +1110,7 @@ mod test {
             // moves from SAMPLE_FULL_GAME, and a new move that should give GameEnded
             get_sample_game()
                 .iter()
-                .map(|m| (m.clone(), None))
+                .map(|m| (*m, None))
                 .chain(vec![(
                     Move::new((7, 0), Player::O),
                     Some(InvalidMove::GameEnded),
@@ -461,6 +1170,337 @@ mod test {
         }
     }
 
+    #[test]
+    fn insert_move_player_label() {
+        let mut board = Board::new();
+        assert_eq!(board.expected_player_for_next_move(), Player::X);
+
+        // a matching label is accepted
+        board.insert_move((4, 4), Player::X).unwrap();
+        assert_eq!(board.expected_player_for_next_move(), Player::O);
+
+        // a spoofed label is rejected without changing the board
+        assert_eq!(
+            board.insert_move((3, 3), Player::X),
+            Err(InvalidMove::NotYourTurn)
+        );
+        assert_eq!(board.moves.len(), 1);
+
+        // without a label, the move is made by the player whose turn it is
+        board.insert_move((3, 3), None).unwrap();
+        board.insert_move((0, 0), None).unwrap();
+        assert_eq!(
+            board.moves,
+            vec![
+                Move::new((4, 4), Player::X),
+                Move::new((3, 3), Player::O),
+                Move::new((0, 0), Player::X),
+            ]
+        );
+        assert_eq!(board.expected_player_for_next_move(), Player::O);
+    }
+
+    #[test]
+    fn terminal_queries() {
+        let mut board = Board::new();
+        assert!(!board.is_over());
+        assert_eq!(board.winner(), None);
+
+        let sample_game = get_sample_game();
+        let (final_move, moves) = sample_game.split_last().unwrap();
+        for new_move in moves {
+            board
+                .insert_move(new_move.coordinates, new_move.player)
+                .unwrap();
+        }
+        assert!(!board.is_over());
+        assert!(!board.is_won_by(Player::X));
+        assert!(!board.get_allowed_moves().is_empty());
+
+        board
+            .insert_move(final_move.coordinates, final_move.player)
+            .unwrap();
+        assert!(board.is_over());
+        assert_eq!(board.winner(), Some(Player::X));
+        assert!(board.is_won_by(Player::X));
+        assert!(!board.is_won_by(Player::O));
+        // there are vacant fields left, but no moves once the game is won
+        assert!(board.get_allowed_moves().is_empty());
+        assert_eq!(
+            board.validate_move(Move::new((0, 0), Player::O)),
+            Err(InvalidMove::GameEnded)
+        );
+    }
+
+    #[test]
+    fn render_snapshot() {
+        let sample_game = get_sample_game();
+        let mut board = Board::new();
+        for (move_count, new_move) in sample_game.iter().enumerate() {
+            let snapshot = board.render_snapshot();
+            assert_eq!(snapshot.allowed_moves, board.get_allowed_moves());
+            assert_eq!(snapshot.forced_subboard, board.forced_subboard());
+            assert_eq!(snapshot.state, board.get_state());
+            assert_eq!(snapshot.next_player, board.get_next_player());
+            for (row, fields) in snapshot.fields.iter().enumerate() {
+                for (column, field) in fields.iter().enumerate() {
+                    let subboard = &board.data[(row / 3, column / 3)];
+                    assert_eq!(*field, subboard.data[(row % 3, column % 3)]);
+                }
+            }
+            for (row, states) in snapshot.sub_boards.iter().enumerate() {
+                for (column, state) in states.iter().enumerate() {
+                    assert_eq!(
+                        *state,
+                        board.data[(row, column)].get_state(board.get_next_player())
+                    );
+                }
+            }
+            // the move after O's move into the middle sub board is forced there
+            if move_count == 2 {
+                assert_eq!(snapshot.forced_subboard, Some((1, 1)));
+            }
+            board
+                .insert_move(new_move.coordinates, new_move.player)
+                .unwrap();
+        }
+
+        let snapshot = board.render_snapshot();
+        assert_eq!(snapshot.state, GameState::Won { winner: Player::X });
+        assert!(snapshot.allowed_moves.is_empty());
+        assert_eq!(snapshot.fields.len(), 9);
+        assert_eq!(snapshot.sub_boards.len(), 3);
+        assert_eq!(
+            snapshot.sub_boards[1][1],
+            GameState::Won { winner: Player::X }
+        );
+    }
+
+    #[test]
+    fn draw_rule() {
+        // X O X
+        // X O O
+        // O X X
+        let drawn = array![
+            [
+                Field::Occupied { player: Player::X },
+                Field::Occupied { player: Player::O },
+                Field::Occupied { player: Player::X }
+            ],
+            [
+                Field::Occupied { player: Player::X },
+                Field::Occupied { player: Player::O },
+                Field::Occupied { player: Player::O }
+            ],
+            [
+                Field::Occupied { player: Player::O },
+                Field::Occupied { player: Player::X },
+                Field::Occupied { player: Player::X }
+            ],
+        ];
+        let won_by_x = Array2::from_elem((3, 3), Field::Occupied { player: Player::X });
+
+        // X won the top left and top middle sub boards, the top right one is drawn
+        let mut board = Board::new();
+        board.data[(0, 0)] = SubBoard::from_cells(won_by_x.clone());
+        board.data[(0, 1)] = SubBoard::from_cells(won_by_x);
+        board.data[(0, 2)] = SubBoard::from_cells(drawn.clone());
+
+        assert_eq!(board.draw_rule, DrawRule::Neither);
+        assert_eq!(
+            board.get_state(),
+            GameState::InProgress {
+                next_player: Player::X
+            }
+        );
+        board.draw_rule = DrawRule::Both;
+        assert_eq!(board.get_state(), GameState::Won { winner: Player::X });
+        assert_eq!(board.render_snapshot().state, board.get_state());
+
+        // a line of drawn sub boards only is won by no one
+        let mut board = Board::new();
+        board.draw_rule = DrawRule::Both;
+        for column in 0..3 {
+            board.data[(1, column)] = SubBoard::from_cells(drawn.clone());
+        }
+        assert_eq!(
+            board.get_state(),
+            GameState::InProgress {
+                next_player: Player::X
+            }
+        );
+
+        // the rule is kept in the settings of the game data
+        let game_data = GameData::from(board.clone());
+        assert_eq!(game_data.settings.draw_rule, DrawRule::Both);
+        assert_eq!(Board::from(game_data).draw_rule, DrawRule::Both);
+    }
+
+    #[test]
+    fn from_cells() {
+        let x = Field::Occupied { player: Player::X };
+        let o = Field::Occupied { player: Player::O };
+        let mut cells = Array2::from_elem(Board::FIELD_SIZE, Field::Vacant);
+        cells[(4, 4)] = x;
+        cells[(3, 3)] = o;
+        cells[(0, 0)] = x;
+        let board = Board::from_cells(cells.clone(), Player::O, Some((0, 0))).unwrap();
+        assert!(board.moves.is_empty());
+        assert_eq!(board.get_next_player(), Player::O);
+        assert_eq!(board.forced_subboard(), Some((0, 0)));
+        assert_eq!(board.get_allowed_moves().len(), 8);
+
+        // the same position as the moves leading there
+        let mut played = Board::new();
+        for (coordinates, player) in [
+            ((4, 4), Player::X),
+            ((3, 3), Player::O),
+            ((0, 0), Player::X),
+        ] {
+            played.insert_move(coordinates, player).unwrap();
+        }
+        assert_eq!(board.data, played.data);
+        assert_eq!(board.get_allowed_moves(), played.get_allowed_moves());
+
+        // moves are made on top of the grid, but it can't be undone
+        let mut board = board;
+        board.insert_move((1, 1), Player::O).unwrap();
+        assert_eq!(board.get_next_player(), Player::X);
+        assert_eq!(board.forced_subboard(), Some((1, 1)));
+        assert_eq!(board.undo_to(0), Err(InvalidMove::OutOfBounds));
+        assert_eq!(board.expected_player_for_next_move(), Player::X);
+        board.insert_move((3, 4), None).unwrap();
+        assert_eq!(board.moves.last(), Some(&Move::new((3, 4), Player::X)));
+
+        // without a forced sub board, every vacant field may be played
+        let board = Board::from_cells(cells.clone(), Player::O, None).unwrap();
+        assert_eq!(board.get_allowed_moves().len(), Board::MAX_MOVES - 3);
+    }
+
+    #[test]
+    fn smaller_board_frees_the_next_move() {
+        let mut board = Board::new().with_settings(&GameSettings {
+            board_size: BoardSize {
+                rows: 1,
+                columns: 2,
+            },
+            ..Default::default()
+        });
+        // sends O to the sub board at (0, 0)
+        board.insert_move((0, 3), None).unwrap();
+        assert_eq!(board.forced_subboard(), Some((0, 0)));
+        // sends X to the sub board at (2, 2), which the board doesn't have
+        board.insert_move((2, 2), None).unwrap();
+        assert_eq!(board.forced_subboard(), None);
+        assert_eq!(board.get_allowed_moves().len(), board.max_moves() - 2);
+        assert_eq!(board.max_moves(), 18);
+    }
+
+    #[test]
+    fn from_cells_inconsistent() {
+        let x = Field::Occupied { player: Player::X };
+        let o = Field::Occupied { player: Player::O };
+        let empty = Array2::from_elem(Board::FIELD_SIZE, Field::Vacant);
+
+        assert_eq!(
+            Board::from_cells(Array2::from_elem((3, 3), Field::Vacant), Player::X, None),
+            Err(InvalidBoard::WrongSize)
+        );
+        let mut cells = empty.clone();
+        cells[(0, 0)] = Field::Disabled;
+        assert_eq!(
+            Board::from_cells(cells, Player::X, None),
+            Err(InvalidBoard::DisabledField)
+        );
+
+        // X moves first, it can't be O's turn on an empty board
+        assert_eq!(
+            Board::from_cells(empty.clone(), Player::O, None),
+            Err(InvalidBoard::ImplausibleCounts { x: 0, o: 0 })
+        );
+        let mut cells = empty.clone();
+        cells[(0, 0)] = x;
+        cells[(4, 4)] = x;
+        assert_eq!(
+            Board::from_cells(cells, Player::O, None),
+            Err(InvalidBoard::ImplausibleCounts { x: 2, o: 0 })
+        );
+
+        // the top row of the sub board in the middle right is X's, the bottom row O's
+        let mut cells = empty.clone();
+        for column in 6..9 {
+            cells[(3, column)] = x;
+            cells[(5, column)] = o;
+        }
+        assert_eq!(
+            Board::from_cells(cells.clone(), Player::X, None),
+            Err(InvalidBoard::WonByBoth {
+                subboard: Some((1, 2))
+            })
+        );
+
+        // a won sub board can't be forced
+        let mut cells = empty.clone();
+        for column in 6..9 {
+            cells[(3, column)] = x;
+        }
+        for coordinates in [(0, 6), (0, 7), (1, 6)] {
+            cells[coordinates] = o;
+        }
+        assert!(Board::from_cells(cells.clone(), Player::X, Some((0, 2))).is_ok());
+        assert_eq!(
+            Board::from_cells(cells.clone(), Player::X, Some((1, 2))),
+            Err(InvalidBoard::InvalidForcedSubBoard)
+        );
+        assert_eq!(
+            Board::from_cells(cells, Player::X, Some((3, 0))),
+            Err(InvalidBoard::InvalidForcedSubBoard)
+        );
+    }
+
+    #[test]
+    fn abstracted_board_cache() {
+        // a board without a cached abstraction computes it from its sub boards
+        fn uncached(board: &Board) -> Board {
+            Board {
+                abstracted_board: AbstractedBoardCache::default(),
+                ..board.clone()
+            }
+        }
+
+        let mut board = Board::new();
+        for m in get_sample_game() {
+            // query the state before each move so a stale cache would be noticed
+            assert_eq!(
+                board.get_abstracted_board(),
+                uncached(&board).get_abstracted_board()
+            );
+            board.insert_move(m.coordinates, m.player).unwrap();
+            assert_eq!(board.get_state(), uncached(&board).get_state());
+            assert_eq!(
+                board.get_allowed_moves(),
+                uncached(&board).get_allowed_moves()
+            );
+        }
+        assert_eq!(board.get_state(), GameState::Won { winner: Player::X });
+
+        board.undo_to(4).unwrap();
+        assert_eq!(
+            board.get_abstracted_board(),
+            uncached(&board).get_abstracted_board()
+        );
+        board.undo_to(0).unwrap();
+        assert_eq!(
+            board.get_abstracted_board(),
+            &Array2::from_elem((3, 3), Field::Vacant)
+        );
+
+        // the cache is neither serialized nor compared
+        let serialized = serde_json::to_string(&board).unwrap();
+        assert!(!serialized.contains("abstracted_board"));
+        assert_eq!(serde_json::from_str::<Board>(&serialized).unwrap(), board);
+    }
+
     // from and into game data
     #[test]
     fn from_game_data() {
@@ -469,7 +1509,7 @@ mod test {
         for new_move in get_sample_game() {
             board
                 .insert_move(new_move.coordinates, new_move.player)
-                .expect(format!("expected to be able to add move {:?}", new_move).as_str());
+                .unwrap_or_else(|_| panic!("expected to be able to add move {:?}", new_move));
         }
         assert_eq!(board.get_state(), GameState::Won { winner: Player::X });
 
@@ -479,4 +1519,125 @@ mod test {
         assert_eq!(board.moves, get_sample_game());
         assert_eq!(board.get_state(), GameState::Won { winner: Player::X });
     }
+    #[test]
+    fn random_playout() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let board = Board::new();
+        for _ in 0..100 {
+            assert!(!board.random_playout(&mut rng).is_in_progress());
+        }
+        // the board itself is not modified
+        assert_eq!(board.moves, vec![]);
+
+        let mut board = Board::new();
+        for new_move in get_sample_game() {
+            board
+                .insert_move(new_move.coordinates, new_move.player)
+                .unwrap();
+        }
+        assert_eq!(
+            board.random_playout(&mut rng),
+            GameState::Won { winner: Player::X }
+        );
+    }
+    #[test]
+    fn play_random_moves() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut board = Board::new();
+        assert_eq!(board.play_random_moves(10, &mut rng), 10);
+        assert_eq!(board.moves.len(), 10);
+        // the moves are legal, so the board can be rebuilt from them
+        let game_data = GameData::from(board.clone());
+        assert_eq!(Board::try_from_game_data(game_data), Ok(board.clone()));
+
+        // no moves are made once the game has ended
+        let made = board.play_random_moves(Board::MAX_MOVES, &mut rng);
+        assert!(made < Board::MAX_MOVES);
+        assert!(!board.get_state().is_in_progress());
+        assert_eq!(board.play_random_moves(1, &mut rng), 0);
+    }
+    #[test]
+    fn canonical() {
+        let moves = &get_sample_game()[..7];
+        let mut board = Board::new();
+        for new_move in moves {
+            board
+                .insert_move(new_move.coordinates, new_move.player)
+                .unwrap();
+        }
+
+        for symmetry in Symmetry::ALL {
+            let mut transformed_board = Board::new();
+            for new_move in moves {
+                transformed_board
+                    .insert_move(
+                        symmetry.apply(new_move.coordinates, (9, 9)),
+                        new_move.player,
+                    )
+                    .unwrap();
+            }
+            assert_eq!(
+                transformed_board.canonical(),
+                board.canonical(),
+                "canonical form differs for {:?}",
+                symmetry
+            );
+        }
+
+        // a different position has a different canonical form
+        let mut other_board = Board::new();
+        for new_move in &get_sample_game()[..6] {
+            other_board
+                .insert_move(new_move.coordinates, new_move.player)
+                .unwrap();
+        }
+        assert_ne!(other_board.canonical(), board.canonical());
+    }
+    #[test]
+    fn transform() {
+        let sample_game = get_sample_game();
+        for move_count in [0, 7, sample_game.len()] {
+            let mut board = Board::new();
+            for new_move in &sample_game[..move_count] {
+                board
+                    .insert_move(new_move.coordinates, new_move.player)
+                    .unwrap();
+            }
+
+            assert_eq!(board.rotate90().rotate90().rotate90().rotate90(), board);
+            assert_eq!(board.rotate90().rotate90(), board.rotate180());
+            assert_eq!(board.rotate180().rotate90(), board.rotate270());
+            assert_eq!(board.mirror_horizontal().mirror_horizontal(), board);
+            assert_eq!(board.mirror_vertical().mirror_vertical(), board);
+
+            for symmetry in Symmetry::ALL {
+                let transformed_board = board.transform(symmetry);
+                // replaying the transformed moves leads to the transformed board
+                let mut replayed_board = Board::new_with_id(board.game_id);
+                for new_move in &transformed_board.moves {
+                    replayed_board
+                        .insert_move(new_move.coordinates, new_move.player)
+                        .unwrap();
+                }
+                assert_eq!(replayed_board, transformed_board, "{:?}", symmetry);
+
+                assert_eq!(transformed_board.get_state(), board.get_state());
+                assert_eq!(
+                    transformed_board.get_allowed_moves().len(),
+                    board.get_allowed_moves().len()
+                );
+                assert_eq!(
+                    transformed_board.forced_subboard(),
+                    board
+                        .forced_subboard()
+                        .map(|subboard| symmetry.apply(subboard, (3, 3)))
+                );
+                assert_eq!(transformed_board.canonical(), board.canonical());
+            }
+        }
+    }
 }
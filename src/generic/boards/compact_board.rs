@@ -0,0 +1,78 @@
+use crate::{Coordinates, Field, Player};
+
+/// One of the 8 symmetries of a square board: the rotations and reflections.
+/// Applied to the full grid of fields they transform the outer board and the
+/// contents of each sub-board consistently, since the board is made of 3x3 sub-boards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+    Transpose,
+    AntiTranspose,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::Transpose,
+        Symmetry::AntiTranspose,
+    ];
+
+    /// whether the symmetry maps a grid of the given size onto itself
+    pub fn is_applicable(&self, size: Coordinates) -> bool {
+        size.0 == size.1
+            || matches!(
+                self,
+                Symmetry::Identity
+                    | Symmetry::Rotate180
+                    | Symmetry::FlipHorizontal
+                    | Symmetry::FlipVertical
+            )
+    }
+
+    /// transforms coordinates on a grid of the given size (rows, columns).
+    /// Rotations are clockwise, horizontal flips mirror the columns.
+    pub fn apply(&self, coordinates: Coordinates, size: Coordinates) -> Coordinates {
+        let (row, column) = coordinates;
+        let (last_row, last_column) = (size.0 - 1, size.1 - 1);
+        match self {
+            Symmetry::Identity => (row, column),
+            Symmetry::Rotate90 => (column, last_row - row),
+            Symmetry::Rotate180 => (last_row - row, last_column - column),
+            Symmetry::Rotate270 => (last_column - column, row),
+            Symmetry::FlipHorizontal => (row, last_column - column),
+            Symmetry::FlipVertical => (last_row - row, column),
+            Symmetry::Transpose => (column, row),
+            Symmetry::AntiTranspose => (last_column - column, last_row - row),
+        }
+    }
+}
+
+/// A compact, comparable representation of a position, e.g. for transposition tables.
+/// Holds every field of the board row by row and the last move, which decides
+/// where the next move has to be made.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CompactBoard {
+    pub cells: Vec<u8>,
+    pub last_move: Option<Coordinates>,
+}
+
+impl CompactBoard {
+    pub fn encode_field(field: Field) -> u8 {
+        match field {
+            Field::Vacant => 0,
+            Field::Occupied { player: Player::X } => 1,
+            Field::Occupied { player: Player::O } => 2,
+            Field::Disabled => 3,
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Coordinates, SubBoard};
+
+// The board uses three kinds of coordinates that are all a row and a column. Each of them
+// has its own type, so that a field of a sub board can't be looked up with the index of
+// a sub board or the other way around. All of them are (row, column) tuples on the wire.
+
+/// the position of a field in the full grid of fields, like the coordinates of a move.
+/// It is split into the sub board and the cell in that sub board with `split`.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(from = "Coordinates", into = "Coordinates")]
+pub struct GlobalCoord {
+    pub row: usize,
+    pub column: usize,
+}
+
+/// the position of a sub board in the grid of sub boards
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(from = "Coordinates", into = "Coordinates")]
+pub struct SubBoardIndex {
+    pub row: usize,
+    pub column: usize,
+}
+
+/// the position of a field in its sub board
+///
+/// Usage:
+/// ```
+/// use tictactoe_extreme::{CellIndex, SubBoard};
+///
+/// let sub_board = SubBoard::new();
+/// assert!(sub_board.cell(CellIndex::new(1, 1)).is_vacant());
+/// ```
+///
+/// The index of a sub board can't be used instead:
+/// ```compile_fail
+/// use tictactoe_extreme::{SubBoard, SubBoardIndex};
+///
+/// let sub_board = SubBoard::new();
+/// sub_board.cell(SubBoardIndex::new(1, 1));
+/// ```
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(from = "Coordinates", into = "Coordinates")]
+pub struct CellIndex {
+    pub row: usize,
+    pub column: usize,
+}
+
+impl GlobalCoord {
+    pub fn new(row: usize, column: usize) -> Self {
+        Self { row, column }
+    }
+
+    /// the sub board the field is in and the position of the field in that sub board.
+    /// Example: (5,1) -> ((1,0), (2,1))  1*3+2 = 5, 0*3+1 = 1
+    pub fn split(self) -> (SubBoardIndex, CellIndex) {
+        (
+            SubBoardIndex::new(self.row / SubBoard::SIZE.0, self.column / SubBoard::SIZE.1),
+            CellIndex::new(self.row % SubBoard::SIZE.0, self.column % SubBoard::SIZE.1),
+        )
+    }
+
+    /// the field at the given cell of the given sub board, the inverse of `split`
+    pub fn join(sub_board: SubBoardIndex, cell: CellIndex) -> Self {
+        Self::new(
+            sub_board.row * SubBoard::SIZE.0 + cell.row,
+            sub_board.column * SubBoard::SIZE.1 + cell.column,
+        )
+    }
+}
+
+impl SubBoardIndex {
+    pub fn new(row: usize, column: usize) -> Self {
+        Self { row, column }
+    }
+}
+
+impl CellIndex {
+    pub fn new(row: usize, column: usize) -> Self {
+        Self { row, column }
+    }
+
+    /// the sub board the next player is sent to by a move in this cell, which is the
+    /// sub board at the same position in the grid of sub boards
+    pub fn target_sub_board(self) -> SubBoardIndex {
+        SubBoardIndex::new(self.row, self.column)
+    }
+}
+
+impl From<Coordinates> for GlobalCoord {
+    fn from((row, column): Coordinates) -> Self {
+        Self::new(row, column)
+    }
+}
+
+impl From<GlobalCoord> for Coordinates {
+    fn from(coordinates: GlobalCoord) -> Self {
+        (coordinates.row, coordinates.column)
+    }
+}
+
+impl From<Coordinates> for SubBoardIndex {
+    fn from((row, column): Coordinates) -> Self {
+        Self::new(row, column)
+    }
+}
+
+impl From<SubBoardIndex> for Coordinates {
+    fn from(index: SubBoardIndex) -> Self {
+        (index.row, index.column)
+    }
+}
+
+impl From<Coordinates> for CellIndex {
+    fn from((row, column): Coordinates) -> Self {
+        Self::new(row, column)
+    }
+}
+
+impl From<CellIndex> for Coordinates {
+    fn from(index: CellIndex) -> Self {
+        (index.row, index.column)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split_and_join() {
+        for row in 0..9 {
+            for column in 0..9 {
+                let coordinates = GlobalCoord::new(row, column);
+                let (sub_board, cell) = coordinates.split();
+                assert_eq!(GlobalCoord::join(sub_board, cell), coordinates);
+            }
+        }
+        assert_eq!(
+            GlobalCoord::new(5, 1).split(),
+            (SubBoardIndex::new(1, 0), CellIndex::new(2, 1))
+        );
+        assert_eq!(
+            CellIndex::new(2, 1).target_sub_board(),
+            SubBoardIndex::new(2, 1)
+        );
+    }
+
+    #[test]
+    fn tuple_on_the_wire() {
+        assert_eq!(
+            serde_json::to_string(&GlobalCoord::new(5, 1)).unwrap(),
+            serde_json::to_string(&(5, 1)).unwrap()
+        );
+        assert_eq!(
+            serde_json::from_str::<SubBoardIndex>("[1,0]").unwrap(),
+            SubBoardIndex::new(1, 0)
+        );
+        assert_eq!(
+            serde_json::from_str::<CellIndex>("[2,1]").unwrap(),
+            CellIndex::new(2, 1)
+        );
+        assert_eq!(Coordinates::from(CellIndex::new(2, 1)), (2, 1));
+    }
+}
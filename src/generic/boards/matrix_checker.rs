@@ -1,5 +1,5 @@
 use crate::{Field, GameState, Player};
-use ndarray::{s, Array1, Array2, ArrayBase, ViewRepr};
+use ndarray::{s, Array1, Array2, ArrayBase, ArrayView1, ViewRepr};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 struct WinnerRegisterer {
@@ -24,7 +24,7 @@ impl WinnerRegisterer {
 /// Usage:
 /// ```
 /// use ndarray::array;
-/// use tictactoe_v2::{Field, Player, GameState, check_matrix};
+/// use tictactoe_extreme::{Field, Player, GameState, check_matrix};
 ///
 /// // O X X
 /// // O O O <-- O wins
@@ -40,30 +40,49 @@ impl WinnerRegisterer {
 /// ```
 ///
 pub fn check_matrix(matrix: &Array2<Field>, next_player: Player) -> GameState {
+    check_matrix_by(matrix, next_player, get_winner_in_row)
+}
+
+/// Checks if a matrix of fields contains a winner, counting disabled fields for both players
+///
+/// A row, column or diagonal is won by a player if all of its fields that aren't disabled
+/// are occupied by them. A row of disabled fields only is won by no one.
+pub(crate) fn check_matrix_with_shared_draws(
+    matrix: &Array2<Field>,
+    next_player: Player,
+) -> GameState {
+    check_matrix_by(matrix, next_player, get_winner_in_row_with_shared_draws)
+}
+
+fn check_matrix_by(
+    matrix: &Array2<Field>,
+    next_player: Player,
+    winner_in_row: fn(ArrayView1<Field>) -> Option<Player>,
+) -> GameState {
     let mut winner_registerer = WinnerRegisterer::default();
 
     // check diagonal
-    winner_registerer.register(get_winner_in_row(matrix.diag()));
+    winner_registerer.register(winner_in_row(matrix.diag()));
 
     // check anti-diagonal
-    winner_registerer.register(get_winner_in_row(matrix.slice(s![..;-1, ..]).diag()));
+    winner_registerer.register(winner_in_row(matrix.slice(s![..;-1, ..]).diag()));
 
     // check rows
     for row in matrix.rows() {
-        winner_registerer.register(get_winner_in_row(row));
+        winner_registerer.register(winner_in_row(row));
     }
 
     // check columns
     for column in matrix.columns() {
-        winner_registerer.register(get_winner_in_row(column));
+        winner_registerer.register(winner_in_row(column));
     }
 
     if let Some(winner) = winner_registerer.get_winner() {
         GameState::Won { winner }
-    } else if matrix.iter().all(|field| !matches!(field, Field::Vacant)) {
-        return GameState::Draw;
+    } else if !matrix.iter().any(Field::is_vacant) {
+        GameState::Draw
     } else {
-        return GameState::InProgress { next_player };
+        GameState::InProgress { next_player }
     }
 }
 
@@ -76,15 +95,26 @@ fn get_winner_in_row(
     let potential_winner = list.get(0)?;
 
     if Array1::from_elem(list.len(), *potential_winner) == list {
-        match potential_winner {
-            Field::Occupied { player } => Some(*player),
-            _ => None,
-        }
+        potential_winner.occupant()
     } else {
         None
     }
 }
 
+/// Checks if a list of fields contains a winner, skipping disabled fields
+///
+/// This function is used by check_matrix_with_shared_draws to check rows, columns and diagonals.
+fn get_winner_in_row_with_shared_draws(
+    list: ArrayBase<ViewRepr<&Field>, ndarray::prelude::Dim<[usize; 1]>>,
+) -> Option<Player> {
+    let mut fields = list.iter().filter(|field| **field != Field::Disabled);
+    let potential_winner = fields.next()?.occupant()?;
+
+    fields
+        .all(|field| field.is_occupied_by(potential_winner))
+        .then_some(potential_winner)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
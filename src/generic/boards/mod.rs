@@ -1,7 +1,21 @@
+mod analysis;
+mod annotated;
 mod board;
+mod compact_board;
+mod coordinates;
 mod matrix_checker;
+mod render;
+mod rules;
 mod sub_board;
+mod svg;
 
-pub use board::Board;
+pub use analysis::Analysis;
+pub use annotated::AnnotatedMove;
+pub use board::{Board, InvalidBoard, InvalidMove};
+pub use compact_board::{CompactBoard, Symmetry};
+pub use coordinates::{CellIndex, GlobalCoord, SubBoardIndex};
 pub use matrix_checker::check_matrix;
+pub(crate) use matrix_checker::check_matrix_with_shared_draws;
+pub use render::RenderSnapshot;
+pub use rules::{RuleSet, StandardRules};
 pub use sub_board::SubBoard;
@@ -0,0 +1,65 @@
+use ndarray::Array2;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{Board, Coordinates, Field, GameState, GlobalCoord, Player, SubBoard};
+
+/// everything a client needs to draw a board, see `Board::render_snapshot`
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct RenderSnapshot {
+    /// the fields of the whole grid, row by row
+    pub fields: Vec<Vec<Field>>,
+    /// the state of every sub board, row by row
+    pub sub_boards: Vec<Vec<GameState>>,
+    pub allowed_moves: Vec<Coordinates>,
+    /// the sub board the next move must be made in, if the next player is limited to one
+    pub forced_subboard: Option<Coordinates>,
+    pub state: GameState,
+    pub next_player: Player,
+}
+
+impl Board {
+    /// Collects the fields, the sub board states, the allowed moves, the forced sub board
+    /// and the state of the game. Every sub board is evaluated once and every field is
+    /// visited once, instead of once per accessor.
+    pub fn render_snapshot(&self) -> RenderSnapshot {
+        let next_player = self.get_next_player();
+        let sub_boards = self.data.map(|sub_board| sub_board.get_state(next_player));
+        let abstracted_board: Array2<Field> = sub_boards.map(|state| match state {
+            GameState::InProgress { .. } => Field::Vacant,
+            GameState::Draw => Field::Disabled,
+            GameState::Won { winner } => Field::Occupied { player: *winner },
+        });
+        let forced_subboard = self.forced_subboard_of(&abstracted_board);
+        let state = self.state_of(&abstracted_board);
+
+        let (sub_board_rows, sub_board_columns) = self.data.dim();
+        let mut fields = vec![];
+        let mut allowed_moves = vec![];
+        for row in 0..sub_board_rows * SubBoard::SIZE.0 {
+            let mut field_row = vec![];
+            for column in 0..sub_board_columns * SubBoard::SIZE.1 {
+                let (subboard_index, field_index) = GlobalCoord::new(row, column).split();
+                let field = self.sub_board(subboard_index).cell(field_index);
+                if state.is_in_progress()
+                    && field.is_vacant()
+                    && abstracted_board[Coordinates::from(subboard_index)].is_vacant()
+                    && forced_subboard.is_none_or(|forced| forced == subboard_index)
+                {
+                    allowed_moves.push((row, column));
+                }
+                field_row.push(field);
+            }
+            fields.push(field_row);
+        }
+
+        RenderSnapshot {
+            fields,
+            sub_boards: sub_boards.outer_iter().map(|row| row.to_vec()).collect(),
+            allowed_moves,
+            forced_subboard: forced_subboard.map(Coordinates::from),
+            state,
+            next_player,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::{fmt::Debug, sync::Arc};
+
+use crate::{Board, InvalidMove, Move};
+
+/// decides whether a move may be made on a board. Boards follow `StandardRules` unless
+/// they were given another rule set with `Board::with_rules`. House rules usually
+/// check their own restriction on top of `StandardRules`.
+pub trait RuleSet: Debug + Send + Sync {
+    fn validate(&self, board: &Board, new_move: Move) -> Result<(), InvalidMove>;
+}
+
+/// the rules of ultimate tic-tac-toe
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StandardRules;
+
+impl RuleSet for StandardRules {
+    fn validate(&self, board: &Board, new_move: Move) -> Result<(), InvalidMove> {
+        // NotYourTurn
+        if board.expected_player_for_next_move() != new_move.player {
+            return Err(InvalidMove::NotYourTurn);
+        }
+        // GameEnded
+        if board.is_over() {
+            return Err(InvalidMove::GameEnded);
+        }
+        // OutOfBounds
+        if !board.in_bounds(new_move.coordinates) {
+            return Err(InvalidMove::OutOfBounds);
+        }
+        // FieldOccupied
+        if let Some((subboard_index, field_index)) =
+            board.get_subboard_for_move(new_move.coordinates.into())
+        {
+            if !board
+                .sub_board(subboard_index)
+                .cell(field_index)
+                .is_vacant()
+            {
+                return Err(InvalidMove::FieldOccupied);
+            }
+        }
+        // SubBoardNotActive
+        if !board.get_allowed_moves().contains(&new_move.coordinates) {
+            return Err(InvalidMove::SubBoardNotActive);
+        }
+        Ok(())
+    }
+}
+
+/// the rule set of a board. Like the moves it validates, it is kept by clones of the
+/// board, but it is neither serialized nor compared.
+#[derive(Debug, Clone)]
+pub(crate) struct Rules(Arc<dyn RuleSet>);
+
+impl Rules {
+    pub(crate) fn new(rules: impl RuleSet + 'static) -> Self {
+        Self(Arc::new(rules))
+    }
+
+    pub(crate) fn validate(&self, board: &Board, new_move: Move) -> Result<(), InvalidMove> {
+        self.0.validate(board, new_move)
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self::new(StandardRules)
+    }
+}
+
+impl PartialEq for Rules {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Rules {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Player;
+
+    /// a house rule that forbids opening in the center
+    #[derive(Debug)]
+    struct NoCenterOpening;
+
+    impl RuleSet for NoCenterOpening {
+        fn validate(&self, board: &Board, new_move: Move) -> Result<(), InvalidMove> {
+            if board.moves.is_empty() && new_move.coordinates == (4, 4) {
+                return Err(InvalidMove::ForbiddenByRules);
+            }
+            StandardRules.validate(board, new_move)
+        }
+    }
+
+    #[test]
+    fn custom_rules() {
+        let mut board = Board::new().with_rules(NoCenterOpening);
+        assert_eq!(
+            board.insert_move((4, 4), Player::X),
+            Err(InvalidMove::ForbiddenByRules)
+        );
+        assert!(board.moves.is_empty());
+
+        // the standard rules still apply
+        assert_eq!(
+            board.insert_move((0, 0), Player::O),
+            Err(InvalidMove::NotYourTurn)
+        );
+        board.insert_move((1, 1), Player::X).unwrap();
+        // only the first move is restricted
+        assert_eq!(board.insert_move((4, 4), Player::O), Ok(()));
+
+        // clones keep the rules, the standard board allows the move
+        let board = Board::new().with_rules(NoCenterOpening);
+        assert_eq!(
+            board.clone().validate_move(Move::new((4, 4), Player::X)),
+            Err(InvalidMove::ForbiddenByRules)
+        );
+        assert_eq!(
+            Board::new().validate_move(Move::new((4, 4), Player::X)),
+            Ok(())
+        );
+    }
+}
@@ -1,14 +1,32 @@
 use ndarray::Array2;
 use serde::{Deserialize, Serialize};
 
-use crate::{generic::boards::check_matrix, Coordinates, Field, GameState, Player};
+use crate::{generic::boards::check_matrix, CellIndex, Coordinates, Field, GameState, Player};
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(from = "SerializedSubBoard")]
 pub struct SubBoard {
+    /// the fields. Changing them directly instead of with `set_cell` leaves `state` behind.
     pub data: Array2<Field>,
+    /// the state of the sub board as a field of the board: vacant while it is in
+    /// progress, disabled once it is drawn and occupied by its winner. It is updated
+    /// whenever a field is set, so the board doesn't have to check every sub board.
     pub state: Field,
 }
 
+/// a serialized sub board, whose state is computed from its fields again rather than
+/// trusted
+#[derive(Deserialize)]
+struct SerializedSubBoard {
+    data: Array2<Field>,
+}
+
+impl From<SerializedSubBoard> for SubBoard {
+    fn from(serialized: SerializedSubBoard) -> Self {
+        Self::from_cells(serialized.data)
+    }
+}
+
 impl Default for SubBoard {
     fn default() -> Self {
         Self::new()
@@ -25,9 +43,39 @@ impl SubBoard {
         }
     }
 
+    /// a sub board with the given fields
+    pub fn from_cells(data: Array2<Field>) -> Self {
+        let mut sub_board = Self {
+            data,
+            state: Field::Vacant,
+        };
+        sub_board.state = sub_board.compute_state();
+        sub_board
+    }
+
+    /// the field at the given cell
+    pub fn cell(&self, index: CellIndex) -> Field {
+        self.data[Coordinates::from(index)]
+    }
+
+    /// sets the field at the given cell and updates the state of the sub board
+    pub(crate) fn set_cell(&mut self, index: CellIndex, field: Field) {
+        self.data[Coordinates::from(index)] = field;
+        self.state = self.compute_state();
+    }
+
     pub fn get_state(&self, next_player: Player) -> GameState {
         check_matrix(&self.data, next_player)
     }
+
+    /// the state as a field, see `state`. Whose turn it is doesn't matter for that.
+    fn compute_state(&self) -> Field {
+        match self.get_state(Player::X) {
+            GameState::InProgress { .. } => Field::Vacant,
+            GameState::Draw => Field::Disabled,
+            GameState::Won { winner } => Field::Occupied { player: winner },
+        }
+    }
 }
 
 #[cfg(test)]
@@ -51,4 +99,20 @@ mod test {
             GameState::Won { winner: Player::X }
         );
     }
+
+    #[test]
+    fn set_cell() {
+        let mut subboard = SubBoard::new();
+        for column in 0..3 {
+            assert_eq!(subboard.state, Field::Vacant);
+            subboard.set_cell((1, column).into(), Field::Occupied { player: Player::O });
+        }
+        assert_eq!(subboard.state, Field::Occupied { player: Player::O });
+
+        // the state isn't trusted when deserializing
+        let mut serialized = serde_json::to_value(&subboard).unwrap();
+        serialized["state"] = serde_json::to_value(Field::Vacant).unwrap();
+        let deserialized: SubBoard = serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized, subboard);
+    }
 }
@@ -0,0 +1,248 @@
+use std::{fmt::Write, time::Duration};
+
+use crate::{Board, Coordinates, GameState, Player, SubBoard};
+
+impl Board {
+    const SVG_CELL_SIZE: usize = 40;
+    const SVG_PADDING: usize = 10;
+
+    /// Returns the board after each of its moves, starting with the board after the first move
+    pub fn replay(&self) -> Vec<Board> {
+        let mut board = self.empty_copy();
+        self.moves
+            .iter()
+            .map(|m| {
+                // the moves of a board have been validated when they were made
+                board
+                    .render_move(m)
+                    .expect("the moves of a board are always in bounds");
+                board.moves.push(*m);
+                board.clone()
+            })
+            .collect()
+    }
+
+    /// Renders the board as an SVG image, with every won sub board covered by the mark of its winner
+    pub fn to_svg(&self) -> String {
+        let mut content = String::new();
+        for m in self.moves.iter() {
+            content.push_str(&Self::svg_mark(m.coordinates, m.player, 1));
+        }
+        for (subboard_index, player) in self.won_subboards() {
+            content.push_str(&Self::svg_subboard_mark(subboard_index, player));
+        }
+        self.svg_document(&content)
+    }
+
+    /// Renders the game as an animated SVG image that makes one move every `frame_duration`.
+    /// Each move is a group that becomes visible with a SMIL `<set>` animation, together
+    /// with the sub board it has won, if any.
+    pub fn to_replay_svg(&self, frame_duration: Duration) -> String {
+        let mut content = String::new();
+        let mut won_subboards = vec![];
+        for (step, board) in self.replay().iter().enumerate() {
+            let m = board.moves[step];
+            let mut step_content = Self::svg_mark(m.coordinates, m.player, 1);
+            for (subboard_index, player) in board.won_subboards() {
+                if !won_subboards.contains(&subboard_index) {
+                    won_subboards.push(subboard_index);
+                    step_content.push_str(&Self::svg_subboard_mark(subboard_index, player));
+                }
+            }
+            let _ = write!(
+                content,
+                r#"<g opacity="0"><set attributeName="opacity" to="1" begin="{}ms" fill="freeze"/>{}</g>"#,
+                frame_duration.as_millis() * (step as u128 + 1),
+                step_content
+            );
+        }
+        self.svg_document(&content)
+    }
+
+    /// Renders the board as a PNG image of the same size as its SVG image
+    #[cfg(feature = "png")]
+    pub fn to_png(&self) -> Vec<u8> {
+        use resvg::{tiny_skia, usvg};
+
+        // the SVG is generated above, so it always parses and has a size
+        let tree = usvg::Tree::from_str(&self.to_svg(), &usvg::Options::default())
+            .expect("the SVG of a board is always valid");
+        let size = tree.size().to_int_size();
+        let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+            .expect("the SVG of a board is never empty");
+        resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+        pixmap
+            .encode_png()
+            .expect("encoding into memory doesn't fail")
+    }
+
+    /// the sub boards that have been won, with their winner
+    fn won_subboards(&self) -> Vec<(Coordinates, Player)> {
+        let next_player = self.get_next_player();
+        self.data
+            .indexed_iter()
+            .filter_map(
+                |(subboard_index, sub_board)| match sub_board.get_state(next_player) {
+                    GameState::Won { winner } => Some((subboard_index, winner)),
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+
+    /// wraps the content into an SVG document showing the grid of the board
+    fn svg_document(&self, content: &str) -> String {
+        let (rows, columns) = self.get_field_size();
+        let grid_width = columns * Self::SVG_CELL_SIZE;
+        let grid_height = rows * Self::SVG_CELL_SIZE;
+        let width = grid_width + 2 * Self::SVG_PADDING;
+        let height = grid_height + 2 * Self::SVG_PADDING;
+        let start = Self::SVG_PADDING;
+
+        let mut grid = String::new();
+        // the borders of the sub boards are drawn thicker than those of the fields
+        for line in 0..=rows {
+            let stroke_width = if line % SubBoard::SIZE.0 == 0 { 3 } else { 1 };
+            let offset = Self::SVG_PADDING + line * Self::SVG_CELL_SIZE;
+            let end = Self::SVG_PADDING + grid_width;
+            let _ = write!(
+                grid,
+                r#"<line x1="{start}" y1="{offset}" x2="{end}" y2="{offset}" stroke="black" stroke-width="{stroke_width}"/>"#,
+            );
+        }
+        for line in 0..=columns {
+            let stroke_width = if line % SubBoard::SIZE.1 == 0 { 3 } else { 1 };
+            let offset = Self::SVG_PADDING + line * Self::SVG_CELL_SIZE;
+            let end = Self::SVG_PADDING + grid_height;
+            let _ = write!(
+                grid,
+                r#"<line x1="{offset}" y1="{start}" x2="{offset}" y2="{end}" stroke="black" stroke-width="{stroke_width}"/>"#,
+            );
+        }
+
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}"><rect width="{width}" height="{height}" fill="white"/>{grid}{content}</svg>"#
+        )
+    }
+
+    /// the mark of the player in the square of `cells` fields whose top left field is at `coordinates`
+    fn svg_mark(coordinates: Coordinates, player: Player, cells: usize) -> String {
+        let size = cells * Self::SVG_CELL_SIZE;
+        let inset = size / 5;
+        let left = Self::SVG_PADDING + coordinates.1 * Self::SVG_CELL_SIZE + inset;
+        let top = Self::SVG_PADDING + coordinates.0 * Self::SVG_CELL_SIZE + inset;
+        let right = left + size - 2 * inset;
+        let bottom = top + size - 2 * inset;
+        let stroke_width = 3 * cells;
+        match player {
+            Player::X => format!(
+                r#"<path d="M{left} {top}L{right} {bottom}M{right} {top}L{left} {bottom}" stroke="crimson" stroke-width="{stroke_width}" stroke-linecap="round" fill="none"/>"#
+            ),
+            Player::O => format!(
+                r#"<circle cx="{}" cy="{}" r="{}" stroke="royalblue" stroke-width="{stroke_width}" fill="none"/>"#,
+                (left + right) / 2,
+                (top + bottom) / 2,
+                (right - left) / 2
+            ),
+        }
+    }
+
+    /// the mark of the winner covering a whole sub board
+    fn svg_subboard_mark(subboard_index: Coordinates, player: Player) -> String {
+        let coordinates = (
+            subboard_index.0 * SubBoard::SIZE.0,
+            subboard_index.1 * SubBoard::SIZE.1,
+        );
+        format!(
+            r#"<g opacity="0.6">{}</g>"#,
+            Self::svg_mark(coordinates, player, SubBoard::SIZE.0)
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{BoardSize, GameSettings, Move};
+
+    fn get_board() -> Board {
+        let mut board = Board::new();
+        // X wins the top left sub board with its last move
+        for new_move in [
+            Move::new((0, 0), Player::X),
+            Move::new((0, 1), Player::O),
+            Move::new((0, 4), Player::X),
+            Move::new((1, 3), Player::O),
+            Move::new((3, 1), Player::X),
+            Move::new((1, 4), Player::O),
+            Move::new((4, 4), Player::X),
+            Move::new((3, 3), Player::O),
+            Move::new((1, 1), Player::X),
+            Move::new((5, 5), Player::O),
+            Move::new((7, 7), Player::X),
+            Move::new((3, 4), Player::O),
+            Move::new((2, 5), Player::X),
+            Move::new((6, 6), Player::O),
+            Move::new((2, 2), Player::X),
+        ] {
+            board
+                .insert_move(new_move.coordinates, new_move.player)
+                .unwrap();
+        }
+        board
+    }
+
+    #[test]
+    fn replay() {
+        let board = get_board();
+        let replay = board.replay();
+        assert_eq!(replay.len(), board.moves.len());
+        for (step, replayed_board) in replay.iter().enumerate() {
+            assert_eq!(replayed_board.moves, board.moves[..=step]);
+        }
+        assert_eq!(replay.last(), Some(&board));
+    }
+
+    #[test]
+    fn to_svg() {
+        let board = get_board();
+        let svg = board.to_svg();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>"));
+        // one path per X, one circle per O and one big X for the won sub board
+        assert_eq!(svg.matches("<path").count(), 8 + 1);
+        assert_eq!(svg.matches("<circle").count(), 7);
+    }
+
+    #[test]
+    fn to_svg_of_other_size() {
+        let mut board = Board::new().with_settings(&GameSettings {
+            board_size: BoardSize {
+                rows: 1,
+                columns: 2,
+            },
+            ..Default::default()
+        });
+        board.insert_move((2, 5), None).unwrap();
+        let svg = board.to_svg();
+        assert!(
+            svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="260" height="140""#)
+        );
+        // 4 lines between the rows and 7 between the columns of the fields
+        assert_eq!(svg.matches("<line").count(), 4 + 7);
+        assert_eq!(svg.matches("<path").count(), 1);
+        assert_eq!(board.replay().last(), Some(&board));
+    }
+
+    #[test]
+    fn to_replay_svg() {
+        let board = get_board();
+        let svg = board.to_replay_svg(Duration::from_millis(250));
+        assert_eq!(svg.matches("<set ").count(), board.moves.len());
+        assert!(svg.contains(r#"begin="250ms""#));
+        assert!(svg.contains(r#"begin="3750ms""#));
+        // the won sub board appears together with the winning move
+        let last_step = svg.rsplit("<g opacity=\"0\">").next().unwrap();
+        assert_eq!(last_step.matches("<path").count(), 2);
+    }
+}
@@ -1,9 +1,65 @@
 use crate::Player;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum Field {
     Vacant,
     Occupied { player: Player },
     Disabled,
 }
+
+impl Field {
+    pub fn is_vacant(&self) -> bool {
+        matches!(self, Field::Vacant)
+    }
+    pub fn is_disabled(&self) -> bool {
+        matches!(self, Field::Disabled)
+    }
+    /// Returns the player occupying the field, if there is one
+    pub fn occupant(&self) -> Option<Player> {
+        match self {
+            Field::Occupied { player } => Some(*player),
+            _ => None,
+        }
+    }
+    pub fn is_occupied_by(&self, player: Player) -> bool {
+        self.occupant() == Some(player)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const X: Field = Field::Occupied { player: Player::X };
+
+    #[test]
+    fn is_vacant() {
+        assert!(Field::Vacant.is_vacant());
+        assert!(!X.is_vacant());
+        assert!(!Field::Disabled.is_vacant());
+    }
+
+    #[test]
+    fn is_disabled() {
+        assert!(!Field::Vacant.is_disabled());
+        assert!(!X.is_disabled());
+        assert!(Field::Disabled.is_disabled());
+    }
+
+    #[test]
+    fn occupant() {
+        assert_eq!(Field::Vacant.occupant(), None);
+        assert_eq!(X.occupant(), Some(Player::X));
+        assert_eq!(Field::Disabled.occupant(), None);
+    }
+
+    #[test]
+    fn is_occupied_by() {
+        assert!(!Field::Vacant.is_occupied_by(Player::X));
+        assert!(X.is_occupied_by(Player::X));
+        assert!(!X.is_occupied_by(Player::O));
+        assert!(!Field::Disabled.is_occupied_by(Player::O));
+    }
+}
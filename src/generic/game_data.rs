@@ -1,13 +1,151 @@
-use crate::Move;
+use crate::{Board, GameResult, GameSettings, InvalidMove, Move, Player};
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use uuid::Uuid;
 
+/// why a sequence of moves can't be a game, with the index of the offending move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum InvalidGameData {
+    /// the move is made on the same field as the move at `first`
+    DuplicateCoordinates { index: usize, first: usize },
+    /// the move is made by the same player as the move before it
+    SamePlayerTwice { index: usize },
+    /// the move breaks the rules when the moves are replayed
+    InvalidMove {
+        index: usize,
+        invalid_move: InvalidMove,
+    },
+}
+
+impl Display for InvalidGameData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DuplicateCoordinates { index, first } => write!(
+                f,
+                "move {} is made on the same field as move {}",
+                index, first
+            ),
+            Self::SamePlayerTwice { index } => write!(
+                f,
+                "move {} is made by the same player as the move before it",
+                index
+            ),
+            Self::InvalidMove {
+                index,
+                invalid_move,
+            } => write!(f, "move {} is invalid: {:?}", index, invalid_move),
+        }
+    }
+}
+
+/// why two histories of a game can't be merged, see `GameData::merge`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum MergeConflict {
+    /// the histories belong to different games
+    DifferentGames,
+    /// the games are played with different settings
+    DifferentSettings,
+    /// both games have ended, but with different moves or results
+    BothEnded,
+    /// the history the merge settled on doesn't replay
+    InvalidHistory(InvalidGameData),
+}
+
+impl Display for MergeConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DifferentGames => write!(f, "the histories belong to different games"),
+            Self::DifferentSettings => write!(f, "the games have different settings"),
+            Self::BothEnded => write!(f, "both games have ended differently"),
+            Self::InvalidHistory(invalid) => {
+                write!(f, "the merged history is invalid: {}", invalid)
+            }
+        }
+    }
+}
+
 // TODO: is this abstraction layer necessary?
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(try_from = "UncheckedGameData")]
 pub struct GameData {
+    /// the moves in the order they were made. Moves outside the board of the settings are
+    /// rejected when the game data is deserialized, so stored or imported data can't make
+    /// a board panic.
     pub moves: Vec<Move>,
     pub game_id: Uuid,
+    /// the outcome of the game, set once the game has ended
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<GameResult>,
+    /// when the moves were made, in milliseconds since the unix epoch. Games stored
+    /// before moves were timed have no times for their first moves, so the times
+    /// belong to the last `move_times.len()` moves.
+    #[serde(default)]
+    pub move_times: Vec<u64>,
+    #[serde(default)]
+    pub settings: GameSettings,
+    /// when the game was created, in milliseconds since the unix epoch.
+    /// Games stored before this was recorded were created at 0.
+    #[serde(default)]
+    pub created_at: u64,
+    /// the name of the player who created the game, as they gave it. It only attributes
+    /// the game and is not checked against any player token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+}
+
+/// `GameData` as it is deserialized, before its moves are checked against the size of
+/// its board. The moves come before the settings, so they can't be checked on their own.
+#[derive(Deserialize)]
+struct UncheckedGameData {
+    moves: Vec<Move>,
+    game_id: Uuid,
+    #[serde(default)]
+    result: Option<GameResult>,
+    #[serde(default)]
+    move_times: Vec<u64>,
+    #[serde(default)]
+    settings: GameSettings,
+    #[serde(default)]
+    created_at: u64,
+    #[serde(default)]
+    created_by: Option<String>,
+}
+
+impl TryFrom<UncheckedGameData> for GameData {
+    type Error = String;
+
+    fn try_from(game_data: UncheckedGameData) -> Result<Self, Self::Error> {
+        let field_size = game_data.settings.board_size.field_size();
+        for (index, m) in game_data.moves.iter().enumerate() {
+            Move::try_new(m.coordinates, m.player, field_size).map_err(|_| {
+                format!("move {} at {:?} is not on the board", index, m.coordinates)
+            })?;
+        }
+        Ok(Self {
+            moves: game_data.moves,
+            game_id: game_data.game_id,
+            result: game_data.result,
+            move_times: game_data.move_times,
+            settings: game_data.settings,
+            created_at: game_data.created_at,
+            created_by: game_data.created_by,
+        })
+    }
+}
+
+/// the 64 bit FNV-1a hash of the bytes
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(PRIME)
+    })
 }
 
 impl Default for GameData {
@@ -21,16 +159,436 @@ impl GameData {
         Self {
             moves: vec![],
             game_id: Uuid::new_v4(),
+            result: None,
+            move_times: vec![],
+            settings: GameSettings::default(),
+            created_at: Self::now(),
+            created_by: None,
         }
     }
     pub fn new_with_id(id: Uuid) -> Self {
         Self {
             moves: vec![],
             game_id: id,
+            result: None,
+            move_times: vec![],
+            settings: GameSettings::default(),
+            created_at: Self::now(),
+            created_by: None,
         }
     }
 
+    /// the current time in milliseconds since the unix epoch, as stored in `move_times`
+    pub fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or_default()
+    }
+
     pub fn add_move(&mut self, m: Move) {
+        self.add_move_at(m, Self::now());
+    }
+
+    /// Adds the move, recording that it was made at the given time
+    pub fn add_move_at(&mut self, m: Move, time: u64) {
         self.moves.push(m);
+        self.move_times.push(time);
+        self.update_result();
+    }
+
+    /// Returns true if a move of the player at the given time would follow their
+    /// previous move faster than the minimum move interval of the settings allows
+    pub fn is_too_fast(&self, player: Player, time: u64) -> bool {
+        let Some(min_move_interval) = self.settings.min_move_interval else {
+            return false;
+        };
+        self.moves
+            .iter()
+            .rev()
+            .zip(self.move_times.iter().rev())
+            .find(|(m, _)| m.player == player)
+            .is_some_and(|(_, previous)| time.saturating_sub(*previous) < min_move_interval)
+    }
+
+    /// when the last move was made, or when the game was created if it has no timed moves
+    pub fn last_activity(&self) -> u64 {
+        self.move_times.last().copied().unwrap_or(self.created_at)
+    }
+
+    /// Replays the moves and returns the result if the game has ended
+    pub fn compute_result(&self) -> Option<GameResult> {
+        Board::try_from_game_data(self.clone())
+            .ok()
+            .and_then(|board| GameResult::from_state(board.get_state(), self.moves.len()))
+    }
+
+    /// removes every move after the first `move_number` moves, together with their
+    /// times, and returns the removed moves. Like every result, a stored result is kept,
+    /// which is why the providers refuse to truncate games that have ended.
+    pub fn truncate_moves(&mut self, move_number: usize) -> Vec<Move> {
+        let untimed_moves = self.moves.len().saturating_sub(self.move_times.len());
+        self.move_times
+            .truncate(move_number.saturating_sub(untimed_moves));
+        let removed_moves = self.moves.split_off(move_number.min(self.moves.len()));
+        self.update_result();
+        removed_moves
+    }
+
+    /// checks that the moves form a valid game, e.g. before imported game data is stored.
+    /// Fields played twice and players moving twice in a row are reported before the moves
+    /// are replayed, so importers learn which move is wrong instead of a bare replay error.
+    pub fn validate(&self) -> Result<(), InvalidGameData> {
+        let mut played_fields = HashMap::new();
+        for (index, m) in self.moves.iter().enumerate() {
+            if let Some(&first) = played_fields.get(&m.coordinates) {
+                return Err(InvalidGameData::DuplicateCoordinates { index, first });
+            }
+            played_fields.insert(m.coordinates, index);
+            if index > 0 && self.moves[index - 1].player == m.player {
+                return Err(InvalidGameData::SamePlayerTwice { index });
+            }
+        }
+
+        let mut board = Board::new_with_id(self.game_id).with_settings(&self.settings);
+        for (index, m) in self.moves.iter().enumerate() {
+            board
+                .insert_move(m.coordinates, m.player)
+                .map_err(|invalid_move| InvalidGameData::InvalidMove {
+                    index,
+                    invalid_move,
+                })?;
+        }
+        Ok(())
+    }
+
+    /// a hash of the game id and the moves in their order, which identifies the position
+    /// of the game. It doesn't depend on the serialization or on the hasher of the
+    /// standard library, so it stays the same across processes and releases.
+    pub fn content_hash(&self) -> u64 {
+        let mut bytes = self.game_id.as_bytes().to_vec();
+        for m in self.moves.iter() {
+            bytes.extend_from_slice(&(m.coordinates.0 as u64).to_le_bytes());
+            bytes.extend_from_slice(&(m.coordinates.1 as u64).to_le_bytes());
+            bytes.push(match m.player {
+                Player::X => 0,
+                Player::O => 1,
+            });
+        }
+        fnv1a(&bytes)
+    }
+
+    /// a hash of everything that is stored of the game, e.g. for ETags. Unlike
+    /// `content_hash` it also changes with the result, the settings and the move times.
+    pub fn version_hash(&self) -> u64 {
+        fnv1a(&serde_json::to_vec(self).expect("game data is always serializable"))
+    }
+
+    /// when the move at the given index was made, if its time was recorded
+    fn move_time(&self, index: usize) -> Option<u64> {
+        let untimed_moves = self.moves.len().saturating_sub(self.move_times.len());
+        index
+            .checked_sub(untimed_moves)
+            .and_then(|timed_index| self.move_times.get(timed_index))
+            .copied()
+    }
+
+    /// merges two histories of the same game, e.g. ones that were continued in
+    /// different regions. The moves both have in common are kept. Where they diverge,
+    /// the history whose next move was made first wins; moves without a time come last,
+    /// and moves made at the same time are ordered by their coordinates and player.
+    /// A game that has ended is final, so it wins over one that was continued or
+    /// diverged from it. The result doesn't depend on which history is `self`.
+    pub fn merge(&self, other: &GameData) -> Result<GameData, MergeConflict> {
+        if self.game_id != other.game_id {
+            return Err(MergeConflict::DifferentGames);
+        }
+        if self.settings != other.settings {
+            return Err(MergeConflict::DifferentSettings);
+        }
+        let (winner, loser) = match (self.result.is_some(), other.result.is_some()) {
+            (true, true) if (&self.moves, self.result) != (&other.moves, other.result) => {
+                return Err(MergeConflict::BothEnded);
+            }
+            (true, false) => (self, other),
+            (false, true) => (other, self),
+            _ => {
+                let common = self
+                    .moves
+                    .iter()
+                    .zip(other.moves.iter())
+                    .take_while(|(own, others)| own == others)
+                    .count();
+                let divergence = |game_data: &GameData| {
+                    game_data.moves.get(common).map(|m| {
+                        let time = game_data.move_time(common);
+                        (time.is_none(), time, m.coordinates, m.player == Player::O)
+                    })
+                };
+                match (divergence(self), divergence(other)) {
+                    (None, _) => (other, self),
+                    (_, None) => (self, other),
+                    (own, others) if own <= others => (self, other),
+                    _ => (other, self),
+                }
+            }
+        };
+        winner.validate().map_err(MergeConflict::InvalidHistory)?;
+
+        Ok(GameData {
+            created_at: winner.created_at.min(loser.created_at),
+            created_by: winner.created_by.clone().or(loser.created_by.clone()),
+            ..winner.clone()
+        })
+    }
+
+    /// Stores the result once the game has ended. An already stored result is never replaced.
+    pub fn update_result(&mut self) {
+        if self.result.is_none() {
+            self.result = self.compute_result();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Coordinates;
+
+    #[test]
+    fn is_too_fast() {
+        let mut game_data = GameData::new();
+        game_data.add_move_at(Move::new((0, 0), Player::X), 1_000);
+        game_data.add_move_at(Move::new((0, 1), Player::O), 1_100);
+        assert!(!game_data.is_too_fast(Player::X, 1_200));
+
+        game_data.settings.min_move_interval = Some(500);
+        assert!(game_data.is_too_fast(Player::X, 1_200));
+        assert!(!game_data.is_too_fast(Player::X, 1_500));
+        assert!(game_data.is_too_fast(Player::O, 1_500));
+
+        // moves without a time don't count
+        game_data.move_times.clear();
+        assert!(!game_data.is_too_fast(Player::X, 1_200));
+    }
+
+    fn game_data(moves: &[((usize, usize), Player)]) -> GameData {
+        let mut game_data = GameData::new();
+        game_data.moves = moves
+            .iter()
+            .map(|&(coordinates, player)| Move::new(coordinates, player))
+            .collect();
+        game_data
+    }
+
+    #[test]
+    fn deserialize_out_of_bounds() {
+        let mut json =
+            serde_json::to_value(game_data(&[((0, 0), Player::X), ((8, 8), Player::O)])).unwrap();
+        assert!(serde_json::from_value::<GameData>(json.clone()).is_ok());
+
+        json["moves"][1]["coordinates"] = serde_json::json!([9, 0]);
+        let error = serde_json::from_value::<GameData>(json.clone()).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("move 1 at (9, 0) is not on the board"));
+
+        // the bounds are the ones of the board size of the game
+        json["settings"] = serde_json::json!({ "board_size": { "rows": 4, "columns": 1 } });
+        assert!(serde_json::from_value::<GameData>(json.clone()).is_ok());
+        json["moves"][0]["coordinates"] = serde_json::json!([0, 3]);
+        let error = serde_json::from_value::<GameData>(json.clone()).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("move 0 at (0, 3) is not on the board"));
+
+        json["settings"] = serde_json::json!({ "board_size": { "rows": 0, "columns": 1 } });
+        assert!(serde_json::from_value::<GameData>(json).is_err());
+    }
+
+    #[test]
+    fn validate() {
+        let valid = game_data(&[
+            ((0, 0), Player::X),
+            ((0, 1), Player::O),
+            ((0, 4), Player::X),
+        ]);
+        assert_eq!(valid.validate(), Ok(()));
+
+        let duplicate = game_data(&[
+            ((0, 0), Player::X),
+            ((0, 1), Player::O),
+            ((0, 4), Player::X),
+            ((0, 1), Player::O),
+        ]);
+        assert_eq!(
+            duplicate.validate(),
+            Err(InvalidGameData::DuplicateCoordinates { index: 3, first: 1 })
+        );
+
+        let same_player_twice = game_data(&[
+            ((0, 0), Player::X),
+            ((0, 1), Player::O),
+            ((0, 4), Player::O),
+        ]);
+        assert_eq!(
+            same_player_twice.validate(),
+            Err(InvalidGameData::SamePlayerTwice { index: 2 })
+        );
+
+        // O moves outside the sub board X sent it to
+        let wrong_subboard = game_data(&[((0, 0), Player::X), ((4, 4), Player::O)]);
+        assert_eq!(
+            wrong_subboard.validate(),
+            Err(InvalidGameData::InvalidMove {
+                index: 1,
+                invalid_move: InvalidMove::SubBoardNotActive
+            })
+        );
+    }
+
+    #[test]
+    fn content_hash() {
+        let game_id = Uuid::new_v4();
+        let mut game_data = GameData::new_with_id(game_id);
+        game_data.add_move_at(Move::new((0, 0), Player::X), 1_000);
+        game_data.add_move_at(Move::new((0, 1), Player::O), 1_100);
+
+        // only the id and the moves count, not when the moves were made
+        let mut equal_game = GameData::new_with_id(game_id);
+        equal_game.add_move_at(Move::new((0, 0), Player::X), 5_000);
+        equal_game.add_move_at(Move::new((0, 1), Player::O), 6_000);
+        assert_eq!(game_data.content_hash(), equal_game.content_hash());
+
+        let mut other_game = GameData::new();
+        other_game.moves = game_data.moves.clone();
+        assert_ne!(game_data.content_hash(), other_game.content_hash());
+
+        let mut reordered_game = GameData::new_with_id(game_id);
+        reordered_game.moves = vec![Move::new((0, 1), Player::O), Move::new((0, 0), Player::X)];
+        assert_ne!(game_data.content_hash(), reordered_game.content_hash());
+
+        game_data.add_move(Move::new((0, 3), Player::X));
+        assert_ne!(game_data.content_hash(), equal_game.content_hash());
+    }
+
+    #[test]
+    fn version_hash() {
+        let mut game_data = GameData::new();
+        game_data.add_move_at(Move::new((0, 0), Player::X), 1_000);
+        assert_eq!(game_data.version_hash(), game_data.clone().version_hash());
+
+        let mut timed_differently = game_data.clone();
+        timed_differently.move_times = vec![2_000];
+        assert_ne!(game_data.version_hash(), timed_differently.version_hash());
+
+        // the moves stay the same, but the game has ended
+        let mut abandoned = game_data.clone();
+        abandoned.result = Some(GameResult::abandoned(1));
+        assert_eq!(game_data.content_hash(), abandoned.content_hash());
+        assert_ne!(game_data.version_hash(), abandoned.version_hash());
+
+        let mut private = game_data.clone();
+        private.settings.private = true;
+        assert_ne!(game_data.version_hash(), private.version_hash());
+    }
+
+    #[test]
+    fn truncate_moves() {
+        let mut game_data = GameData::new();
+        game_data.moves.push(Move::new((0, 0), Player::X));
+        game_data.add_move_at(Move::new((0, 1), Player::O), 1_000);
+        game_data.add_move_at(Move::new((0, 3), Player::X), 1_100);
+        game_data.add_move_at(Move::new((1, 0), Player::O), 1_200);
+
+        assert_eq!(
+            game_data.truncate_moves(2),
+            vec![Move::new((0, 3), Player::X), Move::new((1, 0), Player::O)]
+        );
+        assert_eq!(game_data.moves.len(), 2);
+        // the first move was stored without a time
+        assert_eq!(game_data.move_times, vec![1_000]);
+
+        assert_eq!(game_data.truncate_moves(0).len(), 2);
+        assert!(game_data.move_times.is_empty());
+        assert_eq!(game_data.truncate_moves(5), vec![]);
+    }
+
+    /// the game with X's opening move, and a copy continued with the given move of O
+    fn diverging_games(own: (Coordinates, u64), other: (Coordinates, u64)) -> [GameData; 2] {
+        let mut base = GameData::new();
+        base.add_move_at(Move::new((4, 4), Player::X), 1_000);
+        [own, other].map(|(coordinates, time)| {
+            let mut game_data = base.clone();
+            game_data.add_move_at(Move::new(coordinates, Player::O), time);
+            game_data
+        })
+    }
+
+    #[test]
+    fn merge_prefix() {
+        let [game_data, _] = diverging_games(((3, 3), 1_100), ((3, 3), 1_100));
+        let mut behind = game_data.clone();
+        behind.truncate_moves(1);
+        behind.created_by = Some("Alice".to_string());
+
+        let expected = GameData {
+            created_by: Some("Alice".to_string()),
+            ..game_data.clone()
+        };
+        assert_eq!(game_data.merge(&behind), Ok(expected.clone()));
+        assert_eq!(behind.merge(&game_data), Ok(expected));
+        assert_eq!(game_data.merge(&game_data), Ok(game_data.clone()));
+    }
+
+    #[test]
+    fn merge_diverging() {
+        // the move made first wins
+        let [own, other] = diverging_games(((3, 3), 1_100), ((3, 4), 1_050));
+        assert_eq!(own.merge(&other), Ok(other.clone()));
+        assert_eq!(other.merge(&own), Ok(other.clone()));
+
+        // moves made at the same time are ordered by their coordinates
+        let [own, other] = diverging_games(((3, 4), 1_100), ((3, 3), 1_100));
+        assert_eq!(own.merge(&other), Ok(other.clone()));
+        assert_eq!(other.merge(&own), Ok(other.clone()));
+
+        // a move without a time comes after every timed move
+        let mut untimed = own.clone();
+        untimed.move_times.clear();
+        assert_eq!(untimed.merge(&other), Ok(other.clone()));
+
+        // a game that has ended is final
+        let mut abandoned = own.clone();
+        abandoned.truncate_moves(1);
+        abandoned.result = Some(GameResult::abandoned(1));
+        assert_eq!(abandoned.merge(&other), Ok(abandoned.clone()));
+        assert_eq!(other.merge(&abandoned), Ok(abandoned.clone()));
+    }
+
+    #[test]
+    fn merge_conflict() {
+        let [mut own, mut other] = diverging_games(((3, 3), 1_100), ((3, 4), 1_050));
+        assert_eq!(
+            own.merge(&GameData::new()),
+            Err(MergeConflict::DifferentGames)
+        );
+
+        own.result = Some(GameResult::abandoned(2));
+        other.result = Some(GameResult::abandoned(2));
+        assert_eq!(own.merge(&other), Err(MergeConflict::BothEnded));
+        assert_eq!(other.merge(&own), Err(MergeConflict::BothEnded));
+
+        // the earlier move isn't allowed in its sub board
+        let [own, other] = diverging_games(((3, 3), 1_100), ((0, 0), 1_050));
+        assert_eq!(
+            own.merge(&other),
+            Err(MergeConflict::InvalidHistory(
+                InvalidGameData::InvalidMove {
+                    index: 1,
+                    invalid_move: InvalidMove::SubBoardNotActive
+                }
+            ))
+        );
     }
 }
@@ -0,0 +1,45 @@
+use crate::{GameState, Player};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// The outcome of a finished game
+#[derive(Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GameResult {
+    /// the player who won the game, or None if it ended in a draw
+    pub winner: Option<Player>,
+    /// the number of moves made until the game ended
+    pub move_count: usize,
+    /// true if the game was given up as a draw because no move was made for too long
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub abandoned: bool,
+}
+
+impl GameResult {
+    /// Returns the result for the given state, or None if the game is still in progress
+    pub fn from_state(state: GameState, move_count: usize) -> Option<Self> {
+        match state {
+            GameState::Won { winner } => Some(Self {
+                winner: Some(winner),
+                move_count,
+                abandoned: false,
+            }),
+            GameState::Draw => Some(Self {
+                winner: None,
+                move_count,
+                abandoned: false,
+            }),
+            GameState::InProgress { .. } => None,
+        }
+    }
+
+    /// the result of a game that was abandoned after the given number of moves. It
+    /// counts as a draw.
+    pub fn abandoned(move_count: usize) -> Self {
+        Self {
+            winner: None,
+            move_count,
+            abandoned: true,
+        }
+    }
+}
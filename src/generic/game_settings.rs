@@ -0,0 +1,204 @@
+use schemars::JsonSchema;
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+
+use crate::{Board, Coordinates, SubBoard};
+
+/// settings a game is played with. Every setting has a default, which games stored
+/// before the setting existed are read with, and defaults aren't serialized.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct GameSettings {
+    /// the minimum number of milliseconds between two consecutive moves of the same
+    /// player. Faster moves are rejected to deter scripted play. `None` allows any pace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_move_interval: Option<u64>,
+    /// how drawn sub boards count towards the lines of the overall board
+    #[serde(default, skip_serializing_if = "DrawRule::is_neither")]
+    pub draw_rule: DrawRule,
+    /// the rules the moves of the game are checked against
+    #[serde(default, skip_serializing_if = "RuleSetKind::is_standard")]
+    pub rules: RuleSetKind,
+    /// only the player tokens registered in the game may follow it over the websocket
+    /// or read it over the REST api, see `DataProvider::may_view`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub private: bool,
+    /// the number of rows and columns of sub boards of the board. Sizes that aren't
+    /// `BoardSize::is_valid` are rejected when the settings are deserialized.
+    #[serde(
+        default,
+        skip_serializing_if = "BoardSize::is_default",
+        deserialize_with = "deserialize_board_size"
+    )]
+    pub board_size: BoardSize,
+}
+
+fn deserialize_board_size<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BoardSize, D::Error> {
+    let board_size = BoardSize::deserialize(deserializer)?;
+    if !board_size.is_valid() {
+        return Err(D::Error::custom(format!(
+            "a board has 1 to {} sub boards in every direction, not {}x{}",
+            BoardSize::MAX,
+            board_size.rows,
+            board_size.columns
+        )));
+    }
+    Ok(board_size)
+}
+
+/// the dimensions of a board in sub boards, see `Board::with_settings`
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BoardSize {
+    pub rows: usize,
+    pub columns: usize,
+}
+
+impl Default for BoardSize {
+    /// the 3x3 sub boards of ultimate tic-tac-toe
+    fn default() -> Self {
+        Self::from(Board::SIZE)
+    }
+}
+
+impl BoardSize {
+    /// the most sub boards a board can have in a row or a column
+    pub const MAX: usize = 5;
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// whether the board has at least one and at most `MAX` sub boards in every direction
+    pub fn is_valid(&self) -> bool {
+        (1..=Self::MAX).contains(&self.rows) && (1..=Self::MAX).contains(&self.columns)
+    }
+
+    /// the number of rows and columns of fields, like `Board::FIELD_SIZE` for the default size
+    pub fn field_size(&self) -> Coordinates {
+        (
+            self.rows * SubBoard::SIZE.0,
+            self.columns * SubBoard::SIZE.1,
+        )
+    }
+
+    /// every field can be played at most once, so a game can't have more moves than fields
+    pub fn max_moves(&self) -> usize {
+        let (rows, columns) = self.field_size();
+        rows * columns
+    }
+}
+
+impl From<Coordinates> for BoardSize {
+    fn from((rows, columns): Coordinates) -> Self {
+        Self { rows, columns }
+    }
+}
+
+impl From<BoardSize> for Coordinates {
+    fn from(size: BoardSize) -> Self {
+        (size.rows, size.columns)
+    }
+}
+
+/// how a drawn sub board counts when looking for a winner of the overall board
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DrawRule {
+    /// a drawn sub board belongs to no one and blocks every line through it
+    #[default]
+    Neither,
+    /// a drawn sub board counts for both players and completes a line of either of them.
+    /// A line made only of drawn sub boards is won by no one.
+    Both,
+}
+
+impl DrawRule {
+    pub fn is_neither(&self) -> bool {
+        *self == Self::Neither
+    }
+}
+
+/// the rule sets a game can be stored with, see `RuleSet` and `Board::with_settings`
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleSetKind {
+    /// the rules of ultimate tic-tac-toe, see `StandardRules`
+    #[default]
+    Standard,
+}
+
+impl RuleSetKind {
+    pub fn is_standard(&self) -> bool {
+        *self == Self::Standard
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{GameData, Move, Player};
+
+    #[test]
+    fn settings_in_game_data() {
+        // a game stored before the settings existed
+        let game_id = uuid::Uuid::new_v4();
+        let old = format!(
+            r#"{{"moves":[{{"coordinates":[4,4],"player":"X"}}],"game_id":"{}"}}"#,
+            game_id
+        );
+        let game_data: GameData = serde_json::from_str(&old).unwrap();
+        assert_eq!(game_data.settings, GameSettings::default());
+        assert_eq!(
+            game_data.settings.board_size,
+            BoardSize {
+                rows: 3,
+                columns: 3
+            }
+        );
+        assert_eq!(game_data.moves, vec![Move::new((4, 4), Player::X)]);
+
+        // the default settings aren't written
+        let json = serde_json::to_value(GameSettings::default()).unwrap();
+        assert_eq!(json, serde_json::json!({}));
+
+        let mut game_data = GameData::new_with_id(game_id);
+        game_data.settings = GameSettings {
+            min_move_interval: Some(500),
+            draw_rule: DrawRule::Both,
+            rules: RuleSetKind::Standard,
+            private: true,
+            board_size: BoardSize {
+                rows: 2,
+                columns: 4,
+            },
+        };
+        let json = serde_json::to_string(&game_data).unwrap();
+        assert_eq!(serde_json::from_str::<GameData>(&json).unwrap(), game_data);
+        assert_eq!(
+            serde_json::to_value(game_data.settings).unwrap()["board_size"],
+            serde_json::json!({ "rows": 2, "columns": 4 })
+        );
+    }
+
+    #[test]
+    fn board_reads_settings() {
+        let settings = GameSettings {
+            draw_rule: DrawRule::Both,
+            board_size: BoardSize {
+                rows: 2,
+                columns: 4,
+            },
+            ..Default::default()
+        };
+        let board = Board::new().with_settings(&settings);
+        assert_eq!(board.data.dim(), (2, 4));
+        assert_eq!(board.draw_rule, DrawRule::Both);
+
+        let mut game_data = GameData::from(board);
+        assert_eq!(game_data.settings.board_size, settings.board_size);
+
+        // the default board is the one of ultimate tic-tac-toe
+        game_data.settings = GameSettings::default();
+        let board = Board::try_from_game_data(game_data).unwrap();
+        assert_eq!(board.data.dim(), Board::SIZE);
+    }
+}
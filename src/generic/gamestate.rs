@@ -1,8 +1,10 @@
 use std::fmt::Display;
 
 use crate::Player;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum GameState {
     Won { winner: Player },
     Draw,
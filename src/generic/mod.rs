@@ -1,13 +1,25 @@
 mod boards;
 mod field;
 mod game_data;
+mod game_result;
+mod game_settings;
 mod gamestate;
 mod r#move;
+mod notation;
 mod player;
+mod stats;
 
-pub use boards::{check_matrix, Board, SubBoard};
+pub use boards::{
+    check_matrix, Analysis, AnnotatedMove, Board, CellIndex, CompactBoard, GlobalCoord,
+    InvalidBoard, InvalidMove, RenderSnapshot, RuleSet, StandardRules, SubBoard, SubBoardIndex,
+    Symmetry,
+};
 pub use field::Field;
-pub use game_data::GameData;
+pub use game_data::{GameData, InvalidGameData, MergeConflict};
+pub use game_result::GameResult;
+pub use game_settings::{BoardSize, DrawRule, GameSettings, RuleSetKind};
 pub use gamestate::GameState;
+pub use notation::{parse_algebraic, to_algebraic, NotationError};
 pub use player::Player;
-pub use r#move::{Coordinates, Move};
+pub use r#move::{Coordinates, GameDelta, Move};
+pub use stats::{GlobalStats, PlayerStats};
@@ -1,19 +1,70 @@
-use crate::Player;
+use crate::{GameResult, InvalidMove, Player};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 pub type Coordinates = (usize, usize);
 
-#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Move {
     pub coordinates: Coordinates,
     pub player: Player,
 }
 
 impl Move {
+    /// creates a move without checking its coordinates, they are only checked once the
+    /// move is validated against a board
     pub fn new(coordinates: Coordinates, player: Player) -> Self {
         Self {
             coordinates,
             player,
         }
     }
+
+    /// creates a move whose coordinates lie within a grid of `board_size` fields, like
+    /// `Board::FIELD_SIZE`, so moves from outside can be rejected before they reach a board
+    pub fn try_new(
+        coordinates: Coordinates,
+        player: Player,
+        board_size: Coordinates,
+    ) -> Result<Self, InvalidMove> {
+        if coordinates.0 >= board_size.0 || coordinates.1 >= board_size.1 {
+            return Err(InvalidMove::OutOfBounds);
+        }
+        Ok(Self::new(coordinates, player))
+    }
+}
+
+/// a move together with its index in the moves of the game, which is all a subscriber
+/// that already has the earlier moves needs to know about an update
+#[derive(Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GameDelta {
+    #[serde(rename = "move")]
+    pub move_: Move,
+    /// the number of moves made before this one
+    pub index: usize,
+    /// the result of the game, if the move has ended it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<GameResult>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Board;
+
+    #[test]
+    fn try_new() {
+        for coordinates in [(0, 0), (4, 4), (8, 8), (0, 8)] {
+            assert_eq!(
+                Move::try_new(coordinates, Player::X, Board::FIELD_SIZE),
+                Ok(Move::new(coordinates, Player::X))
+            );
+        }
+        for coordinates in [(9, 0), (0, 9), (9, 9), (usize::MAX, 0)] {
+            assert_eq!(
+                Move::try_new(coordinates, Player::O, Board::FIELD_SIZE),
+                Err(InvalidMove::OutOfBounds)
+            );
+        }
+    }
 }
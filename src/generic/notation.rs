@@ -0,0 +1,78 @@
+use std::fmt::Display;
+
+use crate::Coordinates;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotationError {
+    MissingColumn,
+    InvalidRow(String),
+}
+
+impl Display for NotationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingColumn => write!(f, "the coordinate must start with a column letter"),
+            Self::InvalidRow(row) => write!(f, "invalid row number: {}", row),
+        }
+    }
+}
+
+/// Parses a human readable coordinate like `e5` into (row, column) coordinates.
+/// The letter names the column starting at `a`, the number the row starting at `1`,
+/// so `a1` is the top left field (0, 0) and `e5` is the center of the board (4, 4).
+/// Whether the coordinates lie on the board is left to the move validation.
+pub fn parse_algebraic(coordinate: &str) -> Result<Coordinates, NotationError> {
+    let coordinate = coordinate.trim();
+    let mut chars = coordinate.chars();
+    let column = chars
+        .next()
+        .filter(char::is_ascii_alphabetic)
+        .ok_or(NotationError::MissingColumn)?;
+    let column = (column.to_ascii_lowercase() as u8 - b'a') as usize;
+
+    let row = chars.as_str();
+    let row = row
+        .parse::<usize>()
+        .ok()
+        .and_then(|row| row.checked_sub(1))
+        .ok_or_else(|| NotationError::InvalidRow(row.to_string()))?;
+
+    Ok((row, column))
+}
+
+/// Formats coordinates the way `parse_algebraic` reads them, e.g. (4, 4) -> `e5`.
+/// Only columns up to `z` can be represented.
+pub fn to_algebraic(coordinates: Coordinates) -> String {
+    let (row, column) = coordinates;
+    format!("{}{}", (b'a' + column as u8) as char, row + 1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_algebraic("a1"), Ok((0, 0)));
+        assert_eq!(parse_algebraic("e5"), Ok((4, 4)));
+        assert_eq!(parse_algebraic("I2"), Ok((1, 8)));
+        assert_eq!(parse_algebraic("c10"), Ok((9, 2)));
+        assert_eq!(parse_algebraic("5e"), Err(NotationError::MissingColumn));
+        assert_eq!(parse_algebraic(""), Err(NotationError::MissingColumn));
+        assert_eq!(
+            parse_algebraic("a0"),
+            Err(NotationError::InvalidRow("0".to_string()))
+        );
+        assert_eq!(
+            parse_algebraic("ab"),
+            Err(NotationError::InvalidRow("b".to_string()))
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        for coordinates in [(0, 0), (4, 4), (8, 1), (2, 7)] {
+            assert_eq!(parse_algebraic(&to_algebraic(coordinates)), Ok(coordinates));
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{GameResult, Player};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// The outcomes of the finished games a player token took part in
+#[derive(Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlayerStats {
+    pub wins: u64,
+    pub losses: u64,
+    pub draws: u64,
+}
+
+impl PlayerStats {
+    /// the field that a result counts towards for the given side
+    pub fn field_for(result: &GameResult, player: Player) -> &'static str {
+        match result.winner {
+            None => "draws",
+            Some(winner) if winner == player => "wins",
+            Some(_) => "losses",
+        }
+    }
+
+    /// counts the result of a game the player played as the given side
+    pub fn record(&mut self, result: &GameResult, player: Player) {
+        self.increment(Self::field_for(result, player), 1);
+    }
+
+    /// adds `amount` to the field with the given name, unknown names are ignored
+    pub fn increment(&mut self, field: &str, amount: u64) {
+        match field {
+            "wins" => self.wins += amount,
+            "losses" => self.losses += amount,
+            "draws" => self.draws += amount,
+            _ => {}
+        }
+    }
+}
+
+/// The outcomes of all finished games
+#[derive(Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct GlobalStats {
+    pub x_wins: u64,
+    pub o_wins: u64,
+    pub draws: u64,
+}
+
+impl GlobalStats {
+    /// the field that a result counts towards
+    pub fn field_for(result: &GameResult) -> &'static str {
+        match result.winner {
+            None => "draws",
+            Some(Player::X) => "x_wins",
+            Some(Player::O) => "o_wins",
+        }
+    }
+
+    /// counts the result of a game
+    pub fn record(&mut self, result: &GameResult) {
+        self.increment(Self::field_for(result), 1);
+    }
+
+    /// adds `amount` to the field with the given name, unknown names are ignored
+    pub fn increment(&mut self, field: &str, amount: u64) {
+        match field {
+            "x_wins" => self.x_wins += amount,
+            "o_wins" => self.o_wins += amount,
+            "draws" => self.draws += amount,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn record() {
+        let x_won = GameResult {
+            winner: Some(Player::X),
+            move_count: 17,
+            abandoned: false,
+        };
+        let draw = GameResult {
+            winner: None,
+            move_count: 81,
+            abandoned: false,
+        };
+
+        let mut global = GlobalStats::default();
+        global.record(&x_won);
+        global.record(&draw);
+        assert_eq!(
+            global,
+            GlobalStats {
+                x_wins: 1,
+                o_wins: 0,
+                draws: 1
+            }
+        );
+
+        let mut player = PlayerStats::default();
+        player.record(&x_won, Player::X);
+        player.record(&x_won, Player::O);
+        player.record(&draw, Player::O);
+        assert_eq!(
+            player,
+            PlayerStats {
+                wins: 1,
+                losses: 1,
+                draws: 1
+            }
+        );
+    }
+}
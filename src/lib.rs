@@ -1,9 +1,19 @@
 // #![deny(unused_crate_dependencies)]
 
+#[cfg(feature = "client")]
+mod client;
 mod client_management;
+mod config;
 mod data_provider;
 mod generic;
+mod logging;
+mod tournament;
 
+#[cfg(feature = "client")]
+pub use client::*;
 pub use client_management::*;
+pub use config::*;
 pub use data_provider::*;
 pub use generic::*;
+pub use logging::*;
+pub use tournament::*;
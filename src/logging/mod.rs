@@ -0,0 +1,82 @@
+use std::io::Write;
+
+use chrono::{SecondsFormat, Utc};
+use log::Record;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::LogFormat;
+
+/// sets up the logger of the binary. The level is read from `RUST_LOG` in both formats.
+pub fn init_logger(format: LogFormat) -> Result<(), log::SetLoggerError> {
+    let mut builder = env_logger::builder();
+    builder.is_test(true);
+    if format == LogFormat::Json {
+        builder.format(|buf, record| writeln!(buf, "{}", json_line(record)));
+    }
+    builder.try_init()
+}
+
+/// the record as a json object with its time, level, target and message. The log
+/// lines don't carry fields of their own, so the first game id a message names is
+/// added as `game_id` to find every line about a game.
+pub fn json_line(record: &Record) -> Value {
+    let message = record.args().to_string();
+    let mut line = json!({
+        "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        "level": record.level().as_str(),
+        "target": record.target(),
+        "message": message,
+    });
+    if let Some(game_id) = find_uuid(&message) {
+        line["game_id"] = json!(game_id);
+    }
+    line
+}
+
+/// the first uuid in the text, in its hyphenated form
+fn find_uuid(text: &str) -> Option<Uuid> {
+    text.split(|c: char| !(c.is_ascii_hexdigit() || c == '-'))
+        .filter(|word| word.len() == 36)
+        .find_map(|word| Uuid::parse_str(word).ok())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use log::Level;
+
+    #[test]
+    fn json_lines() {
+        let game_id = Uuid::new_v4();
+        let line = json_line(
+            &Record::builder()
+                .args(format_args!("Stored result Draw of game {}", game_id))
+                .level(Level::Info)
+                .target("tictactoe_extreme::redis")
+                .build(),
+        );
+        let parsed: Value = serde_json::from_str(&line.to_string()).unwrap();
+        assert_eq!(parsed["level"], "INFO");
+        assert_eq!(parsed["target"], "tictactoe_extreme::redis");
+        assert_eq!(
+            parsed["message"],
+            format!("Stored result Draw of game {}", game_id)
+        );
+        assert_eq!(parsed["game_id"], game_id.to_string());
+        assert!(parsed["timestamp"].is_string());
+
+        // quotes and line breaks in the message don't break the line
+        let line = json_line(
+            &Record::builder()
+                .args(format_args!("unexpected \"input\"\nin the request"))
+                .level(Level::Warn)
+                .build(),
+        )
+        .to_string();
+        assert!(!line.contains('\n'));
+        let parsed: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed["message"], "unexpected \"input\"\nin the request");
+        assert!(parsed.get("game_id").is_none());
+    }
+}
@@ -2,92 +2,355 @@
 // mod data_provider;
 // mod generic;
 
-use log::info;
-use tokio::{signal::ctrl_c, spawn};
+use std::time::Duration;
+
+use log::{error, info, warn};
+use tokio::{
+    select,
+    signal::ctrl_c,
+    spawn,
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    time::sleep,
+};
 
 use tictactoe_extreme::{
-    r#static::StaticServer, rest_api::ApiServer, websocket::WebSocketServer, CacheProvider,
-    DataProvider, RedisProvider, RedisProviderArgs, Server,
+    init_logger, migrate, r#static::StaticServer, rest_api::ApiServer, websocket::WebSocketServer,
+    CacheProvider, Config, DataProvider, DataProviderKind, Reaper, RedisProvider,
+    RedisProviderArgs, Server,
 };
 
+/// starts the server in the background. If it fails to start or stops with an
+/// error, a message naming the server and its address is sent to `errors`.
+fn spawn_server<T, S>(name: &'static str, mut server: S, errors: UnboundedSender<String>)
+where
+    T: DataProvider + Default,
+    S: Server<T> + Send + 'static,
+{
+    let address = server.get_address();
+    spawn(async move {
+        if let Err(e) = server.start().await {
+            let _ = errors.send(format!(
+                "The {} server on {} failed: {:?}",
+                name, address, e
+            ));
+        }
+    });
+}
+
+/// starts all servers in this process, sharing one data provider, and the reaper if
+/// it is enabled
+fn spawn_all<T: DataProvider + Default + 'static>(
+    config: &Config,
+    data_provider: &T,
+    errors: &UnboundedSender<String>,
+) {
+    spawn_server(
+        "webserver",
+        StaticServer::from_config(config, data_provider.clone()),
+        errors.clone(),
+    );
+    spawn_server(
+        "api",
+        ApiServer::from_config(config, data_provider.clone()),
+        errors.clone(),
+    );
+    spawn_server(
+        "websocket",
+        WebSocketServer::from_config(config, data_provider.clone()),
+        errors.clone(),
+    );
+    if config.reaper.enabled {
+        spawn(Reaper::from_config(config, data_provider.clone()).run());
+    }
+}
+
+/// how often redis is tried at startup before `DATA_PROVIDER_FALLBACK` is used instead
+const REDIS_STARTUP_ATTEMPTS: u32 = 5;
+const REDIS_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// the redis provider, or None if redis can't be reached within a few attempts and
+/// `DATA_PROVIDER_FALLBACK` is set. Without a fallback redis isn't checked, requests
+/// are answered with its errors until it can be reached.
+async fn connect_redis(config: &Config) -> Option<RedisProvider> {
+    let data_provider = RedisProvider::new(config.redis.clone()).unwrap();
+    if config.data_provider_fallback.is_none() {
+        return Some(data_provider);
+    }
+    for attempt in 1..=REDIS_STARTUP_ATTEMPTS {
+        match data_provider.ping() {
+            Ok(()) => return Some(data_provider),
+            Err(e) => warn!(
+                "Redis can't be reached (attempt {} of {}): {}",
+                attempt, REDIS_STARTUP_ATTEMPTS, e
+            ),
+        }
+        if attempt < REDIS_STARTUP_ATTEMPTS {
+            sleep(REDIS_RETRY_DELAY).await;
+        }
+    }
+    warn!("Falling back to the cache, the games are kept in memory of this process only");
+    None
+}
+
+/// starts all servers with the data provider chosen by `DATA_PROVIDER` and runs them
+/// until shutdown
+async fn serve_all(
+    config: Config,
+    errors: UnboundedSender<String>,
+    server_errors: UnboundedReceiver<String>,
+) {
+    // all servers run in this process, so they can't share a port
+    config
+        .check_distinct_ports()
+        .unwrap_or_else(|e| panic!("Invalid configuration: {}", e));
+
+    match config.data_provider {
+        DataProviderKind::Cache => {
+            let data_provider = CacheProvider::new(config.cache.clone()).unwrap();
+            spawn_all(&config, &data_provider, &errors);
+            run_until_shutdown(data_provider, errors, server_errors).await;
+        }
+        DataProviderKind::Redis => match connect_redis(&config).await {
+            Some(data_provider) => {
+                spawn_all(&config, &data_provider, &errors);
+                run_until_shutdown(data_provider, errors, server_errors).await;
+            }
+            None => {
+                let data_provider = CacheProvider::new(config.cache.clone()).unwrap();
+                spawn_all(&config, &data_provider, &errors);
+                run_until_shutdown(data_provider, errors, server_errors).await;
+            }
+        },
+    }
+}
+
+/// starts the server with the given name and runs it until shutdown. It stores its
+/// games in redis, or in the cache if `DATA_PROVIDER_FALLBACK` is used.
+async fn serve_one(
+    config: Config,
+    server: String,
+    errors: UnboundedSender<String>,
+    server_errors: UnboundedReceiver<String>,
+) {
+    match connect_redis(&config).await {
+        Some(data_provider) => {
+            spawn_one(&config, &server, &data_provider, &errors);
+            run_until_shutdown(data_provider, errors, server_errors).await;
+        }
+        None => {
+            let data_provider = CacheProvider::new(config.cache.clone()).unwrap();
+            spawn_one(&config, &server, &data_provider, &errors);
+            run_until_shutdown(data_provider, errors, server_errors).await;
+        }
+    }
+}
+
+/// starts the server with the given name in the background
+fn spawn_one<T: DataProvider + Default + 'static>(
+    config: &Config,
+    server: &str,
+    data_provider: &T,
+    errors: &UnboundedSender<String>,
+) {
+    match server {
+        "webserver" => {
+            // start webserver
+            info!("Starting webserver");
+            spawn_server(
+                "webserver",
+                StaticServer::from_config(config, data_provider.clone()),
+                errors.clone(),
+            );
+        }
+        "api" => {
+            // start api server
+            info!("Starting api server");
+            spawn_server(
+                "api",
+                ApiServer::from_config(config, data_provider.clone()),
+                errors.clone(),
+            );
+        }
+        "websocket" => {
+            // start websocket server
+            info!("Starting websocket server");
+            spawn_server(
+                "websocket",
+                WebSocketServer::from_config(config, data_provider.clone()),
+                errors.clone(),
+            );
+        }
+        _ => {
+            panic!("Unknown server: {}", server);
+        }
+    }
+}
+
+/// waits for ctrl-c or for a server to fail. On ctrl-c the subscriptions of the
+/// data provider are closed before returning.
+async fn run_until_shutdown<T: DataProvider>(
+    mut data_provider: T,
+    errors: UnboundedSender<String>,
+    mut server_errors: UnboundedReceiver<String>,
+) {
+    drop(errors);
+
+    // wait for ctrl-c or for a server to fail, e.g. because its port is already in use
+    select! {
+        _ = ctrl_c() => {}
+        Some(message) = server_errors.recv() => {
+            error!("{}", message);
+            std::process::exit(1);
+        }
+    }
+
+    info!("Shutting down");
+    if let Err(e) = data_provider.close() {
+        error!("Could not close the data provider: {:?}", e);
+    }
+}
+
+/// copies every game of the configured redis server to the redis server at `target`,
+/// given as `hostname:port` and reached with the same credentials
+fn migrate_to(config: &Config, target: &str) {
+    let (server_hostname, server_port) = target
+        .rsplit_once(':')
+        .and_then(|(hostname, port)| Some((hostname.to_string(), port.parse().ok()?)))
+        .unwrap_or_else(|| panic!("Invalid target {}, expected hostname:port", target));
+    let from = RedisProvider::new(config.redis.clone()).unwrap();
+    let mut to = RedisProvider::new(RedisProviderArgs {
+        server_hostname,
+        server_port,
+        ..config.redis.clone()
+    })
+    .unwrap();
+    match migrate(&from, &mut to) {
+        Ok(migrated) => info!("Migrated {} games to {}", migrated, target),
+        Err(e) => {
+            error!("Migration failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() {
-    env_logger::builder()
-        .is_test(true)
-        .try_init()
-        .expect("Failed to init logger");
+    let config = Config::from_env().unwrap_or_else(|e| panic!("Invalid configuration: {}", e));
+    init_logger(config.log_format).expect("Failed to init logger");
     // read command line arguments
     let args: Vec<String> = std::env::args().collect();
+    let (errors, server_errors) = unbounded_channel();
 
     match args.get(1) {
-        None => {
-            let data_provider = CacheProvider::default();
-
-            // all ports must be different from each other, if one is not set, none of the others can be 3000
-            let ports = [
-                std::env::var("WEBSERVER_PORT"),
-                std::env::var("API_PORT"),
-                std::env::var("WEBSOCKET_PORT"),
-            ]
-            .iter()
-            .map(|val| val.clone().unwrap_or("3000".to_string()))
-            .collect::<Vec<_>>();
-
-            let unique_ports = ports.iter().collect::<std::collections::HashSet<_>>();
-            if unique_ports.len() != ports.len() {
-                panic!(
-                    "All ports must be different from each other. Got: {:?}",
-                    ports
-                );
-            }
-
-            let mut static_server = StaticServer::from_env(data_provider.clone());
-            let mut api_server = ApiServer::from_env(data_provider.clone());
-            let mut websocket_server = WebSocketServer::from_env(data_provider.clone());
-            spawn(async move {
-                static_server.start().await.unwrap();
-            });
-            spawn(async move {
-                api_server.start().await.unwrap();
-            });
-            spawn(async move {
-                websocket_server.start().await.unwrap();
-            });
-        }
-        Some(server) => {
-            let data_provider = RedisProvider::new(RedisProviderArgs::from_env()).unwrap();
-            match server.as_str() {
-                "webserver" => {
-                    // start webserver
-                    info!("Starting webserver");
-                    let mut static_server = StaticServer::from_env(data_provider.clone());
-                    spawn(async move {
-                        static_server.start().await.unwrap();
-                    });
-                }
-                "api" => {
-                    // start api server
-                    info!("Starting api server");
-                    let mut api_server = ApiServer::from_env(data_provider.clone());
-                    spawn(async move {
-                        api_server.start().await.unwrap();
-                    });
-                }
-                "websocket" => {
-                    // start websocket server
-                    info!("Starting websocket server");
-                    let mut websocket_server = WebSocketServer::from_env(data_provider.clone());
-                    spawn(async move {
-                        websocket_server.start().await.unwrap();
-                    });
-                }
-                _ => {
-                    panic!("Unknown server: {}", server);
-                }
-            }
+        None => serve_all(config, errors, server_errors).await,
+        Some(command) if command == "migrate" => {
+            let target = args.get(2).expect("Usage: migrate <hostname>:<port>");
+            migrate_to(&config, target);
         }
+        Some(server) => serve_one(config, server.clone(), errors, server_errors).await,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{collections::HashMap, net::TcpListener};
+    use tictactoe_extreme::GameData;
+    use uuid::Uuid;
+
+    /// a port nothing listens on
+    fn free_port() -> u16 {
+        TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+    }
+
+    /// starts all servers with the given `DATA_PROVIDER` and returns the body of
+    /// the answer to creating a game
+    async fn create_game_with(data_provider: &str) -> String {
+        let api_port = free_port();
+        let vars: HashMap<String, String> = [
+            ("DATA_PROVIDER", data_provider.to_string()),
+            ("WEBSERVER_PORT", free_port().to_string()),
+            ("API_PORT", api_port.to_string()),
+            ("WEBSOCKET_PORT", free_port().to_string()),
+            // no redis is running, so the redis provider can only fail
+            ("REDIS_SERVER_HOSTNAME", "127.0.0.1".to_string()),
+            ("REDIS_SERVER_PORT", free_port().to_string()),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect();
+        let config = Config::from_vars(&vars).unwrap();
+        let (errors, server_errors) = unbounded_channel();
+        spawn(serve_all(config, errors, server_errors));
+        sleep(Duration::from_millis(200)).await;
+
+        reqwest::Client::new()
+            .put(format!("http://127.0.0.1:{}/api/v1/games", api_port))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn fall_back_to_cache() {
+        let api_port = free_port();
+        let vars: HashMap<String, String> = [
+            ("API_PORT", api_port.to_string()),
+            // no redis is running
+            ("REDIS_SERVER_HOSTNAME", "127.0.0.1".to_string()),
+            ("REDIS_SERVER_PORT", free_port().to_string()),
+            ("DATA_PROVIDER_FALLBACK", "cache".to_string()),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect();
+        let config = Config::from_vars(&vars).unwrap();
+        let (errors, server_errors) = unbounded_channel();
+        spawn(serve_one(config, "api".to_string(), errors, server_errors));
+        sleep(REDIS_RETRY_DELAY * REDIS_STARTUP_ATTEMPTS + Duration::from_millis(500)).await;
+
+        let client = reqwest::Client::new();
+        let body = client
+            .put(format!("http://127.0.0.1:{}/api/v1/games", api_port))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        let game_id = serde_json::from_str::<Uuid>(&body).unwrap();
+
+        // the game is stored in the cache and can be read back
+        let body = client
+            .get(format!(
+                "http://127.0.0.1:{}/api/v1/games/{}",
+                api_port, game_id
+            ))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        let game_data = serde_json::from_str::<GameData>(&body).unwrap();
+        assert_eq!(game_data.game_id, game_id);
     }
 
-    // wait for ctrl-c
-    ctrl_c().await.unwrap();
+    #[tokio::test]
+    async fn serve_all_uses_configured_provider() {
+        let body = create_game_with("cache").await;
+        assert!(serde_json::from_str::<Uuid>(&body).is_ok(), "{}", body);
+
+        // the redis provider answers with its error instead of storing the game in memory
+        let body = create_game_with("redis").await;
+        assert!(serde_json::from_str::<Uuid>(&body).is_err(), "{}", body);
+        assert!(body.contains("Connection"), "{}", body);
+    }
 }
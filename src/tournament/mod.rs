@@ -0,0 +1,256 @@
+use std::{collections::HashSet, fmt::Display};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{DataProvider, GameResult, Player};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TournamentError {
+    TooFewParticipants,
+    DuplicateParticipant(String),
+}
+
+impl Display for TournamentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooFewParticipants => write!(f, "a tournament needs at least two participants"),
+            Self::DuplicateParticipant(participant) => {
+                write!(
+                    f,
+                    "the participant {} was given more than once",
+                    participant
+                )
+            }
+        }
+    }
+}
+
+/// a single game of a round in the bracket
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct Matchup {
+    /// the participant playing X
+    pub x: String,
+    /// the participant playing O. `None` if `x` advances without playing
+    pub o: Option<String>,
+    /// the game the matchup is decided in, created once the matchup is scheduled
+    pub game_id: Option<Uuid>,
+    pub winner: Option<String>,
+}
+
+impl Matchup {
+    fn new(x: String, o: Option<String>) -> Self {
+        Self {
+            // a participant without opponent advances right away
+            winner: if o.is_none() { Some(x.clone()) } else { None },
+            x,
+            o,
+            game_id: None,
+        }
+    }
+}
+
+/// A single-elimination tournament. The winners of each round are paired for
+/// the next one until only a single participant is left.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct Tournament {
+    pub tournament_id: Uuid,
+    pub participants: Vec<String>,
+    /// the matchups of every round so far, the last one is the current round
+    pub rounds: Vec<Vec<Matchup>>,
+}
+
+impl Tournament {
+    pub fn new(participants: Vec<String>) -> Result<Self, TournamentError> {
+        if participants.len() < 2 {
+            return Err(TournamentError::TooFewParticipants);
+        }
+        let mut seen = HashSet::new();
+        if let Some(duplicate) = participants
+            .iter()
+            .find(|participant| !seen.insert(*participant))
+        {
+            return Err(TournamentError::DuplicateParticipant(duplicate.clone()));
+        }
+
+        Ok(Self {
+            tournament_id: Uuid::new_v4(),
+            rounds: vec![Self::pair(&participants)],
+            participants,
+        })
+    }
+
+    /// pairs neighbouring participants, the last one gets a bye if the number is odd
+    fn pair(participants: &[String]) -> Vec<Matchup> {
+        participants
+            .chunks(2)
+            .map(|pair| Matchup::new(pair[0].clone(), pair.get(1).cloned()))
+            .collect()
+    }
+
+    /// Returns the winner of the tournament once the final has been decided
+    pub fn winner(&self) -> Option<&str> {
+        match self.rounds.last()?.as_slice() {
+            [final_matchup] => final_matchup.winner.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Brings the bracket up to date with the games of the data provider: games
+    /// are created for undecided matchups, finished games decide their matchup
+    /// and once a round is decided, its winners are paired for the next one.
+    /// A drawn game is replayed in a new game, and so is a game that no longer
+    /// exists, e.g. because it has expired.
+    /// Returns true if the tournament has changed.
+    pub fn update<T: DataProvider>(&mut self, data_provider: &mut T) -> Result<bool, T::ErrorKind> {
+        let mut changed = false;
+        loop {
+            let round = self
+                .rounds
+                .last_mut()
+                .expect("a tournament has at least one round");
+            for matchup in round.iter_mut().filter(|matchup| matchup.winner.is_none()) {
+                let result = match matchup.game_id {
+                    Some(game_id) if !data_provider.game_exists(game_id)? => {
+                        Some(GameResult::abandoned(0))
+                    }
+                    Some(game_id) => data_provider.get_game_data(game_id)?.result,
+                    None => None,
+                };
+                match result {
+                    Some(GameResult {
+                        winner: Some(Player::X),
+                        ..
+                    }) => matchup.winner = Some(matchup.x.clone()),
+                    Some(GameResult {
+                        winner: Some(Player::O),
+                        ..
+                    }) => matchup.winner = matchup.o.clone(),
+                    Some(GameResult { winner: None, .. }) => {
+                        matchup.game_id = Some(data_provider.create_game(None)?)
+                    }
+                    None if matchup.game_id.is_none() => {
+                        matchup.game_id = Some(data_provider.create_game(None)?)
+                    }
+                    None => continue,
+                }
+                changed = true;
+            }
+
+            let winners: Option<Vec<String>> =
+                round.iter().map(|matchup| matchup.winner.clone()).collect();
+            match winners {
+                Some(winners) if winners.len() > 1 => self.rounds.push(Self::pair(&winners)),
+                _ => return Ok(changed),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::CacheProvider;
+
+    /// sets the result of the game directly, as if it had been played to the end
+    fn finish_game(data_provider: &CacheProvider, game_id: Uuid, winner: Option<Player>) {
+        data_provider
+            .hash_map
+            .lock()
+            .unwrap()
+            .get_mut(&game_id)
+            .unwrap()
+            .result = Some(GameResult {
+            winner,
+            move_count: 17,
+            abandoned: false,
+        });
+    }
+
+    fn participants(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn new_tournament() {
+        assert_eq!(
+            Tournament::new(participants(&["a"])),
+            Err(TournamentError::TooFewParticipants)
+        );
+        assert_eq!(
+            Tournament::new(participants(&["a", "b", "a"])),
+            Err(TournamentError::DuplicateParticipant("a".to_string()))
+        );
+
+        let tournament = Tournament::new(participants(&["a", "b", "c"])).unwrap();
+        assert_eq!(
+            tournament.rounds,
+            vec![vec![
+                Matchup::new("a".to_string(), Some("b".to_string())),
+                Matchup {
+                    x: "c".to_string(),
+                    o: None,
+                    game_id: None,
+                    winner: Some("c".to_string())
+                }
+            ]]
+        );
+    }
+
+    #[test]
+    fn four_player_bracket() {
+        let mut data_provider = CacheProvider::default();
+        let mut tournament = Tournament::new(participants(&["a", "b", "c", "d"])).unwrap();
+
+        assert!(tournament.update(&mut data_provider).unwrap());
+        assert!(!tournament.update(&mut data_provider).unwrap());
+        let semi_finals: Vec<Uuid> = tournament.rounds[0]
+            .iter()
+            .map(|matchup| matchup.game_id.unwrap())
+            .collect();
+        assert_eq!(semi_finals.len(), 2);
+
+        // a draw is replayed, the other semi final is won by O
+        finish_game(&data_provider, semi_finals[0], None);
+        finish_game(&data_provider, semi_finals[1], Some(Player::O));
+        assert!(tournament.update(&mut data_provider).unwrap());
+        let replay = tournament.rounds[0][0].game_id.unwrap();
+        assert_ne!(replay, semi_finals[0]);
+        assert_eq!(tournament.rounds[0][1].winner.as_deref(), Some("d"));
+        assert_eq!(tournament.rounds.len(), 1);
+
+        finish_game(&data_provider, replay, Some(Player::X));
+        assert!(tournament.update(&mut data_provider).unwrap());
+        assert_eq!(tournament.rounds.len(), 2);
+        let final_matchup = tournament.rounds[1][0].clone();
+        assert_eq!(final_matchup.x, "a");
+        assert_eq!(final_matchup.o.as_deref(), Some("d"));
+        assert_eq!(tournament.winner(), None);
+
+        finish_game(
+            &data_provider,
+            final_matchup.game_id.unwrap(),
+            Some(Player::O),
+        );
+        assert!(tournament.update(&mut data_provider).unwrap());
+        assert_eq!(tournament.winner(), Some("d"));
+        assert!(!tournament.update(&mut data_provider).unwrap());
+    }
+
+    #[test]
+    fn missing_game_is_replayed() {
+        let mut data_provider = CacheProvider::default();
+        let mut tournament = Tournament::new(participants(&["a", "b"])).unwrap();
+        tournament.update(&mut data_provider).unwrap();
+        let game_id = tournament.rounds[0][0].game_id.unwrap();
+
+        // e.g. the game has expired before it was finished
+        data_provider.hash_map.lock().unwrap().remove(&game_id);
+        assert!(tournament.update(&mut data_provider).unwrap());
+        let replay = tournament.rounds[0][0].game_id.unwrap();
+        assert_ne!(replay, game_id);
+        assert!(data_provider.game_exists(replay).unwrap());
+        assert_eq!(tournament.rounds[0][0].winner, None);
+    }
+}
@@ -0,0 +1,108 @@
+//! starts the static, REST and websocket servers on one shared `CacheProvider`, the
+//! way `main.rs` does without arguments, and checks that they see each other's changes
+
+use futures_util::StreamExt;
+use reqwest::{Client, StatusCode};
+use std::time::Duration;
+use tictactoe_extreme::{
+    r#static::StaticServer, rest_api::ApiServer, websocket::OutgoingMessage,
+    websocket::WebSocketServer, CacheProvider, Move, Player, Server,
+};
+use tokio::{
+    net::TcpStream,
+    spawn,
+    time::{sleep, timeout},
+};
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use uuid::Uuid;
+
+fn random_port() -> u16 {
+    rand::random::<u16>().max(1024)
+}
+
+#[tokio::test]
+async fn serve_all_with_shared_provider() {
+    let data_provider = CacheProvider::default();
+    let mut webserver = StaticServer::new(
+        "127.0.0.1".to_string(),
+        random_port(),
+        data_provider.clone(),
+    );
+    let mut api = ApiServer::new(
+        "127.0.0.1".to_string(),
+        random_port(),
+        data_provider.clone(),
+    );
+    let mut websocket = WebSocketServer::new(
+        "127.0.0.1".to_string(),
+        random_port(),
+        data_provider.clone(),
+    );
+    let webserver_address = webserver.get_address();
+    let api_address = api.get_address();
+    let websocket_address = websocket.get_address();
+    spawn(async move { webserver.start().await.unwrap() });
+    spawn(async move { api.start().await.unwrap() });
+    spawn(async move { websocket.start().await.unwrap() });
+    sleep(Duration::from_millis(200)).await;
+
+    let client = Client::new();
+    let response = client
+        .get(format!("http://{}/", webserver_address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // the game is created through the REST API ...
+    let response = client
+        .put(format!("http://{}/api/v1/games", api_address))
+        .send()
+        .await
+        .unwrap();
+    let game_id = serde_json::from_str::<Uuid>(&response.text().await.unwrap()).unwrap();
+
+    // ... and the websocket server finds it in the shared provider
+    let (mut ws_stream, _) = timeout(
+        Duration::from_secs(1),
+        connect_async(format!("ws://{}/{}", websocket_address, game_id)),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    async fn next_message(
+        ws_stream: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+    ) -> OutgoingMessage {
+        let message = timeout(Duration::from_secs(1), ws_stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        serde_json::from_str::<OutgoingMessage>(&message.to_string()).unwrap()
+    }
+    match next_message(&mut ws_stream).await {
+        OutgoingMessage::GameState { game_state, .. } => assert!(game_state.moves.is_empty()),
+        other => panic!("expected the game state, got {:?}", other),
+    }
+
+    // a move made through the REST API reaches the websocket subscriber
+    let new_move = Move::new((4, 4), Player::X);
+    let response = client
+        .post(format!(
+            "http://{}/api/v1/games/{}/moves",
+            api_address, game_id
+        ))
+        .body(serde_json::to_string(&new_move).unwrap())
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.text().await.unwrap(), "\"ok\"");
+    match next_message(&mut ws_stream).await {
+        OutgoingMessage::GameState { game_state, .. } => {
+            assert_eq!(game_state.game_id, game_id);
+            assert_eq!(game_state.moves, vec![new_move]);
+        }
+        other => panic!("expected the game state, got {:?}", other),
+    }
+}